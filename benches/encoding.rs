@@ -0,0 +1,87 @@
+//! Benchmarks for the string and byte forms of tickets.
+//!
+//! Every benchmark runs on a [small ticket](testing::small_ticket) and a
+//! [large ticket](testing::large_ticket) with 64 endpoints.
+
+use std::hint::black_box;
+
+use criterion::{
+    BenchmarkGroup, Criterion, criterion_group, criterion_main, measurement::WallTime,
+};
+use iroh_tickets::{Ticket, testing};
+use serde::{Serialize, de::DeserializeOwned};
+
+type Group<'a> = BenchmarkGroup<'a, WallTime>;
+
+/// The string encoding before it was done in a single pass, for comparison.
+fn encode_string_two_pass<T: Ticket>(ticket: &T) -> String {
+    let mut out = T::KIND.to_string();
+    out.push_str(&data_encoding::BASE32_NOPAD.encode(&ticket.encode_bytes()));
+    out.make_ascii_lowercase();
+    out
+}
+
+fn bench_serialize<T: Ticket + Serialize>(group: &mut Group<'_>, name: &str, ticket: &T) {
+    assert_eq!(ticket.encode_string(), encode_string_two_pass(ticket));
+    group.bench_function(format!("{name}/encode_string"), |b| {
+        b.iter(|| black_box(ticket).encode_string())
+    });
+    group.bench_function(format!("{name}/encode_string two pass"), |b| {
+        b.iter(|| encode_string_two_pass(black_box(ticket)))
+    });
+    group.bench_function(format!("{name}/encode_bytes"), |b| {
+        b.iter(|| black_box(ticket).encode_bytes())
+    });
+    group.bench_function(format!("{name}/json"), |b| {
+        b.iter(|| serde_json::to_string(black_box(ticket)).unwrap())
+    });
+}
+
+fn bench_deserialize<T: Ticket + DeserializeOwned>(group: &mut Group<'_>, name: &str, ticket: &T) {
+    let s = ticket.encode_string();
+    let bytes = ticket.encode_bytes();
+    let json = serde_json::to_string(&s).unwrap();
+    group.bench_function(format!("{name}/decode_string"), |b| {
+        b.iter(|| T::decode_string(black_box(&s)).unwrap())
+    });
+    group.bench_function(format!("{name}/decode_bytes"), |b| {
+        b.iter(|| T::decode_bytes(black_box(&bytes)).unwrap())
+    });
+    group.bench_function(format!("{name}/json"), |b| {
+        b.iter(|| serde_json::from_str::<T>(black_box(&json)).unwrap())
+    });
+}
+
+fn bench_base32<T: Ticket>(group: &mut Group<'_>, name: &str, ticket: &T) {
+    let bytes = ticket.encode_bytes();
+    let s = iroh_tickets::encode_raw(T::KIND, &bytes);
+    group.bench_function(format!("{name}/encode"), |b| {
+        b.iter(|| iroh_tickets::encode_raw(T::KIND, black_box(&bytes)))
+    });
+    group.bench_function(format!("{name}/decode"), |b| {
+        b.iter(|| iroh_tickets::decode_raw(T::KIND, black_box(&s)).unwrap())
+    });
+}
+
+fn benches(c: &mut Criterion) {
+    let small = testing::small_ticket();
+    let large = testing::large_ticket(64);
+
+    let mut group = c.benchmark_group("serialize");
+    bench_serialize(&mut group, "small", &small);
+    bench_serialize(&mut group, "large", &large);
+    group.finish();
+
+    let mut group = c.benchmark_group("deserialize");
+    bench_deserialize(&mut group, "small", &small);
+    bench_deserialize(&mut group, "large", &large);
+    group.finish();
+
+    let mut group = c.benchmark_group("base32");
+    bench_base32(&mut group, "small", &small);
+    bench_base32(&mut group, "large", &large);
+    group.finish();
+}
+
+criterion_group!(encoding, benches);
+criterion_main!(encoding);
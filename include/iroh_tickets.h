@@ -0,0 +1,55 @@
+/*
+ * C API of iroh-tickets, built with the `capi` feature.
+ *
+ * All functions taking an output pointer return IROH_TICKET_OK on success and only write
+ * the output then. Tickets returned by the library must be freed with iroh_ticket_free,
+ * strings with iroh_ticket_string_free.
+ */
+
+#ifndef IROH_TICKETS_H
+#define IROH_TICKETS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum IrohTicketError {
+    IROH_TICKET_OK = 0,
+    IROH_TICKET_ERROR_NULL_POINTER = 1,
+    IROH_TICKET_ERROR_INVALID_UTF8 = 2,
+    IROH_TICKET_ERROR_WRONG_KIND = 3,
+    IROH_TICKET_ERROR_INVALID_ENCODING = 4,
+    IROH_TICKET_ERROR_TOO_LONG = 5,
+    IROH_TICKET_ERROR_CHECKSUM = 6,
+    IROH_TICKET_ERROR_INVALID_TICKET = 7,
+} IrohTicketError;
+
+/* A parsed ticket of any of the built-in kinds. */
+typedef struct IrohTicket IrohTicket;
+
+/* Parses an endpoint ticket from its string form. */
+IrohTicketError iroh_ticket_parse_endpoint(const char *s, IrohTicket **out);
+
+/* Parses a ticket of any of the built-in kinds from its string form. */
+IrohTicketError iroh_ticket_parse(const char *s, IrohTicket **out);
+
+/* Serializes a ticket into its string form. */
+IrohTicketError iroh_ticket_serialize(const IrohTicket *ticket, char **out);
+
+/* Returns the kind of a ticket, e.g. "endpoint", or NULL if ticket is NULL. */
+const char *iroh_ticket_kind(const IrohTicket *ticket);
+
+/* Returns a description of an error code. */
+const char *iroh_ticket_error_message(IrohTicketError err);
+
+/* Frees a ticket, does nothing if ticket is NULL. */
+void iroh_ticket_free(IrohTicket *ticket);
+
+/* Frees a string returned by the library, does nothing if s is NULL. */
+void iroh_ticket_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* IROH_TICKETS_H */
@@ -0,0 +1,194 @@
+//! Derive macro for the `iroh_tickets::Ticket` trait.
+//!
+//! This crate is re-exported by `iroh-tickets` behind its `derive` feature, use it from there.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input, spanned::Spanned};
+
+/// Derives `iroh_tickets::Ticket` for a struct.
+///
+/// The kind prefix is given with `#[ticket(kind = "...")]` and must consist of lowercase
+/// ascii letters.
+///
+/// The byte representation is the postcard encoding of the struct fields in declaration
+/// order, so every field needs to implement `serde::Serialize` and `serde::Deserialize`.
+/// Decoding rejects data after the last field, unless `#[ticket(allow_trailing_bytes)]`
+/// is given. Allowing it lets older versions parse tickets that have new fields appended.
+///
+/// Besides the `Ticket` impl this also generates:
+/// - `Display` and `FromStr`, round-tripping the canonical string form.
+/// - `Serialize` and `Deserialize`, using the string form for human readable formats and
+///   the plain fields otherwise.
+///
+/// Generic structs and enums are not supported.
+#[proc_macro_derive(Ticket, attributes(ticket))]
+pub fn derive_ticket(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let TicketArgs {
+        kind,
+        allow_trailing_bytes,
+    } = parse_args(&input)?;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "`#[derive(Ticket)]` does not support generic types",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "`#[derive(Ticket)]` only supports structs",
+        ));
+    };
+
+    let ident = &input.ident;
+    let types: Vec<_> = data.fields.iter().map(|f| &f.ty).collect();
+    let bindings: Vec<_> = (0..types.len())
+        .map(|i| format_ident!("__field{}", i))
+        .collect();
+    let accessors: Vec<_> = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(name) => quote!(#name),
+            None => {
+                let index = syn::Index::from(i);
+                quote!(#index)
+            }
+        })
+        .collect();
+    let construct = match &data.fields {
+        Fields::Named(_) => quote!(Self { #(#accessors: #bindings),* }),
+        Fields::Unnamed(_) => quote!(Self(#(#bindings),*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    let krate = quote!(::iroh_tickets);
+    let private = quote!(#krate::__private);
+    let decode = if allow_trailing_bytes {
+        quote!(#private::postcard::from_bytes(bytes)?)
+    } else {
+        quote!(#krate::decode_postcard(bytes)?)
+    };
+    Ok(quote! {
+        impl #krate::Ticket for #ident {
+            const KIND: &'static str = #kind;
+
+            fn encode_bytes(&self) -> #private::alloc::vec::Vec<u8> {
+                #krate::Ticket::try_encode_bytes(self).expect("postcard serialization failed")
+            }
+
+            fn try_encode_bytes(
+                &self,
+            ) -> ::core::result::Result<#private::alloc::vec::Vec<u8>, #krate::EncodeError> {
+                ::core::result::Result::Ok(#private::postcard::to_allocvec(&(#(&self.#accessors,)*))?)
+            }
+
+            fn decode_bytes(
+                bytes: &[u8],
+            ) -> ::core::result::Result<Self, #krate::ParseError> {
+                #krate::ensure_len::<Self>(bytes)?;
+                let (#(#bindings,)*): (#(#types,)*) = #decode;
+                ::core::result::Result::Ok(#construct)
+            }
+        }
+
+        impl ::core::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(&#krate::Ticket::encode_string(self))
+            }
+        }
+
+        impl ::core::str::FromStr for #ident {
+            type Err = #krate::ParseError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                #krate::Ticket::decode_string(s)
+            }
+        }
+
+        impl #private::serde::Serialize for #ident {
+            fn serialize<S: #private::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::core::result::Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&#krate::Ticket::encode_string(self))
+                } else {
+                    #private::serde::Serialize::serialize(&(#(&self.#accessors,)*), serializer)
+                }
+            }
+        }
+
+        impl<'de> #private::serde::Deserialize<'de> for #ident {
+            fn deserialize<D: #private::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::core::result::Result<Self, D::Error> {
+                if deserializer.is_human_readable() {
+                    let s = <#private::alloc::string::String as #private::serde::Deserialize>::deserialize(
+                        deserializer,
+                    )?;
+                    #krate::Ticket::decode_string(&s).map_err(#private::serde::de::Error::custom)
+                } else {
+                    let (#(#bindings,)*): (#(#types,)*) =
+                        #private::serde::Deserialize::deserialize(deserializer)?;
+                    ::core::result::Result::Ok(#construct)
+                }
+            }
+        }
+    })
+}
+
+/// The arguments of the `#[ticket(...)]` attribute.
+struct TicketArgs {
+    kind: LitStr,
+    allow_trailing_bytes: bool,
+}
+
+/// Parses the `#[ticket(kind = "...", allow_trailing_bytes)]` attribute.
+fn parse_args(input: &DeriveInput) -> syn::Result<TicketArgs> {
+    let mut kind = None;
+    let mut allow_trailing_bytes = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("ticket")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("kind") {
+                let lit: LitStr = meta.value()?.parse()?;
+                let value = lit.value();
+                if value.is_empty() || !value.bytes().all(|b| b.is_ascii_lowercase()) {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        "ticket kind must consist of lowercase ascii letters",
+                    ));
+                }
+                kind = Some(lit);
+                Ok(())
+            } else if meta.path.is_ident("allow_trailing_bytes") {
+                allow_trailing_bytes = true;
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unsupported ticket attribute, expected `kind` or `allow_trailing_bytes`",
+                ))
+            }
+        })?;
+    }
+    let kind = kind.ok_or_else(|| {
+        syn::Error::new(
+            input.ident.span(),
+            "missing `#[ticket(kind = \"...\")]` attribute",
+        )
+    })?;
+    Ok(TicketArgs {
+        kind,
+        allow_trailing_bytes,
+    })
+}
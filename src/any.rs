@@ -0,0 +1,404 @@
+//! A ticket of any of the built-in kinds.
+
+use std::{ops::Range, str::FromStr};
+
+use n0_error::e;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, VersionError, blob::BlobTicket, bundle::BundleTicket,
+    clean_lenient, dns::DnsTicket, endpoint::EndpointTicket, gossip::GossipTicket,
+    invite::InviteTicket, longest_kind_prefix, matches_kind, multi::MultiEndpointTicket,
+    relay::RelayTicket, suggest_kind,
+};
+
+/// A ticket of any of the kinds defined in this crate.
+///
+/// Parsing picks the concrete type by looking at the kind prefix of the string. For
+/// application-defined ticket types use a [`Registry`](crate::registry::Registry) instead.
+///
+/// Serializes as the canonical string form of the contained ticket.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[non_exhaustive]
+pub enum AnyTicket {
+    /// An [`EndpointTicket`].
+    Endpoint(EndpointTicket),
+    /// A [`BlobTicket`].
+    Blob(BlobTicket),
+    /// A [`GossipTicket`].
+    Gossip(GossipTicket),
+    /// A [`MultiEndpointTicket`].
+    MultiEndpoint(MultiEndpointTicket),
+    /// A [`BundleTicket`].
+    Bundle(BundleTicket),
+    /// A [`RelayTicket`].
+    Relay(RelayTicket),
+    /// A [`DnsTicket`].
+    Dns(DnsTicket),
+    /// An [`InviteTicket`].
+    Invite(InviteTicket),
+}
+
+impl AnyTicket {
+    /// The kinds of all ticket types that can be parsed into an [`AnyTicket`].
+    pub const KINDS: &'static [&'static str] = &[
+        EndpointTicket::KIND,
+        BlobTicket::KIND,
+        GossipTicket::KIND,
+        MultiEndpointTicket::KIND,
+        BundleTicket::KIND,
+        RelayTicket::KIND,
+        DnsTicket::KIND,
+        InviteTicket::KIND,
+    ];
+
+    /// The kind of the contained ticket.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Endpoint(_) => EndpointTicket::KIND,
+            Self::Blob(_) => BlobTicket::KIND,
+            Self::Gossip(_) => GossipTicket::KIND,
+            Self::MultiEndpoint(_) => MultiEndpointTicket::KIND,
+            Self::Bundle(_) => BundleTicket::KIND,
+            Self::Relay(_) => RelayTicket::KIND,
+            Self::Dns(_) => DnsTicket::KIND,
+            Self::Invite(_) => InviteTicket::KIND,
+        }
+    }
+
+    /// Encodes the contained ticket into its canonical string form.
+    pub fn encode_string(&self) -> String {
+        match self {
+            Self::Endpoint(ticket) => ticket.encode_string(),
+            Self::Blob(ticket) => ticket.encode_string(),
+            Self::Gossip(ticket) => ticket.encode_string(),
+            Self::MultiEndpoint(ticket) => ticket.encode_string(),
+            Self::Bundle(ticket) => ticket.encode_string(),
+            Self::Relay(ticket) => ticket.encode_string(),
+            Self::Dns(ticket) => ticket.encode_string(),
+            Self::Invite(ticket) => ticket.encode_string(),
+        }
+    }
+
+    /// Converts a ticket string of any of the [`KINDS`](Self::KINDS) into its canonical
+    /// string form, see [`Ticket::canonicalize`].
+    pub fn canonicalize(s: &str) -> Result<String, ParseError> {
+        Ok(Self::decode_string(s)?.encode_string())
+    }
+
+    /// Decodes a ticket string of any of the [`KINDS`](Self::KINDS).
+    pub fn decode_string(s: &str) -> Result<Self, ParseError> {
+        match kind_of(s)? {
+            EndpointTicket::KIND => EndpointTicket::decode_string(s).map(Self::Endpoint),
+            BlobTicket::KIND => BlobTicket::decode_string(s).map(Self::Blob),
+            GossipTicket::KIND => GossipTicket::decode_string(s).map(Self::Gossip),
+            MultiEndpointTicket::KIND => {
+                MultiEndpointTicket::decode_string(s).map(Self::MultiEndpoint)
+            }
+            BundleTicket::KIND => BundleTicket::decode_string(s).map(Self::Bundle),
+            RelayTicket::KIND => RelayTicket::decode_string(s).map(Self::Relay),
+            DnsTicket::KIND => DnsTicket::decode_string(s).map(Self::Dns),
+            InviteTicket::KIND => InviteTicket::decode_string(s).map(Self::Invite),
+            _ => Err(e!(ParseError::UnknownKind { suggestion: None })),
+        }
+    }
+
+    /// Decodes a ticket string of any of the [`KINDS`](Self::KINDS), tolerating mangling
+    /// from copy and paste.
+    ///
+    /// See [`Ticket::decode_string_lenient`].
+    pub fn decode_string_lenient(s: &str) -> Result<Self, ParseError> {
+        Self::decode_string(&clean_lenient(s))
+    }
+
+    /// Encodes the contained ticket into its byte representation.
+    ///
+    /// The bytes do not contain the kind, see [`kind`](Self::kind).
+    pub fn encode_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Endpoint(ticket) => ticket.encode_bytes(),
+            Self::Blob(ticket) => ticket.encode_bytes(),
+            Self::Gossip(ticket) => ticket.encode_bytes(),
+            Self::MultiEndpoint(ticket) => ticket.encode_bytes(),
+            Self::Bundle(ticket) => ticket.encode_bytes(),
+            Self::Relay(ticket) => ticket.encode_bytes(),
+            Self::Dns(ticket) => ticket.encode_bytes(),
+            Self::Invite(ticket) => ticket.encode_bytes(),
+        }
+    }
+
+    /// Encodes the contained ticket with the oldest wire format version up to `max_version`,
+    /// see [`Ticket::to_bytes_versioned`].
+    pub fn to_bytes_versioned(&self, max_version: u32) -> Result<Vec<u8>, VersionError> {
+        match self {
+            Self::Endpoint(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Blob(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Gossip(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::MultiEndpoint(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Bundle(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Relay(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Dns(ticket) => ticket.to_bytes_versioned(max_version),
+            Self::Invite(ticket) => ticket.to_bytes_versioned(max_version),
+        }
+    }
+
+    /// Decodes the byte representation of a ticket of the given `kind`.
+    ///
+    /// The [`KIND_ALIASES`](Ticket::KIND_ALIASES) of the ticket types are accepted as well.
+    pub fn decode_bytes(kind: &str, bytes: &[u8]) -> Result<Self, ParseError> {
+        match builtin_kind(kind).unwrap_or_default() {
+            EndpointTicket::KIND => EndpointTicket::decode_bytes(bytes).map(Self::Endpoint),
+            BlobTicket::KIND => BlobTicket::decode_bytes(bytes).map(Self::Blob),
+            GossipTicket::KIND => GossipTicket::decode_bytes(bytes).map(Self::Gossip),
+            MultiEndpointTicket::KIND => {
+                MultiEndpointTicket::decode_bytes(bytes).map(Self::MultiEndpoint)
+            }
+            BundleTicket::KIND => BundleTicket::decode_bytes(bytes).map(Self::Bundle),
+            RelayTicket::KIND => RelayTicket::decode_bytes(bytes).map(Self::Relay),
+            DnsTicket::KIND => DnsTicket::decode_bytes(bytes).map(Self::Dns),
+            InviteTicket::KIND => InviteTicket::decode_bytes(bytes).map(Self::Invite),
+            _ => Err(e!(ParseError::UnknownKind { suggestion: None })),
+        }
+    }
+
+    /// Encodes the contained ticket into its canonical byte representation, see
+    /// [`Ticket::canonical_bytes`].
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Endpoint(ticket) => ticket.canonical_bytes(),
+            Self::Blob(ticket) => ticket.canonical_bytes(),
+            Self::Gossip(ticket) => ticket.canonical_bytes(),
+            Self::MultiEndpoint(ticket) => ticket.canonical_bytes(),
+            Self::Bundle(ticket) => ticket.canonical_bytes(),
+            Self::Relay(ticket) => ticket.canonical_bytes(),
+            Self::Dns(ticket) => ticket.canonical_bytes(),
+            Self::Invite(ticket) => ticket.canonical_bytes(),
+        }
+    }
+
+    /// The [`Fingerprint`] of the contained ticket.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::from_encoded(self.kind(), &self.encode_bytes())
+    }
+}
+
+/// Returns the kind of a ticket string without decoding its payload.
+///
+/// The kind is matched against the built-in [`AnyTicket::KINDS`], the payload is not
+/// looked at. For application-defined kinds use
+/// [`Registry::kind_of`](crate::registry::Registry::kind_of).
+///
+/// Strings starting with one of the [`KIND_ALIASES`](Ticket::KIND_ALIASES) of a ticket
+/// type return the [`KIND`](Ticket::KIND) of that type.
+pub fn kind_of(s: &str) -> Result<&'static str, ParseError> {
+    let kinds = AnyTicket::KINDS
+        .iter()
+        .chain(ALIASES.iter().copied().flatten());
+    longest_kind_prefix(s, kinds.copied())
+        .and_then(builtin_kind)
+        .ok_or_else(|| {
+            let suggestion = suggest_kind(s, AnyTicket::KINDS.iter().copied());
+            e!(ParseError::UnknownKind { suggestion })
+        })
+}
+
+/// The [`KIND_ALIASES`](Ticket::KIND_ALIASES) of the ticket types in [`AnyTicket::KINDS`].
+const ALIASES: &[&[&str]] = &[
+    EndpointTicket::KIND_ALIASES,
+    BlobTicket::KIND_ALIASES,
+    GossipTicket::KIND_ALIASES,
+    MultiEndpointTicket::KIND_ALIASES,
+    BundleTicket::KIND_ALIASES,
+    RelayTicket::KIND_ALIASES,
+    DnsTicket::KIND_ALIASES,
+    InviteTicket::KIND_ALIASES,
+];
+
+/// The [`KIND`](Ticket::KIND) of the built-in ticket type that `kind` names, see
+/// [`matches_kind`].
+fn builtin_kind(kind: &str) -> Option<&'static str> {
+    if matches_kind::<EndpointTicket>(kind) {
+        Some(EndpointTicket::KIND)
+    } else if matches_kind::<BlobTicket>(kind) {
+        Some(BlobTicket::KIND)
+    } else if matches_kind::<GossipTicket>(kind) {
+        Some(GossipTicket::KIND)
+    } else if matches_kind::<MultiEndpointTicket>(kind) {
+        Some(MultiEndpointTicket::KIND)
+    } else if matches_kind::<BundleTicket>(kind) {
+        Some(BundleTicket::KIND)
+    } else if matches_kind::<RelayTicket>(kind) {
+        Some(RelayTicket::KIND)
+    } else if matches_kind::<DnsTicket>(kind) {
+        Some(DnsTicket::KIND)
+    } else if matches_kind::<InviteTicket>(kind) {
+        Some(InviteTicket::KIND)
+    } else {
+        None
+    }
+}
+
+/// Finds and parses all tickets of the built-in kinds in free-form text.
+///
+/// A ticket is a run of ascii alphanumeric characters that starts with one of the
+/// [`AnyTicket::KINDS`] and parses as such, so tickets surrounded by whitespace,
+/// punctuation or markup like backticks are found. Runs that look like a ticket but fail
+/// to parse are skipped.
+///
+/// Returns the byte range of each ticket in `text`, in order of appearance.
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::{AnyTicket, endpoint::EndpointTicket};
+///
+/// let ticket = EndpointTicket::new(EndpointAddr::new(
+///     SecretKey::from_bytes(&[0u8; 32]).public(),
+/// ));
+/// let text = format!("Join me: `{ticket}`!");
+/// let found = iroh_tickets::scan(&text);
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(&text[found[0].0.clone()], ticket.to_string());
+/// assert_eq!(found[0].1, AnyTicket::from(ticket));
+/// ```
+pub fn scan(text: &str) -> Vec<(Range<usize>, AnyTicket)> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        let len = rest[start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - start);
+        let word = &rest[start..start + len];
+        if kind_of(word).is_ok()
+            && let Ok(ticket) = AnyTicket::decode_string(word)
+        {
+            let offset = text.len() - rest.len() + start;
+            found.push((offset..offset + len, ticket));
+        }
+        rest = &rest[start + len..];
+    }
+    found
+}
+
+impl From<EndpointTicket> for AnyTicket {
+    fn from(ticket: EndpointTicket) -> Self {
+        Self::Endpoint(ticket)
+    }
+}
+
+impl From<BlobTicket> for AnyTicket {
+    fn from(ticket: BlobTicket) -> Self {
+        Self::Blob(ticket)
+    }
+}
+
+impl From<GossipTicket> for AnyTicket {
+    fn from(ticket: GossipTicket) -> Self {
+        Self::Gossip(ticket)
+    }
+}
+
+impl From<MultiEndpointTicket> for AnyTicket {
+    fn from(ticket: MultiEndpointTicket) -> Self {
+        Self::MultiEndpoint(ticket)
+    }
+}
+
+impl From<BundleTicket> for AnyTicket {
+    fn from(ticket: BundleTicket) -> Self {
+        Self::Bundle(ticket)
+    }
+}
+
+impl From<RelayTicket> for AnyTicket {
+    fn from(ticket: RelayTicket) -> Self {
+        Self::Relay(ticket)
+    }
+}
+
+impl From<DnsTicket> for AnyTicket {
+    fn from(ticket: DnsTicket) -> Self {
+        Self::Dns(ticket)
+    }
+}
+
+impl From<InviteTicket> for AnyTicket {
+    fn from(ticket: InviteTicket) -> Self {
+        Self::Invite(ticket)
+    }
+}
+
+impl FromStr for AnyTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode_string(s)
+    }
+}
+
+impl Serialize for AnyTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::decode_string(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+
+    #[test]
+    fn test_any_ticket_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[2u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let any: AnyTicket = ticket.to_string().parse().unwrap();
+        assert_eq!(any.kind(), "endpoint");
+        assert_eq!(any, AnyTicket::from(ticket.clone()));
+        assert_eq!(any.to_string(), ticket.to_string());
+
+        let json = serde_json::to_string(&any).unwrap();
+        assert_eq!(serde_json::from_str::<AnyTicket>(&json).unwrap(), any);
+
+        let multi = MultiEndpointTicket::new([ticket.endpoint_addr().clone()]);
+        let any: AnyTicket = multi.to_string().parse().unwrap();
+        assert_eq!(any, AnyTicket::MultiEndpoint(multi));
+
+        let err = AnyTicket::decode_string("nopeaaaa").unwrap_err();
+        assert!(matches!(err, ParseError::UnknownKind { .. }));
+    }
+
+    #[test]
+    fn test_scan() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[12u8; 32]).public());
+        let endpoint = EndpointTicket::new(addr.clone());
+        let multi = MultiEndpointTicket::new([addr]);
+        let text = format!(
+            "Hi,\n\nconnect with <{endpoint}> or\n> \"{multi}\".\n\
+             Not a ticket: endpoint endpointaaaa blobbing, {endpoint}1"
+        );
+
+        let found = scan(&text);
+        let tickets: Vec<_> = found.iter().map(|(_, ticket)| ticket.clone()).collect();
+        assert_eq!(tickets, [endpoint.clone().into(), multi.clone().into()]);
+        assert_eq!(&text[found[0].0.clone()], endpoint.to_string());
+        assert_eq!(&text[found[1].0.clone()], multi.to_string());
+        assert!(scan("ünïcode endpoint").is_empty());
+    }
+
+    #[test]
+    fn test_kind_of() {
+        assert_eq!(kind_of("endpointaaaa").unwrap(), "endpoint");
+        // The payload is not decoded.
+        assert_eq!(kind_of("endpoint!!").unwrap(), "endpoint");
+        assert_eq!(kind_of("MultiEndpoint").unwrap(), "multiendpoint");
+        assert!(kind_of("endpoin").is_err());
+        assert!(kind_of("").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! [`Arbitrary`] implementations for fuzzing.
+//!
+//! The built-in ticket types and [`AnyTicket`] implement [`Arbitrary`], so fuzz targets of
+//! downstream crates can take tickets as input. Generated tickets use relay and IP
+//! addresses and stay well below [`Ticket::MAX_LEN`](crate::Ticket::MAX_LEN), so they
+//! round-trip through their string and byte forms. [`endpoint_addr`] generates the
+//! addresses, for implementing [`Arbitrary`] for custom ticket types.
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use iroh_tickets::{Ticket, endpoint::EndpointTicket};
+//!
+//! let mut u = Unstructured::new(&[7; 256]);
+//! let ticket = EndpointTicket::arbitrary(&mut u).unwrap();
+//! assert_eq!(
+//!     EndpointTicket::decode_string(&ticket.encode_string()).unwrap(),
+//!     ticket
+//! );
+//! ```
+
+use std::net::SocketAddr;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use iroh_base::{EndpointAddr, EndpointId, RelayUrl, SecretKey, TransportAddr};
+
+use crate::{
+    AnyTicket,
+    blob::{BlobFormat, BlobTicket},
+    bundle::BundleTicket,
+    dns::DnsTicket,
+    endpoint::EndpointTicket,
+    gossip::GossipTicket,
+    invite::InviteTicket,
+    multi::MultiEndpointTicket,
+    relay::RelayTicket,
+};
+
+/// The maximum number of addresses, ALPNs and endpoints generated for a ticket.
+const MAX_ITEMS: usize = 4;
+
+fn items<'a, T>(
+    u: &mut Unstructured<'a>,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=MAX_ITEMS)?;
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        out.push(f(u)?);
+    }
+    Ok(out)
+}
+
+/// Generates an endpoint id.
+pub fn endpoint_id(u: &mut Unstructured<'_>) -> Result<EndpointId> {
+    Ok(SecretKey::from_bytes(&u.arbitrary()?).public())
+}
+
+/// Generates a relay URL.
+pub fn relay_url(u: &mut Unstructured<'_>) -> Result<RelayUrl> {
+    let url = format!("https://relay{}.example.com", u16::arbitrary(u)?);
+    Ok(url.parse().expect("valid relay URL"))
+}
+
+/// Generates an [`EndpointAddr`] with up to four relay and IP addresses.
+pub fn endpoint_addr(u: &mut Unstructured<'_>) -> Result<EndpointAddr> {
+    let id = endpoint_id(u)?;
+    let addrs = items(u, |u| {
+        Ok(if u.arbitrary()? {
+            TransportAddr::Relay(relay_url(u)?)
+        } else {
+            // The wire format does not contain the IPv6 flow info and scope id.
+            let addr = SocketAddr::arbitrary(u)?;
+            TransportAddr::Ip(SocketAddr::new(addr.ip(), addr.port()))
+        })
+    })?;
+    Ok(EndpointAddr::from_parts(id, addrs))
+}
+
+impl<'a> Arbitrary<'a> for EndpointTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let addr = endpoint_addr(u)?;
+        let alpns = items(u, |u| {
+            let len = u.int_in_range(1..=32)?;
+            Ok(u.bytes(len)?.to_vec())
+        })?;
+        Ok(Self::new(addr).with_alpns(alpns))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BlobFormat {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Self::HashSeq
+        } else {
+            Self::Raw
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for BlobTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(endpoint_addr(u)?, u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for GossipTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let topic = u.arbitrary()?;
+        Ok(Self::new(topic, items(u, endpoint_addr)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for MultiEndpointTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(items(u, endpoint_addr)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for RelayTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let ticket = Self::new(relay_url(u)?);
+        Ok(match u.arbitrary::<Option<String>>()? {
+            Some(token) => ticket.with_token(token),
+            None => ticket,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DnsTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::new(format!("host{}.example.com", u16::arbitrary(u)?)))
+    }
+}
+
+impl<'a> Arbitrary<'a> for InviteTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let ticket = Self::new(endpoint_addr(u)?, u.arbitrary()?);
+        Ok(match u.arbitrary::<Option<String>>()? {
+            Some(label) => ticket.with_label(label),
+            None => ticket,
+        })
+    }
+}
+
+/// Contains up to four endpoint, blob, gossip and multi endpoint tickets.
+impl<'a> Arbitrary<'a> for BundleTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut bundle = Self::new();
+        for _ in 0..u.int_in_range(0..=MAX_ITEMS)? {
+            match u.int_in_range(0..=3)? {
+                0 => bundle.push(&EndpointTicket::arbitrary(u)?),
+                1 => bundle.push(&BlobTicket::arbitrary(u)?),
+                2 => bundle.push(&GossipTicket::arbitrary(u)?),
+                _ => bundle.push(&MultiEndpointTicket::arbitrary(u)?),
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+impl<'a> Arbitrary<'a> for AnyTicket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=7)? {
+            0 => Self::Endpoint(u.arbitrary()?),
+            1 => Self::Blob(u.arbitrary()?),
+            2 => Self::Gossip(u.arbitrary()?),
+            3 => Self::MultiEndpoint(u.arbitrary()?),
+            4 => Self::Relay(u.arbitrary()?),
+            5 => Self::Dns(u.arbitrary()?),
+            6 => Self::Invite(u.arbitrary()?),
+            _ => Self::Bundle(u.arbitrary()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{RngExt, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_roundtrip() {
+        let mut rng = rand::rngs::ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let data: Vec<u8> = (0..512).map(|_| rng.random()).collect();
+            let ticket = AnyTicket::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let s = ticket.encode_string();
+            assert_eq!(AnyTicket::decode_string(&s).unwrap(), ticket);
+            assert_eq!(
+                AnyTicket::decode_bytes(ticket.kind(), &ticket.encode_bytes()).unwrap(),
+                ticket
+            );
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! ASCII-armored tickets.
+//!
+//! The armored format is a block of text similar to PEM and OpenPGP armor. The block starts
+//! with a `Kind` header, followed by the base64 of the [byte
+//! representation](Ticket::encode_bytes) wrapped at [`LINE_LEN`] characters and a CRC-24
+//! checksum, so large tickets survive email clients and can be embedded readably in
+//! configuration files.
+//!
+//! ```text
+//! -----BEGIN IROH TICKET-----
+//! Kind: endpoint
+//!
+//! ADtqJ7zOtqQtYqOo0CpvDXNlMhV3HeJDpjrASKGLWdopAA==
+//! =O1Gt
+//! -----END IROH TICKET-----
+//! ```
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let armored = ticket.to_armored();
+//! assert!(armored.starts_with("-----BEGIN IROH TICKET-----\nKind: endpoint\n"));
+//! assert_eq!(EndpointTicket::from_armored(&armored).unwrap(), ticket);
+//! ```
+
+use data_encoding::BASE64;
+use n0_error::e;
+
+use crate::{ParseError, Ticket, matches_kind};
+
+/// The first line of an armored ticket.
+pub const BEGIN: &str = "-----BEGIN IROH TICKET-----";
+
+/// The last line of an armored ticket.
+pub const END: &str = "-----END IROH TICKET-----";
+
+/// The number of base64 characters per line.
+pub const LINE_LEN: usize = 64;
+
+/// The name of the header containing the ticket kind.
+const KIND_HEADER: &str = "Kind";
+
+/// Computes the CRC-24 checksum of `bytes`, as used by OpenPGP armor.
+fn crc24(bytes: &[u8]) -> u32 {
+    const INIT: u32 = 0xb7_04ce;
+    const POLY: u32 = 0x186_4cfb;
+    let mut crc = INIT;
+    for &b in bytes {
+        crc ^= u32::from(b) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+    crc & 0xff_ffff
+}
+
+/// Encodes a ticket as an armored block, ending with a newline.
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    encode_with_headers(ticket, &[])
+}
+
+/// Encodes a ticket as an armored block with additional headers after the `Kind` header.
+///
+/// # Panics
+///
+/// If a header name is empty or contains a `:`, or a name or value contains a line break.
+pub fn encode_with_headers<T: Ticket>(ticket: &T, headers: &[(&str, &str)]) -> String {
+    let bytes = ticket.encode_bytes();
+    let body = BASE64.encode(&bytes);
+    let crc = crc24(&bytes).to_be_bytes();
+    let mut out = format!("{BEGIN}\n{KIND_HEADER}: {}\n", T::KIND);
+    for (name, value) in headers {
+        assert!(
+            !name.is_empty() && !name.contains([':', '\r', '\n']),
+            "invalid header name {name:?}"
+        );
+        assert!(
+            !value.contains(['\r', '\n']),
+            "invalid header value {value:?}"
+        );
+        out.push_str(&format!("{name}: {value}\n"));
+    }
+    out.push('\n');
+    for line in body.as_bytes().chunks(LINE_LEN) {
+        out.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        out.push('\n');
+    }
+    out.push('=');
+    BASE64.encode_append(&crc[1..], &mut out);
+    out.push('\n');
+    out.push_str(END);
+    out.push('\n');
+    out
+}
+
+/// Decodes a ticket from an armored block.
+///
+/// Text before and after the block is ignored, as are headers other than `Kind` and
+/// surrounding whitespace on each line. Fails with [`ParseError::Armor`] if there is no
+/// well-formed block, with [`ParseError::Kind`] if the block contains a different kind of
+/// ticket and with [`ParseError::Checksum`] if the checksum does not match.
+pub fn decode<T: Ticket>(s: &str) -> Result<T, ParseError> {
+    decode_with_headers(s).map(|(ticket, _)| ticket)
+}
+
+/// Decodes a ticket from an armored block and returns it with the headers other than
+/// `Kind`, in order, see [`decode`].
+pub fn decode_with_headers<T: Ticket>(s: &str) -> Result<(T, Vec<(String, String)>), ParseError> {
+    let mut lines = s.lines().map(str::trim);
+    if !lines.by_ref().any(|line| line == BEGIN) {
+        return Err(e!(ParseError::Armor));
+    }
+
+    let mut kind = None;
+    let mut headers = Vec::new();
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or_else(|| e!(ParseError::Armor))?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.eq_ignore_ascii_case(KIND_HEADER) {
+            kind = Some(value);
+        } else {
+            headers.push((name.to_string(), value.to_string()));
+        }
+    }
+    match kind {
+        Some(kind) if matches_kind::<T>(kind) => {}
+        kind => {
+            return Err(e!(ParseError::Kind {
+                expected: T::KIND,
+                found: kind.map(ToString::to_string)
+            }));
+        }
+    }
+
+    let max = BASE64.encode_len(T::MAX_LEN);
+    let mut body = String::new();
+    let crc = loop {
+        let line = lines.next().ok_or_else(|| e!(ParseError::Armor))?;
+        if let Some(crc) = line.strip_prefix('=') {
+            break crc;
+        }
+        if line == END {
+            return Err(e!(ParseError::Armor));
+        }
+        body.push_str(line);
+        if body.len() > max {
+            return Err(e!(ParseError::TooLong {
+                len: body.len(),
+                max
+            }));
+        }
+    };
+    if lines.next() != Some(END) {
+        return Err(e!(ParseError::Armor));
+    }
+
+    let bytes = BASE64.decode(body.as_bytes())?;
+    let crc = match BASE64.decode(crc.as_bytes())?.as_slice() {
+        &[a, b, c] => u32::from_be_bytes([0, a, b, c]),
+        _ => return Err(e!(ParseError::Armor)),
+    };
+    if crc != crc24(&bytes) {
+        return Err(e!(ParseError::Checksum));
+    }
+    Ok((T::decode_bytes(&bytes)?, headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        TicketExt, blob::BlobTicket, endpoint::EndpointTicket, multi::MultiEndpointTicket,
+    };
+
+    #[test]
+    fn test_crc24() {
+        // The check value of CRC-24/OPENPGP.
+        assert_eq!(crc24(b"123456789"), 0x21_cf02);
+    }
+
+    #[test]
+    fn test_armor_roundtrip() {
+        let addrs = (0..4u8).map(|i| EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public()));
+        let ticket = MultiEndpointTicket::new(addrs);
+        let armored = ticket.to_armored();
+        assert!(armored.lines().all(|line| line.len() <= LINE_LEN));
+        assert!(armored.lines().count() > 6);
+        assert_eq!(MultiEndpointTicket::from_armored(&armored).unwrap(), ticket);
+
+        // Quoted in an email, with CRLF line endings and an extra header.
+        let mangled = armored
+            .replace("\n\n", "\nComment: for alice\n\n")
+            .replace('\n', "  \r\n");
+        let email = format!("Hi,\r\n\r\nhere is the ticket:\r\n\r\n{mangled}\r\nBye");
+        assert_eq!(MultiEndpointTicket::from_armored(&email).unwrap(), ticket);
+        let (decoded, headers) = decode_with_headers::<MultiEndpointTicket>(&email).unwrap();
+        assert_eq!(decoded, ticket);
+        assert_eq!(headers, [("Comment".to_string(), "for alice".to_string())]);
+    }
+
+    #[test]
+    fn test_armor_headers() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[30u8; 32]).public(),
+        ));
+        let armored = encode_with_headers(&ticket, &[("Label", "alice"), ("Note", "a: b")]);
+        assert!(armored.contains("Kind: endpoint\nLabel: alice\nNote: a: b\n\n"));
+        let (decoded, headers) = decode_with_headers::<EndpointTicket>(&armored).unwrap();
+        assert_eq!(decoded, ticket);
+        assert_eq!(
+            headers,
+            [
+                ("Label".to_string(), "alice".to_string()),
+                ("Note".to_string(), "a: b".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_armor_errors() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[30u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let armored = ticket.to_armored();
+
+        let err = BlobTicket::from_armored(&armored).unwrap_err();
+        assert!(
+            matches!(err, ParseError::Kind { found: Some(ref found), .. } if found == "endpoint")
+        );
+        let err =
+            EndpointTicket::from_armored(&armored.replace("Kind: endpoint\n", "")).unwrap_err();
+        assert!(
+            matches!(err, ParseError::Kind { found: None, .. }),
+            "{err:?}"
+        );
+
+        let body = armored.lines().nth(3).unwrap();
+        let mut corrupted = body.to_string();
+        corrupted.replace_range(..1, if body.starts_with('A') { "B" } else { "A" });
+        let err = EndpointTicket::from_armored(&armored.replace(body, &corrupted)).unwrap_err();
+        assert!(matches!(err, ParseError::Checksum { .. }), "{err:?}");
+
+        let crc = armored.lines().nth(4).unwrap();
+        for s in [
+            ticket.to_string(),
+            armored.replace(END, ""),
+            armored.replace(&format!("{crc}\n"), ""),
+            armored.replace(crc, "=AAAAAA=="),
+        ] {
+            let err = EndpointTicket::from_armored(&s).unwrap_err();
+            assert!(matches!(err, ParseError::Armor { .. }), "{s}: {err:?}");
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct RoomTicket(Vec<u8>);
+
+    impl Ticket for RoomTicket {
+        const KIND: &'static str = "room";
+        const KIND_ALIASES: &'static [&'static str] = &["chat"];
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            Ok(Self(bytes.to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_armor_kind_alias() {
+        let ticket = RoomTicket(vec![1, 2, 3]);
+        let armored = ticket.to_armored().replace("Kind: room", "Kind: chat");
+        assert_eq!(RoomTicket::from_armored(&armored).unwrap(), ticket);
+        let other = armored.replace("Kind: chat", "Kind: blob");
+        assert!(matches!(
+            RoomTicket::from_armored(&other),
+            Err(ParseError::Kind { .. })
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+//! Base58 string forms of tickets.
+//!
+//! This is an alternative to the canonical base32 string form of [`Ticket::encode_string`].
+//! Base58 strings are about 15% shorter, which helps where tickets are shown or typed. They
+//! are case sensitive though, and can not be told apart from base32 strings reliably, so
+//! both sides need to agree on the encoding. The [`multibase`](crate::multibase) form
+//! avoids this by marking the payload as base58.
+//!
+//! The string is the [`KIND`](Ticket::KIND) followed by the base58 encoding of the
+//! [byte representation](Ticket::encode_bytes), using the bitcoin alphabet.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{base58, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let s = base58::encode(&ticket);
+//! assert!(s.len() < ticket.to_string().len());
+//! assert_eq!(base58::decode::<EndpointTicket>(&s).unwrap(), ticket);
+//! ```
+//!
+//! A ticket type can use base58 as its string form by overriding
+//! [`Ticket::encode_string`], [`Ticket::write_to`] and [`Ticket::decode_string`] with the
+//! functions in this module.
+
+use n0_error::e;
+
+use crate::{ParseError, Ticket, max_kind_len, strip_ticket_kind};
+
+/// Encodes a ticket as its kind followed by base58.
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let mut out = String::new();
+    write_to(ticket, &mut out);
+    out
+}
+
+/// Appends the base58 string form of a ticket to `out`, see [`encode`].
+pub fn write_to<T: Ticket>(ticket: &T, out: &mut String) {
+    out.push_str(T::KIND);
+    out.push_str(&bs58::encode(ticket.encode_bytes()).into_string());
+}
+
+/// Decodes a ticket from the string form written by [`encode`].
+pub fn decode<T: Ticket>(s: &str) -> Result<T, ParseError> {
+    let kind_len = max_kind_len::<T>();
+    // Base58 needs less than 1.37 characters per byte.
+    let max = kind_len + T::MAX_LEN / 100 * 137 + 137;
+    if s.len() > max {
+        return Err(e!(ParseError::TooLong { len: s.len(), max }));
+    }
+    let rest = strip_ticket_kind::<T>(s)?;
+    let offset = s.len() - rest.len();
+    let bytes = decode_payload(rest, offset)?;
+    T::decode_bytes(&bytes)
+}
+
+/// Decodes a base58 payload that starts at `offset` in the ticket string.
+pub(crate) fn decode_payload(payload: &str, offset: usize) -> Result<Vec<u8>, ParseError> {
+    bs58::decode(payload).into_vec().map_err(|err| match err {
+        bs58::decode::Error::InvalidCharacter { index, .. }
+        | bs58::decode::Error::NonAsciiCharacter { index } => {
+            e!(ParseError::InvalidCharacter {
+                offset: offset + index
+            })
+        }
+        _ => ParseError::verification_failed("invalid base58"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_base58_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[14u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let s = encode(&ticket);
+        assert!(s.starts_with("endpoint"));
+        assert!(s.len() < ticket.to_string().len());
+        assert_eq!(decode::<EndpointTicket>(&s).unwrap(), ticket);
+
+        let mut out = String::from("> ");
+        write_to(&ticket, &mut out);
+        assert_eq!(out, format!("> {s}"));
+
+        let mut invalid = s.clone();
+        invalid.replace_range(10..11, "0");
+        let err = decode::<EndpointTicket>(&invalid).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidCharacter { offset: 10, .. }
+        ));
+        let err = decode::<EndpointTicket>(&s.replace("endpoint", "blob")).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+    }
+}
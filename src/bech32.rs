@@ -0,0 +1,112 @@
+//! Bech32m string forms of tickets.
+//!
+//! This is an alternative to the canonical base32 string form of [`Ticket::encode_string`]
+//! that adds a checksum. The [`KIND`](Ticket::KIND) is the human-readable part, followed
+//! by the separator `1` and the [byte representation](Ticket::encode_bytes) with a
+//! [bech32m] checksum. Typos like a single wrong character are detected when decoding
+//! instead of producing a different ticket or garbage bytes.
+//!
+//! Bech32m strings are limited to 1023 characters, which fits tickets of up to about 600
+//! bytes. The checksum only guarantees to detect errors in strings of up to 90 characters,
+//! longer strings still detect most errors.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{ParseError, bech32, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let s = bech32::encode(&ticket).unwrap();
+//! assert!(s.starts_with("endpoint1"));
+//! assert_eq!(bech32::decode::<EndpointTicket>(&s).unwrap(), ticket);
+//!
+//! let typo = s.replacen("q", "p", 1);
+//! let err = bech32::decode::<EndpointTicket>(&typo).unwrap_err();
+//! assert!(matches!(err, ParseError::Checksum { .. }));
+//! ```
+//!
+//! [bech32m]: https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki
+
+use bech32::{Bech32m, Hrp, primitives::decode::CheckedHrpstring};
+use n0_error::{e, stack_error};
+
+use crate::{ParseError, Ticket, matches_kind};
+
+/// An error encoding a ticket as bech32m.
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// The ticket is too long for a bech32m string.
+    #[error("ticket is too long for bech32m")]
+    TooLong {},
+    /// The kind of the ticket is not a valid bech32m human-readable part.
+    #[error("ticket kind is not valid for bech32m")]
+    InvalidKind {},
+}
+
+/// Encodes a ticket as a bech32m string with its kind as the human-readable part.
+pub fn encode<T: Ticket>(ticket: &T) -> Result<String, EncodeError> {
+    let hrp = Hrp::parse(T::KIND).map_err(|_| e!(EncodeError::InvalidKind))?;
+    bech32::encode_lower::<Bech32m>(hrp, &ticket.encode_bytes())
+        .map_err(|_| e!(EncodeError::TooLong))
+}
+
+/// Decodes a ticket from a bech32m string written by [`encode`].
+///
+/// Both lowercase and uppercase strings are accepted.
+pub fn decode<T: Ticket>(s: &str) -> Result<T, ParseError> {
+    let checked = CheckedHrpstring::new::<Bech32m>(s).map_err(|err| match err {
+        bech32::primitives::decode::CheckedHrpstringError::Checksum(_) => {
+            e!(ParseError::Checksum)
+        }
+        _ => ParseError::verification_failed("invalid bech32m string"),
+    })?;
+    let hrp = checked.hrp().to_lowercase();
+    if !matches_kind::<T>(&hrp) {
+        return Err(ParseError::wrong_kind(T::KIND, hrp));
+    }
+    let bytes: Vec<u8> = checked.byte_iter().collect();
+    T::decode_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        blob::{BlobFormat, BlobTicket},
+        endpoint::EndpointTicket,
+    };
+
+    #[test]
+    fn test_bech32_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[15u8; 32]).public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr.clone());
+        let s = encode(&ticket).unwrap();
+        assert!(s.starts_with("endpoint1"));
+        assert_eq!(decode::<EndpointTicket>(&s).unwrap(), ticket);
+        assert_eq!(
+            decode::<EndpointTicket>(&s.to_ascii_uppercase()).unwrap(),
+            ticket
+        );
+
+        // Every single character substitution in the data part is detected.
+        let data_start = "endpoint1".len();
+        for i in data_start..s.len() {
+            let mut typo = s.clone().into_bytes();
+            typo[i] = if typo[i] == b'q' { b'p' } else { b'q' };
+            let typo = String::from_utf8(typo).unwrap();
+            let err = decode::<EndpointTicket>(&typo).unwrap_err();
+            assert!(matches!(err, ParseError::Checksum { .. }), "{i}");
+        }
+
+        let blob = encode(&BlobTicket::new(addr, [0u8; 32], BlobFormat::Raw)).unwrap();
+        let err = decode::<EndpointTicket>(&blob).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+    }
+}
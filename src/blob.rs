@@ -0,0 +1,274 @@
+//! Tickets for blobs.
+
+use std::{collections::BTreeSet, net::SocketAddr, str::FromStr};
+
+use iroh_base::{EndpointAddr, EndpointId, RelayUrl, TransportAddr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket, ensure_len,
+    versioned::{self, Versioned},
+};
+
+/// The format of a blob referenced by a [`BlobTicket`].
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum BlobFormat {
+    /// A raw blob.
+    #[default]
+    Raw,
+    /// A sequence of BLAKE3 hashes, whose blobs should be retrieved as well.
+    HashSeq,
+}
+
+impl BlobFormat {
+    /// Whether this is [`BlobFormat::Raw`].
+    pub fn is_raw(&self) -> bool {
+        matches!(self, Self::Raw)
+    }
+
+    /// Whether this is [`BlobFormat::HashSeq`].
+    pub fn is_hash_seq(&self) -> bool {
+        matches!(self, Self::HashSeq)
+    }
+}
+
+/// A token containing everything to get a blob from a provider.
+///
+/// Contains
+/// - The [`EndpointAddr`] of the provider.
+/// - The BLAKE3 hash of the blob.
+/// - The [`BlobFormat`] of the blob.
+///
+/// The wire format is the one used by iroh-blobs, so tickets can be exchanged with it. That
+/// format only holds a single relay URL and IP addresses, other addresses are dropped when
+/// creating the ticket, see [`BlobTicket::new`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct BlobTicket {
+    addr: EndpointAddr,
+    format: BlobFormat,
+    hash: [u8; 32],
+}
+
+/// Wire format for [`BlobTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0BlobTicket {
+    node: Variant0NodeAddr,
+    format: BlobFormat,
+    hash: [u8; 32],
+}
+
+impl Versioned for Variant0BlobTicket {
+    const VERSION: u32 = 0;
+}
+
+#[derive(Serialize, Deserialize)]
+struct Variant0NodeAddr {
+    endpoint_id: EndpointId,
+    info: Variant0AddrInfo,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Variant0AddrInfo {
+    relay_url: Option<RelayUrl>,
+    direct_addresses: BTreeSet<SocketAddr>,
+}
+
+impl Ticket for BlobTicket {
+    const KIND: &'static str = "blob";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0BlobTicket {
+            node: Variant0NodeAddr {
+                endpoint_id: self.addr.id,
+                info: Variant0AddrInfo {
+                    relay_url: self.addr.relay_urls().next().cloned(),
+                    direct_addresses: self.addr.ip_addrs().cloned().collect(),
+                },
+            },
+            format: self.format,
+            hash: self.hash,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0BlobTicket { node, format, hash } = versioned::decode(bytes)?;
+        let mut addr = EndpointAddr::new(node.endpoint_id);
+        if let Some(relay_url) = node.info.relay_url {
+            addr = addr.with_relay_url(relay_url);
+        }
+        for ip_addr in node.info.direct_addresses {
+            addr = addr.with_ip_addr(ip_addr);
+        }
+        Ok(Self::new(addr, hash, format))
+    }
+}
+
+impl FromStr for BlobTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl BlobTicket {
+    /// Creates a new ticket.
+    ///
+    /// This keeps the first relay URL and the IP addresses of `addr`, without IPv6 flow
+    /// info and scope id, as the wire format can not hold anything else. Other relay URLs
+    /// and custom addresses are dropped, so decoding the encoded ticket returns it
+    /// unchanged.
+    pub fn new(addr: EndpointAddr, hash: [u8; 32], format: BlobFormat) -> Self {
+        let relay = addr.relay_urls().next().cloned().map(TransportAddr::Relay);
+        let ips = addr
+            .ip_addrs()
+            .map(|ip_addr| TransportAddr::Ip(SocketAddr::new(ip_addr.ip(), ip_addr.port())));
+        let addr = EndpointAddr::from_parts(addr.id, relay.into_iter().chain(ips));
+        Self { addr, format, hash }
+    }
+
+    /// The BLAKE3 hash of the blob this ticket can retrieve.
+    pub fn hash(&self) -> [u8; 32] {
+        self.hash
+    }
+
+    /// The [`EndpointAddr`] of the provider for this ticket.
+    pub fn addr(&self) -> &EndpointAddr {
+        &self.addr
+    }
+
+    /// The [`BlobFormat`] for this ticket.
+    pub fn format(&self) -> BlobFormat {
+        self.format
+    }
+
+    /// True if the ticket is for a hash sequence and should retrieve all blobs in it.
+    pub fn recursive(&self) -> bool {
+        self.format.is_hash_seq()
+    }
+
+    /// Returns the contents of the ticket.
+    pub fn into_parts(self) -> (EndpointAddr, [u8; 32], BlobFormat) {
+        let BlobTicket { addr, format, hash } = self;
+        (addr, hash, format)
+    }
+}
+
+impl Serialize for BlobTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.encode_string())
+        } else {
+            let BlobTicket { addr, format, hash } = self;
+            (addr, format, hash).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlobTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::decode_string(&s).map_err(serde::de::Error::custom)
+        } else {
+            let (addr, format, hash) = Deserialize::deserialize(deserializer)?;
+            Ok(Self::new(addr, hash, format))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::HEXLOWER;
+    use iroh_base::{CustomAddr, PublicKey, SecretKey};
+
+    use super::*;
+
+    fn make_ticket() -> BlobTicket {
+        let peer = SecretKey::from_bytes(&[10u8; 32]).public();
+        BlobTicket::new(
+            EndpointAddr::new(peer).with_ip_addr("127.0.0.1:1234".parse().unwrap()),
+            [1u8; 32],
+            BlobFormat::HashSeq,
+        )
+    }
+
+    #[test]
+    fn test_ticket_postcard_json() {
+        let ticket = make_ticket();
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(postcard::from_bytes::<BlobTicket>(&bytes).unwrap(), ticket);
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(serde_json::from_str::<BlobTicket>(&json).unwrap(), ticket);
+    }
+
+    #[test]
+    fn test_ticket_normalized() {
+        let peer = SecretKey::from_bytes(&[10u8; 32]).public();
+        let addr = EndpointAddr::new(peer)
+            .with_relay_url("https://relay0.example.com".parse().unwrap())
+            .with_relay_url("https://relay1.example.com".parse().unwrap())
+            .with_ip_addr("[fe80::1%2]:1234".parse().unwrap())
+            .with_addrs([TransportAddr::Custom(CustomAddr::from_parts(1, b"x"))]);
+        let ticket = BlobTicket::new(addr, [1u8; 32], BlobFormat::Raw);
+        assert_eq!(
+            ticket.addr().relay_urls().collect::<Vec<_>>(),
+            [&"https://relay0.example.com".parse::<RelayUrl>().unwrap()]
+        );
+        assert_eq!(
+            ticket.addr().ip_addrs().collect::<Vec<_>>(),
+            [&"[fe80::1]:1234".parse::<SocketAddr>().unwrap()]
+        );
+        assert_eq!(ticket.addr().addrs.len(), 2);
+
+        let decoded = BlobTicket::decode_bytes(&ticket.encode_bytes()).unwrap();
+        assert_eq!(decoded, ticket);
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(postcard::from_bytes::<BlobTicket>(&bytes).unwrap(), ticket);
+    }
+
+    #[test]
+    fn test_ticket_base32() {
+        let endpoint_id =
+            PublicKey::from_str("ae58ff8833241ac82d6ff7611046ed67b5072d142c588d0063e942d9a75502b6")
+                .unwrap();
+        let hash = HEXLOWER
+            .decode(b"0b84d358e4c8be6c38626b2182ff575818ba6bd3f4b90464994be14cb354a072")
+            .unwrap();
+        let ticket = BlobTicket::new(
+            EndpointAddr::new(endpoint_id),
+            hash.try_into().unwrap(),
+            BlobFormat::Raw,
+        );
+        let base32 = data_encoding::BASE32_NOPAD
+            .decode(
+                ticket
+                    .encode_string()
+                    .strip_prefix("blob")
+                    .unwrap()
+                    .to_ascii_uppercase()
+                    .as_bytes(),
+            )
+            .unwrap();
+        let expected = [
+            // variant
+            "00",
+            // endpoint id, 32 bytes, see above
+            "ae58ff8833241ac82d6ff7611046ed67b5072d142c588d0063e942d9a75502b6",
+            // no relay url
+            "00",
+            // no direct addresses
+            "00",
+            // format: raw
+            "00",
+            // hash, 32 bytes, see above
+            "0b84d358e4c8be6c38626b2182ff575818ba6bd3f4b90464994be14cb354a072",
+        ];
+        let expected = HEXLOWER.decode(expected.concat().as_bytes()).unwrap();
+        assert_eq!(base32, expected);
+    }
+}
@@ -0,0 +1,159 @@
+//! Decoding tickets that borrow from their byte representation.
+//!
+//! [`Ticket::decode_string`](crate::Ticket::decode_string) allocates the byte
+//! representation of every ticket and decodes owned values from it. Services parsing many
+//! tickets can instead implement [`TicketRef`] for a type borrowing from the bytes, and
+//! parse with [`decode_string_ref`], which decodes the payload into a reusable buffer:
+//!
+//! ```
+//! use iroh_tickets::{
+//!     ParseError,
+//!     borrowed::{self, TicketRef},
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Serialize, Deserialize)]
+//! struct RoomTicket<'a> {
+//!     name: &'a str,
+//! }
+//!
+//! impl<'a> TicketRef<'a> for RoomTicket<'a> {
+//!     const KIND: &'static str = "room";
+//!
+//!     fn decode_bytes_ref(bytes: &'a [u8]) -> Result<Self, ParseError> {
+//!         borrowed::decode_postcard(bytes)
+//!     }
+//! }
+//!
+//! let bytes = postcard::to_allocvec(&RoomTicket { name: "lobby" }).unwrap();
+//! let s = iroh_tickets::encode_raw("room", &bytes);
+//!
+//! let mut buf = Vec::new();
+//! let ticket: RoomTicket = borrowed::decode_string_ref(&s, &mut buf).unwrap();
+//! assert_eq!(ticket.name, "lobby");
+//! ```
+
+use alloc::vec::Vec;
+
+use serde::Deserialize;
+
+use crate::{DEFAULT_MAX_LEN, ParseError, assert_valid_kind, e, multibase, strip_kind_of};
+
+/// A ticket type that borrows from its byte representation.
+///
+/// This is the borrowing companion of [`Ticket`](crate::Ticket), see the
+/// [module documentation](self). Types can implement both, with the same kind.
+pub trait TicketRef<'a>: Sized {
+    /// String prefix describing the kind of iroh ticket, see
+    /// [`Ticket::KIND`](crate::Ticket::KIND).
+    const KIND: &'static str;
+
+    /// Former kinds of the ticket, still accepted when decoding, see
+    /// [`Ticket::KIND_ALIASES`](crate::Ticket::KIND_ALIASES).
+    const KIND_ALIASES: &'static [&'static str] = &[];
+
+    /// The maximum length of the byte representation accepted when decoding, see
+    /// [`Ticket::MAX_LEN`](crate::Ticket::MAX_LEN).
+    const MAX_LEN: usize = DEFAULT_MAX_LEN;
+
+    #[doc(hidden)]
+    const VALID_KIND: () = {
+        assert_valid_kind(Self::KIND);
+        let mut i = 0;
+        while i < Self::KIND_ALIASES.len() {
+            assert_valid_kind(Self::KIND_ALIASES[i]);
+            i += 1;
+        }
+    };
+
+    /// Decode a ticket from its byte representation, borrowing from `bytes`.
+    fn decode_bytes_ref(bytes: &'a [u8]) -> Result<Self, ParseError>;
+}
+
+/// Decodes a ticket string into `buf` and decodes the ticket from there.
+///
+/// This accepts the same strings as [`Ticket::decode_string`](crate::Ticket::decode_string).
+/// `buf` is cleared first, reusing it across calls avoids allocating for every ticket.
+pub fn decode_string_ref<'a, T: TicketRef<'a>>(
+    s: &str,
+    buf: &'a mut Vec<u8>,
+) -> Result<T, ParseError> {
+    let () = T::VALID_KIND;
+    let kind_len = T::KIND_ALIASES
+        .iter()
+        .fold(T::KIND.len(), |len, alias| len.max(alias.len()));
+    let max = kind_len + 2 + data_encoding::BASE32_NOPAD.encode_len(T::MAX_LEN);
+    if s.len() > max {
+        return Err(e!(ParseError::TooLong { len: s.len(), max }));
+    }
+    let rest = strip_kind_of(s, T::KIND, T::KIND_ALIASES)?;
+    buf.clear();
+    multibase::decode_payload_into(rest, s.len() - rest.len(), buf)?;
+    if buf.len() > T::MAX_LEN {
+        return Err(e!(ParseError::TooLong {
+            len: buf.len(),
+            max: T::MAX_LEN
+        }));
+    }
+    T::decode_bytes_ref(buf)
+}
+
+/// Decodes postcard data borrowing from `bytes`, rejecting data after the end of the value.
+///
+/// This is [`decode_postcard`](crate::decode_postcard) for types borrowing from the input.
+pub fn decode_postcard<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, ParseError> {
+    let (value, rest) = postcard::take_from_bytes(bytes)?;
+    if !rest.is_empty() {
+        return Err(e!(ParseError::TrailingBytes { len: rest.len() }));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::encode_raw;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct NameTicket<'a> {
+        name: &'a str,
+        data: &'a [u8],
+    }
+
+    impl<'a> TicketRef<'a> for NameTicket<'a> {
+        const KIND: &'static str = "name";
+        const MAX_LEN: usize = 64;
+
+        fn decode_bytes_ref(bytes: &'a [u8]) -> Result<Self, ParseError> {
+            decode_postcard(bytes)
+        }
+    }
+
+    #[test]
+    fn test_decode_string_ref() {
+        let ticket = NameTicket {
+            name: "alice",
+            data: &[1, 2, 3],
+        };
+        let bytes = postcard::to_allocvec(&ticket).unwrap();
+        let s = encode_raw("name", &bytes);
+
+        let mut buf = Vec::new();
+        let decoded: NameTicket = decode_string_ref(&s, &mut buf).unwrap();
+        assert_eq!(decoded, ticket);
+        let decoded: NameTicket = decode_string_ref(&s.to_ascii_uppercase(), &mut buf).unwrap();
+        assert_eq!(decoded, ticket);
+        assert_eq!(buf, bytes);
+
+        let err = decode_string_ref::<NameTicket>(&encode_raw("blob", &bytes), &mut buf);
+        assert!(matches!(err, Err(ParseError::Kind { .. })));
+        let long = postcard::to_allocvec(&(("a".repeat(70)), [0u8; 0])).unwrap();
+        let err = decode_string_ref::<NameTicket>(&encode_raw("name", &long), &mut buf);
+        assert!(matches!(err, Err(ParseError::TooLong { .. })));
+        let trailing = [&bytes[..], &[0]].concat();
+        let err = decode_string_ref::<NameTicket>(&encode_raw("name", &trailing), &mut buf);
+        assert!(matches!(err, Err(ParseError::TrailingBytes { len: 1, .. })));
+    }
+}
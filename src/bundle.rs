@@ -0,0 +1,261 @@
+//! Tickets bundling several other tickets.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AnyTicket, ParseError, Ticket, deserialize_ticket, ensure_len, matches_kind, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket containing several other tickets, possibly of different kinds.
+///
+/// This allows handing out e.g. an endpoint ticket together with a content ticket as a
+/// single string.
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::{
+///     Ticket,
+///     blob::{BlobFormat, BlobTicket},
+///     bundle::BundleTicket,
+///     endpoint::EndpointTicket,
+/// };
+///
+/// let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+/// let mut bundle = BundleTicket::new();
+/// bundle.push(&EndpointTicket::new(addr.clone()));
+/// bundle.push(&BlobTicket::new(addr, [0u8; 32], BlobFormat::Raw));
+///
+/// let parsed: BundleTicket = bundle.to_string().parse().unwrap();
+/// let kinds: Vec<_> = parsed.iter().map(|entry| entry.kind()).collect();
+/// assert_eq!(kinds, ["endpoint", "blob"]);
+/// assert!(parsed.get::<BlobTicket>().unwrap().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleTicket {
+    entries: Vec<BundleEntry>,
+}
+
+/// A single ticket in a [`BundleTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    kind: String,
+    bytes: Vec<u8>,
+}
+
+/// Wire format for [`BundleTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0BundleTicket {
+    entries: Vec<BundleEntry>,
+}
+
+impl Versioned for Variant0BundleTicket {
+    const VERSION: u32 = 0;
+}
+
+impl BundleEntry {
+    /// Creates an entry from a ticket.
+    pub fn new<T: Ticket>(ticket: &T) -> Self {
+        Self {
+            kind: T::KIND.to_string(),
+            bytes: ticket.encode_bytes(),
+        }
+    }
+
+    /// The kind of the contained ticket.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The byte representation of the contained ticket.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Decodes the contained ticket as `T`.
+    ///
+    /// Fails with [`ParseError::Kind`] if the entry is of a different kind.
+    pub fn decode<T: Ticket>(&self) -> Result<T, ParseError> {
+        if !matches_kind::<T>(&self.kind) {
+            return Err(ParseError::wrong_kind(T::KIND, &self.kind));
+        }
+        T::decode_bytes(&self.bytes)
+    }
+
+    /// Decodes the contained ticket as one of the built-in kinds.
+    pub fn decode_any(&self) -> Result<AnyTicket, ParseError> {
+        AnyTicket::decode_bytes(&self.kind, &self.bytes)
+    }
+}
+
+impl BundleTicket {
+    /// Creates an empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a ticket to the bundle.
+    pub fn push<T: Ticket>(&mut self, ticket: &T) {
+        self.entries.push(BundleEntry::new(ticket));
+    }
+
+    /// Adds a ticket to the bundle and returns it, for chaining.
+    pub fn with<T: Ticket>(mut self, ticket: &T) -> Self {
+        self.push(ticket);
+        self
+    }
+
+    /// Returns an iterator over the entries, in the order they were added.
+    pub fn iter(&self) -> std::slice::Iter<'_, BundleEntry> {
+        self.entries.iter()
+    }
+
+    /// Decodes the first entry of kind `T`, if there is one.
+    pub fn get<T: Ticket>(&self) -> Option<Result<T, ParseError>> {
+        self.iter()
+            .find(|entry| entry.kind == T::KIND)
+            .map(BundleEntry::decode)
+    }
+
+    /// The number of tickets in the bundle.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the bundle is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a BundleTicket {
+    type Item = &'a BundleEntry;
+    type IntoIter = std::slice::Iter<'a, BundleEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for BundleTicket {
+    type Item = BundleEntry;
+    type IntoIter = std::vec::IntoIter<BundleEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<BundleEntry> for BundleTicket {
+    fn from_iter<I: IntoIterator<Item = BundleEntry>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Ticket for BundleTicket {
+    const KIND: &'static str = "bundle";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0BundleTicket {
+            entries: self.entries.clone(),
+        })
+    }
+
+    /// Entries keep their order, entries of the built-in kinds use their canonical bytes.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| match entry.decode_any() {
+                Ok(ticket) => BundleEntry {
+                    kind: entry.kind.clone(),
+                    bytes: ticket.canonical_bytes(),
+                },
+                Err(_) => entry.clone(),
+            })
+            .collect();
+        versioned::encode(&Variant0BundleTicket { entries })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0BundleTicket { entries } = versioned::decode(bytes)?;
+        Ok(Self { entries })
+    }
+}
+
+impl std::fmt::Display for BundleTicket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl FromStr for BundleTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl Serialize for BundleTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BundleTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        blob::{BlobFormat, BlobTicket},
+        endpoint::EndpointTicket,
+    };
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[13u8; 32]).public());
+        let endpoint = EndpointTicket::new(addr.clone());
+        let blob = BlobTicket::new(addr, [1u8; 32], BlobFormat::HashSeq);
+        let bundle = BundleTicket::new().with(&endpoint).with(&blob);
+
+        let s = bundle.to_string();
+        assert!(s.starts_with("bundle"));
+        let parsed: BundleTicket = s.parse().unwrap();
+        assert_eq!(parsed, bundle);
+
+        assert_eq!(parsed.get::<EndpointTicket>().unwrap().unwrap(), endpoint);
+        assert_eq!(parsed.get::<BlobTicket>().unwrap().unwrap(), blob);
+        let any: Vec<_> = parsed.iter().map(|e| e.decode_any().unwrap()).collect();
+        assert_eq!(any, [AnyTicket::from(endpoint), AnyTicket::from(blob)]);
+
+        let first = parsed.iter().next().unwrap();
+        assert!(matches!(
+            first.decode::<BlobTicket>(),
+            Err(ParseError::Kind { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_canonical_bytes() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[13u8; 32]).public());
+        let a = EndpointTicket::new(addr.clone()).with_alpns([&b"b"[..], b"a"]);
+        let b = EndpointTicket::new(addr).with_alpns([&b"a"[..], b"b"]);
+        let a = BundleTicket::new().with(&a);
+        let b = BundleTicket::new().with(&b);
+        assert_ne!(a.encode_bytes(), b.encode_bytes());
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+    }
+}
@@ -0,0 +1,220 @@
+//! Tickets that remember their string form.
+//!
+//! A [`CachedTicket`] encodes the wrapped ticket on first use and returns the same string
+//! afterwards, so UIs and logs that display a ticket many times only pay for the encoding
+//! once:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{Ticket, cached::CachedTicket, endpoint::EndpointTicket};
+//!
+//! let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+//! let ticket = CachedTicket::new(EndpointTicket::new(addr));
+//! assert_eq!(ticket.as_str(), ticket.ticket().encode_string());
+//! assert_eq!(ticket.to_string(), ticket.as_str());
+//! ```
+
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EncodeError, Fingerprint, ParseError, Ticket, VersionError};
+
+/// A ticket that caches its [string form](Ticket::encode_string).
+///
+/// This has the same [`KIND`](Ticket::KIND), byte representation and string form as the
+/// wrapped ticket, so it can be used in its place. All methods of [`Ticket`] are forwarded
+/// to the wrapped ticket, and the [`TicketExt`](crate::TicketExt) helpers are built on
+/// them. The wrapped ticket can not be modified,
+/// as that would invalidate the cached string.
+#[derive(Debug, Clone)]
+pub struct CachedTicket<T> {
+    ticket: T,
+    string: OnceLock<String>,
+}
+
+impl<T> CachedTicket<T> {
+    /// Wraps `ticket`, the string form is encoded when it is first needed.
+    pub fn new(ticket: T) -> Self {
+        Self {
+            ticket,
+            string: OnceLock::new(),
+        }
+    }
+
+    /// The wrapped ticket.
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the wrapped ticket.
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+}
+
+impl<T: Ticket> CachedTicket<T> {
+    /// The string form of the wrapped ticket, encoded on the first call.
+    pub fn as_str(&self) -> &str {
+        self.string.get_or_init(|| self.ticket.encode_string())
+    }
+}
+
+impl<T> From<T> for CachedTicket<T> {
+    fn from(ticket: T) -> Self {
+        Self::new(ticket)
+    }
+}
+
+impl<T: PartialEq> PartialEq for CachedTicket<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ticket == other.ticket
+    }
+}
+
+impl<T: Eq> Eq for CachedTicket<T> {}
+
+impl<T: Ticket> Ticket for CachedTicket<T> {
+    const KIND: &'static str = T::KIND;
+    const KIND_ALIASES: &'static [&'static str] = T::KIND_ALIASES;
+    const MAX_LEN: usize = T::MAX_LEN;
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.ticket.encode_bytes()
+    }
+
+    fn canonical_bytes(&self) -> Vec<u8> {
+        self.ticket.canonical_bytes()
+    }
+
+    fn try_encode_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        self.ticket.try_encode_bytes()
+    }
+
+    fn encode_bytes_into(&self, out: &mut Vec<u8>) {
+        self.ticket.encode_bytes_into(out)
+    }
+
+    fn to_bytes_versioned(&self, max_version: u32) -> Result<Vec<u8>, VersionError> {
+        self.ticket.to_bytes_versioned(max_version)
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        T::decode_bytes(bytes).map(Self::new)
+    }
+
+    /// Returns a copy of the cached string.
+    fn encode_string(&self) -> String {
+        self.as_str().to_string()
+    }
+
+    fn write_to(&self, out: &mut String) {
+        out.push_str(self.as_str());
+    }
+
+    /// Decodes the wrapped ticket, the string is not cached as it may not be canonical.
+    fn decode_string(s: &str) -> Result<Self, ParseError> {
+        T::decode_string(s).map(Self::new)
+    }
+
+    fn canonicalize(s: &str) -> Result<String, ParseError> {
+        T::canonicalize(s)
+    }
+
+    fn decode_string_lenient(s: &str) -> Result<Self, ParseError> {
+        T::decode_string_lenient(s).map(Self::new)
+    }
+
+    fn fingerprint(&self) -> Fingerprint {
+        self.ticket.fingerprint()
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for CachedTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<T: Ticket> FromStr for CachedTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+/// Serializes like the wrapped ticket, using the cached string in human readable formats.
+impl<T: Ticket + Serialize> Serialize for CachedTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.as_str())
+        } else {
+            self.ticket.serialize(serializer)
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CachedTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_cached_ticket() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[21u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let inner = EndpointTicket::new(addr);
+        let ticket = CachedTicket::new(inner.clone());
+        assert!(ticket.string.get().is_none());
+        assert_eq!(ticket.to_string(), inner.encode_string());
+        assert!(std::ptr::eq(ticket.as_str(), ticket.as_str()));
+        assert_eq!(ticket.encode_bytes(), inner.encode_bytes());
+
+        let s = inner.encode_string();
+        let decoded =
+            CachedTicket::<EndpointTicket>::decode_string(&s.to_ascii_uppercase()).unwrap();
+        assert_eq!(decoded, ticket);
+        assert_eq!(decoded.as_str(), s);
+        assert_eq!(serde_json::to_value(&ticket).unwrap(), s);
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(bytes, postcard::to_stdvec(&inner).unwrap());
+        assert_eq!(
+            postcard::from_bytes::<CachedTicket<EndpointTicket>>(&bytes).unwrap(),
+            ticket
+        );
+    }
+
+    #[test]
+    fn test_cached_ticket_forwards() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[21u8; 32]).public());
+        let inner = EndpointTicket::new(addr).with_alpns([b"alpn".to_vec()]);
+        let ticket = CachedTicket::new(inner.clone());
+        assert!(ticket.to_bytes_versioned(0).is_err());
+        assert_eq!(
+            ticket.to_bytes_versioned(u32::MAX).unwrap(),
+            inner.to_bytes_versioned(u32::MAX).unwrap()
+        );
+        let mut out = Vec::new();
+        ticket.encode_bytes_into(&mut out);
+        assert_eq!(out, inner.encode_bytes());
+        assert_eq!(ticket.fingerprint(), inner.fingerprint());
+        assert_eq!(ticket.revocation_id(), inner.revocation_id());
+        assert_eq!(
+            CachedTicket::<EndpointTicket>::from_armored(&ticket.to_armored()).unwrap(),
+            ticket
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! A C API for parsing and serializing tickets.
+//!
+//! This exports `extern "C"` functions for C, C++ and other languages with a C FFI. Build
+//! the shared library with
+//!
+//! ```sh
+//! cargo rustc --release --features capi --crate-type cdylib
+//! ```
+//!
+//! and declare the functions with the header at `include/iroh_tickets.h`. Tickets are
+//! passed as opaque [`IrohTicket`] pointers, all functions return an [`IrohTicketError`].
+//!
+//! Strings returned by the library must be freed with [`iroh_ticket_string_free`] and
+//! tickets with [`iroh_ticket_free`].
+
+use std::ffi::{CStr, CString, c_char};
+
+use crate::{AnyTicket, ParseError, Ticket, endpoint::EndpointTicket};
+
+/// The result of a C API function.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrohTicketError {
+    /// The function succeeded.
+    Ok = 0,
+    /// A pointer argument is null.
+    NullPointer = 1,
+    /// A string argument is not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The string is not a ticket of the expected kind.
+    WrongKind = 3,
+    /// The payload of the ticket string has an invalid encoding.
+    InvalidEncoding = 4,
+    /// The ticket is longer than allowed.
+    TooLong = 5,
+    /// The checksum of the ticket does not match.
+    Checksum = 6,
+    /// The ticket data is invalid.
+    InvalidTicket = 7,
+}
+
+impl From<&ParseError> for IrohTicketError {
+    fn from(err: &ParseError) -> Self {
+        match err {
+            ParseError::Kind { .. } | ParseError::UnknownKind { .. } => Self::WrongKind,
+            ParseError::InvalidCharacter { .. }
+            | ParseError::Encoding { .. }
+            | ParseError::UnknownEncoding { .. } => Self::InvalidEncoding,
+            ParseError::TooLong { .. } => Self::TooLong,
+            ParseError::Checksum { .. } => Self::Checksum,
+            _ => Self::InvalidTicket,
+        }
+    }
+}
+
+/// A parsed ticket of any of the built-in kinds.
+#[derive(Debug)]
+pub struct IrohTicket(AnyTicket);
+
+/// Reads a string argument.
+///
+/// # Safety
+///
+/// `s` must be null or point to a nul-terminated string.
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, IrohTicketError> {
+    if s.is_null() {
+        return Err(IrohTicketError::NullPointer);
+    }
+    // SAFETY: `s` is a nul-terminated string, as required by the caller.
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str().map_err(|_| IrohTicketError::InvalidUtf8)
+}
+
+/// Writes `value` to the output argument `out`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn write_out<T>(out: *mut T, value: T) -> IrohTicketError {
+    if out.is_null() {
+        return IrohTicketError::NullPointer;
+    }
+    // SAFETY: `out` is valid for writes, as required by the caller.
+    unsafe { out.write(value) };
+    IrohTicketError::Ok
+}
+
+/// Parses a ticket with `parse` and writes it to `out`.
+///
+/// # Safety
+///
+/// See [`read_str`] and [`write_out`].
+unsafe fn parse_into(
+    s: *const c_char,
+    out: *mut *mut IrohTicket,
+    parse: impl FnOnce(&str) -> Result<AnyTicket, ParseError>,
+) -> IrohTicketError {
+    if out.is_null() {
+        return IrohTicketError::NullPointer;
+    }
+    // SAFETY: forwarded from the caller.
+    let s = match unsafe { read_str(s) } {
+        Ok(s) => s,
+        Err(err) => return err,
+    };
+    match parse(s) {
+        Ok(ticket) => {
+            let ticket = Box::into_raw(Box::new(IrohTicket(ticket)));
+            // SAFETY: forwarded from the caller.
+            unsafe { write_out(out, ticket) }
+        }
+        Err(err) => IrohTicketError::from(&err),
+    }
+}
+
+/// Writes a copy of `s` to `out`, to be freed with [`iroh_ticket_string_free`].
+///
+/// # Safety
+///
+/// See [`write_out`].
+unsafe fn write_string(out: *mut *mut c_char, s: &str) -> IrohTicketError {
+    if out.is_null() {
+        return IrohTicketError::NullPointer;
+    }
+    let s = CString::new(s).expect("ticket strings do not contain nul bytes");
+    // SAFETY: forwarded from the caller.
+    unsafe { write_out(out, s.into_raw()) }
+}
+
+/// Parses an endpoint ticket from its string form.
+///
+/// On success, writes the ticket to `out`, which must be freed with [`iroh_ticket_free`].
+///
+/// # Safety
+///
+/// `s` must be a nul-terminated string and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_parse_endpoint(
+    s: *const c_char,
+    out: *mut *mut IrohTicket,
+) -> IrohTicketError {
+    // SAFETY: forwarded from the caller.
+    unsafe {
+        parse_into(s, out, |s| {
+            EndpointTicket::decode_string(s).map(AnyTicket::Endpoint)
+        })
+    }
+}
+
+/// Parses a ticket of any of the built-in kinds from its string form.
+///
+/// On success, writes the ticket to `out`, which must be freed with [`iroh_ticket_free`].
+///
+/// # Safety
+///
+/// `s` must be a nul-terminated string and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_parse(
+    s: *const c_char,
+    out: *mut *mut IrohTicket,
+) -> IrohTicketError {
+    // SAFETY: forwarded from the caller.
+    unsafe { parse_into(s, out, AnyTicket::decode_string) }
+}
+
+/// Serializes a ticket into its string form.
+///
+/// On success, writes the string to `out`, which must be freed with
+/// [`iroh_ticket_string_free`].
+///
+/// # Safety
+///
+/// `ticket` must be a ticket returned by this library that has not been freed, and `out`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_serialize(
+    ticket: *const IrohTicket,
+    out: *mut *mut c_char,
+) -> IrohTicketError {
+    // SAFETY: `ticket` is null or a valid ticket, as required by the caller.
+    let Some(ticket) = (unsafe { ticket.as_ref() }) else {
+        return IrohTicketError::NullPointer;
+    };
+    // SAFETY: forwarded from the caller.
+    unsafe { write_string(out, &ticket.0.encode_string()) }
+}
+
+/// Returns the kind of a ticket as a static nul-terminated string, e.g. `endpoint`.
+///
+/// Returns null if `ticket` is null. The string must not be freed.
+///
+/// # Safety
+///
+/// `ticket` must be null or a ticket returned by this library that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_kind(ticket: *const IrohTicket) -> *const c_char {
+    // SAFETY: `ticket` is null or a valid ticket, as required by the caller.
+    let Some(ticket) = (unsafe { ticket.as_ref() }) else {
+        return std::ptr::null();
+    };
+    let kind: &CStr = match ticket.0 {
+        AnyTicket::Endpoint(_) => c"endpoint",
+        AnyTicket::Blob(_) => c"blob",
+        AnyTicket::Gossip(_) => c"topic",
+        AnyTicket::MultiEndpoint(_) => c"multiendpoint",
+        AnyTicket::Bundle(_) => c"bundle",
+        AnyTicket::Relay(_) => c"relay",
+        AnyTicket::Dns(_) => c"dns",
+        AnyTicket::Invite(_) => c"invite",
+    };
+    debug_assert_eq!(kind.to_str(), Ok(ticket.0.kind()));
+    kind.as_ptr()
+}
+
+/// Returns a static nul-terminated description of an error code.
+#[unsafe(no_mangle)]
+pub extern "C" fn iroh_ticket_error_message(err: IrohTicketError) -> *const c_char {
+    let message = match err {
+        IrohTicketError::Ok => c"success",
+        IrohTicketError::NullPointer => c"null pointer argument",
+        IrohTicketError::InvalidUtf8 => c"string is not valid UTF-8",
+        IrohTicketError::WrongKind => c"wrong ticket kind",
+        IrohTicketError::InvalidEncoding => c"invalid ticket encoding",
+        IrohTicketError::TooLong => c"ticket is too long",
+        IrohTicketError::Checksum => c"checksum mismatch",
+        IrohTicketError::InvalidTicket => c"invalid ticket",
+    };
+    message.as_ptr()
+}
+
+/// Frees a ticket returned by this library. Does nothing if `ticket` is null.
+///
+/// # Safety
+///
+/// `ticket` must be null or a ticket returned by this library that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_free(ticket: *mut IrohTicket) {
+    if !ticket.is_null() {
+        // SAFETY: `ticket` was created with `Box::into_raw` and is not used afterwards.
+        drop(unsafe { Box::from_raw(ticket) });
+    }
+}
+
+/// Frees a string returned by this library. Does nothing if `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iroh_ticket_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: `s` was created with `CString::into_raw` and is not used afterwards.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::blob::{BlobFormat, BlobTicket};
+
+    #[test]
+    fn test_capi_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[32u8; 32]).public());
+        let ticket = EndpointTicket::new(addr.clone());
+        let s = CString::new(ticket.to_string()).unwrap();
+
+        let mut parsed = ptr::null_mut();
+        let err = unsafe { iroh_ticket_parse_endpoint(s.as_ptr(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::Ok);
+        let kind = unsafe { CStr::from_ptr(iroh_ticket_kind(parsed)) };
+        assert_eq!(kind.to_str().unwrap(), "endpoint");
+
+        let mut out = ptr::null_mut();
+        let err = unsafe { iroh_ticket_serialize(parsed, &mut out) };
+        assert_eq!(err, IrohTicketError::Ok);
+        assert_eq!(unsafe { CStr::from_ptr(out) }, s.as_c_str());
+        unsafe {
+            iroh_ticket_string_free(out);
+            iroh_ticket_free(parsed);
+        }
+
+        let blob = BlobTicket::new(addr, [0u8; 32], BlobFormat::Raw);
+        let s = CString::new(blob.to_string()).unwrap();
+        let mut parsed = ptr::null_mut();
+        let err = unsafe { iroh_ticket_parse_endpoint(s.as_ptr(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::WrongKind);
+        assert!(parsed.is_null());
+        let err = unsafe { iroh_ticket_parse(s.as_ptr(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::Ok);
+        let kind = unsafe { CStr::from_ptr(iroh_ticket_kind(parsed)) };
+        assert_eq!(kind.to_str().unwrap(), "blob");
+        unsafe { iroh_ticket_free(parsed) };
+    }
+
+    #[test]
+    fn test_capi_errors() {
+        let mut parsed = ptr::null_mut();
+        let err = unsafe { iroh_ticket_parse(ptr::null(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::NullPointer);
+        let err = unsafe { iroh_ticket_parse(c"endpoint0".as_ptr(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::InvalidEncoding);
+        let invalid = [0xffu8, 0];
+        let err = unsafe { iroh_ticket_parse(invalid.as_ptr().cast(), &mut parsed) };
+        assert_eq!(err, IrohTicketError::InvalidUtf8);
+        let err = unsafe { iroh_ticket_parse(c"endpoint".as_ptr(), ptr::null_mut()) };
+        assert_eq!(err, IrohTicketError::NullPointer);
+        let err = unsafe { iroh_ticket_serialize(ptr::null(), ptr::null_mut()) };
+        assert_eq!(err, IrohTicketError::NullPointer);
+
+        let message =
+            unsafe { CStr::from_ptr(iroh_ticket_error_message(IrohTicketError::Checksum)) };
+        assert_eq!(message.to_str().unwrap(), "checksum mismatch");
+    }
+}
@@ -0,0 +1,164 @@
+//! Parsing tickets from command line arguments with [clap].
+//!
+//! The built-in ticket types and [`AnyTicket`] implement [`ValueParserFactory`], so they can
+//! be used as argument types directly. Tickets are parsed with
+//! [`decode_string_lenient`](Ticket::decode_string_lenient), as they are usually pasted
+//! into a terminal, and invalid tickets are reported with the [`ParseError`]:
+//!
+//! ```
+//! use clap::Parser;
+//! use iroh_tickets::endpoint::EndpointTicket;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Args {
+//!     #[arg(long)]
+//!     ticket: EndpointTicket,
+//! }
+//!
+//! let err = Args::try_parse_from(["join", "--ticket", "blobaaaa"]).unwrap_err();
+//! assert!(
+//!     err.to_string()
+//!         .contains("invalid value 'blobaaaa' for '--ticket <TICKET>'")
+//! );
+//! ```
+//!
+//! For other ticket types use a [`TicketValueParser`]:
+//!
+//! ```
+//! # use iroh_tickets::endpoint::EndpointTicket as MyTicket;
+//! use clap::Parser;
+//! use iroh_tickets::clap::TicketValueParser;
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     #[arg(long, value_parser = TicketValueParser::<MyTicket>::new())]
+//!     ticket: MyTicket,
+//! }
+//! ```
+//!
+//! [clap]: https://docs.rs/clap
+
+use std::{ffi::OsStr, fmt};
+
+use clap::builder::{TypedValueParser, ValueParserFactory};
+
+use crate::{
+    AnyTicket, ParseError, Ticket, blob::BlobTicket, bundle::BundleTicket,
+    endpoint::EndpointTicket, gossip::GossipTicket, multi::MultiEndpointTicket,
+};
+
+/// A clap value parser for tickets of type `T`.
+pub struct TicketValueParser<T> {
+    parse: fn(&str) -> Result<T, ParseError>,
+}
+
+impl<T: Ticket> TicketValueParser<T> {
+    /// Creates a parser using [`Ticket::decode_string_lenient`].
+    pub fn new() -> Self {
+        Self {
+            parse: T::decode_string_lenient,
+        }
+    }
+}
+
+impl<T: Ticket> Default for TicketValueParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for TicketValueParser<T> {
+    fn clone(&self) -> Self {
+        Self { parse: self.parse }
+    }
+}
+
+impl<T> fmt::Debug for TicketValueParser<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TicketValueParser").finish_non_exhaustive()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> TypedValueParser for TicketValueParser<T> {
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<T, clap::Error> {
+        // Functions are value parsers that report errors with the argument and value.
+        self.parse.parse_ref(cmd, arg, value)
+    }
+}
+
+macro_rules! impl_value_parser_factory {
+    ($($ty:ty),*) => {
+        $(
+            impl ValueParserFactory for $ty {
+                type Parser = TicketValueParser<Self>;
+
+                fn value_parser() -> Self::Parser {
+                    TicketValueParser::new()
+                }
+            }
+        )*
+    };
+}
+
+impl_value_parser_factory!(
+    EndpointTicket,
+    BlobTicket,
+    GossipTicket,
+    MultiEndpointTicket,
+    BundleTicket
+);
+
+impl ValueParserFactory for AnyTicket {
+    type Parser = TicketValueParser<Self>;
+
+    fn value_parser() -> Self::Parser {
+        TicketValueParser {
+            parse: AnyTicket::decode_string_lenient,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+
+    #[derive(Debug, Parser)]
+    struct Args {
+        #[arg(long)]
+        ticket: EndpointTicket,
+        #[arg(long)]
+        any: Option<AnyTicket>,
+    }
+
+    #[test]
+    fn test_value_parser() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[36u8; 32]).public(),
+        ));
+        let s = ticket.to_string();
+        let quoted = format!("`{s}`");
+        let args = Args::try_parse_from(["test", "--ticket", &quoted, "--any", &s]).unwrap();
+        assert_eq!(args.ticket, ticket);
+        assert_eq!(args.any, Some(AnyTicket::Endpoint(ticket)));
+
+        let err = Args::try_parse_from(["test", "--ticket", "endpoint0"]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        let err = err.to_string();
+        assert!(
+            err.contains("invalid value 'endpoint0' for '--ticket <TICKET>'"),
+            "{err}"
+        );
+        let parse_err = EndpointTicket::decode_string("endpoint0").unwrap_err();
+        assert!(err.contains(&parse_err.to_string()), "{err}");
+    }
+}
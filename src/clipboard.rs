@@ -0,0 +1,61 @@
+//! Copying tickets to and pasting them from the system clipboard with [arboard].
+//!
+//! Sharing a ticket on the desktop mostly means copying it in one application and pasting
+//! it into another. [`TicketExt::copy_to_clipboard`] puts the string form of a ticket into the
+//! clipboard, [`TicketExt::paste_from_clipboard`] reads it back with
+//! [`decode_string_lenient`](Ticket::decode_string_lenient), so quotes and line breaks
+//! picked up on the way are ignored:
+//!
+//! ```no_run
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! ticket.copy_to_clipboard()?;
+//! assert_eq!(EndpointTicket::paste_from_clipboard()?, ticket);
+//! # Ok::<_, iroh_tickets::clipboard::ClipboardError>(())
+//! ```
+//!
+//! On Linux, the clipboard is owned by the process that copied to it. When the process
+//! exits, the contents are only kept if a clipboard manager is running.
+//!
+//! [arboard]: https://docs.rs/arboard
+//! [`TicketExt::copy_to_clipboard`]: crate::TicketExt::copy_to_clipboard
+//! [`TicketExt::paste_from_clipboard`]: crate::TicketExt::paste_from_clipboard
+
+use n0_error::stack_error;
+
+use crate::{ParseError, Ticket};
+
+/// Copying to or pasting from the clipboard failed.
+#[stack_error(derive, add_meta)]
+#[non_exhaustive]
+pub enum ClipboardError {
+    /// The clipboard is not available or does not contain text.
+    #[error(transparent)]
+    Clipboard {
+        #[error(source, from, std_err)]
+        source: arboard::Error,
+    },
+    /// The text in the clipboard is not a valid ticket.
+    #[error(transparent)]
+    Parse {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+}
+
+/// Puts the string form of `ticket` into the clipboard.
+pub fn copy<T: Ticket>(ticket: &T) -> Result<(), ClipboardError> {
+    arboard::Clipboard::new()?.set_text(ticket.encode_string())?;
+    Ok(())
+}
+
+/// Decodes a ticket from the text in the clipboard, see
+/// [`decode_string_lenient`](Ticket::decode_string_lenient).
+pub fn paste<T: Ticket>() -> Result<T, ClipboardError> {
+    let text = arboard::Clipboard::new()?.get_text()?;
+    Ok(T::decode_string_lenient(&text)?)
+}
@@ -0,0 +1,189 @@
+//! Pluggable serialization of ticket payloads.
+//!
+//! The byte representation of a ticket is usually written with [postcard], see
+//! [`Postcard`]. A [`WireCodec`] abstracts over the serialization, so a ticket type can use
+//! another format for its payload while keeping the string form, the kind prefix followed
+//! by base32 of the bytes. With the `cbor` feature [`Cbor`] writes payloads as CBOR, which
+//! standard tools can decode.
+//!
+//! [`versioned::encode_with`](crate::versioned::encode_with) and
+//! [`versioned::decode_with`](crate::versioned::decode_with) write versioned wire formats
+//! with a codec:
+//!
+//! ```
+//! # #[cfg(feature = "cbor")] {
+//! use iroh_tickets::{
+//!     ParseError, Ticket,
+//!     codec::Cbor,
+//!     versioned::{self, Versioned},
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct RoomTicket {
+//!     room: String,
+//! }
+//!
+//! impl Versioned for RoomTicket {
+//!     const VERSION: u32 = 0;
+//! }
+//!
+//! impl Ticket for RoomTicket {
+//!     const KIND: &'static str = "room";
+//!
+//!     fn encode_bytes(&self) -> Vec<u8> {
+//!         versioned::encode_with::<Cbor, _>(self)
+//!     }
+//!
+//!     fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+//!         versioned::decode_with::<Cbor, _>(bytes)
+//!     }
+//! }
+//!
+//! let ticket = RoomTicket {
+//!     room: "lobby".into(),
+//! };
+//! // A CBOR sequence of the version and a map with the fields.
+//! assert_eq!(&ticket.encode_bytes()[..2], &[0x00, 0xa1]);
+//! assert_eq!(
+//!     RoomTicket::decode_string(&ticket.encode_string()).unwrap(),
+//!     ticket
+//! );
+//! # }
+//! ```
+//!
+//! [postcard]: https://docs.rs/postcard/latest/postcard/
+
+use alloc::vec::Vec;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{ParseError, e};
+
+/// A serialization format for ticket payloads.
+///
+/// Values are written back to back without framing, so a codec must be able to tell where
+/// a value ends, see [`take_from_bytes`](Self::take_from_bytes).
+pub trait WireCodec {
+    /// Appends the encoding of `value` to `out`.
+    ///
+    /// # Panics
+    ///
+    /// If serialization fails, which does not happen for plain structs and enums.
+    fn encode_into<T: Serialize + ?Sized>(value: &T, out: &mut Vec<u8>);
+
+    /// Decodes a value from the start of `bytes`, returning the remaining bytes.
+    fn take_from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<(T, &[u8]), ParseError>;
+
+    /// Encodes `value`, see [`encode_into`](Self::encode_into).
+    fn encode<T: Serialize + ?Sized>(value: &T) -> Vec<u8> {
+        let mut out = Vec::new();
+        Self::encode_into(value, &mut out);
+        out
+    }
+
+    /// Decodes a value, rejecting data after its end with [`ParseError::TrailingBytes`].
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ParseError> {
+        let (value, rest) = Self::take_from_bytes(bytes)?;
+        if !rest.is_empty() {
+            return Err(e!(ParseError::TrailingBytes { len: rest.len() }));
+        }
+        Ok(value)
+    }
+}
+
+/// The [postcard] codec, used by all tickets in this crate.
+///
+/// [postcard]: https://docs.rs/postcard/latest/postcard/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Postcard;
+
+impl WireCodec for Postcard {
+    fn encode_into<T: Serialize + ?Sized>(value: &T, out: &mut Vec<u8>) {
+        *out = postcard::to_extend(value, core::mem::take(out))
+            .expect("postcard serialization failed");
+    }
+
+    fn take_from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<(T, &[u8]), ParseError> {
+        Ok(postcard::take_from_bytes(bytes)?)
+    }
+}
+
+/// The [CBOR] codec, values are written as a CBOR sequence.
+///
+/// [CBOR]: https://www.rfc-editor.org/rfc/rfc8949
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl WireCodec for Cbor {
+    fn encode_into<T: Serialize + ?Sized>(value: &T, out: &mut Vec<u8>) {
+        ciborium::into_writer(value, out).expect("cbor serialization failed");
+    }
+
+    fn take_from_bytes<T: DeserializeOwned>(mut bytes: &[u8]) -> Result<(T, &[u8]), ParseError> {
+        let value = ciborium::from_reader(&mut bytes)?;
+        Ok((value, bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::versioned::{self, Versioned};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V0 {
+        name: String,
+        port: u16,
+    }
+
+    impl Versioned for V0 {
+        const VERSION: u32 = 0;
+    }
+
+    fn value() -> V0 {
+        V0 {
+            name: "a".into(),
+            port: 443,
+        }
+    }
+
+    #[test]
+    fn test_postcard() {
+        let bytes = Postcard::encode(&value());
+        assert_eq!(bytes, postcard::to_allocvec(&value()).unwrap());
+        assert_eq!(Postcard::decode::<V0>(&bytes).unwrap(), value());
+        assert_eq!(
+            versioned::encode_with::<Postcard, _>(&value()),
+            versioned::encode(&value())
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor() {
+        let bytes = versioned::encode_with::<Cbor, _>(&value());
+        // 0, {"name": "a", "port": 443}
+        let expected = [
+            &[0x00, 0xa2, 0x64][..],
+            b"name",
+            &[0x61, b'a', 0x64],
+            b"port",
+            &[0x19, 0x01, 0xbb],
+        ]
+        .concat();
+        assert_eq!(bytes, expected);
+        assert_eq!(versioned::decode_with::<Cbor, V0>(&bytes).unwrap(), value());
+
+        let err = versioned::decode_with::<Cbor, V0>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }), "{err:?}");
+        let err = versioned::decode_with::<Cbor, V0>(&[&bytes[..], &[0]].concat()).unwrap_err();
+        assert!(matches!(err, ParseError::TrailingBytes { len: 1, .. }));
+        let err = Cbor::decode::<V0>(&[0x00]).unwrap_err();
+        assert!(matches!(err, ParseError::Cbor { .. }), "{err:?}");
+    }
+}
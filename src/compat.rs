@@ -0,0 +1,244 @@
+//! Tickets of older iroh releases.
+//!
+//! Before iroh called them endpoints, it called them nodes, and its tickets for them start
+//! with `node`. A [`NodeTicket`] reads and writes these strings, so applications can keep
+//! accepting tickets handed out by older versions and still hand out tickets they can read:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{Ticket, compat, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(
+//!     EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public())
+//!         .with_ip_addr("198.51.100.1:1234".parse().unwrap()),
+//! );
+//! let legacy = compat::NodeTicket::from(ticket.clone()).to_string();
+//! assert!(legacy.starts_with("node"));
+//!
+//! // Both the old and the new format are accepted.
+//! assert_eq!(compat::decode_endpoint_ticket(&legacy).unwrap(), ticket);
+//! assert_eq!(
+//!     compat::decode_endpoint_ticket(&ticket.to_string()).unwrap(),
+//!     ticket
+//! );
+//! ```
+//!
+//! The old format can hold at most one relay URL and only IP addresses, so converting an
+//! [`EndpointTicket`] into a [`NodeTicket`] drops everything else, see [`NodeTicket::new`].
+
+use std::{collections::BTreeSet, net::SocketAddr, str::FromStr};
+
+use iroh_base::{EndpointAddr, EndpointId, RelayUrl, TransportAddr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket, deserialize_ticket,
+    endpoint::EndpointTicket,
+    ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket for a node, in the format of older iroh releases.
+///
+/// Contains the [`EndpointId`] of the node, at most one relay URL and any number of IP
+/// addresses. Convert it into an [`EndpointTicket`] to use it with current iroh releases.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct NodeTicket {
+    addr: EndpointAddr,
+}
+
+/// Wire format for [`NodeTicket`], as written by older iroh releases.
+#[derive(Serialize, Deserialize)]
+struct Variant0NodeTicket {
+    node: Variant0NodeAddr,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Variant0NodeAddr {
+    node_id: EndpointId,
+    info: Variant0AddrInfo,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Variant0AddrInfo {
+    relay_url: Option<RelayUrl>,
+    direct_addresses: BTreeSet<SocketAddr>,
+}
+
+impl Versioned for Variant0NodeTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for NodeTicket {
+    const KIND: &'static str = "node";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0NodeTicket {
+            node: Variant0NodeAddr {
+                node_id: self.addr.id,
+                info: Variant0AddrInfo {
+                    relay_url: self.relay_url().cloned(),
+                    direct_addresses: self.addr.ip_addrs().copied().collect(),
+                },
+            },
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0NodeTicket { node } = versioned::decode(bytes)?;
+        let addrs = node
+            .info
+            .relay_url
+            .map(TransportAddr::Relay)
+            .into_iter()
+            .chain(
+                node.info
+                    .direct_addresses
+                    .into_iter()
+                    .map(TransportAddr::Ip),
+            );
+        Ok(Self {
+            addr: EndpointAddr::from_parts(node.node_id, addrs),
+        })
+    }
+}
+
+impl NodeTicket {
+    /// Creates a ticket for `addr`, keeping only what the old format can hold.
+    ///
+    /// This keeps the first relay URL and all IP addresses of `addr`, other relay URLs and
+    /// custom addresses are dropped.
+    pub fn new(addr: EndpointAddr) -> Self {
+        let relay = addr.relay_urls().next().cloned().map(TransportAddr::Relay);
+        let ips = addr.ip_addrs().copied().map(TransportAddr::Ip);
+        Self {
+            addr: EndpointAddr::from_parts(
+                addr.id,
+                relay.into_iter().chain(ips).collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    /// The address of the node.
+    pub fn endpoint_addr(&self) -> &EndpointAddr {
+        &self.addr
+    }
+
+    /// The relay URL of the node, if any.
+    pub fn relay_url(&self) -> Option<&RelayUrl> {
+        self.addr.relay_urls().next()
+    }
+}
+
+impl From<NodeTicket> for EndpointTicket {
+    fn from(ticket: NodeTicket) -> Self {
+        EndpointTicket::new(ticket.addr)
+    }
+}
+
+/// Converts into the old format, dropping the ALPNs of the ticket and anything else the old
+/// format can not hold, see [`NodeTicket::new`].
+impl From<EndpointTicket> for NodeTicket {
+    fn from(ticket: EndpointTicket) -> Self {
+        NodeTicket::new(ticket.endpoint_addr().clone())
+    }
+}
+
+impl FromStr for NodeTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl Serialize for NodeTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+/// Decodes an [`EndpointTicket`] from either its own string form or that of a
+/// [`NodeTicket`].
+///
+/// For other strings, the error of parsing them as an [`EndpointTicket`] is returned.
+pub fn decode_endpoint_ticket(s: &str) -> Result<EndpointTicket, ParseError> {
+    match EndpointTicket::decode_string(s) {
+        Ok(ticket) => Ok(ticket),
+        Err(err) => match NodeTicket::decode_string(s) {
+            Ok(ticket) => Ok(ticket.into()),
+            Err(_) => Err(err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{CustomAddr, SecretKey};
+
+    use super::*;
+
+    fn addr() -> EndpointAddr {
+        EndpointAddr::new(SecretKey::from_bytes(&[3u8; 32]).public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap())
+    }
+
+    #[test]
+    fn test_node_ticket_wire_format() {
+        let ticket = NodeTicket::new(addr());
+        let id = addr().id;
+        // The format of older iroh releases: the variant, the node id, the optional relay
+        // URL and the direct addresses.
+        let mut expected = vec![0];
+        expected.extend_from_slice(id.as_bytes());
+        expected.push(1);
+        let url = b"https://relay.example./";
+        expected.push(url.len() as u8);
+        expected.extend_from_slice(url);
+        expected.extend_from_slice(&[1, 0, 198, 51, 100, 1, 0xd2, 0x09]);
+        assert_eq!(ticket.encode_bytes(), expected);
+        assert_eq!(NodeTicket::decode_bytes(&expected).unwrap(), ticket);
+
+        let s = ticket.to_string();
+        assert!(s.starts_with("node"));
+        assert_eq!(s.parse::<NodeTicket>().unwrap(), ticket);
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(serde_json::from_str::<NodeTicket>(&json).unwrap(), ticket);
+    }
+
+    #[test]
+    fn test_node_ticket_conversion() {
+        let full = addr()
+            .with_relay_url("https://other.example./".parse().unwrap())
+            .with_addrs([TransportAddr::Custom(CustomAddr::from_parts(1, b"x"))]);
+        let ticket = EndpointTicket::new(full).with_alpns([b"alpn".to_vec()]);
+        let legacy = NodeTicket::from(ticket.clone());
+        assert_eq!(
+            legacy.relay_url(),
+            Some(&"https://other.example./".parse().unwrap())
+        );
+        assert_eq!(legacy.endpoint_addr().addrs.len(), 2);
+
+        let converted = EndpointTicket::from(legacy.clone());
+        assert!(converted.alpns().is_empty());
+        assert_eq!(converted.endpoint_addr(), legacy.endpoint_addr());
+        assert_eq!(
+            decode_endpoint_ticket(&legacy.to_string()).unwrap(),
+            converted
+        );
+        assert_eq!(decode_endpoint_ticket(&ticket.to_string()).unwrap(), ticket);
+        assert!(matches!(
+            decode_endpoint_ticket("blobfoo"),
+            Err(ParseError::Kind { .. })
+        ));
+    }
+}
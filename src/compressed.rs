@@ -0,0 +1,291 @@
+//! Compressed tickets.
+//!
+//! A [`CompressedTicket`] wraps another ticket and compresses its byte representation,
+//! which keeps large tickets like [`MultiEndpointTicket`](crate::multi::MultiEndpointTicket)
+//! or [`BundleTicket`](crate::bundle::BundleTicket) within the size limits of QR codes or
+//! text messages.
+//!
+//! The wire format is a flag byte naming the [`Compression`], followed by the compressed
+//! bytes of the inner ticket. Deflate requires the `deflate` feature, zstd the `zstd`
+//! feature.
+
+use std::{fmt, str::FromStr};
+
+use n0_error::e;
+use serde::{Deserialize, Serialize};
+
+use crate::{Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket};
+
+/// A compression algorithm for a [`CompressedTicket`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The inner ticket is stored as is.
+    None,
+    /// Raw deflate, as specified in RFC 1951.
+    #[cfg(feature = "deflate")]
+    Deflate,
+    /// Zstandard, as specified in RFC 8878.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The flag byte written on the wire.
+    fn flag(self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "deflate")]
+            Self::Deflate => 1,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 2,
+        }
+    }
+
+    fn from_flag(flag: u8) -> Option<Self> {
+        match flag {
+            0 => Some(Self::None),
+            #[cfg(feature = "deflate")]
+            1 => Some(Self::Deflate),
+            #[cfg(feature = "zstd")]
+            2 => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    fn compress(self, bytes: Vec<u8>) -> Vec<u8> {
+        match self {
+            Self::None => bytes,
+            #[cfg(feature = "deflate")]
+            Self::Deflate => miniz_oxide::deflate::compress_to_vec(
+                &bytes,
+                miniz_oxide::deflate::CompressionLevel::BestCompression as u8,
+            ),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => ruzstd::encoding::compress_to_vec(
+                bytes.as_slice(),
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ),
+        }
+    }
+
+    /// Decompresses `bytes`, rejecting output longer than `max_len`.
+    fn decompress(self, bytes: &[u8], max_len: usize) -> Result<Vec<u8>, ParseError> {
+        match self {
+            Self::None => {
+                // The length of the inner ticket is checked when decoding it.
+                let _ = max_len;
+                Ok(bytes.to_vec())
+            }
+            #[cfg(feature = "deflate")]
+            Self::Deflate => miniz_oxide::inflate::decompress_to_vec_with_limit(bytes, max_len)
+                .map_err(|_| ParseError::verification_failed("invalid deflate data")),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                use std::io::Read;
+
+                // The window never needs to be larger than the output, this protects
+                // against large allocations for frames declaring a huge window.
+                let max_window = max_len.max(128 * 1024) as u64;
+                let decoder =
+                    ruzstd::decoding::StreamingDecoder::new_with_max_window_size(bytes, max_window)
+                        .map_err(|_| ParseError::verification_failed("invalid zstd data"))?;
+                let mut out = Vec::new();
+                decoder
+                    .take(max_len as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|_| ParseError::verification_failed("invalid zstd data"))?;
+                if out.len() > max_len {
+                    return Err(ParseError::verification_failed(
+                        "decompressed ticket is too long",
+                    ));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// A ticket whose byte representation is compressed.
+///
+/// All compressed tickets share the [`KIND`](Ticket::KIND) `"compressed"`, regardless of
+/// the type of the inner ticket. The decompressed inner ticket is limited to the
+/// [`MAX_LEN`](Ticket::MAX_LEN) of its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedTicket<T> {
+    ticket: T,
+    compression: Compression,
+}
+
+impl<T> CompressedTicket<T> {
+    /// Creates a ticket that compresses `ticket` with the given algorithm.
+    pub fn new(ticket: T, compression: Compression) -> Self {
+        Self {
+            ticket,
+            compression,
+        }
+    }
+
+    /// The wrapped ticket.
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the wrapped ticket.
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+
+    /// The compression algorithm used for the wrapped ticket.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+}
+
+impl<T: Ticket> Ticket for CompressedTicket<T> {
+    const KIND: &'static str = "compressed";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.compression.flag()];
+        out.extend(self.compression.compress(self.ticket.encode_bytes()));
+        out
+    }
+
+    /// Compresses the canonical bytes of the inner ticket.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.compression.flag()];
+        out.extend(self.compression.compress(self.ticket.canonical_bytes()));
+        out
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Some((&flag, payload)) = bytes.split_first() else {
+            return Err(e!(ParseError::Truncated));
+        };
+        let compression = Compression::from_flag(flag)
+            .ok_or_else(|| ParseError::verification_failed("unknown compression"))?;
+        let ticket = T::decode_bytes(&compression.decompress(payload, T::MAX_LEN)?)?;
+        Ok(Self {
+            ticket,
+            compression,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for CompressedTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for CompressedTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for CompressedTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for CompressedTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::multi::MultiEndpointTicket;
+
+    fn multi() -> MultiEndpointTicket {
+        (0..8u8)
+            .map(|i| {
+                EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public())
+                    .with_relay_url("https://relay.example./".parse().unwrap())
+                    .with_ip_addr(format!("198.51.100.{i}:1234").parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        let ticket = multi();
+        let algorithms = [
+            Compression::None,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ];
+        for compression in algorithms {
+            let compressed = CompressedTicket::new(ticket.clone(), compression);
+            let bytes = compressed.encode_bytes();
+            assert_eq!(bytes[0], compression.flag());
+            if compression != Compression::None {
+                assert!(bytes.len() < ticket.encode_bytes().len(), "{compression:?}");
+            }
+            let s = compressed.to_string();
+            assert!(s.starts_with("compressed"));
+            let parsed: CompressedTicket<MultiEndpointTicket> = s.parse().unwrap();
+            assert_eq!(parsed, compressed);
+        }
+    }
+
+    #[test]
+    fn test_compressed_errors() {
+        let err = CompressedTicket::<MultiEndpointTicket>::decode_bytes(&[]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+        let err = CompressedTicket::<MultiEndpointTicket>::decode_bytes(&[255, 0]).unwrap_err();
+        assert!(matches!(err, ParseError::Verify { .. }));
+    }
+
+    #[test]
+    #[cfg(any(feature = "deflate", feature = "zstd"))]
+    fn test_decompression_limit() {
+        #[derive(Debug)]
+        struct Small(Vec<u8>);
+
+        impl Ticket for Small {
+            const KIND: &'static str = "small";
+            const MAX_LEN: usize = 1000;
+
+            fn encode_bytes(&self) -> Vec<u8> {
+                self.0.clone()
+            }
+
+            fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+                ensure_len::<Self>(bytes)?;
+                Ok(Self(bytes.to_vec()))
+            }
+        }
+
+        let algorithms = [
+            #[cfg(feature = "deflate")]
+            Compression::Deflate,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ];
+        for compression in algorithms {
+            let ok = CompressedTicket::new(Small(vec![0; 1000]), compression);
+            assert!(CompressedTicket::<Small>::decode_bytes(&ok.encode_bytes()).is_ok());
+            let bomb = CompressedTicket::new(Small(vec![0; 100_000]), compression);
+            let bytes = bomb.encode_bytes();
+            assert!(bytes.len() < 1000, "{compression:?}");
+            let err = CompressedTicket::<Small>::decode_bytes(&bytes).unwrap_err();
+            assert!(matches!(err, ParseError::Verify { .. }), "{compression:?}");
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Crockford base32 string forms of tickets, for typing tickets by hand.
+//!
+//! This is an alternative to the canonical base32 string form of [`Ticket::encode_string`]
+//! using [Crockford's base32] alphabet with a trailing check symbol. The alphabet leaves
+//! out the letters I, L, O and U, and decoding reads `O` as `0` and `I` or `L` as `1`, so
+//! the most common confusions when reading a ticket do not matter. Decoding ignores case
+//! and hyphens, which can be used to group characters. The check symbol detects a single
+//! wrong character and swapped adjacent characters.
+//!
+//! The string is the [`KIND`](Ticket::KIND) followed by the uppercase Crockford base32
+//! encoding of the [byte representation](Ticket::encode_bytes) and the check symbol.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{crockford, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let s = crockford::encode(&ticket);
+//! assert_eq!(crockford::decode::<EndpointTicket>(&s).unwrap(), ticket);
+//! let sloppy = s.to_lowercase().replace('0', "o");
+//! assert_eq!(
+//!     crockford::decode::<EndpointTicket>(&sloppy).unwrap(),
+//!     ticket
+//! );
+//! ```
+//!
+//! [Crockford's base32]: https://www.crockford.com/base32.html
+
+use std::sync::LazyLock;
+
+use data_encoding::{Encoding, Specification};
+use n0_error::e;
+
+use crate::{ParseError, Ticket, max_kind_len, strip_ticket_kind};
+
+/// The symbols for the values 0 to 31.
+const SYMBOLS: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The check symbols for the values 0 to 36, the symbols followed by five extra symbols.
+const CHECK_SYMBOLS: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+static ENCODING: LazyLock<Encoding> = LazyLock::new(|| {
+    let mut spec = Specification::new();
+    spec.symbols.push_str(SYMBOLS);
+    spec.ignore.push('-');
+    spec.translate.from.push_str("abcdefghjkmnpqrstvwxyzOoIiLl");
+    spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ001111");
+    spec.encoding().expect("valid specification")
+});
+
+/// Returns the value of the check symbol for `bytes`.
+///
+/// This is the value of the encoded symbols as a base32 number, modulo 37.
+fn check_value(bytes: &[u8]) -> usize {
+    let mut check = 0;
+    let mut acc = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        acc = (acc << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            check = (check * 32 + usize::from(acc >> bits)) % 37;
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if bits > 0 {
+        check = (check * 32 + usize::from(acc << (5 - bits))) % 37;
+    }
+    check
+}
+
+/// Returns the value of a check symbol, accepting the same aliases as the payload.
+fn parse_check(symbol: char) -> Option<usize> {
+    let symbol = match symbol.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        symbol => symbol,
+    };
+    CHECK_SYMBOLS.iter().position(|&s| char::from(s) == symbol)
+}
+
+/// Encodes a ticket as its kind followed by Crockford base32 and a check symbol.
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let mut out = String::new();
+    write_to(ticket, &mut out);
+    out
+}
+
+/// Appends the Crockford base32 string form of a ticket to `out`, see [`encode`].
+pub fn write_to<T: Ticket>(ticket: &T, out: &mut String) {
+    let bytes = ticket.encode_bytes();
+    out.push_str(T::KIND);
+    ENCODING.encode_append(&bytes, out);
+    out.push(char::from(CHECK_SYMBOLS[check_value(&bytes)]));
+}
+
+/// Decodes a ticket from the string form written by [`encode`].
+pub fn decode<T: Ticket>(s: &str) -> Result<T, ParseError> {
+    let kind_len = max_kind_len::<T>();
+    // Leave room for a hyphen after every character.
+    let max = kind_len + 2 * (ENCODING.encode_len(T::MAX_LEN) + 1);
+    if s.len() > max {
+        return Err(e!(ParseError::TooLong { len: s.len(), max }));
+    }
+    let rest = strip_ticket_kind::<T>(s)?;
+    let offset = s.len() - rest.len();
+    let mut chars = rest.chars();
+    let Some(check) = chars.next_back() else {
+        return Err(e!(ParseError::Truncated));
+    };
+    let payload = chars.as_str();
+    let check = parse_check(check).ok_or_else(|| {
+        e!(ParseError::InvalidCharacter {
+            offset: offset + payload.len()
+        })
+    })?;
+    let bytes = ENCODING
+        .decode(payload.as_bytes())
+        .map_err(|err| ParseError::from_data_encoding(err, offset))?;
+    if check_value(&bytes) != check {
+        return Err(e!(ParseError::Checksum));
+    }
+    T::decode_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_check_value() {
+        // The symbols 0b11111 and 0b00100, 31 * 32 + 4 in base 10.
+        assert_eq!(check_value(&[0xf9]), 996 % 37);
+        assert_eq!(check_value(&[]), 0);
+    }
+
+    #[test]
+    fn test_crockford_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[19u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let s = encode(&ticket);
+        assert!(s.starts_with("endpoint"));
+        assert_eq!(decode::<EndpointTicket>(&s).unwrap(), ticket);
+
+        let payload = &s["endpoint".len()..];
+        let grouped: Vec<_> = payload.as_bytes().chunks(4).map(|c| c.to_vec()).collect();
+        let grouped = String::from_utf8(grouped.join(&b'-')).unwrap();
+        let sloppy = format!("endpoint{}", grouped.to_lowercase())
+            .replace('0', "o")
+            .replace('1', "l");
+        assert_eq!(decode::<EndpointTicket>(&sloppy).unwrap(), ticket);
+
+        // Every single character substitution in the payload is detected.
+        for i in "endpoint".len()..s.len() {
+            let mut typo = s.clone().into_bytes();
+            typo[i] = if typo[i] == b'2' { b'3' } else { b'2' };
+            let typo = String::from_utf8(typo).unwrap();
+            assert!(decode::<EndpointTicket>(&typo).is_err(), "{i}");
+        }
+
+        // Swapping adjacent characters is detected.
+        let mut swapped = s.clone().into_bytes();
+        let i = (10..).find(|&i| swapped[i] != swapped[i + 1]).unwrap();
+        swapped.swap(i, i + 1);
+        let err = decode::<EndpointTicket>(&String::from_utf8(swapped).unwrap()).unwrap_err();
+        assert!(matches!(err, ParseError::Checksum { .. }));
+
+        let err = decode::<EndpointTicket>(&format!("{s}!")).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidCharacter { .. }));
+        let err = decode::<EndpointTicket>("endpoint").unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+    }
+}
@@ -0,0 +1,107 @@
+//! Tickets as `data:` URIs.
+//!
+//! A ticket data URI has the form `data:application/x-iroh-ticket;base64,<payload>`, where
+//! `<payload>` is the base64 encoding of the [string form](Ticket::encode_string). This is
+//! useful where a payload needs a MIME type, e.g. in HTML attributes or clipboard formats.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let uri = ticket.to_data_uri();
+//! assert!(uri.starts_with("data:application/x-iroh-ticket;base64,"));
+//! assert_eq!(EndpointTicket::from_data_uri(&uri).unwrap(), ticket);
+//! ```
+
+use data_encoding::BASE64;
+use n0_error::e;
+
+use crate::{MIME_TYPE, ParseError, Ticket, uri::percent_decode};
+
+/// Formats a ticket as a base64 `data:` URI of type [`MIME_TYPE`].
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let mut out = format!("data:{MIME_TYPE};base64,");
+    BASE64.encode_append(ticket.encode_string().as_bytes(), &mut out);
+    out
+}
+
+/// Parses a ticket from a `data:` URI of type [`MIME_TYPE`].
+///
+/// Parameters of the media type, like a `charset`, are ignored. Both base64 and
+/// percent-encoded URIs are accepted. Fails with [`ParseError::Uri`] if `uri` is not a data
+/// URI of the right type.
+pub fn decode<T: Ticket>(uri: &str) -> Result<T, ParseError> {
+    let uri = uri.trim();
+    let rest = uri
+        .get(.."data:".len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .map(|_| &uri["data:".len()..])
+        .ok_or_else(|| e!(ParseError::Uri))?;
+    let (header, data) = rest.split_once(',').ok_or_else(|| e!(ParseError::Uri))?;
+    let mut params = header.split(';');
+    let media_type = params.next().unwrap_or_default().trim();
+    if !media_type.eq_ignore_ascii_case(MIME_TYPE) {
+        return Err(e!(ParseError::Uri));
+    }
+    let base64 = params.any(|param| param.trim().eq_ignore_ascii_case("base64"));
+    let s = if base64 {
+        let offset = uri.len() - data.len();
+        let bytes = BASE64
+            .decode(percent_decode(data)?.as_bytes())
+            .map_err(|err| ParseError::from_data_encoding(err, offset))?;
+        String::from_utf8(bytes).map_err(|_| e!(ParseError::Uri))?
+    } else {
+        percent_decode(data)?
+    };
+    T::decode_string(&s)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_data_uri() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[29u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let uri = ticket.to_data_uri();
+        assert_eq!(EndpointTicket::from_data_uri(&uri).unwrap(), ticket);
+
+        let (_, payload) = uri.split_once(',').unwrap();
+        for uri in [
+            format!("DATA:Application/X-Iroh-Ticket;charset=US-ASCII;base64,{payload}"),
+            format!(
+                "data:{MIME_TYPE};base64,{}",
+                payload.replace('=', "%3D").replace('+', "%2B")
+            ),
+            format!("data:{MIME_TYPE},{ticket}"),
+        ] {
+            assert_eq!(
+                EndpointTicket::from_data_uri(&uri).unwrap(),
+                ticket,
+                "{uri}"
+            );
+        }
+
+        for uri in [
+            format!("data:text/plain;base64,{payload}"),
+            format!("data:{MIME_TYPE};base64"),
+            format!("http:{MIME_TYPE};base64,{payload}"),
+        ] {
+            let err = EndpointTicket::from_data_uri(&uri).unwrap_err();
+            assert!(matches!(err, ParseError::Uri { .. }), "{uri}");
+        }
+        let err =
+            EndpointTicket::from_data_uri(&format!("data:{MIME_TYPE};base64,a!==")).unwrap_err();
+        assert!(
+            matches!(err, ParseError::InvalidCharacter { offset: 39, .. }),
+            "{err:?}"
+        );
+    }
+}
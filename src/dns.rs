@@ -0,0 +1,326 @@
+//! Publishing tickets in DNS TXT records.
+//!
+//! A TXT record is made of strings of at most 255 bytes, and a lookup returns the records
+//! at a name in no particular order. [`to_txt_records`] [splits](crate::parts) a ticket
+//! into records that each hold a single string, and [`from_txt_records`] reassembles the
+//! ticket from the records returned by any resolver. Tickets that fit into a single string
+//! are published as a single record with the canonical string form.
+//!
+//! By convention tickets are published at the [`TXT_LABEL`] below a domain, see
+//! [`txt_name`]. Other TXT records at the same name are ignored.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{dns, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! assert_eq!(dns::txt_name("example.com"), "_iroh.example.com");
+//! let mut records = dns::to_txt_records(&ticket);
+//! records.push("v=spf1 -all".to_string());
+//! assert_eq!(
+//!     dns::from_txt_records::<EndpointTicket>(&records).unwrap(),
+//!     ticket
+//! );
+//! ```
+//!
+//! A [`DnsTicket`] only contains a domain name, and stands in for the [`EndpointTicket`]
+//! published at that domain. The published ticket can be replaced at any time, e.g. when
+//! the addresses of the endpoint change, while the [`DnsTicket`] stays the same. With the
+//! `dns` feature, [`DnsTicket::resolve`] looks up the current ticket.
+
+use std::str::FromStr;
+#[cfg(feature = "dns")]
+use std::time::Duration;
+
+#[cfg(feature = "dns")]
+use iroh_dns::dns::{DnsError, DnsResolver};
+use n0_error::e;
+#[cfg(feature = "dns")]
+use n0_error::stack_error;
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(feature = "dns", doc))]
+use crate::endpoint::EndpointTicket;
+use crate::{
+    ParseError, Ticket, deserialize_ticket, ensure_len,
+    parts::{self, Reassembler},
+    serialize_ticket, strip_ticket_kind,
+    versioned::{self, Versioned},
+};
+
+/// The label tickets are published at, below the domain.
+pub const TXT_LABEL: &str = "_iroh";
+
+/// The maximum length of a string in a TXT record.
+pub const MAX_TXT_STRING_LEN: usize = 255;
+
+/// Returns the name to publish tickets for `domain` at, e.g. `_iroh.example.com`.
+pub fn txt_name(domain: &str) -> String {
+    format!("{TXT_LABEL}.{}", domain.trim_end_matches('.'))
+}
+
+/// Encodes a ticket as the contents of TXT records, one string of at most
+/// [`MAX_TXT_STRING_LEN`] bytes per record.
+pub fn to_txt_records<T: Ticket>(ticket: &T) -> Vec<String> {
+    parts::split(ticket, MAX_TXT_STRING_LEN).expect("kind is shorter than a txt string")
+}
+
+/// Decodes a ticket from the contents of the TXT records at a name, in any order.
+///
+/// Records that are not a ticket of kind `T` are ignored. Fails with
+/// [`ParseError::MissingParts`] if some parts of a split ticket are missing.
+pub fn from_txt_records<T: Ticket>(
+    records: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<T, ParseError> {
+    let mut reassembler = Reassembler::new();
+    for record in records {
+        let record = record.as_ref();
+        if strip_ticket_kind::<T>(record).is_err() {
+            continue;
+        }
+        if let Some(ticket) = reassembler.push(record)? {
+            return Ok(ticket);
+        }
+    }
+    match reassembler.total() {
+        Some(_) => Err(e!(ParseError::MissingParts {
+            count: reassembler.missing()
+        })),
+        None => Err(ParseError::wrong_prefix(T::KIND)),
+    }
+}
+
+/// The timeout of the TXT lookup in [`DnsTicket::resolve`].
+#[cfg(feature = "dns")]
+pub const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A ticket pointing at the [`EndpointTicket`] published at a domain.
+///
+/// The ticket is published in TXT records at the [`txt_name`] of the domain, see
+/// [`to_txt_records`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct DnsTicket {
+    name: String,
+}
+
+/// Wire format for [`DnsTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0DnsTicket {
+    name: String,
+}
+
+impl Versioned for Variant0DnsTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for DnsTicket {
+    const KIND: &'static str = "dns";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0DnsTicket {
+            name: self.name.clone(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0DnsTicket { name } = versioned::decode(bytes)?;
+        Ok(Self::new(name))
+    }
+}
+
+impl DnsTicket {
+    /// Creates a ticket for the domain `name`, e.g. `example.com`.
+    ///
+    /// The name is lowercased and a trailing dot is removed.
+    pub fn new(name: impl Into<String>) -> Self {
+        let mut name = name.into();
+        name.make_ascii_lowercase();
+        if name.ends_with('.') {
+            name.pop();
+        }
+        Self { name }
+    }
+
+    /// The domain name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the TXT records holding the ticket, see [`txt_name`].
+    pub fn txt_name(&self) -> String {
+        txt_name(&self.name)
+    }
+
+    /// Looks up the [`EndpointTicket`] currently published at the domain.
+    #[cfg(feature = "dns")]
+    pub async fn resolve(&self, resolver: &DnsResolver) -> Result<EndpointTicket, ResolveError> {
+        let records = resolver
+            .lookup_txt(self.txt_name(), RESOLVE_TIMEOUT)
+            .await?
+            .map(|record| record.to_string())
+            .collect::<Vec<_>>();
+        Ok(from_txt_records(records)?)
+    }
+}
+
+impl FromStr for DnsTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl Serialize for DnsTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+/// An error resolving a [`DnsTicket`].
+#[cfg(feature = "dns")]
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ResolveError {
+    /// The TXT lookup failed.
+    #[error(transparent)]
+    Lookup {
+        #[error(source, from, std_err)]
+        source: DnsError,
+    },
+    /// The TXT records do not contain a valid ticket.
+    #[error(transparent)]
+    Parse {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{endpoint::EndpointTicket, multi::MultiEndpointTicket};
+
+    #[test]
+    fn test_txt_records() {
+        let addrs = (0..8u8).map(|i| {
+            EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public())
+                .with_ip_addr(format!("198.51.100.{i}:1234").parse().unwrap())
+        });
+        let ticket = MultiEndpointTicket::new(addrs);
+        let mut records = to_txt_records(&ticket);
+        assert!(records.len() > 1);
+        assert!(records.iter().all(|r| r.len() <= MAX_TXT_STRING_LEN));
+        records.reverse();
+        records.insert(1, "v=spf1 -all".to_string());
+        records.push("endpointnotatickettoo".to_string());
+        assert_eq!(
+            from_txt_records::<MultiEndpointTicket>(&records).unwrap(),
+            ticket
+        );
+
+        let err = from_txt_records::<MultiEndpointTicket>(&records[1..]).unwrap_err();
+        assert!(matches!(err, ParseError::MissingParts { count: 1, .. }));
+        let err = from_txt_records::<EndpointTicket>(["v=spf1 -all"]).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+        assert_eq!(txt_name("example.com."), "_iroh.example.com");
+    }
+
+    #[test]
+    fn test_dns_ticket_roundtrip() {
+        let ticket = DnsTicket::new("Example.COM.");
+        assert_eq!(ticket.name(), "example.com");
+        assert_eq!(ticket.txt_name(), "_iroh.example.com");
+        let s = ticket.to_string();
+        assert!(s.starts_with("dns"));
+        assert_eq!(s.parse::<DnsTicket>().unwrap(), ticket);
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+        assert_eq!(serde_json::from_str::<DnsTicket>(&json).unwrap(), ticket);
+    }
+
+    #[cfg(feature = "dns")]
+    #[tokio::test]
+    async fn test_dns_ticket_resolve() {
+        use iroh_dns::dns::{BoxIter, Resolver, TxtRecordData};
+
+        type BoxFuture<T> = std::pin::Pin<Box<dyn Future<Output = T> + Send>>;
+
+        #[derive(Debug, Clone)]
+        struct TxtResolver(Vec<String>);
+
+        impl Resolver for TxtResolver {
+            fn lookup_ipv4(
+                &self,
+                _host: String,
+            ) -> BoxFuture<Result<BoxIter<std::net::Ipv4Addr>, DnsError>> {
+                Box::pin(async { Err(e!(DnsError::NoResponse)) })
+            }
+
+            fn lookup_ipv6(
+                &self,
+                _host: String,
+            ) -> BoxFuture<Result<BoxIter<std::net::Ipv6Addr>, DnsError>> {
+                Box::pin(async { Err(e!(DnsError::NoResponse)) })
+            }
+
+            fn lookup_txt(
+                &self,
+                host: String,
+            ) -> BoxFuture<Result<BoxIter<TxtRecordData>, DnsError>> {
+                let records = self.0.clone();
+                Box::pin(async move {
+                    if host != "_iroh.example.com" {
+                        return Err(e!(DnsError::NxDomain));
+                    }
+                    let records = records.into_iter().map(|record| {
+                        TxtRecordData::from(vec![record.into_bytes().into_boxed_slice()])
+                    });
+                    Ok(Box::new(records) as BoxIter<_>)
+                })
+            }
+
+            fn clear_cache(&self) {}
+
+            fn reset(&self) -> Box<dyn Resolver> {
+                Box::new(self.clone())
+            }
+        }
+
+        let ticket =
+            EndpointTicket::new(EndpointAddr::new(SecretKey::from_bytes(&[1; 32]).public()));
+        let mut records = to_txt_records(&ticket);
+        records.push("v=spf1 -all".to_string());
+        let resolver = DnsResolver::custom(TxtResolver(records));
+
+        let resolved = DnsTicket::new("example.com")
+            .resolve(&resolver)
+            .await
+            .unwrap();
+        assert_eq!(resolved, ticket);
+        let err = DnsTicket::new("example.org")
+            .resolve(&resolver)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ResolveError::Lookup { .. }));
+        let empty = DnsResolver::custom(TxtResolver(vec!["v=spf1 -all".to_string()]));
+        let err = DnsTicket::new("example.com")
+            .resolve(&empty)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ResolveError::Parse { .. }));
+    }
+}
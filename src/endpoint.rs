@@ -1,17 +1,26 @@
 //! Tickets for endpoints.
 
-use std::{collections::BTreeSet, str::FromStr};
+use std::{cmp::Ordering, collections::BTreeSet, fmt, net::IpAddr, str::FromStr};
 
 use iroh_base::{EndpointAddr, EndpointId, TransportAddr};
-use serde::{Deserialize, Serialize};
+use n0_error::{e, stack_error};
+use serde::{
+    Deserialize, Serialize,
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+};
 
-use crate::{ParseError, Ticket};
+use crate::{
+    ParseError, Ticket, VersionError, ensure_len,
+    versioned::{self, UpgradeFrom, Versioned},
+};
 
 /// A token containing information for establishing a connection to an endpoint.
 ///
 /// Contains
 /// - The [`EndpointId`] of the endpoint to connect to (a 32-byte ed25519 public key).
 /// - Any known [`TransportAddr`]s on which the endpoint can be reached.
+/// - Optionally the ALPNs the endpoint accepts connections for.
 ///
 /// This allows establishing a connection to the endpoint in most circumstances where it is
 /// possible to do so.
@@ -23,48 +32,208 @@ use crate::{ParseError, Ticket};
 /// [`Display`]: std::fmt::Display
 /// [`FromStr`]: std::str::FromStr
 ///
+/// Tickets are ordered by [`EndpointId`], then by their relay URLs, then by their other
+/// addresses and finally by their ALPNs, so tickets for the same endpoint are next to each
+/// other in sorted collections. See [`same_endpoint`](Self::same_endpoint) for comparing
+/// tickets regardless of the order of their ALPNs.
+///
 /// [`EndpointId`]: iroh_base::EndpointId
 /// [`TransportAddr`]: iroh_base::TransportAddr
-#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, derive_more::Display)]
 #[display("{}", Ticket::encode_string(self))]
 pub struct EndpointTicket {
     addr: EndpointAddr,
+    alpns: Vec<Vec<u8>>,
+}
+
+/// Two [`EndpointTicket`]s for different endpoints can not be merged.
+#[stack_error(derive, add_meta)]
+#[error("can not merge tickets for {this} and {other}")]
+pub struct MergeError {
+    /// The endpoint of the ticket that was merged into.
+    pub this: EndpointId,
+    /// The endpoint of the other ticket.
+    pub other: EndpointId,
+}
+
+/// The addresses of an [`EndpointTicket`] by how they can be reached, see
+/// [`EndpointTicket::reachability`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Reachability {
+    /// The number of relay URLs.
+    pub relays: usize,
+    /// The number of IP addresses reachable from other networks.
+    pub public_addrs: usize,
+    /// The number of IP addresses only reachable from the local network or host, or not at
+    /// all, e.g. private network addresses or addresses with port 0.
+    pub local_addrs: usize,
+    /// The number of addresses for custom transports.
+    pub custom_addrs: usize,
+}
+
+impl Reachability {
+    /// Returns whether the endpoint can be reached from other networks, through a relay or
+    /// a public IP address.
+    pub fn is_dialable(&self) -> bool {
+        self.relays > 0 || self.public_addrs > 0
+    }
+}
+
+/// The differences between two [`EndpointTicket`]s, see [`EndpointTicket::diff`].
+///
+/// The [`Display`](fmt::Display) form lists the changes for logging, e.g.
+/// `+relay:https://new.example./, -relay:https://old.example./, +ip:198.51.100.2:1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TicketDiff {
+    /// Whether the tickets are for different endpoints.
+    pub id_changed: bool,
+    /// The addresses only in the newer ticket, in order.
+    pub added: Vec<TransportAddr>,
+    /// The addresses only in the older ticket, in order.
+    pub removed: Vec<TransportAddr>,
+    /// The ALPNs only in the newer ticket.
+    pub added_alpns: Vec<Vec<u8>>,
+    /// The ALPNs only in the older ticket.
+    pub removed_alpns: Vec<Vec<u8>>,
+}
+
+impl TicketDiff {
+    /// Returns whether the tickets are for the same endpoint with the same addresses and
+    /// ALPNs, see [`EndpointTicket::same_endpoint`].
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns whether relay URLs were added or removed.
+    pub fn relay_changed(&self) -> bool {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .any(TransportAddr::is_relay)
+    }
+
+    /// Returns whether addresses other than relay URLs, e.g. IP addresses, were added or
+    /// removed.
+    pub fn addrs_changed(&self) -> bool {
+        self.added
+            .iter()
+            .chain(&self.removed)
+            .any(|addr| !addr.is_relay())
+    }
+}
+
+impl fmt::Display for TicketDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no changes");
+        }
+        let mut changes = Vec::new();
+        if self.id_changed {
+            changes.push("endpoint id changed".to_string());
+        }
+        changes.extend(self.added.iter().map(|addr| format!("+{addr}")));
+        changes.extend(self.removed.iter().map(|addr| format!("-{addr}")));
+        let alpn = |alpn: &Vec<u8>| String::from_utf8_lossy(alpn).into_owned();
+        changes.extend(
+            self.added_alpns
+                .iter()
+                .map(|a| format!("+alpn:{}", alpn(a))),
+        );
+        changes.extend(
+            self.removed_alpns
+                .iter()
+                .map(|a| format!("-alpn:{}", alpn(a))),
+        );
+        f.write_str(&changes.join(", "))
+    }
 }
 
 /// Wire format for [`EndpointTicket`].
 #[derive(Serialize, Deserialize)]
-enum TicketWireFormat {
-    Variant1(Variant1EndpointTicket),
+struct Variant0EndpointTicket {
+    addr: Variant1EndpointAddr,
+}
+
+impl Versioned for Variant0EndpointTicket {
+    const VERSION: u32 = 0;
 }
 
+/// Wire format for [`EndpointTicket`] with ALPNs.
 #[derive(Serialize, Deserialize)]
 struct Variant1EndpointTicket {
     addr: Variant1EndpointAddr,
+    alpns: Vec<Vec<u8>>,
+}
+
+impl Versioned for Variant1EndpointTicket {
+    const VERSION: u32 = 1;
+
+    fn decode_older(version: u32, payload: &[u8]) -> Result<Self, ParseError> {
+        versioned::upgrade::<Variant0EndpointTicket, Self>(version, payload)
+    }
+}
+
+impl UpgradeFrom<Variant0EndpointTicket> for Variant1EndpointTicket {
+    fn upgrade_from(Variant0EndpointTicket { addr }: Variant0EndpointTicket) -> Self {
+        Self {
+            addr,
+            alpns: Vec::new(),
+        }
+    }
 }
 
 impl Ticket for EndpointTicket {
     const KIND: &'static str = "endpoint";
 
     fn encode_bytes(&self) -> Vec<u8> {
-        let data = TicketWireFormat::Variant1(Variant1EndpointTicket {
-            addr: Variant1EndpointAddr {
-                id: self.addr.id,
-                info: Variant1AddrInfo {
-                    addrs: self.addr.addrs.clone(),
-                },
-            },
-        });
-        postcard::to_stdvec(&data).expect("postcard serialization failed")
+        let addr = Variant1EndpointAddr::from(&self.addr);
+        // Tickets without ALPNs keep using the older variant, so older parsers can read them.
+        if self.alpns.is_empty() {
+            versioned::encode(&Variant0EndpointTicket { addr })
+        } else {
+            versioned::encode(&Variant1EndpointTicket {
+                addr,
+                alpns: self.alpns.clone(),
+            })
+        }
+    }
+
+    /// Tickets with ALPNs require version 1, all others are written as version 0.
+    fn to_bytes_versioned(&self, max_version: u32) -> Result<Vec<u8>, VersionError> {
+        let required = if self.alpns.is_empty() {
+            Variant0EndpointTicket::VERSION
+        } else {
+            Variant1EndpointTicket::VERSION
+        };
+        if required > max_version {
+            return Err(e!(VersionError {
+                required,
+                max_version
+            }));
+        }
+        Ok(self.encode_bytes())
+    }
+
+    /// The ALPNs are sorted and deduplicated.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let mut alpns = self.alpns.clone();
+        alpns.sort();
+        alpns.dedup();
+        Self {
+            addr: self.addr.clone(),
+            alpns,
+        }
+        .encode_bytes()
     }
 
     fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
-        let res: TicketWireFormat = postcard::from_bytes(bytes)?;
-        let TicketWireFormat::Variant1(Variant1EndpointTicket { addr }) = res;
+        ensure_len::<Self>(bytes)?;
+        let Variant1EndpointTicket { addr, alpns } = versioned::decode(bytes)?;
         Ok(Self {
-            addr: EndpointAddr {
-                id: addr.id,
-                addrs: addr.info.addrs,
-            },
+            addr: addr.into(),
+            alpns,
         })
     }
 }
@@ -80,19 +249,366 @@ impl FromStr for EndpointTicket {
 impl EndpointTicket {
     /// Creates a new ticket.
     pub fn new(addr: EndpointAddr) -> Self {
-        Self { addr }
+        Self {
+            addr,
+            alpns: Vec::new(),
+        }
+    }
+
+    /// Creates a ticket for the given endpoint, once it is online.
+    ///
+    /// This waits for [`Endpoint::online`](iroh::Endpoint::online), so the ticket contains
+    /// the home relay and the direct addresses known at that point. Note that this waits
+    /// indefinitely if no relay can be reached.
+    ///
+    /// The ticket does not contain any ALPNs, add the ones the endpoint accepts with
+    /// [`with_alpns`](Self::with_alpns).
+    ///
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> n0_error::Result<()> {
+    /// use iroh::{Endpoint, endpoint::presets};
+    /// use iroh_tickets::endpoint::EndpointTicket;
+    ///
+    /// let endpoint = Endpoint::bind(presets::N0).await?;
+    /// let ticket = EndpointTicket::from_endpoint(&endpoint).await;
+    /// println!("{ticket}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "iroh")]
+    pub async fn from_endpoint(endpoint: &iroh::Endpoint) -> Self {
+        endpoint.online().await;
+        Self::new(endpoint.addr())
+    }
+
+    /// Parses a ticket, or a bare [`EndpointId`] as a ticket without addresses.
+    ///
+    /// Many users share just the id of an endpoint, and address lookup can fill in the
+    /// addresses when connecting. This accepts everything
+    /// [`decode_string_lenient`](Ticket::decode_string_lenient) accepts, and otherwise an id
+    /// in hex, as displayed by [`EndpointId`], or in z-base-32, as used in pkarr domain
+    /// names. If neither works, the error of parsing `s` as a ticket is returned.
+    ///
+    /// ```
+    /// use iroh_base::SecretKey;
+    /// use iroh_tickets::endpoint::EndpointTicket;
+    ///
+    /// let id = SecretKey::from_bytes(&[0u8; 32]).public();
+    /// let ticket = EndpointTicket::parse_loose(&id.to_string()).unwrap();
+    /// assert_eq!(ticket.endpoint_addr().id, id);
+    /// assert!(ticket.endpoint_addr().addrs.is_empty());
+    /// assert_eq!(EndpointTicket::parse_loose(&id.to_z32()).unwrap(), ticket);
+    /// ```
+    pub fn parse_loose(s: &str) -> Result<Self, ParseError> {
+        let err = match Self::decode_string_lenient(s) {
+            Ok(ticket) => return Ok(ticket),
+            Err(err) => err,
+        };
+        let id = crate::clean_lenient(s).to_ascii_lowercase();
+        let id = match id.len() {
+            64 => id.parse().ok(),
+            52 => EndpointId::from_z32(&id).ok(),
+            _ => None,
+        };
+        match id {
+            Some(id) => Ok(Self::new(EndpointAddr::new(id))),
+            None => Err(err),
+        }
+    }
+
+    /// Returns a builder for a ticket that only contains some of the addresses of `addr`.
+    pub fn builder(addr: EndpointAddr) -> EndpointTicketBuilder {
+        EndpointTicketBuilder::new(addr)
+    }
+
+    /// Sets the ALPNs the endpoint accepts connections for.
+    pub fn with_alpns(mut self, alpns: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        self.alpns = alpns.into_iter().map(Into::into).collect();
+        self
     }
 
     /// The [`EndpointAddr`] of the provider for this ticket.
     pub fn endpoint_addr(&self) -> &EndpointAddr {
         &self.addr
     }
+
+    /// Returns this ticket without IP addresses that are not reachable from other networks.
+    ///
+    /// This removes loopback, link-local, unspecified and private network (RFC 1918 and
+    /// unique local IPv6) addresses, so sharing the ticket does not reveal the local network
+    /// setup. Relay URLs are kept. See [`EndpointTicketBuilder`] for more filters.
+    pub fn sanitized(&self) -> Self {
+        EndpointTicket::builder(self.addr.clone())
+            .alpns(self.alpns.iter().cloned())
+            .strip_private_addrs()
+            .build()
+    }
+
+    /// Returns whether the endpoint can be reached from other networks with this ticket.
+    ///
+    /// This is the case if the ticket contains a relay URL or a publicly routable IP
+    /// address. Apps can warn before sharing a ticket that only works on the local network,
+    /// see [`reachability`](Self::reachability) for details.
+    pub fn is_dialable(&self) -> bool {
+        self.reachability().is_dialable()
+    }
+
+    /// Counts the addresses of the ticket by how they can be reached.
+    pub fn reachability(&self) -> Reachability {
+        let mut reachability = Reachability::default();
+        for addr in &self.addr.addrs {
+            match addr {
+                TransportAddr::Relay(_) => reachability.relays += 1,
+                TransportAddr::Ip(addr) if addr.port() == 0 || is_private_ip(addr.ip()) => {
+                    reachability.local_addrs += 1
+                }
+                TransportAddr::Ip(_) => reachability.public_addrs += 1,
+                _ => reachability.custom_addrs += 1,
+            }
+        }
+        reachability
+    }
+
+    /// Returns whether both tickets are for the same endpoint with the same addresses and
+    /// ALPNs, ignoring the order of the ALPNs.
+    ///
+    /// Unlike `==`, this treats tickets that only differ in the preference order of their
+    /// ALPNs as equal, e.g. for deduplicating tickets received from different sources.
+    pub fn same_endpoint(&self, other: &EndpointTicket) -> bool {
+        self.addr == other.addr
+            && self.alpns.iter().collect::<BTreeSet<_>>()
+                == other.alpns.iter().collect::<BTreeSet<_>>()
+    }
+
+    /// Lists the differences from this ticket to a `newer` one.
+    ///
+    /// Useful for logging why a refreshed ticket differs, or for showing what changed before
+    /// accepting a new ticket for a [pinned](crate::pinning) endpoint. The order of ALPNs is
+    /// ignored.
+    pub fn diff(&self, newer: &EndpointTicket) -> TicketDiff {
+        let (old, new) = (&self.addr.addrs, &newer.addr.addrs);
+        TicketDiff {
+            id_changed: self.addr.id != newer.addr.id,
+            added: new.difference(old).cloned().collect(),
+            removed: old.difference(new).cloned().collect(),
+            added_alpns: (newer.alpns.iter())
+                .filter(|alpn| !self.alpns.contains(alpn))
+                .cloned()
+                .collect(),
+            removed_alpns: (self.alpns.iter())
+                .filter(|alpn| !newer.alpns.contains(alpn))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Merges two tickets for the same endpoint.
+    ///
+    /// The result contains the direct addresses and ALPNs of both tickets. `other` is
+    /// treated as the newer ticket: if it contains relay URLs, they replace the ones of this
+    /// ticket.
+    ///
+    /// Fails if the tickets are for different endpoints.
+    pub fn merge(&self, other: &EndpointTicket) -> Result<EndpointTicket, MergeError> {
+        if self.addr.id != other.addr.id {
+            return Err(e!(MergeError {
+                this: self.addr.id,
+                other: other.addr.id,
+            }));
+        }
+        let replace_relays = other.addr.relay_urls().next().is_some();
+        let addrs = self
+            .addr
+            .addrs
+            .iter()
+            .filter(|addr| !(replace_relays && addr.is_relay()))
+            .chain(&other.addr.addrs)
+            .cloned();
+        let mut alpns = self.alpns.clone();
+        for alpn in &other.alpns {
+            if !alpns.contains(alpn) {
+                alpns.push(alpn.clone());
+            }
+        }
+        Ok(EndpointTicket {
+            addr: EndpointAddr::from_parts(self.addr.id, addrs),
+            alpns,
+        })
+    }
+
+    /// The ALPNs the endpoint accepts connections for, if known.
+    ///
+    /// These are the protocols to pass when connecting to the endpoint, in order of
+    /// preference.
+    pub fn alpns(&self) -> &[Vec<u8>] {
+        &self.alpns
+    }
+
+    /// Connects to the endpoint of this ticket using the given ALPN.
+    ///
+    /// This is a shorthand for [`Endpoint::connect`](iroh::Endpoint::connect) with the
+    /// [`EndpointAddr`] of this ticket.
+    #[cfg(feature = "iroh")]
+    pub async fn connect(
+        &self,
+        endpoint: &iroh::Endpoint,
+        alpn: &[u8],
+    ) -> Result<iroh::endpoint::Connection, iroh::endpoint::ConnectError> {
+        endpoint.connect(self.addr.clone(), alpn).await
+    }
+}
+
+/// Builder for an [`EndpointTicket`] with a filtered set of addresses.
+///
+/// Leaving out addresses keeps tickets short, e.g. for QR codes, and avoids sharing local
+/// network addresses with others.
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::endpoint::EndpointTicket;
+///
+/// let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public())
+///     .with_ip_addr("192.168.1.2:1234".parse().unwrap())
+///     .with_ip_addr("203.0.113.5:1234".parse().unwrap())
+///     .with_ip_addr("[2001:db8::1]:1234".parse().unwrap());
+/// let ticket = EndpointTicket::builder(addr)
+///     .ipv4_only()
+///     .strip_private_addrs()
+///     .build();
+/// let ips: Vec<_> = ticket.endpoint_addr().ip_addrs().collect();
+/// assert_eq!(ips, [&"203.0.113.5:1234".parse().unwrap()]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EndpointTicketBuilder {
+    addr: EndpointAddr,
+    alpns: Vec<Vec<u8>>,
+    relay_only: bool,
+    direct_only: bool,
+    ipv4_only: bool,
+    strip_private_addrs: bool,
+    max_direct_addrs: Option<usize>,
+}
+
+impl EndpointTicketBuilder {
+    /// Creates a builder that keeps all addresses of `addr` unless configured otherwise.
+    pub fn new(addr: EndpointAddr) -> Self {
+        Self {
+            addr,
+            alpns: Vec::new(),
+            relay_only: false,
+            direct_only: false,
+            ipv4_only: false,
+            strip_private_addrs: false,
+            max_direct_addrs: None,
+        }
+    }
+
+    /// Sets the ALPNs the endpoint accepts connections for.
+    pub fn alpns(mut self, alpns: impl IntoIterator<Item = impl Into<Vec<u8>>>) -> Self {
+        self.alpns = alpns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only keeps relay URLs.
+    pub fn relay_only(mut self) -> Self {
+        self.relay_only = true;
+        self
+    }
+
+    /// Only keeps direct IP addresses.
+    pub fn direct_only(mut self) -> Self {
+        self.direct_only = true;
+        self
+    }
+
+    /// Drops all IPv6 addresses.
+    pub fn ipv4_only(mut self) -> Self {
+        self.ipv4_only = true;
+        self
+    }
+
+    /// Drops IP addresses that are not reachable from other networks.
+    ///
+    /// These are loopback, link-local, unspecified and private network addresses.
+    pub fn strip_private_addrs(mut self) -> Self {
+        self.strip_private_addrs = true;
+        self
+    }
+
+    /// Keeps at most `n` direct IP addresses.
+    ///
+    /// This is applied after the other filters. Addresses are kept in their sort order,
+    /// IPv4 before IPv6.
+    pub fn max_direct_addrs(mut self, n: usize) -> Self {
+        self.max_direct_addrs = Some(n);
+        self
+    }
+
+    /// Builds the ticket.
+    pub fn build(self) -> EndpointTicket {
+        let mut direct_addrs = 0;
+        let addrs = self.addr.addrs.into_iter().filter(|addr| match addr {
+            TransportAddr::Relay(_) => !self.direct_only,
+            TransportAddr::Ip(ip_addr) => {
+                let keep = !self.relay_only
+                    && (!self.ipv4_only || ip_addr.is_ipv4())
+                    && (!self.strip_private_addrs || !is_private_ip(ip_addr.ip()))
+                    && self.max_direct_addrs.is_none_or(|max| direct_addrs < max);
+                direct_addrs += usize::from(keep);
+                keep
+            }
+            _ => !self.relay_only && !self.direct_only,
+        });
+        EndpointTicket {
+            addr: EndpointAddr::from_parts(self.addr.id, addrs),
+            alpns: self.alpns,
+        }
+    }
+}
+
+/// Whether `ip` is not reachable from other networks.
+pub(crate) fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback() || ip.is_link_local() || ip.is_private() || ip.is_unspecified()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_ip(ip.into()),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unique_local()
+                    || ip.is_unspecified()
+            }
+        },
+    }
+}
+
+impl Ord for EndpointTicket {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn other_addrs(addr: &EndpointAddr) -> impl Iterator<Item = &TransportAddr> {
+            addr.addrs.iter().filter(|addr| !addr.is_relay())
+        }
+        self.addr
+            .id
+            .cmp(&other.addr.id)
+            .then_with(|| self.addr.relay_urls().cmp(other.addr.relay_urls()))
+            .then_with(|| other_addrs(&self.addr).cmp(other_addrs(&other.addr)))
+            .then_with(|| self.alpns.cmp(&other.alpns))
+    }
+}
+
+impl PartialOrd for EndpointTicket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl From<EndpointAddr> for EndpointTicket {
     /// Creates a ticket from given addressing info.
     fn from(addr: EndpointAddr) -> Self {
-        Self { addr }
+        Self::new(addr)
     }
 }
 
@@ -103,13 +619,42 @@ impl From<EndpointTicket> for EndpointAddr {
     }
 }
 
+/// The binary serde form of tickets with ALPNs has this in place of the endpoint id.
+///
+/// Older releases serialize only the [`EndpointAddr`] of a ticket, which starts with the
+/// endpoint id. The y coordinate 2 is not on the curve, so these bytes are not a valid
+/// public key and never start such a legacy form.
+const ALPNS_MARKER: [u8; 32] = {
+    let mut marker = [0u8; 32];
+    marker[0] = 2;
+    marker
+};
+
+/// The name and fields of the binary serde form, which is that of [`EndpointAddr`].
+const SERDE_NAME: &str = "EndpointAddr";
+const SERDE_FIELDS: &[&str] = &["id", "addrs"];
+
+/// The field of the binary serde form of tickets with ALPNs that replaces `addrs`.
+const SERDE_TICKET: &str = "ticket";
+
+/// In binary formats, tickets without ALPNs are serialized as their [`EndpointAddr`], like
+/// in older releases, so these can still read them.
+///
+/// Tickets with ALPNs are serialized as a struct of the same name and size, with 32 bytes
+/// that are not a valid endpoint id as `id` and the [byte representation](Ticket::encode_bytes) as `ticket`
+/// in place of `addrs`. Formats with field names, like CBOR, tell the forms apart by the
+/// fields. Positional formats, like postcard, tell them apart by the `id`.
 impl Serialize for EndpointTicket {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         if serializer.is_human_readable() {
             serializer.serialize_str(&self.encode_string())
+        } else if self.alpns.is_empty() {
+            self.addr.serialize(serializer)
         } else {
-            let EndpointTicket { addr } = self;
-            (addr).serialize(serializer)
+            let mut state = serializer.serialize_struct(SERDE_NAME, SERDE_FIELDS.len())?;
+            state.serialize_field("id", &ALPNS_MARKER)?;
+            state.serialize_field(SERDE_TICKET, &self.encode_bytes())?;
+            state.end()
         }
     }
 }
@@ -120,14 +665,79 @@ impl<'de> Deserialize<'de> for EndpointTicket {
             let s = String::deserialize(deserializer)?;
             Self::decode_string(&s).map_err(serde::de::Error::custom)
         } else {
-            let peer = Deserialize::deserialize(deserializer)?;
-            Ok(Self::new(peer))
+            deserializer.deserialize_struct(SERDE_NAME, SERDE_FIELDS, BinaryVisitor)
+        }
+    }
+}
+
+/// Reads the binary serde form of an [`EndpointTicket`], see its [`Serialize`] impl.
+struct BinaryVisitor;
+
+impl BinaryVisitor {
+    fn ticket<E: de::Error>(bytes: &[u8]) -> Result<EndpointTicket, E> {
+        EndpointTicket::decode_bytes(bytes).map_err(de::Error::custom)
+    }
+
+    fn addr<E: de::Error>(
+        id: [u8; 32],
+        addrs: BTreeSet<TransportAddr>,
+    ) -> Result<EndpointTicket, E> {
+        let id = EndpointId::from_bytes(&id).map_err(de::Error::custom)?;
+        Ok(EndpointTicket::new(EndpointAddr::from_parts(id, addrs)))
+    }
+}
+
+impl<'de> Visitor<'de> for BinaryVisitor {
+    type Value = EndpointTicket;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an endpoint ticket")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let id: [u8; 32] = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if id == ALPNS_MARKER {
+            let bytes: Vec<u8> = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Self::ticket(&bytes)
+        } else {
+            let addrs = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Self::addr(id, addrs)
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut id = None;
+        let mut addrs = None;
+        let mut ticket = None::<Vec<u8>>;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = Some(map.next_value()?),
+                "addrs" => addrs = Some(map.next_value()?),
+                SERDE_TICKET => ticket = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        match (id, addrs, ticket) {
+            (None, ..) => Err(de::Error::missing_field("id")),
+            (Some(ALPNS_MARKER), _, Some(bytes)) => Self::ticket(&bytes),
+            (Some(ALPNS_MARKER), ..) => Err(de::Error::missing_field(SERDE_TICKET)),
+            (Some(id), Some(addrs), _) => Self::addr(id, addrs),
+            (Some(_), None, _) => Err(de::Error::missing_field("addrs")),
         }
     }
 }
 
+/// Wire format for an [`EndpointAddr`], shared by the tickets in this crate.
 #[derive(Serialize, Deserialize)]
-struct Variant1EndpointAddr {
+pub(crate) struct Variant1EndpointAddr {
     id: EndpointId,
     info: Variant1AddrInfo,
 }
@@ -137,6 +747,26 @@ struct Variant1AddrInfo {
     addrs: BTreeSet<TransportAddr>,
 }
 
+impl From<&EndpointAddr> for Variant1EndpointAddr {
+    fn from(addr: &EndpointAddr) -> Self {
+        Self {
+            id: addr.id,
+            info: Variant1AddrInfo {
+                addrs: addr.addrs.clone(),
+            },
+        }
+    }
+}
+
+impl From<Variant1EndpointAddr> for EndpointAddr {
+    fn from(addr: Variant1EndpointAddr) -> Self {
+        Self {
+            id: addr.id,
+            addrs: addr.info.addrs,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -145,10 +775,11 @@ mod tests {
     };
 
     use data_encoding::HEXLOWER;
-    use iroh_base::{PublicKey, SecretKey, TransportAddr};
+    use iroh_base::{CustomAddr, PublicKey, RelayUrl, SecretKey, TransportAddr};
     use rand::{RngExt, SeedableRng};
 
     use super::*;
+    use crate::AnyTicket;
 
     fn make_ticket() -> EndpointTicket {
         let mut rng = rand::rngs::ChaCha8Rng::seed_from_u64(0u64);
@@ -156,6 +787,7 @@ mod tests {
         let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 1234));
         EndpointTicket {
             addr: EndpointAddr::from_parts(peer, [TransportAddr::Ip(addr)]),
+            alpns: Vec::new(),
         }
     }
 
@@ -189,6 +821,7 @@ mod tests {
                     TransportAddr::Ip("127.0.0.1:1024".parse().unwrap()),
                 ],
             ),
+            alpns: Vec::new(),
         };
         let base32 = data_encoding::BASE32_NOPAD
             .decode(
@@ -232,4 +865,508 @@ mod tests {
         let expected = HEXLOWER.decode(expected.concat().as_bytes()).unwrap();
         assert_eq!(base32, expected);
     }
+
+    #[test]
+    fn test_ticket_alpns() {
+        let ticket = make_ticket();
+        assert_eq!(ticket.encode_bytes()[0], 0);
+
+        let ticket = ticket.with_alpns([&b"iroh/test/0"[..], b"iroh/test/1"]);
+        let bytes = ticket.encode_bytes();
+        assert_eq!(bytes[0], 1);
+        let parsed = EndpointTicket::decode_bytes(&bytes).unwrap();
+        assert_eq!(
+            parsed.alpns(),
+            [b"iroh/test/0".to_vec(), b"iroh/test/1".to_vec()]
+        );
+        assert_eq!(parsed, ticket);
+
+        let s = ticket.to_string();
+        assert_eq!(s.parse::<EndpointTicket>().unwrap(), ticket);
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(bytes[..32], ALPNS_MARKER);
+        assert_eq!(
+            postcard::from_bytes::<EndpointTicket>(&bytes).unwrap(),
+            ticket
+        );
+    }
+
+    #[test]
+    fn test_ticket_cbor() {
+        fn round_trip<T: Serialize, U: serde::de::DeserializeOwned>(value: &T) -> U {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(value, &mut bytes).unwrap();
+            ciborium::from_reader(bytes.as_slice()).unwrap()
+        }
+
+        let ticket = make_ticket();
+        assert_eq!(round_trip::<_, EndpointTicket>(&ticket), ticket);
+        // Written by older releases, which serialized only the address.
+        assert_eq!(
+            round_trip::<_, EndpointTicket>(ticket.endpoint_addr()),
+            ticket
+        );
+        assert_eq!(
+            round_trip::<_, EndpointAddr>(&ticket),
+            *ticket.endpoint_addr()
+        );
+
+        let ticket = ticket.with_alpns([b"iroh/test/0"]);
+        assert_eq!(round_trip::<_, EndpointTicket>(&ticket), ticket);
+        let tickets = vec![ticket.clone(), EndpointTicket::new(ticket.addr.clone())];
+        assert_eq!(round_trip::<_, Vec<EndpointTicket>>(&tickets), tickets);
+    }
+
+    #[test]
+    fn test_ticket_postcard_legacy() {
+        assert!(PublicKey::from_bytes(&ALPNS_MARKER).is_err());
+        // Written by older releases, which serialized only the address: the endpoint id, two
+        // addrs, a relay URL and an IPv4 address.
+        let legacy = [
+            "ae58ff8833241ac82d6ff7611046ed67b5072d142c588d0063e942d9a75502b6",
+            "02",
+            "00",
+            "10",
+            "687474703a2f2f646572702e6d652e2f",
+            "01",
+            "00",
+            "7f0000018008",
+        ];
+        let legacy = HEXLOWER.decode(legacy.concat().as_bytes()).unwrap();
+        let ticket = EndpointTicket::new(EndpointAddr::from_parts(
+            PublicKey::from_str("ae58ff8833241ac82d6ff7611046ed67b5072d142c588d0063e942d9a75502b6")
+                .unwrap(),
+            [
+                TransportAddr::Relay("http://derp.me./".parse().unwrap()),
+                TransportAddr::Ip("127.0.0.1:1024".parse().unwrap()),
+            ],
+        ));
+        assert_eq!(
+            postcard::from_bytes::<EndpointTicket>(&legacy).unwrap(),
+            ticket
+        );
+        // Tickets without ALPNs are still written the same way.
+        assert_eq!(postcard::to_stdvec(&ticket).unwrap(), legacy);
+
+        // Both forms can be mixed in a sequence.
+        let tickets = vec![ticket.clone().with_alpns([b"alpn"]), ticket];
+        let bytes = postcard::to_stdvec(&tickets).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<Vec<EndpointTicket>>(&bytes).unwrap(),
+            tickets
+        );
+    }
+
+    #[test]
+    fn test_ticket_parse_errors() {
+        let ticket = make_ticket();
+        let s = ticket.to_string();
+
+        let blob = crate::blob::BlobTicket::new(
+            ticket.endpoint_addr().clone(),
+            [0u8; 32],
+            crate::blob::BlobFormat::Raw,
+        );
+        let err = EndpointTicket::decode_string(&blob.to_string()).unwrap_err();
+        assert!(
+            matches!(&err, ParseError::Kind { expected: "endpoint", found: Some(found), .. } if found == "blob")
+        );
+        assert_eq!(err.to_string(), "wrong kind, expected endpoint, found blob");
+        let err = EndpointTicket::decode_string("nope").unwrap_err();
+        assert!(matches!(err, ParseError::Kind { found: None, .. }));
+
+        let mut invalid = s.clone();
+        invalid.replace_range(12..13, "1");
+        let err = EndpointTicket::decode_string(&invalid).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidCharacter { offset: 12, .. }
+        ));
+
+        let err = EndpointTicket::decode_string(&s[..s.len() - 8]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+
+        let mut bytes = ticket.encode_bytes();
+        bytes.push(0);
+        let err = EndpointTicket::decode_bytes(&bytes).unwrap_err();
+        assert!(matches!(err, ParseError::TrailingBytes { len: 1, .. }));
+
+        let long = vec![0u8; crate::DEFAULT_MAX_LEN + 1];
+        let err = EndpointTicket::decode_bytes(&long).unwrap_err();
+        assert!(matches!(err, ParseError::TooLong { .. }));
+        let long = format!("endpoint{}", "a".repeat(200_000));
+        let err = EndpointTicket::decode_string(&long).unwrap_err();
+        assert!(matches!(err, ParseError::TooLong { len: 200_008, .. }));
+    }
+
+    #[test]
+    fn test_ticket_to_bytes_versioned() {
+        let ticket = make_ticket();
+        assert_eq!(ticket.to_bytes_versioned(0).unwrap(), ticket.encode_bytes());
+        let ticket = ticket.with_alpns([b"echo"]);
+        let err = ticket.to_bytes_versioned(0).unwrap_err();
+        assert_eq!((err.required, err.max_version), (1, 0));
+        let bytes = ticket.to_bytes_versioned(1).unwrap();
+        assert_eq!(EndpointTicket::decode_bytes(&bytes).unwrap(), ticket);
+    }
+
+    #[test]
+    fn test_ticket_unknown_version() {
+        // A ticket from a future release, in a wire format version that does not exist yet.
+        let future = postcard::to_stdvec(&(9u32, [1u8; 32])).unwrap();
+        let s = crate::encode_raw(EndpointTicket::KIND, &future);
+        let err = AnyTicket::decode_string(&s).unwrap_err();
+        let ParseError::UnknownVersion {
+            version: 9, raw, ..
+        } = err
+        else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(raw, future);
+        assert_eq!(crate::encode_raw(EndpointTicket::KIND, &raw), s);
+        assert_eq!(crate::decode_raw(EndpointTicket::KIND, &s).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_ticket_write_to() {
+        let ticket = make_ticket();
+        let mut out = String::from("Ticket: ");
+        ticket.write_to(&mut out);
+        assert_eq!(out, format!("Ticket: {ticket}"));
+
+        let mut out = vec![1, 2];
+        ticket.encode_bytes_into(&mut out);
+        assert_eq!(out[2..], ticket.encode_bytes());
+    }
+
+    #[test]
+    fn test_ticket_lenient() {
+        let ticket = make_ticket();
+        let s = ticket.to_string();
+        let (start, end) = s.split_at(30);
+        for mangled in [
+            format!("  {s}\n"),
+            format!("\"{s}\""),
+            format!("`{s}`"),
+            format!("<{s}>"),
+            format!("'` {s} `'"),
+            format!("{start}\r\n{end}"),
+            format!("{start} \n {end}"),
+        ] {
+            assert!(EndpointTicket::decode_string(&mangled).is_err());
+            assert_eq!(
+                EndpointTicket::decode_string_lenient(&mangled).unwrap(),
+                ticket
+            );
+        }
+        assert!(EndpointTicket::decode_string_lenient(&format!("\"{s}")).is_err());
+    }
+
+    #[test]
+    fn test_ticket_canonical_bytes() {
+        let ticket = make_ticket();
+        assert_eq!(ticket.canonical_bytes(), ticket.encode_bytes());
+
+        let a = ticket.clone().with_alpns([&b"b"[..], b"a"]);
+        let b = ticket.clone().with_alpns([&b"a"[..], b"b", b"a"]);
+        assert_ne!(a.encode_bytes(), b.encode_bytes());
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        let decoded = EndpointTicket::decode_bytes(&a.canonical_bytes()).unwrap();
+        assert_eq!(decoded.alpns(), [b"a", b"b"]);
+    }
+
+    #[test]
+    fn test_ticket_canonicalize() {
+        let ticket = make_ticket();
+        let s = ticket.to_string();
+        assert!(!s.bytes().any(|b| b.is_ascii_uppercase()));
+        let (start, end) = s.split_at(20);
+        let mixed = format!("{start}{}", end.to_ascii_uppercase());
+        for variant in [
+            s.to_ascii_uppercase(),
+            mixed,
+            "EndPoint".to_string() + &s[8..],
+        ] {
+            assert_eq!(EndpointTicket::decode_string(&variant).unwrap(), ticket);
+            assert_eq!(EndpointTicket::canonicalize(&variant).unwrap(), s);
+            assert_eq!(AnyTicket::canonicalize(&variant).unwrap(), s);
+        }
+        assert_eq!(EndpointTicket::canonicalize(&s).unwrap(), s);
+        assert!(EndpointTicket::canonicalize("endpoint!").is_err());
+    }
+
+    #[test]
+    fn test_ticket_builder() {
+        let relay: RelayUrl = "https://relay.example./".parse().unwrap();
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[3u8; 32]).public())
+            .with_relay_url(relay.clone())
+            .with_ip_addr("127.0.0.1:1".parse().unwrap())
+            .with_ip_addr("10.0.0.1:1".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1".parse().unwrap())
+            .with_ip_addr("198.51.100.2:1".parse().unwrap())
+            .with_ip_addr("[fe80::1]:1".parse().unwrap())
+            .with_ip_addr("[2001:db8::1]:1".parse().unwrap());
+        let ips = |ticket: &EndpointTicket| -> Vec<String> {
+            let addr = ticket.endpoint_addr();
+            addr.ip_addrs().map(ToString::to_string).collect()
+        };
+
+        let ticket = EndpointTicket::builder(addr.clone()).build();
+        assert_eq!(ticket, EndpointTicket::new(addr.clone()));
+
+        let ticket = EndpointTicket::builder(addr.clone()).relay_only().build();
+        assert_eq!(
+            ticket.endpoint_addr().addrs,
+            [TransportAddr::Relay(relay)].into()
+        );
+
+        let ticket = EndpointTicket::builder(addr.clone()).direct_only().build();
+        assert_eq!(ticket.endpoint_addr().relay_urls().count(), 0);
+        assert_eq!(ips(&ticket).len(), 6);
+
+        let ticket = EndpointTicket::builder(addr.clone())
+            .strip_private_addrs()
+            .build();
+        assert_eq!(
+            ips(&ticket),
+            ["198.51.100.1:1", "198.51.100.2:1", "[2001:db8::1]:1"]
+        );
+        assert_eq!(ticket.endpoint_addr().relay_urls().count(), 1);
+
+        let ticket = EndpointTicket::builder(addr)
+            .ipv4_only()
+            .strip_private_addrs()
+            .max_direct_addrs(1)
+            .alpns([b"alpn"])
+            .build();
+        assert_eq!(ips(&ticket), ["198.51.100.1:1"]);
+        assert_eq!(ticket.alpns(), [b"alpn".to_vec()]);
+    }
+
+    #[test]
+    fn test_ticket_sanitized() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[4u8; 32]).public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("0.0.0.0:1".parse().unwrap())
+            .with_ip_addr("127.0.0.1:1".parse().unwrap())
+            .with_ip_addr("169.254.0.1:1".parse().unwrap())
+            .with_ip_addr("172.16.5.4:1".parse().unwrap())
+            .with_ip_addr("192.168.1.1:1".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1".parse().unwrap())
+            .with_ip_addr("[::1]:1".parse().unwrap())
+            .with_ip_addr("[::ffff:10.1.2.3]:1".parse().unwrap())
+            .with_ip_addr("[fd00::1]:1".parse().unwrap())
+            .with_ip_addr("[fe80::1]:1".parse().unwrap());
+        let ticket = EndpointTicket::new(addr).with_alpns([b"alpn"]);
+
+        let sanitized = ticket.sanitized();
+        let ips: Vec<_> = sanitized.endpoint_addr().ip_addrs().collect();
+        assert_eq!(ips, [&"198.51.100.1:1".parse().unwrap()]);
+        assert_eq!(sanitized.endpoint_addr().relay_urls().count(), 1);
+        assert_eq!(sanitized.alpns(), ticket.alpns());
+    }
+
+    #[test]
+    fn test_ticket_reachability() {
+        let id = SecretKey::from_bytes(&[4u8; 32]).public();
+        let local = EndpointAddr::new(id)
+            .with_ip_addr("192.168.1.1:1".parse().unwrap())
+            .with_ip_addr("198.51.100.1:0".parse().unwrap())
+            .with_ip_addr("[fe80::1]:1".parse().unwrap())
+            .with_addrs([TransportAddr::Custom(CustomAddr::from_parts(1, b"x"))]);
+        let ticket = EndpointTicket::new(local.clone());
+        assert_eq!(
+            ticket.reachability(),
+            Reachability {
+                relays: 0,
+                public_addrs: 0,
+                local_addrs: 3,
+                custom_addrs: 1,
+            }
+        );
+        assert!(!ticket.is_dialable());
+        assert!(!EndpointTicket::new(EndpointAddr::new(id)).is_dialable());
+
+        let public = EndpointTicket::new(
+            local
+                .clone()
+                .with_ip_addr("[2001:db8::1]:1".parse().unwrap()),
+        );
+        assert_eq!(public.reachability().public_addrs, 1);
+        assert!(public.is_dialable());
+        let relayed =
+            EndpointTicket::new(local.with_relay_url("https://relay.example./".parse().unwrap()));
+        assert_eq!(relayed.reachability().relays, 1);
+        assert!(relayed.is_dialable());
+    }
+
+    #[test]
+    fn test_ticket_ord() {
+        let low = SecretKey::from_bytes(&[1u8; 32]).public();
+        let high = SecretKey::from_bytes(&[2u8; 32]).public();
+        let (low, high) = if low < high { (low, high) } else { (high, low) };
+        let relay = |url: &str| EndpointAddr::new(high).with_relay_url(url.parse().unwrap());
+        let tickets = [
+            EndpointTicket::new(
+                EndpointAddr::new(low).with_relay_url("https://z.example./".parse().unwrap()),
+            ),
+            EndpointTicket::new(EndpointAddr::new(high)),
+            EndpointTicket::new(
+                EndpointAddr::new(high).with_ip_addr("198.51.100.1:1".parse().unwrap()),
+            ),
+            EndpointTicket::new(
+                relay("https://a.example./").with_ip_addr("198.51.100.2:1".parse().unwrap()),
+            ),
+            EndpointTicket::new(
+                relay("https://a.example./").with_ip_addr("198.51.100.3:1".parse().unwrap()),
+            ),
+            EndpointTicket::new(
+                relay("https://a.example./").with_ip_addr("198.51.100.3:1".parse().unwrap()),
+            )
+            .with_alpns([b"a"]),
+            EndpointTicket::new(relay("https://b.example./")),
+        ];
+        let mut sorted = tickets.to_vec();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(sorted, tickets);
+        let set: BTreeSet<_> = tickets.iter().chain(&tickets).cloned().collect();
+        assert_eq!(set.len(), tickets.len());
+
+        let ab = tickets[6].clone().with_alpns([b"a", b"b"]);
+        let ba = tickets[6].clone().with_alpns([b"b", b"a"]);
+        assert_ne!(ab, ba);
+        assert!(ab.same_endpoint(&ba));
+        assert!(!ab.same_endpoint(&tickets[6]));
+        // Different addresses of the same endpoint.
+        assert!(!tickets[3].same_endpoint(&tickets[4]));
+    }
+
+    #[test]
+    fn test_ticket_diff() {
+        let id = SecretKey::from_bytes(&[5u8; 32]).public();
+        let old = EndpointTicket::new(
+            EndpointAddr::new(id)
+                .with_relay_url("https://old.example./".parse().unwrap())
+                .with_ip_addr("198.51.100.1:1".parse().unwrap()),
+        )
+        .with_alpns([b"a", b"b"]);
+        let new = EndpointTicket::new(
+            EndpointAddr::new(id)
+                .with_relay_url("https://new.example./".parse().unwrap())
+                .with_ip_addr("198.51.100.1:1".parse().unwrap())
+                .with_ip_addr("198.51.100.2:1".parse().unwrap()),
+        )
+        .with_alpns([b"b", b"c"]);
+
+        let diff = old.diff(&new);
+        assert!(!diff.id_changed && diff.relay_changed() && diff.addrs_changed());
+        assert_eq!(diff.added.len(), 2);
+        assert_eq!(diff.added_alpns, [b"c".to_vec()]);
+        assert_eq!(diff.removed_alpns, [b"a".to_vec()]);
+        assert_eq!(
+            diff.to_string(),
+            "+relay:https://new.example./, +ip:198.51.100.2:1, -relay:https://old.example./, \
+             +alpn:c, -alpn:a"
+        );
+        assert_eq!(new.diff(&old).removed, diff.added);
+
+        let same = old.clone().with_alpns([b"b", b"a"]);
+        assert!(old.diff(&same).is_empty());
+        assert_eq!(old.diff(&same).to_string(), "no changes");
+        let other = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[6u8; 32]).public(),
+        ));
+        assert!(old.diff(&other).id_changed);
+    }
+
+    #[test]
+    fn test_ticket_parse_loose() {
+        let id = SecretKey::from_bytes(&[7u8; 32]).public();
+        let bare = EndpointTicket::new(EndpointAddr::new(id));
+        let hex = id.to_string();
+        assert_eq!(EndpointTicket::parse_loose(&hex).unwrap(), bare);
+        assert_eq!(
+            EndpointTicket::parse_loose(&format!(" `{}` ", hex.to_ascii_uppercase())).unwrap(),
+            bare
+        );
+        assert_eq!(EndpointTicket::parse_loose(&id.to_z32()).unwrap(), bare);
+
+        let ticket = make_ticket();
+        assert_eq!(
+            EndpointTicket::parse_loose(&format!("\"{ticket}\"")).unwrap(),
+            ticket
+        );
+        // Bare ids are not accepted by the strict parsers.
+        assert!(EndpointTicket::from_str(&hex).is_err());
+        let err = EndpointTicket::parse_loose(&hex[1..]).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }), "{err:?}");
+        assert!(EndpointTicket::parse_loose(&format!("{}x", &hex[1..])).is_err());
+    }
+
+    #[test]
+    fn test_ticket_merge() {
+        let id = SecretKey::from_bytes(&[5u8; 32]).public();
+        let old_relay: RelayUrl = "https://old.example./".parse().unwrap();
+        let new_relay: RelayUrl = "https://new.example./".parse().unwrap();
+        let a = EndpointTicket::new(
+            EndpointAddr::new(id)
+                .with_relay_url(old_relay.clone())
+                .with_ip_addr("198.51.100.1:1".parse().unwrap()),
+        )
+        .with_alpns([b"a"]);
+        let b = EndpointTicket::new(
+            EndpointAddr::new(id)
+                .with_relay_url(new_relay.clone())
+                .with_ip_addr("198.51.100.2:1".parse().unwrap()),
+        )
+        .with_alpns([&b"b"[..], b"a"]);
+
+        let merged = a.merge(&b).unwrap();
+        let relays: Vec<_> = merged.endpoint_addr().relay_urls().collect();
+        assert_eq!(relays, [&new_relay]);
+        assert_eq!(merged.endpoint_addr().ip_addrs().count(), 2);
+        assert_eq!(merged.alpns(), [b"a".to_vec(), b"b".to_vec()]);
+
+        let direct = EndpointTicket::new(
+            EndpointAddr::new(id).with_ip_addr("198.51.100.3:1".parse().unwrap()),
+        );
+        let merged = a.merge(&direct).unwrap();
+        let relays: Vec<_> = merged.endpoint_addr().relay_urls().collect();
+        assert_eq!(relays, [&old_relay]);
+
+        let other = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[6u8; 32]).public(),
+        ));
+        assert!(a.merge(&other).is_err());
+    }
+
+    #[cfg(feature = "iroh")]
+    #[tokio::test]
+    async fn test_ticket_connect() {
+        use iroh::{Endpoint, endpoint::presets};
+
+        const ALPN: &[u8] = b"iroh-tickets/test/0";
+
+        let server = Endpoint::builder(presets::Minimal)
+            .alpns(vec![ALPN.to_vec()])
+            .bind()
+            .await
+            .unwrap();
+        let ticket: EndpointTicket = server.addr().into();
+        let ticket: EndpointTicket = ticket.to_string().parse().unwrap();
+
+        let accept = tokio::spawn(async move {
+            let conn = server.accept().await.unwrap().await.unwrap();
+            let id = conn.remote_id();
+            conn.closed().await;
+            id
+        });
+
+        let client = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let conn = ticket.connect(&client, ALPN).await.unwrap();
+        assert_eq!(conn.remote_id(), ticket.endpoint_addr().id);
+        conn.close(0u32.into(), b"done");
+        assert_eq!(accept.await.unwrap(), client.id());
+    }
 }
@@ -0,0 +1,350 @@
+//! A protocol for exchanging tickets between connected endpoints.
+//!
+//! The [`Exchange`] protocol handler serves tickets to peers that ask for them, e.g. the
+//! current [`EndpointTicket`] of an endpoint, and receives tickets that peers push to it. The
+//! [`ExchangeClient`] is the other side, it requests and pushes tickets by their type.
+//!
+//! Each request uses its own bidirectional stream: the client sends a postcard encoded
+//! request and finishes the stream, the handler answers with a postcard encoded response.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> n0_error::Result<()> {
+//! use iroh::{Endpoint, endpoint::presets, protocol::Router};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     exchange::{ALPN, Exchange, ExchangeClient},
+//! };
+//!
+//! let server = Endpoint::bind(presets::N0).await?;
+//! let router = Router::builder(server.clone())
+//!     .accept(ALPN, Exchange::for_endpoint(server.clone()))
+//!     .spawn();
+//!
+//! let client = Endpoint::bind(presets::N0).await?;
+//! let exchange = ExchangeClient::connect(&client, server.addr()).await?;
+//! let ticket: Option<EndpointTicket> = exchange.get().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use iroh::{
+    Endpoint, EndpointAddr, EndpointId,
+    endpoint::{
+        ClosedStream, ConnectError, Connection, ConnectionError, ReadToEndError, WriteError,
+    },
+    protocol::{AcceptError, ProtocolHandler},
+};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{DEFAULT_MAX_LEN, ParseError, Ticket, bundle::BundleEntry, endpoint::EndpointTicket};
+
+/// The ALPN of the ticket exchange protocol.
+pub const ALPN: &[u8] = b"/iroh-tickets/exchange/1";
+
+/// The maximum size of a request or response, leaving room for the kind.
+const MAX_MESSAGE_LEN: usize = DEFAULT_MAX_LEN + 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    /// Asks for the ticket of the given kind.
+    Get { kind: String },
+    /// Offers a ticket.
+    Push { entry: BundleEntry },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    /// The byte representation of the requested ticket.
+    Ticket { bytes: Vec<u8> },
+    /// There is no ticket of the requested kind.
+    NotFound,
+    /// The pushed ticket was accepted.
+    Accepted,
+    /// The pushed ticket was rejected.
+    Rejected,
+}
+
+type PushHandler = dyn Fn(EndpointId, BundleEntry) -> bool + Send + Sync;
+
+/// Protocol handler serving and receiving tickets, see the [module docs](self).
+///
+/// Clones share the served tickets, so tickets can be updated with [`set`](Self::set) after
+/// the handler was added to a [`Router`](iroh::protocol::Router).
+#[derive(Clone, Default)]
+pub struct Exchange {
+    tickets: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    endpoint: Option<Endpoint>,
+    on_push: Option<Arc<PushHandler>>,
+}
+
+impl fmt::Debug for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tickets = self.tickets.lock().expect("poisoned");
+        f.debug_struct("Exchange")
+            .field("kinds", &tickets.keys().collect::<Vec<_>>())
+            .field("endpoint", &self.endpoint.as_ref().map(Endpoint::id))
+            .field("on_push", &self.on_push.is_some())
+            .finish()
+    }
+}
+
+impl Exchange {
+    /// Creates a handler that does not serve any tickets and rejects pushed tickets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a handler that serves the current [`EndpointTicket`] of `endpoint`.
+    ///
+    /// The ticket is created from [`Endpoint::addr`] for every request, so it contains
+    /// the addresses of the endpoint at that time. A ticket passed to [`set`](Self::set)
+    /// takes precedence.
+    pub fn for_endpoint(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint: Some(endpoint),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the function called with tickets pushed by peers.
+    ///
+    /// The function receives the id of the pushing endpoint and the ticket, and returns
+    /// whether the ticket is accepted. Without a function all pushed tickets are rejected.
+    pub fn on_push(
+        mut self,
+        f: impl Fn(EndpointId, BundleEntry) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.on_push = Some(Arc::new(f));
+        self
+    }
+
+    /// Serves `ticket` to peers asking for its kind, replacing any ticket of that kind.
+    pub fn set<T: Ticket>(&self, ticket: &T) {
+        let mut tickets = self.tickets.lock().expect("poisoned");
+        tickets.insert(T::KIND.to_string(), ticket.encode_bytes());
+    }
+
+    /// Stops serving tickets of the given kind.
+    pub fn remove(&self, kind: &str) {
+        self.tickets.lock().expect("poisoned").remove(kind);
+    }
+
+    fn handle(&self, remote: EndpointId, request: Request) -> Response {
+        match request {
+            Request::Get { kind } => {
+                let bytes = self.tickets.lock().expect("poisoned").get(&kind).cloned();
+                let bytes = bytes.or_else(|| match &self.endpoint {
+                    Some(endpoint) if kind == EndpointTicket::KIND => {
+                        Some(EndpointTicket::new(endpoint.addr()).encode_bytes())
+                    }
+                    _ => None,
+                });
+                match bytes {
+                    Some(bytes) => Response::Ticket { bytes },
+                    None => Response::NotFound,
+                }
+            }
+            Request::Push { entry } => match &self.on_push {
+                Some(on_push) if on_push(remote, entry) => Response::Accepted,
+                _ => Response::Rejected,
+            },
+        }
+    }
+}
+
+impl ProtocolHandler for Exchange {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        let remote = connection.remote_id();
+        // The connection ends when the client closes it.
+        while let Ok((mut send, mut recv)) = connection.accept_bi().await {
+            let request = recv
+                .read_to_end(MAX_MESSAGE_LEN)
+                .await
+                .map_err(AcceptError::from_err)?;
+            let request = postcard::from_bytes(&request).map_err(AcceptError::from_err)?;
+            let response = postcard::to_stdvec(&self.handle(remote, request))
+                .expect("postcard serialization failed");
+            send.write_all(&response)
+                .await
+                .map_err(AcceptError::from_err)?;
+            send.finish().map_err(AcceptError::from_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error talking to an [`Exchange`].
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ExchangeError {
+    /// Connecting to the peer failed.
+    #[error(transparent)]
+    Connect {
+        #[error(source, from, std_err)]
+        source: ConnectError,
+    },
+    /// The connection failed.
+    #[error(transparent)]
+    Connection {
+        #[error(source, from, std_err)]
+        source: ConnectionError,
+    },
+    /// Sending the request failed.
+    #[error(transparent)]
+    Write {
+        #[error(source, from, std_err)]
+        source: WriteError,
+    },
+    /// The stream was closed before the request was sent.
+    #[error(transparent)]
+    ClosedStream {
+        #[error(source, from, std_err)]
+        source: ClosedStream,
+    },
+    /// Receiving the response failed.
+    #[error(transparent)]
+    Read {
+        #[error(source, from, std_err)]
+        source: ReadToEndError,
+    },
+    /// The ticket in the response is invalid.
+    #[error(transparent)]
+    Parse {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+    /// The peer sent a response that does not match the request.
+    #[error("unexpected response")]
+    UnexpectedResponse {},
+}
+
+/// Client for the [`Exchange`] protocol.
+#[derive(Debug, Clone)]
+pub struct ExchangeClient {
+    connection: Connection,
+}
+
+impl ExchangeClient {
+    /// Creates a client using an existing connection with the [`ALPN`] of the protocol.
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Connects to the [`Exchange`] of the given endpoint.
+    pub async fn connect(
+        endpoint: &Endpoint,
+        addr: impl Into<EndpointAddr>,
+    ) -> Result<Self, ExchangeError> {
+        let connection = endpoint.connect(addr, ALPN).await?;
+        Ok(Self::new(connection))
+    }
+
+    /// The connection used by this client.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Asks the peer for its ticket of kind `T`, returning `None` if it has none.
+    pub async fn get<T: Ticket>(&self) -> Result<Option<T>, ExchangeError> {
+        let request = Request::Get {
+            kind: T::KIND.to_string(),
+        };
+        match self.request(&request).await? {
+            Response::Ticket { bytes } => Ok(Some(T::decode_bytes(&bytes)?)),
+            Response::NotFound => Ok(None),
+            _ => Err(e!(ExchangeError::UnexpectedResponse)),
+        }
+    }
+
+    /// Pushes a ticket to the peer, returning whether the peer accepted it.
+    pub async fn push<T: Ticket>(&self, ticket: &T) -> Result<bool, ExchangeError> {
+        let request = Request::Push {
+            entry: BundleEntry::new(ticket),
+        };
+        match self.request(&request).await? {
+            Response::Accepted => Ok(true),
+            Response::Rejected => Ok(false),
+            _ => Err(e!(ExchangeError::UnexpectedResponse)),
+        }
+    }
+
+    async fn request(&self, request: &Request) -> Result<Response, ExchangeError> {
+        let (mut send, mut recv) = self.connection.open_bi().await?;
+        let request = postcard::to_stdvec(request).expect("postcard serialization failed");
+        send.write_all(&request).await?;
+        send.finish()?;
+        let response = recv.read_to_end(MAX_MESSAGE_LEN).await?;
+        postcard::from_bytes(&response).map_err(|_| e!(ExchangeError::UnexpectedResponse))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use iroh::{endpoint::presets, protocol::Router};
+    use iroh_base::SecretKey;
+
+    use super::*;
+    use crate::blob::{BlobFormat, BlobTicket};
+
+    #[tokio::test]
+    async fn test_exchange() {
+        let pushed = Arc::new(Mutex::new(Vec::new()));
+        let server = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let exchange = Exchange::for_endpoint(server.clone()).on_push({
+            let pushed = pushed.clone();
+            move |from, entry| {
+                let accept = entry.kind() == BlobTicket::KIND;
+                pushed.lock().unwrap().push((from, entry));
+                accept
+            }
+        });
+        let router = Router::builder(server.clone())
+            .accept(ALPN, exchange.clone())
+            .spawn();
+
+        let client = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let exchange_client = ExchangeClient::connect(&client, server.addr())
+            .await
+            .unwrap();
+
+        let ticket: EndpointTicket = exchange_client.get().await.unwrap().unwrap();
+        assert_eq!(ticket.endpoint_addr().id, server.id());
+        assert_eq!(exchange_client.get::<BlobTicket>().await.unwrap(), None);
+
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[21u8; 32]).public());
+        let blob = BlobTicket::new(addr.clone(), [1u8; 32], BlobFormat::Raw);
+        exchange.set(&blob);
+        assert_eq!(
+            exchange_client.get::<BlobTicket>().await.unwrap(),
+            Some(blob.clone())
+        );
+        exchange.remove(BlobTicket::KIND);
+        assert_eq!(exchange_client.get::<BlobTicket>().await.unwrap(), None);
+
+        assert!(exchange_client.push(&blob).await.unwrap());
+        assert!(
+            !exchange_client
+                .push(&EndpointTicket::new(addr))
+                .await
+                .unwrap()
+        );
+        let pushed = pushed.lock().unwrap().clone();
+        assert_eq!(pushed.len(), 2);
+        assert_eq!(pushed[0].0, client.id());
+        assert_eq!(pushed[0].1.decode::<BlobTicket>().unwrap(), blob);
+
+        exchange_client.connection().close(0u32.into(), b"done");
+        router.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+//! Tickets with a validity window.
+//!
+//! An [`ExpiringTicket`] wraps another ticket together with the time span in which it is
+//! meant to be used, e.g. for invites that should stop working after a day.
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket that is only valid within a time window.
+///
+/// Timestamps are stored with a precision of seconds. Parsing with [`FromStr`] or
+/// [`Ticket::decode_string`] does not look at the validity window, use
+/// [`decode_string_valid_at`](Self::decode_string_valid_at) to reject tickets that are not
+/// valid.
+///
+/// All expiring tickets share the [`KIND`](Ticket::KIND) `"expiring"`, regardless of the
+/// type of the inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiringTicket<T> {
+    ticket: T,
+    not_before: u64,
+    expires_at: u64,
+}
+
+/// Wire format for [`ExpiringTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0ExpiringTicket {
+    ticket: Vec<u8>,
+    not_before: u64,
+    expires_at: u64,
+}
+
+impl Versioned for Variant0ExpiringTicket {
+    const VERSION: u32 = 0;
+}
+
+fn to_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn from_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+impl<T> ExpiringTicket<T> {
+    /// Creates a ticket that is valid from `not_before` until `expires_at`.
+    ///
+    /// Both times are truncated to whole seconds.
+    pub fn new(ticket: T, not_before: SystemTime, expires_at: SystemTime) -> Self {
+        Self {
+            ticket,
+            not_before: to_secs(not_before),
+            expires_at: to_secs(expires_at),
+        }
+    }
+
+    /// Creates a ticket that is valid from now on for the given duration.
+    pub fn valid_for(ticket: T, duration: Duration) -> Self {
+        let now = SystemTime::now();
+        Self::new(ticket, now, now + duration)
+    }
+
+    /// The wrapped ticket.
+    ///
+    /// This does not check whether the ticket is valid, see [`is_valid_at`](Self::is_valid_at).
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the wrapped ticket.
+    ///
+    /// This does not check whether the ticket is valid, see [`is_valid_at`](Self::is_valid_at).
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+
+    /// The time from which on the ticket is valid.
+    pub fn not_before(&self) -> SystemTime {
+        from_secs(self.not_before)
+    }
+
+    /// The time at which the ticket stops being valid.
+    pub fn expires_at(&self) -> SystemTime {
+        from_secs(self.expires_at)
+    }
+
+    /// Returns whether the ticket is valid at `time`.
+    ///
+    /// The window includes [`not_before`](Self::not_before) and excludes
+    /// [`expires_at`](Self::expires_at).
+    pub fn is_valid_at(&self, time: SystemTime) -> bool {
+        let time = to_secs(time);
+        self.not_before <= time && time < self.expires_at
+    }
+}
+
+impl<T: Ticket> ExpiringTicket<T> {
+    /// Decodes a ticket from its string form and checks that it is valid at `time`.
+    pub fn decode_string_valid_at(s: &str, time: SystemTime) -> Result<Self, ParseError> {
+        let ticket = Self::decode_string(s)?;
+        if to_secs(time) < ticket.not_before {
+            Err(ParseError::verification_failed("ticket is not valid yet"))
+        } else if !ticket.is_valid_at(time) {
+            Err(ParseError::verification_failed("ticket has expired"))
+        } else {
+            Ok(ticket)
+        }
+    }
+}
+
+impl<T: Ticket> Ticket for ExpiringTicket<T> {
+    const KIND: &'static str = "expiring";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0ExpiringTicket {
+            ticket: self.ticket.encode_bytes(),
+            not_before: self.not_before,
+            expires_at: self.expires_at,
+        })
+    }
+
+    /// Uses the canonical bytes of the inner ticket.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0ExpiringTicket {
+            ticket: self.ticket.canonical_bytes(),
+            not_before: self.not_before,
+            expires_at: self.expires_at,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0ExpiringTicket {
+            ticket,
+            not_before,
+            expires_at,
+        } = versioned::decode(bytes)?;
+        Ok(Self {
+            ticket: T::decode_bytes(&ticket)?,
+            not_before,
+            expires_at,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for ExpiringTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for ExpiringTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for ExpiringTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for ExpiringTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_expiring() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[9u8; 32]).public(),
+        ));
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let day = Duration::from_secs(24 * 60 * 60);
+        let expiring = ExpiringTicket::new(ticket, start, start + day);
+
+        let s = expiring.to_string();
+        assert!(s.starts_with("expiring"));
+        let parsed: ExpiringTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed, expiring);
+        assert_eq!(parsed.not_before(), start);
+        assert_eq!(parsed.expires_at(), start + day);
+
+        assert!(!parsed.is_valid_at(start - Duration::from_secs(1)));
+        assert!(parsed.is_valid_at(start));
+        assert!(!parsed.is_valid_at(start + day));
+
+        let valid = ExpiringTicket::<EndpointTicket>::decode_string_valid_at(&s, start);
+        assert_eq!(valid.unwrap(), expiring);
+        let expired = ExpiringTicket::<EndpointTicket>::decode_string_valid_at(&s, start + day);
+        assert!(matches!(expired, Err(ParseError::Verify { .. })));
+    }
+}
@@ -0,0 +1,543 @@
+//! Bindings for Kotlin, Swift and other languages via [UniFFI].
+//!
+//! This exports objects for the built-in ticket types and [`parse_ticket`] for tickets of
+//! any of them, so mobile apps embedding iroh don't have to re-implement ticket parsing in
+//! each language. Generate the bindings from the library of the app that depends on this
+//! crate with the `uniffi` feature, using `uniffi-bindgen` in library mode.
+//!
+//! Endpoint ids, relay URLs and IP addresses are passed as strings, see [`EndpointAddr`].
+//!
+//! [UniFFI]: https://mozilla.github.io/uniffi-rs/
+
+use std::sync::Arc;
+
+use n0_error::{e, stack_error};
+
+use crate::{
+    AnyTicket as RustAnyTicket, ParseError, Ticket, blob, bundle, dns, endpoint, gossip, invite,
+    multi, relay,
+};
+
+/// An error returned by the bindings.
+#[stack_error(derive, add_meta)]
+#[derive(uniffi::Error)]
+#[uniffi(flat_error)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum TicketError {
+    /// Parsing a ticket failed.
+    #[error(transparent)]
+    Parse {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+    /// An argument is invalid, e.g. a field of an [`EndpointAddr`] or a hash of the wrong
+    /// length.
+    #[error("invalid {field}")]
+    InvalidField { field: &'static str },
+}
+
+/// The address of an endpoint, see [`iroh_base::EndpointAddr`].
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct EndpointAddr {
+    /// The endpoint id.
+    pub id: String,
+    /// The relay URLs of the endpoint.
+    pub relay_urls: Vec<String>,
+    /// The direct addresses of the endpoint, as `ip:port` strings.
+    pub ip_addrs: Vec<String>,
+}
+
+impl From<&iroh_base::EndpointAddr> for EndpointAddr {
+    fn from(addr: &iroh_base::EndpointAddr) -> Self {
+        Self {
+            id: addr.id.to_string(),
+            relay_urls: addr.relay_urls().map(ToString::to_string).collect(),
+            ip_addrs: addr.ip_addrs().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl TryFrom<EndpointAddr> for iroh_base::EndpointAddr {
+    type Error = TicketError;
+
+    fn try_from(addr: EndpointAddr) -> Result<Self, Self::Error> {
+        let id = addr
+            .id
+            .parse()
+            .map_err(|_| e!(TicketError::InvalidField { field: "id" }))?;
+        let mut out = iroh_base::EndpointAddr::new(id);
+        for url in addr.relay_urls {
+            let url = url
+                .parse()
+                .map_err(|_| e!(TicketError::InvalidField { field: "relay URL" }))?;
+            out = out.with_relay_url(url);
+        }
+        for ip_addr in addr.ip_addrs {
+            let ip_addr = ip_addr.parse().map_err(|_| {
+                e!(TicketError::InvalidField {
+                    field: "IP address"
+                })
+            })?;
+            out = out.with_ip_addr(ip_addr);
+        }
+        Ok(out)
+    }
+}
+
+fn to_rust_addrs(addrs: Vec<EndpointAddr>) -> Result<Vec<iroh_base::EndpointAddr>, TicketError> {
+    addrs.into_iter().map(TryInto::try_into).collect()
+}
+
+fn to_hash(bytes: Vec<u8>, field: &'static str) -> Result<[u8; 32], TicketError> {
+    bytes
+        .try_into()
+        .map_err(|_| e!(TicketError::InvalidField { field }))
+}
+
+/// Exports the methods shared by all ticket objects.
+macro_rules! ticket_object {
+    ($name:ident, $inner:ty) => {
+        #[uniffi::export]
+        impl $name {
+            /// Parses a ticket from its string form.
+            #[uniffi::constructor]
+            pub fn parse(s: String) -> Result<Self, TicketError> {
+                Ok(Self(<$inner>::decode_string(&s)?))
+            }
+
+            /// Parses a ticket from its string form, tolerating mangling from copy and
+            /// paste.
+            #[uniffi::constructor]
+            pub fn parse_lenient(s: String) -> Result<Self, TicketError> {
+                Ok(Self(<$inner>::decode_string_lenient(&s)?))
+            }
+
+            /// Decodes a ticket from its byte representation.
+            #[uniffi::constructor]
+            pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, TicketError> {
+                Ok(Self(<$inner>::decode_bytes(&bytes)?))
+            }
+
+            /// The string form of the ticket.
+            pub fn encode_string(&self) -> String {
+                self.0.encode_string()
+            }
+
+            /// The byte representation of the ticket.
+            pub fn encode_bytes(&self) -> Vec<u8> {
+                self.0.encode_bytes()
+            }
+
+            /// The fingerprint of the ticket, see [`Ticket::fingerprint`].
+            pub fn fingerprint(&self) -> String {
+                self.0.fingerprint().to_string()
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(ticket: $inner) -> Self {
+                Self(ticket)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(ticket: $name) -> Self {
+                ticket.0
+            }
+        }
+    };
+}
+
+/// An [`endpoint::EndpointTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct EndpointTicket(endpoint::EndpointTicket);
+
+ticket_object!(EndpointTicket, endpoint::EndpointTicket);
+
+#[uniffi::export]
+impl EndpointTicket {
+    /// Creates a ticket for an endpoint, with the given ALPNs.
+    #[uniffi::constructor]
+    pub fn new(addr: EndpointAddr, alpns: Vec<Vec<u8>>) -> Result<Self, TicketError> {
+        let ticket = endpoint::EndpointTicket::new(addr.try_into()?).with_alpns(alpns);
+        Ok(Self(ticket))
+    }
+
+    /// The address of the endpoint.
+    pub fn endpoint_addr(&self) -> EndpointAddr {
+        self.0.endpoint_addr().into()
+    }
+
+    /// The ALPNs of the ticket.
+    pub fn alpns(&self) -> Vec<Vec<u8>> {
+        self.0.alpns().to_vec()
+    }
+}
+
+/// The format of a blob, see [`blob::BlobFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum BlobFormat {
+    /// A raw blob.
+    Raw,
+    /// A sequence of BLAKE3 hashes, whose blobs should be retrieved as well.
+    HashSeq,
+}
+
+impl From<blob::BlobFormat> for BlobFormat {
+    fn from(format: blob::BlobFormat) -> Self {
+        match format {
+            blob::BlobFormat::Raw => Self::Raw,
+            blob::BlobFormat::HashSeq => Self::HashSeq,
+        }
+    }
+}
+
+impl From<BlobFormat> for blob::BlobFormat {
+    fn from(format: BlobFormat) -> Self {
+        match format {
+            BlobFormat::Raw => Self::Raw,
+            BlobFormat::HashSeq => Self::HashSeq,
+        }
+    }
+}
+
+/// A [`blob::BlobTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct BlobTicket(blob::BlobTicket);
+
+ticket_object!(BlobTicket, blob::BlobTicket);
+
+#[uniffi::export]
+impl BlobTicket {
+    /// Creates a ticket for a blob, `hash` must be 32 bytes.
+    #[uniffi::constructor]
+    pub fn new(addr: EndpointAddr, hash: Vec<u8>, format: BlobFormat) -> Result<Self, TicketError> {
+        let hash = to_hash(hash, "hash")?;
+        Ok(Self(blob::BlobTicket::new(
+            addr.try_into()?,
+            hash,
+            format.into(),
+        )))
+    }
+
+    /// The address of the provider.
+    pub fn addr(&self) -> EndpointAddr {
+        self.0.addr().into()
+    }
+
+    /// The BLAKE3 hash of the blob.
+    pub fn hash(&self) -> Vec<u8> {
+        self.0.hash().to_vec()
+    }
+
+    /// The format of the blob.
+    pub fn format(&self) -> BlobFormat {
+        self.0.format().into()
+    }
+}
+
+/// A [`gossip::GossipTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct GossipTicket(gossip::GossipTicket);
+
+ticket_object!(GossipTicket, gossip::GossipTicket);
+
+#[uniffi::export]
+impl GossipTicket {
+    /// Creates a ticket for a topic, `topic` must be 32 bytes.
+    #[uniffi::constructor]
+    pub fn new(topic: Vec<u8>, bootstrap: Vec<EndpointAddr>) -> Result<Self, TicketError> {
+        let topic = to_hash(topic, "topic")?;
+        Ok(Self(gossip::GossipTicket::new(
+            topic,
+            to_rust_addrs(bootstrap)?,
+        )))
+    }
+
+    /// The topic id.
+    pub fn topic(&self) -> Vec<u8> {
+        self.0.topic().to_vec()
+    }
+
+    /// The endpoints to join the topic through.
+    pub fn bootstrap(&self) -> Vec<EndpointAddr> {
+        self.0.bootstrap().iter().map(Into::into).collect()
+    }
+}
+
+/// A [`multi::MultiEndpointTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct MultiEndpointTicket(multi::MultiEndpointTicket);
+
+ticket_object!(MultiEndpointTicket, multi::MultiEndpointTicket);
+
+#[uniffi::export]
+impl MultiEndpointTicket {
+    /// Creates a ticket for several endpoints.
+    #[uniffi::constructor]
+    pub fn new(endpoints: Vec<EndpointAddr>) -> Result<Self, TicketError> {
+        Ok(Self(multi::MultiEndpointTicket::new(to_rust_addrs(
+            endpoints,
+        )?)))
+    }
+
+    /// The addresses of the endpoints.
+    pub fn endpoints(&self) -> Vec<EndpointAddr> {
+        self.0.endpoints().map(Into::into).collect()
+    }
+}
+
+/// A [`relay::RelayTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct RelayTicket(relay::RelayTicket);
+
+ticket_object!(RelayTicket, relay::RelayTicket);
+
+#[uniffi::export]
+impl RelayTicket {
+    /// Creates a ticket for the relay at `url`, with an optional token.
+    #[uniffi::constructor]
+    pub fn new(url: String, token: Option<String>) -> Result<Self, TicketError> {
+        let url = url
+            .parse()
+            .map_err(|_| e!(TicketError::InvalidField { field: "relay URL" }))?;
+        let ticket = relay::RelayTicket::new(url);
+        Ok(Self(match token {
+            Some(token) => ticket.with_token(token),
+            None => ticket,
+        }))
+    }
+
+    /// The URL of the relay.
+    pub fn url(&self) -> String {
+        self.0.url().to_string()
+    }
+
+    /// The token to authenticate with the relay, if any.
+    pub fn token(&self) -> Option<String> {
+        self.0.token().map(ToString::to_string)
+    }
+}
+
+/// A [`dns::DnsTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct DnsTicket(dns::DnsTicket);
+
+ticket_object!(DnsTicket, dns::DnsTicket);
+
+#[uniffi::export]
+impl DnsTicket {
+    /// Creates a ticket for the domain `name`, see [`dns::DnsTicket::new`].
+    #[uniffi::constructor]
+    pub fn new(name: String) -> Self {
+        Self(dns::DnsTicket::new(name))
+    }
+
+    /// The domain name.
+    pub fn name(&self) -> String {
+        self.0.name().to_string()
+    }
+
+    /// The name of the TXT records holding the ticket.
+    pub fn txt_name(&self) -> String {
+        self.0.txt_name()
+    }
+}
+
+/// An [`invite::InviteTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct InviteTicket(invite::InviteTicket);
+
+ticket_object!(InviteTicket, invite::InviteTicket);
+
+#[uniffi::export]
+impl InviteTicket {
+    /// Creates an invite with an optional label, `token` must be 32 bytes.
+    #[uniffi::constructor]
+    pub fn new(
+        addr: EndpointAddr,
+        token: Vec<u8>,
+        label: Option<String>,
+    ) -> Result<Self, TicketError> {
+        let ticket = invite::InviteTicket::new(addr.try_into()?, to_hash(token, "token")?);
+        Ok(Self(match label {
+            Some(label) => ticket.with_label(label),
+            None => ticket,
+        }))
+    }
+
+    /// The address of the endpoint to connect to.
+    pub fn addr(&self) -> EndpointAddr {
+        self.0.addr().into()
+    }
+
+    /// The bearer token to present to the endpoint.
+    pub fn token(&self) -> Vec<u8> {
+        self.0.token().to_vec()
+    }
+
+    /// The label of the invite.
+    pub fn label(&self) -> Option<String> {
+        self.0.label().map(ToString::to_string)
+    }
+}
+
+/// A ticket in a [`BundleTicket`], see [`bundle::BundleEntry`].
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct BundleEntry {
+    /// The kind of the ticket.
+    pub kind: String,
+    /// The byte representation of the ticket, see [`decode_ticket_bytes`].
+    pub bytes: Vec<u8>,
+}
+
+/// A [`bundle::BundleTicket`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display, uniffi::Object)]
+#[uniffi::export(Debug, Display, Eq)]
+pub struct BundleTicket(bundle::BundleTicket);
+
+ticket_object!(BundleTicket, bundle::BundleTicket);
+
+#[uniffi::export]
+impl BundleTicket {
+    /// The tickets in the bundle.
+    pub fn entries(&self) -> Vec<BundleEntry> {
+        self.0
+            .iter()
+            .map(|entry| BundleEntry {
+                kind: entry.kind().to_string(),
+                bytes: entry.bytes().to_vec(),
+            })
+            .collect()
+    }
+}
+
+/// A ticket of any of the built-in kinds, see [`crate::AnyTicket`].
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum AnyTicket {
+    /// An [`EndpointTicket`].
+    Endpoint { ticket: Arc<EndpointTicket> },
+    /// A [`BlobTicket`].
+    Blob { ticket: Arc<BlobTicket> },
+    /// A [`GossipTicket`].
+    Gossip { ticket: Arc<GossipTicket> },
+    /// A [`MultiEndpointTicket`].
+    MultiEndpoint { ticket: Arc<MultiEndpointTicket> },
+    /// A [`BundleTicket`].
+    Bundle { ticket: Arc<BundleTicket> },
+    /// A [`RelayTicket`].
+    Relay { ticket: Arc<RelayTicket> },
+    /// A [`DnsTicket`].
+    Dns { ticket: Arc<DnsTicket> },
+    /// An [`InviteTicket`].
+    Invite { ticket: Arc<InviteTicket> },
+}
+
+impl From<RustAnyTicket> for AnyTicket {
+    fn from(ticket: RustAnyTicket) -> Self {
+        match ticket {
+            RustAnyTicket::Endpoint(ticket) => Self::Endpoint {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Blob(ticket) => Self::Blob {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Gossip(ticket) => Self::Gossip {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::MultiEndpoint(ticket) => Self::MultiEndpoint {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Bundle(ticket) => Self::Bundle {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Relay(ticket) => Self::Relay {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Dns(ticket) => Self::Dns {
+                ticket: Arc::new(ticket.into()),
+            },
+            RustAnyTicket::Invite(ticket) => Self::Invite {
+                ticket: Arc::new(ticket.into()),
+            },
+        }
+    }
+}
+
+/// Parses a ticket string of any of the built-in kinds.
+#[uniffi::export]
+pub fn parse_ticket(s: String) -> Result<AnyTicket, TicketError> {
+    Ok(RustAnyTicket::decode_string(&s)?.into())
+}
+
+/// Parses a ticket string of any of the built-in kinds, tolerating mangling from copy and
+/// paste.
+#[uniffi::export]
+pub fn parse_ticket_lenient(s: String) -> Result<AnyTicket, TicketError> {
+    Ok(RustAnyTicket::decode_string_lenient(&s)?.into())
+}
+
+/// Decodes the byte representation of a ticket of the given kind.
+#[uniffi::export]
+pub fn decode_ticket_bytes(kind: String, bytes: Vec<u8>) -> Result<AnyTicket, TicketError> {
+    Ok(RustAnyTicket::decode_bytes(&kind, &bytes)?.into())
+}
+
+/// Returns the kind of a ticket string without decoding its payload, see
+/// [`crate::kind_of`].
+#[uniffi::export]
+pub fn ticket_kind(s: String) -> Result<String, TicketError> {
+    Ok(crate::kind_of(&s)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        let id = SecretKey::from_bytes(&[31u8; 32]).public();
+        let addr = EndpointAddr {
+            id: id.to_string(),
+            relay_urls: vec!["https://relay.example./".to_string()],
+            ip_addrs: vec!["198.51.100.1:1234".to_string()],
+        };
+        let ticket = EndpointTicket::new(addr.clone(), vec![b"echo".to_vec()]).unwrap();
+        assert_eq!(ticket.endpoint_addr(), addr);
+        let parsed = EndpointTicket::parse(ticket.encode_string()).unwrap();
+        assert_eq!(parsed, ticket);
+        match parse_ticket(ticket.to_string()).unwrap() {
+            AnyTicket::Endpoint { ticket: parsed } => assert_eq!(*parsed, ticket),
+            other => panic!("unexpected ticket {other:?}"),
+        }
+
+        let blob = BlobTicket::new(addr.clone(), vec![7; 32], BlobFormat::HashSeq).unwrap();
+        let blob = BlobTicket::from_bytes(blob.encode_bytes()).unwrap();
+        assert_eq!(blob.hash(), vec![7; 32]);
+        assert_eq!(blob.format(), BlobFormat::HashSeq);
+
+        let err = BlobTicket::new(addr.clone(), vec![7; 31], BlobFormat::Raw).unwrap_err();
+        assert!(matches!(
+            err,
+            TicketError::InvalidField { field: "hash", .. }
+        ));
+        let bad = EndpointAddr {
+            ip_addrs: vec!["not an address".to_string()],
+            ..addr
+        };
+        let err = EndpointTicket::new(bad, vec![]).unwrap_err();
+        assert!(matches!(err, TicketError::InvalidField { .. }));
+        let err = parse_ticket("blob".to_string()).unwrap_err();
+        assert!(matches!(err, TicketError::Parse { .. }));
+    }
+}
@@ -0,0 +1,329 @@
+//! Ticket files.
+//!
+//! A ticket file, with the extension [`EXTENSION`], contains an [armored](crate::armor)
+//! ticket. Besides the `Kind`, the armor headers can contain a `Label` describing the ticket
+//! and the time it was `Created`, in RFC 3339 format in UTC:
+//!
+//! ```text
+//! -----BEGIN IROH TICKET-----
+//! Kind: endpoint
+//! Label: alice's laptop
+//! Created: 2026-10-14T07:42:50Z
+//!
+//! ADtqJ7zOtqQtYqOo0CpvDXNlMhV3HeJDpjrASKGLWdopAA==
+//! =O1Gt
+//! -----END IROH TICKET-----
+//! ```
+//!
+//! This gives tickets exchanged as email attachments or kept next to configuration files a
+//! canonical shape, that can also be read by humans:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, file::TicketFile};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let file = TicketFile::new(ticket).with_label("alice's laptop");
+//! let contents = file.encode();
+//! assert!(contents.contains("\nLabel: alice's laptop\n"));
+//!
+//! let parsed = TicketFile::<EndpointTicket>::decode(&contents).unwrap();
+//! assert_eq!(parsed.label(), Some("alice's laptop"));
+//! assert_eq!(parsed.created_at(), file.created_at());
+//! ```
+
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use n0_error::e;
+
+use crate::{ParseError, Ticket, armor};
+
+/// The file extension of ticket files, without the leading dot.
+pub const EXTENSION: &str = "iroh-ticket";
+
+/// The name of the header containing the label.
+const LABEL_HEADER: &str = "Label";
+
+/// The name of the header containing the creation time.
+const CREATED_HEADER: &str = "Created";
+
+/// Writes `ticket` to a file at `path`, with the current time as its creation time.
+///
+/// Use [`TicketFile`] to set a label.
+pub fn write<T: Ticket>(ticket: &T, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode(ticket, None, Some(now())))
+}
+
+/// Reads a ticket from the file at `path`, ignoring the metadata, see [`TicketFile::read`].
+pub fn read<T: Ticket>(path: impl AsRef<Path>) -> io::Result<T> {
+    TicketFile::read(path).map(TicketFile::into_ticket)
+}
+
+/// The contents of a ticket file, a ticket with optional metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TicketFile<T> {
+    ticket: T,
+    label: Option<String>,
+    created_at: Option<SystemTime>,
+}
+
+impl<T: Ticket> TicketFile<T> {
+    /// Creates the contents of a ticket file created now, rounded down to seconds.
+    pub fn new(ticket: T) -> Self {
+        Self {
+            ticket,
+            label: None,
+            created_at: Some(now()),
+        }
+    }
+
+    /// Sets the label, line breaks are replaced by spaces.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into().replace(['\r', '\n'], " "));
+        self
+    }
+
+    /// Sets the creation time, `None` omits it from the file.
+    ///
+    /// Only whole seconds are stored, times before 1970 are stored as 1970.
+    pub fn with_created_at(mut self, created_at: Option<SystemTime>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// The ticket.
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the ticket.
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+
+    /// The label, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The creation time, if any.
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at
+    }
+
+    /// Encodes the contents of the file.
+    pub fn encode(&self) -> String {
+        encode(&self.ticket, self.label.as_deref(), self.created_at)
+    }
+
+    /// Decodes the contents of a file.
+    ///
+    /// Unknown headers are ignored. Fails with [`ParseError::Armor`] if the `Created` header
+    /// is not a time in the format written by [`encode`](Self::encode), and otherwise like
+    /// [`armor::decode`].
+    pub fn decode(s: &str) -> Result<Self, ParseError> {
+        let (ticket, headers) = armor::decode_with_headers(s)?;
+        let mut file = Self {
+            ticket,
+            label: None,
+            created_at: None,
+        };
+        for (name, value) in headers {
+            if name.eq_ignore_ascii_case(LABEL_HEADER) {
+                file.label = Some(value);
+            } else if name.eq_ignore_ascii_case(CREATED_HEADER) {
+                let created_at = parse_rfc3339(&value).ok_or_else(|| e!(ParseError::Armor))?;
+                file.created_at = Some(created_at);
+            }
+        }
+        Ok(file)
+    }
+
+    /// Writes the file to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.encode())
+    }
+
+    /// Reads the file at `path`.
+    ///
+    /// Invalid contents fail with [`io::ErrorKind::InvalidData`] wrapping the
+    /// [`ParseError`].
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::decode(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn encode<T: Ticket>(ticket: &T, label: Option<&str>, created_at: Option<SystemTime>) -> String {
+    let created_at = created_at.map(format_rfc3339);
+    let mut headers = Vec::new();
+    if let Some(label) = label {
+        headers.push((LABEL_HEADER, label));
+    }
+    if let Some(created_at) = &created_at {
+        headers.push((CREATED_HEADER, created_at.as_str()));
+    }
+    armor::encode_with_headers(ticket, &headers)
+}
+
+/// The current time, rounded down to seconds.
+fn now() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(unix_secs(SystemTime::now()))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats `time` as `YYYY-MM-DDTHH:MM:SSZ`.
+fn format_rfc3339(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Parses a time in the format written by [`format_rfc3339`].
+fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let b = s.as_bytes();
+    if b.len() != 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || b[19] != b'Z' {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| -> Option<u64> {
+        let digits = &s[range];
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if year < 1970 || !(1..=12).contains(&month) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day)?;
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Converts days since 1970-01-01 to a date, see
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a date since 1970 to days since 1970-01-01, returns `None` for invalid days.
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let month_len = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=month_len).contains(&day) {
+        return None;
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year % 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146097 + doe - 719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_rfc3339() {
+        for (secs, s) in [
+            (0, "1970-01-01T00:00:00Z"),
+            (951_782_400, "2000-02-29T00:00:00Z"),
+            (1_791_963_770, "2026-10-14T07:42:50Z"),
+            (4_107_542_399, "2100-02-28T23:59:59Z"),
+        ] {
+            let time = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(format_rfc3339(time), s);
+            assert_eq!(parse_rfc3339(s), Some(time), "{s}");
+        }
+        for s in [
+            "2026-10-14 07:42:50Z",
+            "2026-10-14T07:42:50",
+            "2026-13-01T00:00:00Z",
+            "2026-02-29T00:00:00Z",
+            "2026-10-14T24:00:00Z",
+            "1969-12-31T23:59:59Z",
+            "2026-10-1+T07:42:50Z",
+        ] {
+            assert_eq!(parse_rfc3339(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_ticket_file() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[33u8; 32]).public(),
+        ));
+        let created_at = UNIX_EPOCH + Duration::from_secs(1_791_963_770);
+        let file = TicketFile::new(ticket.clone())
+            .with_label("alice\nlaptop")
+            .with_created_at(Some(created_at));
+        let contents = file.encode();
+        assert!(
+            contents
+                .contains("Kind: endpoint\nLabel: alice laptop\nCreated: 2026-10-14T07:42:50Z\n\n")
+        );
+        assert_eq!(TicketFile::decode(&contents).unwrap(), file);
+        assert_eq!(EndpointTicket::from_armored(&contents).unwrap(), ticket);
+
+        let bare = TicketFile::new(ticket.clone()).with_created_at(None);
+        assert_eq!(bare.encode(), ticket.to_armored());
+        let err = TicketFile::<EndpointTicket>::decode(&contents.replace("07:42:50Z", "later"))
+            .unwrap_err();
+        assert!(matches!(err, ParseError::Armor { .. }));
+
+        let path = std::env::temp_dir().join(format!(
+            "iroh-tickets-file-{}.{EXTENSION}",
+            std::process::id()
+        ));
+        ticket.write_to_file(&path).unwrap();
+        assert_eq!(EndpointTicket::read_from_file(&path).unwrap(), ticket);
+        let read = TicketFile::<EndpointTicket>::read(&path).unwrap();
+        assert!(read.created_at().is_some());
+        fs::write(&path, "not a ticket").unwrap();
+        let err = EndpointTicket::read_from_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+}
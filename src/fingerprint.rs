@@ -0,0 +1,94 @@
+//! Short fingerprints of tickets.
+
+use core::fmt;
+
+use crate::Ticket;
+
+/// Key derivation context for fingerprints.
+const FINGERPRINT_CONTEXT: &str = "iroh-tickets 2026 ticket fingerprint";
+
+/// A short, stable hash of a ticket, see [`Ticket::fingerprint`].
+///
+/// Fingerprints are meant for displaying and comparing tickets in user interfaces. With
+/// 8 bytes they are too short to protect against deliberately crafted collisions, don't use
+/// them to authenticate tickets.
+///
+/// The [`Display`](fmt::Display) form is 16 lowercase hex characters.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint([u8; 8]);
+
+impl Fingerprint {
+    /// Computes the fingerprint of a ticket.
+    pub fn of<T: Ticket>(ticket: &T) -> Self {
+        Self::from_encoded(T::KIND, &ticket.encode_bytes())
+    }
+
+    /// Computes the fingerprint of the byte representation of a ticket of the given kind.
+    pub fn from_encoded(kind: &str, bytes: &[u8]) -> Self {
+        let mut hasher = blake3::Hasher::new_derive_key(FINGERPRINT_CONTEXT);
+        hasher.update(kind.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(bytes);
+        let mut out = [0u8; 8];
+        hasher.finalize_xof().fill(&mut out);
+        Self(out)
+    }
+
+    /// Creates a fingerprint from its bytes, see [`as_bytes`](Self::as_bytes).
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self(bytes)
+    }
+
+    /// The bytes of the fingerprint.
+    pub fn as_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&data_encoding::HEXLOWER.encode(&self.0))
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fingerprint({self})")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        AnyTicket,
+        blob::{BlobFormat, BlobTicket},
+        endpoint::EndpointTicket,
+    };
+
+    #[test]
+    fn test_fingerprint() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[7u8; 32]).public());
+        let ticket = EndpointTicket::new(addr.clone());
+        let fingerprint = ticket.fingerprint();
+        assert_eq!(fingerprint.to_string().len(), 16);
+
+        let parsed: EndpointTicket = ticket.to_string().parse().unwrap();
+        assert_eq!(parsed.fingerprint(), fingerprint);
+        let other = EndpointTicket::new(addr.clone()).with_alpns([b"alpn"]);
+        assert_ne!(other.fingerprint(), fingerprint);
+
+        // The kind is part of the fingerprint.
+        let blob = BlobTicket::new(addr, [0u8; 32], BlobFormat::Raw);
+        assert_ne!(
+            Fingerprint::from_encoded("other", &blob.encode_bytes()),
+            blob.fingerprint()
+        );
+        assert_eq!(
+            AnyTicket::from(blob.clone()).fingerprint(),
+            blob.fingerprint()
+        );
+    }
+}
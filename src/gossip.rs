@@ -0,0 +1,158 @@
+//! Tickets for gossip topics.
+
+use std::str::FromStr;
+
+use iroh_base::EndpointAddr;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket,
+    endpoint::Variant1EndpointAddr,
+    ensure_len,
+    versioned::{self, Versioned},
+};
+
+/// A token containing everything to join a gossip topic.
+///
+/// Contains
+/// - The 32 byte id of the topic.
+/// - The [`EndpointAddr`]s of peers that are already subscribed to the topic, to bootstrap
+///   the swarm from.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct GossipTicket {
+    topic: [u8; 32],
+    bootstrap: Vec<EndpointAddr>,
+}
+
+/// Wire format for [`GossipTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0GossipTicket {
+    topic: [u8; 32],
+    bootstrap: Vec<Variant1EndpointAddr>,
+}
+
+impl Versioned for Variant0GossipTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for GossipTicket {
+    const KIND: &'static str = "topic";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0GossipTicket {
+            topic: self.topic,
+            bootstrap: self.bootstrap.iter().map(Into::into).collect(),
+        })
+    }
+
+    /// The bootstrap peers are sorted and deduplicated.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bootstrap = self.bootstrap.clone();
+        bootstrap.sort();
+        bootstrap.dedup();
+        Self {
+            topic: self.topic,
+            bootstrap,
+        }
+        .encode_bytes()
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0GossipTicket { topic, bootstrap } = versioned::decode(bytes)?;
+        Ok(Self {
+            topic,
+            bootstrap: bootstrap.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl FromStr for GossipTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl GossipTicket {
+    /// Creates a new ticket.
+    pub fn new(topic: [u8; 32], bootstrap: impl IntoIterator<Item = EndpointAddr>) -> Self {
+        Self {
+            topic,
+            bootstrap: bootstrap.into_iter().collect(),
+        }
+    }
+
+    /// The id of the topic.
+    pub fn topic(&self) -> [u8; 32] {
+        self.topic
+    }
+
+    /// The peers to bootstrap the swarm from.
+    pub fn bootstrap(&self) -> &[EndpointAddr] {
+        &self.bootstrap
+    }
+
+    /// Returns the contents of the ticket.
+    pub fn into_parts(self) -> ([u8; 32], Vec<EndpointAddr>) {
+        (self.topic, self.bootstrap)
+    }
+}
+
+impl Serialize for GossipTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.encode_string())
+        } else {
+            let GossipTicket { topic, bootstrap } = self;
+            (topic, bootstrap).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GossipTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::decode_string(&s).map_err(serde::de::Error::custom)
+        } else {
+            let (topic, bootstrap) = Deserialize::deserialize(deserializer)?;
+            Ok(Self { topic, bootstrap })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn test_gossip_ticket_roundtrip() {
+        let peers = [11u8, 12].map(|i| {
+            EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public())
+                .with_ip_addr("127.0.0.1:1234".parse().unwrap())
+        });
+        let ticket = GossipTicket::new([42u8; 32], peers.clone());
+
+        let s = ticket.to_string();
+        assert!(s.starts_with("topic"));
+        assert_eq!(s.parse::<GossipTicket>().unwrap(), ticket);
+
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<GossipTicket>(&bytes).unwrap(),
+            ticket
+        );
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(serde_json::from_str::<GossipTicket>(&json).unwrap(), ticket);
+
+        let [a, b] = peers;
+        let reordered = GossipTicket::new([42u8; 32], [b.clone(), a, b]);
+        assert_ne!(reordered.encode_bytes(), ticket.encode_bytes());
+        assert_eq!(reordered.canonical_bytes(), ticket.canonical_bytes());
+    }
+}
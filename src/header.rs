@@ -0,0 +1,104 @@
+//! Tickets in HTTP headers.
+//!
+//! Tickets are passed in the [`NAME`] header, with the [string form](Ticket::encode_string)
+//! as the value. The string form only contains visible ASCII characters, so it is a valid
+//! header value without any further encoding.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket, header};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let value = ticket.to_header_value();
+//! let line = format!("{}: {value}", header::NAME);
+//! assert!(line.starts_with("Iroh-Ticket: endpoint"));
+//! assert_eq!(EndpointTicket::from_header_value(&value).unwrap(), ticket);
+//! ```
+
+use n0_error::e;
+
+use crate::{ParseError, Ticket};
+
+/// The name of the HTTP header for passing tickets.
+pub const NAME: &str = "Iroh-Ticket";
+
+/// Returns whether `b` is allowed in a ticket header value, a visible ASCII character.
+fn is_allowed(b: u8) -> bool {
+    b.is_ascii_graphic()
+}
+
+/// Encodes a ticket as a header value.
+///
+/// # Panics
+///
+/// In debug builds, if the string form of the ticket contains characters that are not
+/// allowed in header values. This can only happen for tickets that override
+/// [`Ticket::encode_string`].
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let value = ticket.encode_string();
+    debug_assert!(
+        value.bytes().all(is_allowed),
+        "string form is not a valid header value"
+    );
+    value
+}
+
+/// Decodes a ticket from a header value.
+///
+/// Leading and trailing spaces and tabs are ignored, as in HTTP. Any other character that
+/// is not visible ASCII fails with [`ParseError::InvalidCharacter`]. This accepts the raw
+/// bytes of a header value, e.g. from `http::HeaderValue::as_bytes`, as well as strings.
+pub fn decode<T: Ticket>(value: impl AsRef<[u8]>) -> Result<T, ParseError> {
+    let value = value.as_ref();
+    let is_space = |b: &u8| *b == b' ' || *b == b'\t';
+    let start = value
+        .iter()
+        .position(|b| !is_space(b))
+        .unwrap_or(value.len());
+    let end = value
+        .iter()
+        .rposition(|b| !is_space(b))
+        .map_or(start, |i| i + 1);
+    let trimmed = &value[start..end];
+    if let Some(offset) = trimmed.iter().position(|b| !is_allowed(*b)) {
+        return Err(e!(ParseError::InvalidCharacter {
+            offset: start + offset
+        }));
+    }
+    let s = std::str::from_utf8(trimmed).expect("ascii is valid utf8");
+    T::decode_string(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_header_value() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[25u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let value = ticket.to_header_value();
+        assert_eq!(value, ticket.to_string());
+        assert_eq!(EndpointTicket::from_header_value(&value).unwrap(), ticket);
+        let padded = format!(" \t{value} ");
+        assert_eq!(decode::<EndpointTicket>(padded.as_bytes()).unwrap(), ticket);
+
+        for (value, expected) in [
+            (format!("{value}\r\nSet-Cookie: a=b"), value.len()),
+            (format!(" endpoint {}", &value[8..]), 9),
+            (format!("endpoint\u{e9}{}", &value[8..]), 8),
+        ] {
+            let err = decode::<EndpointTicket>(&value).unwrap_err();
+            assert!(
+                matches!(err, ParseError::InvalidCharacter { offset, .. } if offset == expected),
+                "{err:?}"
+            );
+        }
+    }
+}
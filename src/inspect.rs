@@ -0,0 +1,332 @@
+//! Inspecting ticket strings for debugging.
+
+use std::fmt;
+
+use iroh_base::EndpointAddr;
+
+use crate::{AnyTicket, ParseError, kind_of, multibase, strip_kind};
+
+/// A value in the decoded fields of an [`Inspection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldValue {
+    /// A textual value, like an endpoint id or an address.
+    Text(String),
+    /// Binary data, like a hash.
+    Bytes(Vec<u8>),
+    /// A sequence of values.
+    List(Vec<FieldValue>),
+    /// Named values, in the order of the wire format.
+    Map(Vec<(&'static str, FieldValue)>),
+}
+
+impl FieldValue {
+    /// Returns the value for `key` if this is a [`FieldValue::Map`].
+    pub fn get(&self, key: &str) -> Option<&FieldValue> {
+        match self {
+            Self::Map(entries) => entries
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn text(value: impl fmt::Display) -> Self {
+        Self::Text(value.to_string())
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        match self {
+            Self::Text(text) => writeln!(f, " {text}"),
+            Self::Bytes(bytes) => writeln!(f, " {}", data_encoding::HEXLOWER.encode(bytes)),
+            Self::List(values) if values.is_empty() => writeln!(f, " []"),
+            Self::Map(entries) if entries.is_empty() => writeln!(f, " {{}}"),
+            Self::List(values) => {
+                writeln!(f)?;
+                for value in values {
+                    write!(f, "{:indent$}-", "")?;
+                    value.write(f, indent + 2)?;
+                }
+                Ok(())
+            }
+            Self::Map(entries) => {
+                writeln!(f)?;
+                for (name, value) in entries {
+                    write!(f, "{:indent$}{name}:", "")?;
+                    value.write(f, indent + 2)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The result of [`inspect`]ing a ticket string.
+///
+/// The [`Display`](fmt::Display) form lists everything that could be found out about the
+/// ticket, one item per line.
+#[derive(Debug)]
+pub struct Inspection {
+    kind: Option<&'static str>,
+    payload_len: Option<usize>,
+    version: Option<u32>,
+    fields: Option<FieldValue>,
+    error: Option<ParseError>,
+}
+
+impl Inspection {
+    /// The kind of the ticket, if it is one of the [`AnyTicket::KINDS`].
+    pub fn kind(&self) -> Option<&'static str> {
+        self.kind
+    }
+
+    /// The length of the byte representation, if the payload could be decoded.
+    pub fn payload_len(&self) -> Option<usize> {
+        self.payload_len
+    }
+
+    /// The wire format version, the index of the variant of the wire format enum.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// The fields of the ticket, if the ticket could be decoded.
+    ///
+    /// This is a [`FieldValue::Map`] with the names of the fields as keys.
+    pub fn fields(&self) -> Option<&FieldValue> {
+        self.fields.as_ref()
+    }
+
+    /// The error that stopped the inspection, if any.
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "kind: {}", self.kind.unwrap_or("unknown"))?;
+        if let Some(len) = self.payload_len {
+            writeln!(f, "payload: {len} bytes")?;
+        }
+        if let Some(version) = self.version {
+            writeln!(f, "version: {version}")?;
+        }
+        if let Some(fields) = &self.fields {
+            write!(f, "fields:")?;
+            fields.write(f, 2)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reports what can be found out about a ticket string, for debugging tools.
+///
+/// This never fails. It decodes as much of `s` as possible and records the
+/// [error](Inspection::error) that stopped it, e.g. a ticket with a valid payload but an
+/// unknown wire format version reports the [kind](Inspection::kind), the
+/// [payload length](Inspection::payload_len) and the [version](Inspection::version), but
+/// no [fields](Inspection::fields).
+///
+/// Only the built-in [`AnyTicket::KINDS`] are recognized.
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::{endpoint::EndpointTicket, inspect};
+///
+/// let ticket = EndpointTicket::new(EndpointAddr::new(
+///     SecretKey::from_bytes(&[0u8; 32]).public(),
+/// ));
+/// let inspection = inspect(&ticket.to_string());
+/// assert_eq!(inspection.kind(), Some("endpoint"));
+/// assert_eq!(inspection.version(), Some(0));
+/// println!("{inspection}");
+/// ```
+pub fn inspect(s: &str) -> Inspection {
+    let mut inspection = Inspection {
+        kind: None,
+        payload_len: None,
+        version: None,
+        fields: None,
+        error: None,
+    };
+    if let Err(err) = inspect_into(s, &mut inspection) {
+        inspection.error = Some(err);
+    }
+    inspection
+}
+
+fn inspect_into(s: &str, inspection: &mut Inspection) -> Result<(), ParseError> {
+    let kind = kind_of(s)?;
+    inspection.kind = Some(kind);
+    let rest = strip_kind(s, kind).expect("kind_of matched the prefix");
+    let bytes = multibase::decode_payload(rest, kind.len())?;
+    inspection.payload_len = Some(bytes.len());
+    // All built-in kinds use a versioned wire format.
+    let (version, _) = postcard::take_from_bytes::<u32>(&bytes)?;
+    inspection.version = Some(version);
+    let ticket = AnyTicket::decode_bytes(kind, &bytes)?;
+    inspection.fields = Some(fields(&ticket));
+    Ok(())
+}
+
+fn addr(addr: &EndpointAddr) -> FieldValue {
+    FieldValue::Map(vec![
+        ("id", FieldValue::text(addr.id)),
+        (
+            "addrs",
+            FieldValue::List(addr.addrs.iter().map(FieldValue::text).collect()),
+        ),
+    ])
+}
+
+fn fields(ticket: &AnyTicket) -> FieldValue {
+    match ticket {
+        AnyTicket::Endpoint(ticket) => FieldValue::Map(vec![
+            ("addr", addr(ticket.endpoint_addr())),
+            (
+                "alpns",
+                FieldValue::List(
+                    ticket
+                        .alpns()
+                        .iter()
+                        .map(|alpn| match std::str::from_utf8(alpn) {
+                            Ok(text) => FieldValue::text(text),
+                            Err(_) => FieldValue::Bytes(alpn.clone()),
+                        })
+                        .collect(),
+                ),
+            ),
+        ]),
+        AnyTicket::Blob(ticket) => FieldValue::Map(vec![
+            ("addr", addr(ticket.addr())),
+            ("hash", FieldValue::Bytes(ticket.hash().to_vec())),
+            (
+                "format",
+                FieldValue::text(format_args!("{:?}", ticket.format())),
+            ),
+        ]),
+        AnyTicket::Gossip(ticket) => FieldValue::Map(vec![
+            ("topic", FieldValue::Bytes(ticket.topic().to_vec())),
+            (
+                "bootstrap",
+                FieldValue::List(ticket.bootstrap().iter().map(addr).collect()),
+            ),
+        ]),
+        AnyTicket::MultiEndpoint(ticket) => FieldValue::Map(vec![(
+            "endpoints",
+            FieldValue::List(ticket.endpoints().map(addr).collect()),
+        )]),
+        AnyTicket::Relay(ticket) => {
+            let mut fields = vec![("url", FieldValue::text(ticket.url()))];
+            if let Some(token) = ticket.token() {
+                fields.push(("token", FieldValue::text(token)));
+            }
+            FieldValue::Map(fields)
+        }
+        AnyTicket::Dns(ticket) => FieldValue::Map(vec![("name", FieldValue::text(ticket.name()))]),
+        AnyTicket::Invite(ticket) => {
+            let mut fields = vec![
+                ("addr", addr(ticket.addr())),
+                ("token", FieldValue::Bytes(ticket.token().to_vec())),
+            ];
+            if let Some(label) = ticket.label() {
+                fields.push(("label", FieldValue::text(label)));
+            }
+            FieldValue::Map(fields)
+        }
+        AnyTicket::Bundle(ticket) => FieldValue::Map(vec![(
+            "entries",
+            FieldValue::List(
+                ticket
+                    .iter()
+                    .map(|entry| {
+                        let contents = match entry.decode_any() {
+                            Ok(ticket) => fields(&ticket),
+                            Err(_) => FieldValue::Bytes(entry.bytes().to_vec()),
+                        };
+                        FieldValue::Map(vec![
+                            ("kind", FieldValue::text(entry.kind())),
+                            ("ticket", contents),
+                        ])
+                    })
+                    .collect(),
+            ),
+        )]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::SecretKey;
+
+    use super::*;
+    use crate::{Ticket, bundle::BundleTicket, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_inspect() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[20u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr.clone()).with_alpns([b"echo"]);
+        let inspection = inspect(&ticket.to_string());
+        assert_eq!(inspection.kind(), Some("endpoint"));
+        assert_eq!(inspection.payload_len(), Some(ticket.encode_bytes().len()));
+        assert_eq!(inspection.version(), Some(1));
+        assert!(inspection.error().is_none());
+        let fields = inspection.fields().unwrap();
+        assert_eq!(
+            fields.get("addr").unwrap().get("id"),
+            Some(&FieldValue::Text(addr.id.to_string()))
+        );
+        assert_eq!(
+            fields.get("alpns"),
+            Some(&FieldValue::List(vec![FieldValue::Text("echo".into())]))
+        );
+        let text = inspection.to_string();
+        assert!(text.contains("version: 1\n"), "{text}");
+        assert!(text.contains("      - ip:198.51.100.1:1234\n"), "{text}");
+
+        let bundle = BundleTicket::new().with(&ticket);
+        let inspection = inspect(&bundle.to_string());
+        let entries = inspection.fields().unwrap().get("entries").unwrap();
+        let FieldValue::List(entries) = entries else {
+            panic!("{entries:?}");
+        };
+        assert_eq!(entries[0].get("ticket"), Some(fields));
+    }
+
+    #[test]
+    fn test_inspect_errors() {
+        let inspection = inspect("nope");
+        assert_eq!(inspection.kind(), None);
+        assert!(matches!(
+            inspection.error(),
+            Some(ParseError::UnknownKind { .. })
+        ));
+
+        let inspection = inspect("endpoint!");
+        assert_eq!(inspection.kind(), Some("endpoint"));
+        assert_eq!(inspection.payload_len(), None);
+
+        let future = postcard::to_stdvec(&(7u32, 1u8)).unwrap();
+        let s = format!(
+            "endpoint{}",
+            data_encoding::BASE32_NOPAD
+                .encode(&future)
+                .to_ascii_lowercase()
+        );
+        let inspection = inspect(&s);
+        assert_eq!(inspection.payload_len(), Some(2));
+        assert_eq!(inspection.version(), Some(7));
+        assert!(inspection.fields().is_none());
+        assert!(matches!(
+            inspection.error(),
+            Some(ParseError::UnknownVersion { version: 7, .. })
+        ));
+        assert!(inspection.to_string().contains("error: "));
+    }
+}
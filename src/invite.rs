@@ -0,0 +1,321 @@
+//! Tickets for joining an application instance.
+//!
+//! An [`InviteTicket`] contains the address of the endpoint to connect to and a bearer
+//! token the joining peer presents to it. An [`InviteIssuer`] on the inviting endpoint mints
+//! the tokens and later verifies them, without storing the invites it has handed out:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::invite::InviteIssuer;
+//!
+//! let secret_key = SecretKey::from_bytes(&[0u8; 32]);
+//! let issuer = InviteIssuer::from_secret_key(&secret_key);
+//! let addr = EndpointAddr::new(secret_key.public());
+//! let ticket = issuer.mint(addr, 7, Some("alice's laptop".to_string()));
+//!
+//! // The joining peer presents the ticket, the issuer recovers the invite id.
+//! assert_eq!(issuer.verify(&ticket).unwrap(), 7);
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use iroh_base::{EndpointAddr, EndpointId, SecretKey};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket,
+    endpoint::Variant1EndpointAddr,
+    ensure_len,
+    versioned::{self, Versioned},
+};
+
+/// Key derivation context for issuer keys derived from a [`SecretKey`].
+const ISSUER_KEY_CONTEXT: &str = "iroh-tickets 2026 invite issuer key";
+
+/// Domain separation prefix for the token MAC.
+const TOKEN_CONTEXT: &[u8] = b"iroh-tickets invite token\0";
+
+/// The length of the invite id at the start of a token.
+const ID_LEN: usize = 8;
+
+/// A ticket for joining an application instance.
+///
+/// Contains
+/// - The [`EndpointAddr`] of the endpoint to connect to.
+/// - A 32 byte bearer token, anyone holding it can redeem the invite.
+/// - Optionally a label describing the invite, e.g. for whom it was created.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct InviteTicket {
+    addr: EndpointAddr,
+    token: [u8; 32],
+    label: Option<String>,
+}
+
+/// Wire format for [`InviteTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0InviteTicket {
+    addr: Variant1EndpointAddr,
+    token: [u8; 32],
+    label: Option<String>,
+}
+
+impl Versioned for Variant0InviteTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for InviteTicket {
+    const KIND: &'static str = "invite";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0InviteTicket {
+            addr: (&self.addr).into(),
+            token: self.token,
+            label: self.label.clone(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0InviteTicket { addr, token, label } = versioned::decode(bytes)?;
+        Ok(Self {
+            addr: addr.into(),
+            token,
+            label,
+        })
+    }
+
+    /// Covers only the token, which is what the invite is redeemed with.
+    fn revocation_id(&self) -> Fingerprint {
+        Fingerprint::from_encoded(Self::KIND, &self.token)
+    }
+}
+
+impl FromStr for InviteTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl InviteTicket {
+    /// Creates a new ticket with the given token.
+    ///
+    /// Use [`InviteIssuer::mint`] to create tickets whose tokens can be verified.
+    pub fn new(addr: EndpointAddr, token: [u8; 32]) -> Self {
+        Self {
+            addr,
+            token,
+            label: None,
+        }
+    }
+
+    /// Sets the label of the invite.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// The address of the endpoint to connect to.
+    pub fn addr(&self) -> &EndpointAddr {
+        &self.addr
+    }
+
+    /// The bearer token to present to the endpoint.
+    pub fn token(&self) -> &[u8; 32] {
+        &self.token
+    }
+
+    /// The label of the invite.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the contents of the ticket.
+    pub fn into_parts(self) -> (EndpointAddr, [u8; 32], Option<String>) {
+        (self.addr, self.token, self.label)
+    }
+}
+
+impl Serialize for InviteTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.encode_string())
+        } else {
+            let InviteTicket { addr, token, label } = self;
+            (addr, token, label).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for InviteTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::decode_string(&s).map_err(serde::de::Error::custom)
+        } else {
+            let (addr, token, label) = Deserialize::deserialize(deserializer)?;
+            Ok(Self { addr, token, label })
+        }
+    }
+}
+
+/// The token of an invite was not minted by this [`InviteIssuer`].
+///
+/// This is also returned if the token was minted for another endpoint or label.
+#[stack_error(derive, add_meta)]
+#[error("invalid invite token")]
+pub struct InvalidInvite {}
+
+/// Mints and verifies the tokens of [`InviteTicket`]s.
+///
+/// A token consists of an invite id chosen by the application, e.g. a database row or a
+/// counter, and a MAC over the id, the [`EndpointId`] of the ticket and its label. Verifying
+/// a token therefore needs no state besides the issuer key, and returns the invite id so
+/// the application can look up or revoke the invite.
+#[derive(Clone)]
+pub struct InviteIssuer {
+    key: [u8; 32],
+}
+
+impl fmt::Debug for InviteIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InviteIssuer").finish_non_exhaustive()
+    }
+}
+
+impl InviteIssuer {
+    /// Creates an issuer with the given secret key.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Creates an issuer with a key derived from the secret key of an endpoint.
+    pub fn from_secret_key(secret_key: &SecretKey) -> Self {
+        Self::new(blake3::derive_key(
+            ISSUER_KEY_CONTEXT,
+            &secret_key.to_bytes(),
+        ))
+    }
+
+    /// Creates an invite for `addr` with the given id and label.
+    pub fn mint(&self, addr: EndpointAddr, id: u64, label: Option<String>) -> InviteTicket {
+        let token = self.token(&addr.id, id, label.as_deref());
+        InviteTicket { addr, token, label }
+    }
+
+    /// Checks that the token of `ticket` was minted by this issuer and returns its invite
+    /// id.
+    pub fn verify(&self, ticket: &InviteTicket) -> Result<u64, InvalidInvite> {
+        self.verify_token(&ticket.addr.id, &ticket.token, ticket.label())
+    }
+
+    /// Checks that `token` was minted by this issuer for `endpoint_id` and `label` and
+    /// returns its invite id.
+    ///
+    /// This is for peers that present only the token, not the whole ticket.
+    pub fn verify_token(
+        &self,
+        endpoint_id: &EndpointId,
+        token: &[u8; 32],
+        label: Option<&str>,
+    ) -> Result<u64, InvalidInvite> {
+        let id = u64::from_le_bytes(token[..ID_LEN].try_into().expect("8 bytes"));
+        let expected = self.token(endpoint_id, id, label);
+        // Compare in constant time, to not leak how much of the MAC matched.
+        let diff = expected
+            .iter()
+            .zip(token)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            return Err(e!(InvalidInvite));
+        }
+        Ok(id)
+    }
+
+    fn token(&self, endpoint_id: &EndpointId, id: u64, label: Option<&str>) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new_keyed(&self.key);
+        hasher.update(TOKEN_CONTEXT);
+        hasher.update(endpoint_id.as_bytes());
+        hasher.update(&id.to_le_bytes());
+        if let Some(label) = label {
+            hasher.update(&[1]);
+            hasher.update(label.as_bytes());
+        } else {
+            hasher.update(&[0]);
+        }
+        let mut token = [0u8; 32];
+        token[..ID_LEN].copy_from_slice(&id.to_le_bytes());
+        hasher.finalize_xof().fill(&mut token[ID_LEN..]);
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(i: u8) -> EndpointAddr {
+        EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap())
+    }
+
+    #[test]
+    fn test_invite_ticket_roundtrip() {
+        let ticket = InviteTicket::new(addr(1), [5u8; 32]).with_label("guest");
+        let s = ticket.to_string();
+        assert!(s.starts_with("invite"));
+        let parsed: InviteTicket = s.parse().unwrap();
+        assert_eq!(parsed, ticket);
+        assert_eq!(parsed.label(), Some("guest"));
+
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<InviteTicket>(&bytes).unwrap(),
+            ticket
+        );
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+    }
+
+    #[test]
+    fn test_invite_issuer() {
+        let issuer = InviteIssuer::from_secret_key(&SecretKey::from_bytes(&[1u8; 32]));
+        let ticket = issuer.mint(addr(1), 42, Some("guest".into()));
+        assert_eq!(issuer.verify(&ticket).unwrap(), 42);
+        let parsed = InviteTicket::decode_string(&ticket.encode_string()).unwrap();
+        assert_eq!(issuer.verify(&parsed).unwrap(), 42);
+        assert_eq!(
+            issuer
+                .verify_token(&addr(1).id, ticket.token(), Some("guest"))
+                .unwrap(),
+            42
+        );
+        let unlabeled = issuer.mint(addr(1), 42, None);
+        assert_ne!(unlabeled.token(), ticket.token());
+        assert_eq!(issuer.verify(&unlabeled).unwrap(), 42);
+
+        // Changing the label, the endpoint, the id or the issuer invalidates the token.
+        let relabeled = ticket.clone().with_label("admin");
+        assert!(issuer.verify(&relabeled).is_err());
+        let (_, token, label) = ticket.clone().into_parts();
+        let moved = InviteTicket {
+            addr: addr(2),
+            token,
+            label,
+        };
+        assert!(issuer.verify(&moved).is_err());
+        let mut token = *ticket.token();
+        token[0] ^= 1;
+        assert!(
+            issuer
+                .verify_token(&addr(1).id, &token, Some("guest"))
+                .is_err()
+        );
+        let other = InviteIssuer::new([9u8; 32]);
+        assert!(other.verify(&ticket).is_err());
+        assert!(!format!("{issuer:?}").contains('['));
+    }
+}
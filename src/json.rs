@@ -0,0 +1,202 @@
+//! Self-describing JSON representation of tickets.
+//!
+//! [`to_json`] creates a JSON object with the `kind`, the `ticket` string and, for the
+//! built-in kinds, the wire format `version` and the decoded `fields`, as reported by
+//! [`inspect`]. This suits logging pipelines and web APIs that should not
+//! ship opaque base32 blobs:
+//!
+//! ```json
+//! {
+//!   "kind": "endpoint",
+//!   "ticket": "endpoint...",
+//!   "version": 1,
+//!   "fields": {
+//!     "addr": { "id": "...", "addrs": ["ip:198.51.100.1:1234"] },
+//!     "alpns": ["echo"]
+//!   }
+//! }
+//! ```
+//!
+//! Binary fields are hex encoded. [`from_json`] reads the ticket from the `ticket` member,
+//! the decoded fields are for humans and tools only. [`TicketJson`] is the typed form of
+//! the object. With the `ts-rs` feature it derives [`ts_rs::TS`], and
+//! [`typescript_definitions`] returns the TypeScript declarations, to keep web front-ends
+//! in sync with this representation.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let json = ticket.to_json();
+//! assert_eq!(json["kind"], "endpoint");
+//! assert!(json["fields"]["addr"]["id"].is_string());
+//! assert_eq!(EndpointTicket::from_json(&json).unwrap(), ticket);
+//! ```
+
+use std::collections::BTreeMap;
+
+use n0_error::e;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{FieldValue, ParseError, Ticket, inspect, matches_kind};
+
+/// The JSON representation of a ticket, see [`to_json`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[non_exhaustive]
+pub struct TicketJson {
+    /// The kind of the ticket.
+    pub kind: String,
+    /// The wire format version, only present for the built-in kinds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-rs", ts(optional))]
+    pub version: Option<u32>,
+    /// The decoded fields, only present for the built-in kinds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-rs", ts(optional))]
+    pub fields: Option<FieldJson>,
+    /// The canonical string form of the ticket.
+    pub ticket: String,
+}
+
+impl TicketJson {
+    /// Creates the JSON representation of a ticket.
+    pub fn new<T: Ticket>(ticket: &T) -> Self {
+        let s = ticket.encode_string();
+        let inspection = inspect(&s);
+        let builtin = inspection.kind() == Some(T::KIND);
+        Self {
+            kind: T::KIND.to_string(),
+            version: inspection.version().filter(|_| builtin),
+            fields: inspection.fields().filter(|_| builtin).map(FieldJson::from),
+            ticket: s,
+        }
+    }
+}
+
+/// A decoded field of a [`TicketJson`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts-rs", derive(ts_rs::TS))]
+#[serde(untagged)]
+pub enum FieldJson {
+    /// A textual value, binary data is hex encoded.
+    Text(String),
+    /// A sequence of values.
+    List(Vec<FieldJson>),
+    /// Named values.
+    Map(BTreeMap<String, FieldJson>),
+}
+
+impl From<&FieldValue> for FieldJson {
+    fn from(value: &FieldValue) -> Self {
+        match value {
+            FieldValue::Text(text) => Self::Text(text.clone()),
+            FieldValue::Bytes(bytes) => Self::Text(data_encoding::HEXLOWER.encode(bytes)),
+            FieldValue::List(values) => Self::List(values.iter().map(Self::from).collect()),
+            FieldValue::Map(entries) => Self::Map(
+                entries
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), Self::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Creates the JSON representation of a ticket, a [`TicketJson`] as a [`Value`].
+///
+/// `version` and `fields` are only present for the built-in kinds.
+pub fn to_json<T: Ticket>(ticket: &T) -> Value {
+    serde_json::to_value(TicketJson::new(ticket)).expect("ticket JSON serialization failed")
+}
+
+/// The TypeScript declarations of [`TicketJson`] and [`FieldJson`].
+#[cfg(feature = "ts-rs")]
+pub fn typescript_definitions() -> String {
+    use ts_rs::TS;
+
+    fn decl<T: TS>(out: &mut String) {
+        out.push_str(&T::docs().unwrap_or_default());
+        out.push_str("export ");
+        out.push_str(&T::decl());
+        out.push('\n');
+    }
+
+    let mut out = String::new();
+    decl::<TicketJson>(&mut out);
+    out.push('\n');
+    decl::<FieldJson>(&mut out);
+    out
+}
+
+/// Reads a ticket from its JSON representation.
+///
+/// Fails with [`ParseError::Json`] if `json` is not an object with a `ticket` string and
+/// with [`ParseError::Kind`] if its `kind` is not [`T::KIND`](Ticket::KIND).
+pub fn from_json<T: Ticket>(json: &Value) -> Result<T, ParseError> {
+    let kind = json.get("kind").and_then(Value::as_str);
+    let ticket = json.get("ticket").and_then(Value::as_str);
+    let (Some(kind), Some(ticket)) = (kind, ticket) else {
+        return Err(e!(ParseError::Json));
+    };
+    if !matches_kind::<T>(kind) {
+        return Err(ParseError::wrong_kind(T::KIND, kind));
+    }
+    T::decode_string(ticket)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{TicketExt, blob::BlobTicket, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_json() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[34u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr.clone()).with_alpns([b"echo"]);
+        let json = ticket.to_json();
+        assert_eq!(
+            json,
+            json!({
+                "kind": "endpoint",
+                "version": 1,
+                "fields": {
+                    "addr": { "id": addr.id.to_string(), "addrs": ["ip:198.51.100.1:1234"] },
+                    "alpns": ["echo"],
+                },
+                "ticket": ticket.to_string(),
+            })
+        );
+        let parsed: Value = serde_json::from_str(&json.to_string()).unwrap();
+        assert_eq!(EndpointTicket::from_json(&parsed).unwrap(), ticket);
+
+        let blob = BlobTicket::new(addr, [1u8; 32], Default::default()).to_json();
+        assert_eq!(blob["fields"]["hash"], "01".repeat(32));
+        let err = EndpointTicket::from_json(&blob).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+        let err = EndpointTicket::from_json(&json!({ "kind": "endpoint" })).unwrap_err();
+        assert!(matches!(err, ParseError::Json { .. }));
+    }
+
+    #[cfg(feature = "ts-rs")]
+    #[test]
+    fn test_typescript_definitions() {
+        let ts = typescript_definitions();
+        assert!(ts.starts_with("/**\n * The JSON representation of a ticket"));
+        assert!(ts.contains("export type TicketJson = {"));
+        assert!(ts.contains("\nversion?: number,"));
+        assert!(ts.contains("\nfields?: FieldJson,"));
+        assert!(ts.contains("\nticket: string,"));
+        assert!(ts.ends_with(
+            "export type FieldJson = string | Array<FieldJson> | { [key in string]?: FieldJson };\n"
+        ));
+    }
+}
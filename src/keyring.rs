@@ -0,0 +1,291 @@
+//! Storing tickets in the platform keychain with [keyring].
+//!
+//! [`KeyringTicketStore`] is a [`TicketStore`] for tickets that carry secrets, e.g.
+//! [invites](crate::invite) or [sealed](crate::sealed) tickets, which should not be written
+//! to plain files. It uses the macOS and iOS Keychain, the Windows Credential Manager and
+//! the Secret Service on Linux and the BSDs. On other platforms, `keyring` falls back to a
+//! mock store that does not persist anything.
+//!
+//! ```no_run
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, keyring::KeyringTicketStore, store::TicketStore};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let mut store = KeyringTicketStore::new("com.example.app");
+//! store.put("laptop", &ticket, None)?;
+//! let stored = store.get("laptop")?.unwrap();
+//! assert_eq!(stored.decode::<EndpointTicket>()?, ticket);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [keyring]: https://docs.rs/keyring
+
+use std::{collections::BTreeSet, io, time::SystemTime};
+
+use keyring::Entry;
+
+use crate::store::{StoredTicket, TicketStore};
+
+/// The user name of the entry listing the labels of a store.
+const INDEX_USER: &str = "iroh-tickets-index";
+
+/// A [`TicketStore`] keeping the tickets in the platform keychain.
+///
+/// Every ticket is a keychain entry for the service name of the store and its label as the
+/// user name, its secret is the JSON form of the [`StoredTicket`]. Keychains can not list
+/// their entries, so the store keeps the labels in an additional entry with the user name
+/// `iroh-tickets-index`, which can not be used as a label.
+#[derive(Debug, Clone)]
+pub struct KeyringTicketStore {
+    service: String,
+}
+
+impl KeyringTicketStore {
+    /// Creates a store for the entries of `service`, e.g. the bundle identifier of the
+    /// application.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    /// The service name of the keychain entries.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    fn entry(&self, user: &str) -> io::Result<Entry> {
+        Entry::new(&self.service, user).map_err(to_io)
+    }
+
+    fn ticket_entry(&self, label: &str) -> io::Result<Entry> {
+        if label == INDEX_USER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "label is reserved for the index",
+            ));
+        }
+        self.entry(label)
+    }
+
+    fn read_index(&self) -> io::Result<BTreeSet<String>> {
+        match read(&self.entry(INDEX_USER)?)? {
+            Some(index) => Ok(index),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    fn write_index(&self, index: &BTreeSet<String>) -> io::Result<()> {
+        let entry = self.entry(INDEX_USER)?;
+        if index.is_empty() {
+            return delete(&entry).map(|_| ());
+        }
+        entry
+            .set_password(&serde_json::to_string(index)?)
+            .map_err(to_io)
+    }
+
+    /// Returns the stored entry, including expired ones.
+    fn read_ticket(&self, label: &str) -> io::Result<Option<StoredTicket>> {
+        read(&self.ticket_entry(label)?)
+    }
+}
+
+impl TicketStore for KeyringTicketStore {
+    fn insert(&mut self, entry: StoredTicket) -> io::Result<()> {
+        let label = entry.label().to_string();
+        self.ticket_entry(&label)?
+            .set_password(&serde_json::to_string(&entry)?)
+            .map_err(to_io)?;
+        let mut index = self.read_index()?;
+        if index.insert(label) {
+            self.write_index(&index)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, label: &str) -> io::Result<Option<StoredTicket>> {
+        Ok(self.read_ticket(label)?.filter(|entry| !entry.is_expired()))
+    }
+
+    fn list(&self) -> io::Result<Vec<StoredTicket>> {
+        let mut entries = Vec::new();
+        for label in self.read_index()? {
+            entries.extend(self.get(&label)?);
+        }
+        Ok(entries)
+    }
+
+    fn remove(&mut self, label: &str) -> io::Result<bool> {
+        let removed = delete(&self.ticket_entry(label)?)?;
+        let mut index = self.read_index()?;
+        if index.remove(label) {
+            self.write_index(&index)?;
+        }
+        Ok(removed)
+    }
+
+    fn gc(&mut self) -> io::Result<usize> {
+        let now = SystemTime::now();
+        let mut index = self.read_index()?;
+        let before = index.len();
+        let mut removed = 0;
+        for label in index.clone() {
+            match self.read_ticket(&label)? {
+                Some(entry) if !entry.is_expired_at(now) => {}
+                Some(_) => {
+                    delete(&self.ticket_entry(&label)?)?;
+                    index.remove(&label);
+                    removed += 1;
+                }
+                // Removed from the keychain by someone else.
+                None => {
+                    index.remove(&label);
+                }
+            }
+        }
+        if index.len() != before {
+            self.write_index(&index)?;
+        }
+        Ok(removed)
+    }
+}
+
+fn read<T: serde::de::DeserializeOwned>(entry: &Entry) -> io::Result<Option<T>> {
+    match entry.get_password() {
+        Ok(secret) => Ok(Some(serde_json::from_str(&secret)?)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(to_io(err)),
+    }
+}
+
+/// Deletes the entry, returns whether it existed.
+fn delete(entry: &Entry) -> io::Result<bool> {
+    match entry.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(err) => Err(to_io(err)),
+    }
+}
+
+fn to_io(err: keyring::Error) -> io::Error {
+    match err {
+        keyring::Error::Invalid(..) | keyring::Error::TooLong(..) => {
+            io::Error::new(io::ErrorKind::InvalidInput, err)
+        }
+        err => io::Error::other(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use iroh_base::{EndpointAddr, SecretKey};
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    type Secrets = Arc<Mutex<HashMap<(String, String), Vec<u8>>>>;
+
+    /// A keychain in memory, unlike the mock store of `keyring` entries with the same
+    /// service and user share their secret.
+    #[derive(Debug, Default)]
+    struct MemoryKeychain(Secrets);
+
+    #[derive(Debug)]
+    struct MemoryCredential {
+        secrets: Secrets,
+        key: (String, String),
+    }
+
+    impl CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets.insert(self.key.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            let secrets = self.secrets.lock().unwrap();
+            secrets
+                .get(&self.key)
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            let mut secrets = self.secrets.lock().unwrap();
+            secrets
+                .remove(&self.key)
+                .map(|_| ())
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl CredentialBuilderApi for MemoryKeychain {
+        fn build(
+            &self,
+            _target: Option<&str>,
+            service: &str,
+            user: &str,
+        ) -> keyring::Result<Box<Credential>> {
+            Ok(Box::new(MemoryCredential {
+                secrets: self.0.clone(),
+                key: (service.to_string(), user.to_string()),
+            }))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_keyring_ticket_store() {
+        keyring::set_default_credential_builder(Box::new(MemoryKeychain::default()));
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[32u8; 32]).public(),
+        ));
+        let mut store = KeyringTicketStore::new("iroh-tickets-test");
+        store.put("laptop", &ticket, None).unwrap();
+        store
+            .put("phone", &ticket, Some(Duration::from_secs(3600)))
+            .unwrap();
+        store.put("old", &ticket, Some(Duration::ZERO)).unwrap();
+
+        let stored = store.get("laptop").unwrap().unwrap();
+        assert_eq!(stored.decode::<EndpointTicket>().unwrap(), ticket);
+        assert_eq!(store.get("old").unwrap(), None);
+        let labels: Vec<_> = store
+            .list()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.label().to_string())
+            .collect();
+        assert_eq!(labels, ["laptop", "phone"]);
+
+        // A second store for the same service sees the same entries.
+        let mut other = KeyringTicketStore::new("iroh-tickets-test");
+        assert_eq!(other.gc().unwrap(), 1);
+        assert!(other.remove("laptop").unwrap());
+        assert!(!store.remove("laptop").unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(KeyringTicketStore::new("other").list().unwrap().is_empty());
+
+        let err = store.put(INDEX_USER, &ticket, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
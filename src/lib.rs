@@ -1,8 +1,176 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(iroh_docsrs, feature(doc_cfg))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-use n0_error::{e, stack_error};
+extern crate alloc;
 
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "std")]
+pub(crate) use n0_error::e;
+#[cfg(feature = "std")]
+use n0_error::stack_error;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::no_std::e;
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{EncodeError, ParseError, VersionError};
+
+#[cfg(feature = "std")]
+mod any;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "std")]
+pub mod armor;
+#[cfg(feature = "base58")]
+pub mod base58;
+#[cfg(feature = "bech32")]
+pub mod bech32;
+#[cfg(feature = "std")]
+pub mod blob;
+pub mod borrowed;
+#[cfg(feature = "std")]
+pub mod bundle;
+#[cfg(feature = "std")]
+pub mod cached;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "clap")]
+pub mod clap;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod compressed;
+#[cfg(feature = "std")]
+pub mod crockford;
+#[cfg(feature = "std")]
+pub mod data_uri;
+#[cfg(feature = "std")]
+pub mod dns;
+#[cfg(feature = "std")]
 pub mod endpoint;
+#[cfg(feature = "iroh")]
+pub mod exchange;
+#[cfg(feature = "std")]
+pub mod expiring;
+#[cfg(feature = "uniffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod file;
+mod fingerprint;
+#[cfg(feature = "std")]
+pub mod gossip;
+#[cfg(feature = "std")]
+pub mod header;
+#[cfg(feature = "std")]
+mod inspect;
+#[cfg(feature = "std")]
+pub mod invite;
+#[cfg(feature = "serde_json")]
+pub mod json;
+#[cfg(feature = "keyring")]
+pub mod keyring;
+#[cfg(feature = "std")]
+pub mod link;
+#[cfg(feature = "std")]
+pub mod mac;
+#[cfg(feature = "std")]
+pub mod multi;
+pub mod multibase;
+#[cfg(feature = "ndef")]
+pub mod ndef;
+#[cfg(not(feature = "std"))]
+mod no_std;
+#[cfg(feature = "std")]
+pub mod once;
+#[cfg(feature = "std")]
+pub mod parts;
+#[cfg(feature = "std")]
+pub mod pinning;
+#[cfg(feature = "pkarr")]
+pub mod pkarr;
+#[cfg(feature = "std")]
+pub mod policy;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+#[cfg(feature = "password")]
+pub mod protected;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "qr")]
+pub mod qr;
+mod redacted;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod relay;
+#[cfg(feature = "rendezvous")]
+pub mod rendezvous;
+#[cfg(feature = "iroh")]
+pub mod resolver;
+#[cfg(feature = "std")]
+pub mod revocation;
+#[cfg(feature = "rusqlite")]
+pub mod rusqlite;
+#[cfg(feature = "schemars")]
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod scoped;
+#[cfg(feature = "sealed")]
+pub mod sealed;
+#[cfg(feature = "zeroize")]
+pub mod secret;
+pub mod serde_helpers;
+#[cfg(feature = "std")]
+pub mod signed;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod uri;
+#[cfg(feature = "std")]
+mod vectors;
+pub mod versioned;
+#[cfg(feature = "visual")]
+pub mod visual;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "words")]
+pub mod words;
+
+#[cfg(feature = "derive")]
+pub use iroh_tickets_derive::Ticket;
+
+#[cfg(feature = "std")]
+pub use self::{
+    any::{AnyTicket, kind_of, scan},
+    inspect::{FieldValue, Inspection, inspect},
+    vectors::{TestVector, vectors},
+};
+pub use self::{fingerprint::Fingerprint, redacted::Redacted};
+
+#[doc(hidden)]
+pub mod __private {
+    //! Re-exports used by generated code, not part of the public API.
+    pub extern crate alloc;
+    pub use postcard;
+    pub use serde;
+}
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+/// The default for [`Ticket::MAX_LEN`], 64 KiB.
+pub const DEFAULT_MAX_LEN: usize = 64 * 1024;
+
+/// The MIME type of the [string form](Ticket::encode_string) of a ticket.
+pub const MIME_TYPE: &str = "application/x-iroh-ticket";
 
 /// A ticket is a serializable object combining information required for an operation.
 ///
@@ -10,6 +178,7 @@ pub mod endpoint;
 /// [`decode_bytes`], and to and from a canonical string form (the lowercase [`KIND`]
 /// prefix followed by base32 of the bytes) via [`encode_string`] / [`decode_string`].
 /// Implementers only need to provide [`KIND`], [`encode_bytes`], and [`decode_bytes`].
+/// Conversions to other formats are provided for every ticket by [`TicketExt`].
 ///
 /// Versioning is left to the implementer. Some kinds of tickets might need
 /// versioning, others might not.
@@ -26,12 +195,90 @@ pub mod endpoint;
 pub trait Ticket: Sized {
     /// String prefix describing the kind of iroh ticket.
     ///
-    /// This should be lower case ascii characters.
+    /// This must consist of lowercase ascii letters, which is checked at compile time when
+    /// the ticket is encoded or decoded as a string, see [`assert_valid_kind`].
     const KIND: &'static str;
 
+    /// Former kinds of the ticket, still accepted when decoding.
+    ///
+    /// This keeps stored tickets working after a ticket kind was renamed. The default
+    /// [`decode_string`](Self::decode_string) accepts the [`KIND`](Self::KIND) and each of
+    /// these as prefix, while [`encode_string`](Self::encode_string) always uses the
+    /// [`KIND`](Self::KIND). Aliases must be valid kinds as well, see [`assert_valid_kind`].
+    const KIND_ALIASES: &'static [&'static str] = &[];
+
+    #[doc(hidden)]
+    const VALID_KIND: () = {
+        assert_valid_kind(Self::KIND);
+        let mut i = 0;
+        while i < Self::KIND_ALIASES.len() {
+            assert_valid_kind(Self::KIND_ALIASES[i]);
+            i += 1;
+        }
+    };
+
+    /// The maximum length of the byte representation accepted when decoding.
+    ///
+    /// Longer input is rejected with [`ParseError::TooLong`] before it is decoded, which
+    /// protects services parsing untrusted tickets from large allocations. The default
+    /// string decoding enforces the corresponding string length, implementations of
+    /// [`decode_bytes`](Self::decode_bytes) should check the length with [`ensure_len`].
+    const MAX_LEN: usize = DEFAULT_MAX_LEN;
+
     /// Encode the ticket into its byte representation.
+    ///
+    /// The encoding must be deterministic, encoding the same ticket twice produces the same
+    /// bytes. Tickets that are equal but not identical, e.g. containing the same set of
+    /// values in different order, may still produce different bytes, see
+    /// [`canonical_bytes`](Self::canonical_bytes).
     fn encode_bytes(&self) -> Vec<u8>;
 
+    /// Encode the ticket into its canonical byte representation.
+    ///
+    /// Semantically equal tickets produce identical canonical bytes, which allows
+    /// content-addressing and deduplicating tickets by their bytes. Canonical bytes are
+    /// accepted by [`decode_bytes`](Self::decode_bytes) like any other byte representation.
+    ///
+    /// The default implementation returns [`encode_bytes`](Self::encode_bytes), which is
+    /// correct if the byte representation has no redundancy. Tickets containing sets or
+    /// other unordered or normalizable data should override this, e.g. to sort and
+    /// deduplicate the values.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        self.encode_bytes()
+    }
+
+    /// Encode the ticket into its byte representation, returning an error if that fails.
+    ///
+    /// [`encode_bytes`](Self::encode_bytes) can not fail, so tickets whose encoding can
+    /// fail have to panic there. Such tickets should implement this method and use it in
+    /// [`encode_bytes`](Self::encode_bytes), so callers can handle the error instead. The
+    /// default implementation returns the result of [`encode_bytes`](Self::encode_bytes).
+    fn try_encode_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        Ok(self.encode_bytes())
+    }
+
+    /// Append the byte representation of the ticket to `out`.
+    ///
+    /// This allows reusing a buffer when encoding many tickets. The default implementation
+    /// appends the result of [`encode_bytes`](Self::encode_bytes), implementers can
+    /// override it to write directly into `out`, e.g. with [`versioned::encode_into`].
+    fn encode_bytes_into(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.encode_bytes());
+    }
+
+    /// Encode the ticket with the oldest wire format version that can represent it, as long
+    /// as that is at most `max_version`.
+    ///
+    /// This allows producing tickets for a receiver that is known to run an older release,
+    /// which only parses the versions up to `max_version`. Returns a [`VersionError`] if the
+    /// ticket contains data that only newer versions can represent. The default
+    /// implementation returns [`encode_bytes`](Self::encode_bytes) for any `max_version`,
+    /// which is correct for tickets with a single wire format version.
+    fn to_bytes_versioned(&self, max_version: u32) -> Result<Vec<u8>, VersionError> {
+        let _ = max_version;
+        Ok(self.encode_bytes())
+    }
+
     /// Decode a ticket from its byte representation.
     fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError>;
 
@@ -40,40 +287,280 @@ pub trait Ticket: Sized {
     /// The default implementation produces the lowercase [`KIND`](Self::KIND) prefix
     /// followed by base32 (no padding) of [`encode_bytes`](Self::encode_bytes).
     /// Implementers may override this to use a different string encoding, in which
-    /// case [`decode_string`](Self::decode_string) must be overridden to match.
+    /// case [`write_to`](Self::write_to) and [`decode_string`](Self::decode_string) must
+    /// be overridden to match.
     fn encode_string(&self) -> String {
-        let mut out = Self::KIND.to_string();
-        data_encoding::BASE32_NOPAD.encode_append(&self.encode_bytes(), &mut out);
-        out.make_ascii_lowercase();
+        let mut out = String::new();
+        self.write_to(&mut out);
         out
     }
 
+    /// Append the canonical string form of the ticket to `out`.
+    ///
+    /// This allows reusing a buffer when encoding many tickets, see
+    /// [`encode_string`](Self::encode_string) for the format.
+    fn write_to(&self, out: &mut String) {
+        let () = Self::VALID_KIND;
+        write_raw(Self::KIND, &self.encode_bytes(), out);
+    }
+
     /// Decode a ticket from its canonical string form.
     ///
-    /// The default implementation expects the lowercase [`KIND`](Self::KIND) prefix
-    /// followed by base32 (no padding) of the bytes accepted by
-    /// [`decode_bytes`](Self::decode_bytes). Implementers that override
+    /// The default implementation expects the [`KIND`](Self::KIND) prefix, or one of the
+    /// [`KIND_ALIASES`](Self::KIND_ALIASES), followed by base32 (no padding) of the bytes
+    /// accepted by [`decode_bytes`](Self::decode_bytes).
+    /// Both are case insensitive, so uppercase and mixed case strings are accepted while
+    /// [`encode_string`](Self::encode_string) always produces lowercase, see
+    /// [`canonicalize`](Self::canonicalize). Payloads in other encodings with a
+    /// [`multibase`] prefix are detected and decoded as well. Implementers that override
     /// [`encode_string`](Self::encode_string) must override this to match.
     fn decode_string(s: &str) -> Result<Self, ParseError> {
-        let expected = Self::KIND;
-        let Some(rest) = s.strip_prefix(expected) else {
-            return Err(e!(ParseError::Kind { expected }));
-        };
-        let bytes = data_encoding::BASE32_NOPAD.decode(rest.to_ascii_uppercase().as_bytes())?;
-        Self::decode_bytes(&bytes)
+        Self::decode_bytes(&decode_string_payload::<Self>(s)?)
+    }
+
+    /// Converts a ticket string into the string form produced by
+    /// [`encode_string`](Self::encode_string).
+    ///
+    /// Any string accepted by [`decode_string`](Self::decode_string) is accepted, e.g. in
+    /// uppercase or with a [`multibase`] payload. Two strings of the same ticket have the
+    /// same canonical form, so canonical strings can be compared for equality.
+    fn canonicalize(s: &str) -> Result<String, ParseError> {
+        Ok(Self::decode_string(s)?.encode_string())
     }
+
+    /// Decode a ticket from its string form, tolerating mangling from copy and paste.
+    ///
+    /// This removes surrounding quotes, backticks and angle brackets as well as all
+    /// whitespace, including line breaks inserted by email clients, before passing the
+    /// result to [`decode_string`](Self::decode_string).
+    fn decode_string_lenient(s: &str) -> Result<Self, ParseError> {
+        Self::decode_string(&clean_lenient(s))
+    }
+
+    /// A short, stable hash of the ticket, for displaying and comparing tickets.
+    ///
+    /// This covers the [`KIND`](Self::KIND) and [`encode_bytes`](Self::encode_bytes), so
+    /// tickets with the same fingerprint have the same content.
+    fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::of(self)
+    }
+
+    /// Identifies the ticket in a [`RevocationList`](revocation::RevocationList).
+    ///
+    /// The default implementation is the fingerprint of the
+    /// [`KIND`](Self::KIND) and the [`canonical_bytes`](Self::canonical_bytes), so equal
+    /// tickets that are encoded differently can not escape a revocation. Tickets that are
+    /// authorized by a secret they carry, e.g. the token of an [`InviteTicket`], should
+    /// override this to only cover the secret, so changing other fields does not escape a
+    /// revocation either.
+    ///
+    /// [`InviteTicket`]: invite::InviteTicket
+    fn revocation_id(&self) -> Fingerprint {
+        Fingerprint::from_encoded(Self::KIND, &self.canonical_bytes())
+    }
+}
+
+/// Helpers converting a ticket from and to other formats, implemented for every [`Ticket`].
+///
+/// These are built on the [`Ticket`] methods, so tickets only implement those, and wrappers
+/// like [`CachedTicket`](cached::CachedTicket) that change how a ticket is encoded are used
+/// by all of them.
+pub trait TicketExt: Ticket {
+    /// Returns an adapter that displays the ticket without its content, for logging.
+    ///
+    /// ```
+    /// use iroh_base::{EndpointAddr, SecretKey};
+    /// use iroh_tickets::{Ticket, TicketExt, endpoint::EndpointTicket};
+    ///
+    /// let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+    /// let ticket = EndpointTicket::new(addr);
+    /// let redacted = ticket.redacted().to_string();
+    /// assert!(redacted.starts_with("endpoint"));
+    /// assert!(redacted.ends_with(&format!("({})", ticket.fingerprint())));
+    /// ```
+    fn redacted(&self) -> Redacted<'_, Self> {
+        Redacted::new(self)
+    }
+
+    /// Split the string form of the ticket into parts of at most `max_len` bytes, see
+    /// [`parts`].
+    #[cfg(feature = "std")]
+    fn split(&self, max_len: usize) -> Result<Vec<String>, parts::SplitError> {
+        parts::split(self, max_len)
+    }
+
+    /// Encode the ticket as the value of the [`Iroh-Ticket`](header::NAME) HTTP header, see
+    /// [`header`].
+    #[cfg(feature = "std")]
+    fn to_header_value(&self) -> String {
+        header::encode(self)
+    }
+
+    /// Decode a ticket from the value of an HTTP header, see [`header::decode`].
+    #[cfg(feature = "std")]
+    fn from_header_value(value: impl AsRef<[u8]>) -> Result<Self, ParseError> {
+        header::decode(value)
+    }
+
+    /// Encode the ticket as an ASCII-armored block, see [`armor`].
+    #[cfg(feature = "std")]
+    fn to_armored(&self) -> String {
+        armor::encode(self)
+    }
+
+    /// Decode a ticket from an ASCII-armored block, see [`armor::decode`].
+    #[cfg(feature = "std")]
+    fn from_armored(s: &str) -> Result<Self, ParseError> {
+        armor::decode(s)
+    }
+
+    /// Write the ticket to a [ticket file](mod@file) at `path`, see [`file::write`].
+    #[cfg(feature = "std")]
+    fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        file::write(self, path)
+    }
+
+    /// Read a ticket from a [ticket file](mod@file) at `path`, see [`file::read`].
+    #[cfg(feature = "std")]
+    fn read_from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        file::read(path)
+    }
+
+    /// Encode the ticket as a `data:` URI of type [`MIME_TYPE`], see [`data_uri`].
+    #[cfg(feature = "std")]
+    fn to_data_uri(&self) -> String {
+        data_uri::encode(self)
+    }
+
+    /// Decode a ticket from a `data:` URI, see [`data_uri::decode`].
+    #[cfg(feature = "std")]
+    fn from_data_uri(uri: &str) -> Result<Self, ParseError> {
+        data_uri::decode(uri)
+    }
+
+    /// Put the string form of the ticket into the system clipboard, see [`clipboard`].
+    #[cfg(feature = "clipboard")]
+    fn copy_to_clipboard(&self) -> Result<(), clipboard::ClipboardError> {
+        clipboard::copy(self)
+    }
+
+    /// Decode a ticket from the text in the system clipboard, see [`clipboard::paste`].
+    #[cfg(feature = "clipboard")]
+    fn paste_from_clipboard() -> Result<Self, clipboard::ClipboardError> {
+        clipboard::paste()
+    }
+
+    /// Creates the self-describing JSON representation of the ticket, see [`json`].
+    #[cfg(feature = "serde_json")]
+    fn to_json(&self) -> serde_json::Value {
+        json::to_json(self)
+    }
+
+    /// Reads a ticket from the JSON representation created by [`to_json`](Self::to_json).
+    #[cfg(feature = "serde_json")]
+    fn from_json(json: &serde_json::Value) -> Result<Self, ParseError> {
+        json::from_json(json)
+    }
+
+    /// Creates a QR code containing the ticket, see [`qr`].
+    #[cfg(feature = "qr")]
+    fn to_qr(&self) -> Result<qr::Qr, qr::QrError> {
+        qr::Qr::new(self)
+    }
+
+    /// Prints a QR code containing the ticket to stdout, see [`qr::Qr::to_unicode`].
+    ///
+    /// This is for headless servers, where the terminal is the only way to show the ticket.
+    #[cfg(feature = "qr")]
+    fn print_qr(&self) -> Result<(), qr::QrError> {
+        println!("{}", self.to_qr()?.to_unicode());
+        Ok(())
+    }
+
+    /// Encode the ticket as a sequence of words for reading it aloud, see [`words`].
+    #[cfg(feature = "words")]
+    fn to_words(&self) -> String {
+        words::encode(self)
+    }
+
+    /// Decode a ticket from words written by [`to_words`](Self::to_words).
+    #[cfg(feature = "words")]
+    fn from_words(s: &str) -> Result<Self, ParseError> {
+        words::decode(s)
+    }
+}
+
+impl<T: Ticket> TicketExt for T {}
+
+/// An error serializing an iroh ticket, see [`Ticket::try_encode_bytes`].
+#[cfg(feature = "std")]
+#[stack_error(derive, add_meta)]
+#[error("failed to encode ticket")]
+pub struct EncodeError {
+    #[error(source, from, std_err)]
+    source: postcard::Error,
+}
+
+/// A ticket can not be encoded with the requested wire format version, see
+/// [`Ticket::to_bytes_versioned`].
+#[cfg(feature = "std")]
+#[stack_error(derive, add_meta)]
+#[error("the ticket requires wire format version {required}, newer than {max_version}")]
+pub struct VersionError {
+    /// The oldest version that can represent the ticket.
+    pub required: u32,
+    /// The newest version that was allowed.
+    pub max_version: u32,
 }
 
 /// An error deserializing an iroh ticket.
-#[stack_error(derive, add_meta, from_sources)]
+#[cfg(feature = "std")]
+#[stack_error(derive, add_meta)]
 #[allow(missing_docs)]
 #[non_exhaustive]
 pub enum ParseError {
     /// Found a ticket with the wrong prefix, indicating the wrong kind.
-    #[error("wrong prefix, expected {expected}")]
+    #[error(
+        "wrong kind, expected {expected}{}",
+        found.as_ref().map(|found| format!(", found {found}")).unwrap_or_default()
+    )]
     Kind {
         /// The expected prefix.
         expected: &'static str,
+        /// The kind of the ticket, if it is known.
+        found: Option<String>,
+    },
+    /// The string contains a character that is not valid in its encoding.
+    #[error("invalid character at offset {offset}")]
+    InvalidCharacter {
+        /// The byte offset of the character in the string.
+        offset: usize,
+    },
+    /// This looks like a ticket, but base32 decoding failed.
+    #[error(transparent)]
+    Encoding {
+        #[error(source, std_err)]
+        source: data_encoding::DecodeError,
+    },
+    /// The input is longer than allowed for this kind of ticket, see [`Ticket::MAX_LEN`].
+    #[error("ticket is too long, {len} exceeds the maximum of {max}")]
+    TooLong {
+        /// The length of the input.
+        len: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The checksum of the string does not match, it was mistyped or corrupted.
+    #[error("checksum mismatch")]
+    Checksum {},
+    /// The ticket data ends before the ticket is complete.
+    #[error("ticket data is truncated")]
+    Truncated {},
+    /// The ticket data continues after the end of the ticket.
+    #[error("{len} unexpected bytes after the ticket data")]
+    TrailingBytes {
+        /// The number of unexpected bytes.
+        len: usize,
     },
     /// This looks like a ticket, but postcard deserialization failed.
     #[error(transparent)]
@@ -81,12 +568,69 @@ pub enum ParseError {
         #[error(source, std_err)]
         source: postcard::Error,
     },
-    /// This looks like a ticket, but base32 decoding failed.
+    /// This looks like a ticket, but CBOR deserialization failed, see [`codec::Cbor`].
+    #[cfg(feature = "cbor")]
     #[error(transparent)]
-    Encoding {
+    Cbor {
         #[error(source, std_err)]
-        source: data_encoding::DecodeError,
+        source: ciborium::de::Error<std::io::Error>,
     },
+    /// The ticket kind is not one of the kinds that can be parsed here.
+    #[error(
+        "unknown ticket kind{}",
+        suggestion.map(|kind| format!(", did you mean {kind}?")).unwrap_or_default()
+    )]
+    UnknownKind {
+        /// A known kind that is similar to the start of the string, if any.
+        suggestion: Option<&'static str>,
+    },
+    /// The payload uses a wire format version this implementation does not know.
+    ///
+    /// This is usually a ticket from a newer release. `raw` holds its byte representation,
+    /// so it can be stored or forwarded unchanged, e.g. with [`encode_raw`].
+    #[error("unknown wire format version {version}")]
+    UnknownVersion {
+        /// The version found on the wire.
+        version: u32,
+        /// The byte representation of the ticket, including the version.
+        ///
+        /// This is empty if only the payload after the version was available, see
+        /// [`versioned::decode_version`].
+        raw: Vec<u8>,
+    },
+    /// The payload has a multibase prefix for an unsupported encoding.
+    #[error("unknown payload encoding {prefix:?}")]
+    UnknownEncoding {
+        /// The multibase prefix character.
+        prefix: char,
+    },
+    /// A word is not in the word list, see [`TicketExt::from_words`].
+    #[error("unknown word at position {index}")]
+    UnknownWord {
+        /// The position of the word, starting at 0.
+        index: usize,
+    },
+    /// The string is not a valid part of a ticket, see [`parts`].
+    #[error("invalid ticket part")]
+    InvalidPart {},
+    /// Not all parts of a ticket were given, see [`parts`].
+    #[error("{count} ticket parts are missing")]
+    MissingParts {
+        /// The number of missing parts.
+        count: usize,
+    },
+    /// The string is not a valid ticket URI, or a URL does not contain a ticket.
+    #[error("invalid ticket URI")]
+    Uri {},
+    /// The data is not a valid NDEF message.
+    #[error("invalid NDEF message")]
+    Ndef {},
+    /// The string is not a valid ASCII-armored ticket, see [`armor`].
+    #[error("invalid armored ticket")]
+    Armor {},
+    /// The JSON value is not a ticket object, see [`TicketExt::from_json`].
+    #[error("invalid ticket JSON")]
+    Json {},
     /// Verification of the deserialized bytes failed.
     #[error("verification failed: {message}")]
     Verify { message: &'static str },
@@ -98,7 +642,29 @@ impl ParseError {
     ///
     /// Indicate the expected prefix.
     pub fn wrong_prefix(expected: &'static str) -> Self {
-        e!(ParseError::Kind { expected })
+        e!(ParseError::Kind {
+            expected,
+            found: None
+        })
+    }
+
+    /// Returns a [`ParseError`] that indicates a ticket of kind `found` was given where one
+    /// of kind `expected` was expected.
+    pub fn wrong_kind(expected: &'static str, found: impl Into<String>) -> Self {
+        e!(ParseError::Kind {
+            expected,
+            found: Some(found.into())
+        })
+    }
+
+    /// Converts a decoding error for a payload starting at `offset` in the ticket string.
+    pub(crate) fn from_data_encoding(source: data_encoding::DecodeError, offset: usize) -> Self {
+        match source.kind {
+            data_encoding::DecodeKind::Symbol => e!(ParseError::InvalidCharacter {
+                offset: offset + source.position
+            }),
+            _ => e!(ParseError::Encoding { source }),
+        }
     }
 
     /// Return a `ParseError` variant that indicates verification of the
@@ -107,3 +673,285 @@ impl ParseError {
         e!(ParseError::Verify { message })
     }
 }
+
+impl From<postcard::Error> for ParseError {
+    fn from(source: postcard::Error) -> Self {
+        match source {
+            postcard::Error::DeserializeUnexpectedEnd => e!(ParseError::Truncated),
+            source => e!(ParseError::Postcard { source }),
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl From<ciborium::de::Error<std::io::Error>> for ParseError {
+    fn from(source: ciborium::de::Error<std::io::Error>) -> Self {
+        match source {
+            ciborium::de::Error::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                e!(ParseError::Truncated)
+            }
+            source => e!(ParseError::Cbor { source }),
+        }
+    }
+}
+
+impl From<data_encoding::DecodeError> for ParseError {
+    fn from(source: data_encoding::DecodeError) -> Self {
+        Self::from_data_encoding(source, 0)
+    }
+}
+
+/// Decodes postcard data, rejecting data after the end of the value.
+///
+/// Plain [`postcard::from_bytes`] ignores unread input, so a corrupted or extended ticket
+/// would be silently accepted. Fails with [`ParseError::TrailingBytes`] in that case.
+pub fn decode_postcard<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, ParseError> {
+    <codec::Postcard as codec::WireCodec>::decode(bytes)
+}
+
+/// Encodes a byte representation with a kind into the canonical string form.
+///
+/// This produces the same string as [`Ticket::encode_string`] without decoding the bytes,
+/// e.g. to forward a ticket whose wire format version is not known, see
+/// [`ParseError::UnknownVersion`].
+pub fn encode_raw(kind: &str, bytes: &[u8]) -> String {
+    let mut out = String::new();
+    write_raw(kind, bytes, &mut out);
+    out
+}
+
+/// Decodes the byte representation from the string form of a ticket of type `T`, see
+/// [`Ticket::decode_string`].
+///
+/// The length of `s` is checked against [`Ticket::MAX_LEN`] before the payload is decoded.
+pub(crate) fn decode_string_payload<T: Ticket>(s: &str) -> Result<Vec<u8>, ParseError> {
+    let () = T::VALID_KIND;
+    // Allow for a multibase prefix, all supported encodings are at most as long as base32.
+    let max = max_kind_len::<T>() + 2 + data_encoding::BASE32_NOPAD.encode_len(T::MAX_LEN);
+    if s.len() > max {
+        return Err(e!(ParseError::TooLong { len: s.len(), max }));
+    }
+    let rest = strip_ticket_kind::<T>(s)?;
+    multibase::decode_payload(rest, s.len() - rest.len())
+}
+
+#[cfg(feature = "std")]
+/// Whether `kind` is the [`KIND`](Ticket::KIND) of `T` or one of its
+/// [`KIND_ALIASES`](Ticket::KIND_ALIASES), ignoring ascii case.
+pub(crate) fn matches_kind<T: Ticket>(kind: &str) -> bool {
+    kind_matches(kind, T::KIND, T::KIND_ALIASES)
+}
+
+fn kind_matches(kind: &str, expected: &str, aliases: &[&str]) -> bool {
+    core::iter::once(expected)
+        .chain(aliases.iter().copied())
+        .any(|candidate| candidate.eq_ignore_ascii_case(kind))
+}
+
+/// The length of the longest kind [matching](matches_kind) `T`.
+pub(crate) fn max_kind_len<T: Ticket>() -> usize {
+    T::KIND_ALIASES
+        .iter()
+        .fold(T::KIND.len(), |len, alias| len.max(alias.len()))
+}
+
+/// Strips the kind prefix of a ticket of type `T` from `s`, see [`strip_kind_of`].
+pub(crate) fn strip_ticket_kind<T: Ticket>(s: &str) -> Result<&str, ParseError> {
+    strip_kind_of(s, T::KIND, T::KIND_ALIASES)
+}
+
+/// Strips the longest prefix of `s` that is `expected` or one of its `aliases`, ignoring
+/// ascii case.
+pub(crate) fn strip_kind_of<'a>(
+    s: &'a str,
+    expected: &'static str,
+    aliases: &[&str],
+) -> Result<&'a str, ParseError> {
+    let max = aliases
+        .iter()
+        .fold(expected.len(), |len, alias| len.max(alias.len()));
+    let len = (1..=max.min(s.len())).rev().find(|&len| {
+        s.get(..len)
+            .is_some_and(|prefix| kind_matches(prefix, expected, aliases))
+    });
+    let Some(len) = len else {
+        #[cfg(feature = "std")]
+        let found = kind_of(s).ok().map(alloc::string::ToString::to_string);
+        #[cfg(not(feature = "std"))]
+        let found = None;
+        return Err(e!(ParseError::Kind { expected, found }));
+    };
+    Ok(&s[len..])
+}
+
+/// Decodes a string of the given kind into its byte representation.
+///
+/// This is the counterpart of [`encode_raw`], it accepts the same strings as
+/// [`Ticket::decode_string`] without decoding the ticket from the bytes. As only the kind is
+/// known, [`KIND_ALIASES`](Ticket::KIND_ALIASES) are not accepted.
+pub fn decode_raw(kind: &'static str, s: &str) -> Result<Vec<u8>, ParseError> {
+    let rest = strip_kind_of(s, kind, &[])?;
+    multibase::decode_payload(rest, kind.len())
+}
+
+/// Lowercase base32 without padding, also accepting uppercase input when decoding.
+pub(crate) const BASE32_LOWER: data_encoding::Encoding = data_encoding_macro::new_encoding! {
+    symbols: "abcdefghijklmnopqrstuvwxyz234567",
+    translate_from: "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    translate_to: "abcdefghijklmnopqrstuvwxyz",
+};
+
+/// Appends the kind and the base32 of `bytes` to `out` in a single pass.
+pub(crate) fn write_raw(kind: &str, bytes: &[u8], out: &mut String) {
+    out.reserve(kind.len() + BASE32_LOWER.encode_len(bytes.len()));
+    out.push_str(kind);
+    BASE32_LOWER.encode_append(bytes, out);
+}
+
+/// Rejects byte representations longer than [`Ticket::MAX_LEN`].
+///
+/// This is meant to be called at the start of [`Ticket::decode_bytes`].
+pub fn ensure_len<T: Ticket>(bytes: &[u8]) -> Result<(), ParseError> {
+    if bytes.len() > T::MAX_LEN {
+        return Err(e!(ParseError::TooLong {
+            len: bytes.len(),
+            max: T::MAX_LEN
+        }));
+    }
+    Ok(())
+}
+
+/// Panics if `kind` is not a valid [`Ticket::KIND`].
+///
+/// Kinds must be non-empty and consist of lowercase ascii letters. Other characters would
+/// be changed by case insensitive decoding, confused with the base32 payload or removed by
+/// [lenient decoding](Ticket::decode_string_lenient). In a const context the panic is a
+/// compile error, the default string encoding and decoding of [`Ticket`] check the kind
+/// this way:
+///
+/// ```compile_fail
+/// use iroh_tickets::{ParseError, Ticket};
+///
+/// struct BadTicket;
+///
+/// impl Ticket for BadTicket {
+///     const KIND: &'static str = "Bad Ticket";
+///
+///     fn encode_bytes(&self) -> Vec<u8> {
+///         Vec::new()
+///     }
+///
+///     fn decode_bytes(_bytes: &[u8]) -> Result<Self, ParseError> {
+///         Ok(Self)
+///     }
+/// }
+///
+/// BadTicket.encode_string();
+/// ```
+pub const fn assert_valid_kind(kind: &str) {
+    let bytes = kind.as_bytes();
+    assert!(!bytes.is_empty(), "ticket kind must not be empty");
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(
+            bytes[i].is_ascii_lowercase(),
+            "ticket kind must consist of lowercase ascii letters"
+        );
+        i += 1;
+    }
+}
+
+/// Undoes common mangling of ticket strings, see [`Ticket::decode_string_lenient`].
+pub(crate) fn clean_lenient(s: &str) -> String {
+    let mut s = s.trim();
+    loop {
+        let inner = [("\"", "\""), ("'", "'"), ("`", "`"), ("<", ">")]
+            .into_iter()
+            .find_map(|(start, end)| s.strip_prefix(start)?.strip_suffix(end));
+        match inner {
+            Some(inner) => s = inner.trim(),
+            None => break,
+        }
+    }
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(feature = "std")]
+/// Returns the kind that is closest to the start of `s`, to suggest it for typos.
+///
+/// Kinds are compared by edit distance, allowing one edit for every three characters.
+fn suggest_kind<'a>(s: &str, kinds: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let s = s.as_bytes();
+    kinds
+        .into_iter()
+        .filter_map(|kind| {
+            let len = kind.len();
+            let distance = (len.saturating_sub(1)..=len + 1)
+                .filter_map(|end| s.get(..end))
+                .map(|start| edit_distance(kind.as_bytes(), start))
+                .min()?;
+            (distance <= len / 3).then_some((distance, kind))
+        })
+        .min_by_key(|(distance, kind)| (*distance, core::cmp::Reverse(kind.len())))
+        .map(|(_, kind)| kind)
+}
+
+#[cfg(feature = "std")]
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(x != y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(feature = "std")]
+/// Returns the longest of `kinds` that `s` starts with.
+fn longest_kind_prefix<'a>(s: &str, kinds: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    kinds
+        .into_iter()
+        .filter(|kind| strip_kind(s, kind).is_some())
+        .max_by_key(|kind| kind.len())
+}
+
+#[cfg(feature = "std")]
+/// Strips the `kind` prefix from `s`, ignoring ascii case.
+pub(crate) fn strip_kind<'a>(s: &'a str, kind: &str) -> Option<&'a str> {
+    let prefix = s.get(..kind.len())?;
+    prefix.eq_ignore_ascii_case(kind).then(|| &s[kind.len()..])
+}
+
+/// Serializes a ticket as its string form in human readable formats and as its bytes
+/// otherwise.
+pub(crate) fn serialize_ticket<T: Ticket, S: serde::Serializer>(
+    ticket: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&ticket.encode_string())
+    } else {
+        serializer.serialize_bytes(&ticket.encode_bytes())
+    }
+}
+
+/// Deserializes a ticket written by [`serialize_ticket`].
+pub(crate) fn deserialize_ticket<'de, T: Ticket, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    use serde::Deserialize;
+
+    if deserializer.is_human_readable() {
+        let s = String::deserialize(deserializer)?;
+        T::decode_string(&s).map_err(serde::de::Error::custom)
+    } else {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        T::decode_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
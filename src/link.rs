@@ -0,0 +1,204 @@
+//! Tickets in web links.
+//!
+//! [`embed_in_url`] adds a ticket to any URL as the [`PARAM`] parameter, in the query or in
+//! the fragment, so a link to a web page can carry a ticket for "click this link to join"
+//! flows. [`extract_from_url`] finds the ticket in such a link again. A ticket in the
+//! fragment is not sent to the web server, the page reads it in the browser.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     link::{self, Placement},
+//! };
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let url = link::embed_in_url(
+//!     "https://example.com/join?room=1",
+//!     &ticket,
+//!     Placement::Fragment,
+//! );
+//! assert!(url.starts_with("https://example.com/join?room=1#ticket=endpoint"));
+//! assert_eq!(
+//!     link::extract_from_url::<EndpointTicket>(&url).unwrap(),
+//!     ticket
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use n0_error::e;
+
+use crate::{ParseError, Ticket, uri::percent_decode};
+
+/// The name of the URL parameter holding the ticket.
+pub const PARAM: &str = "ticket";
+
+/// Where in a URL [`embed_in_url`] puts the ticket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Placement {
+    /// In the query, sent to the web server.
+    Query,
+    /// In the fragment, only visible to the page in the browser.
+    #[default]
+    Fragment,
+}
+
+/// The parts of a URL, split at the first `?` and the first `#`.
+struct Parts<'a> {
+    base: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> Parts<'a> {
+    fn new(url: &'a str) -> Self {
+        let (rest, fragment) = match url.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (url, None),
+        };
+        let (base, query) = match rest.split_once('?') {
+            Some((base, query)) => (base, Some(query)),
+            None => (rest, None),
+        };
+        Self {
+            base,
+            query,
+            fragment,
+        }
+    }
+}
+
+fn is_param(pair: &str) -> bool {
+    let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+    key == PARAM
+}
+
+/// Returns `params` with the ticket parameter replaced by `value`.
+fn with_param(params: Option<&str>, value: &str) -> String {
+    let mut out = String::new();
+    for pair in params.into_iter().flat_map(|p| p.split('&')) {
+        if !pair.is_empty() && !is_param(pair) {
+            out.push_str(pair);
+            out.push('&');
+        }
+    }
+    out.push_str(PARAM);
+    out.push('=');
+    out.push_str(value);
+    out
+}
+
+/// Returns the percent-decoded value of the ticket parameter in `params`.
+fn find_param(params: &str) -> Option<Result<String, ParseError>> {
+    params
+        .split('&')
+        .find(|pair| is_param(pair))
+        .map(|pair| percent_decode(pair.split_once('=').map_or("", |(_, value)| value)))
+}
+
+/// Percent-encodes all characters but the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(char::from(b));
+        } else {
+            write!(out, "%{b:02X}").expect("writing to a string is infallible");
+        }
+    }
+    out
+}
+
+/// Adds `ticket` to `url` as the [`PARAM`] parameter, replacing an existing one.
+///
+/// Other parameters in the query or fragment are kept.
+pub fn embed_in_url<T: Ticket>(url: &str, ticket: &T, placement: Placement) -> String {
+    let Parts {
+        base,
+        query,
+        fragment,
+    } = Parts::new(url);
+    let value = percent_encode(&ticket.encode_string());
+    let (query, fragment) = match placement {
+        Placement::Query => (Some(with_param(query, &value)), fragment.map(String::from)),
+        Placement::Fragment => (query.map(String::from), Some(with_param(fragment, &value))),
+    };
+    let mut out = String::from(base);
+    if let Some(query) = query {
+        out.push('?');
+        out.push_str(&query);
+    }
+    if let Some(fragment) = fragment {
+        out.push('#');
+        out.push_str(&fragment);
+    }
+    out
+}
+
+/// Extracts the ticket from the [`PARAM`] parameter of a URL created with
+/// [`embed_in_url`].
+///
+/// The fragment is searched before the query. Fails with [`ParseError::Uri`] if neither
+/// contains the parameter.
+pub fn extract_from_url<T: Ticket>(url: &str) -> Result<T, ParseError> {
+    let Parts {
+        query, fragment, ..
+    } = Parts::new(url.trim());
+    let value = fragment
+        .and_then(find_param)
+        .or_else(|| query.and_then(find_param))
+        .ok_or_else(|| e!(ParseError::Uri))??;
+    T::decode_string(&value)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_embed_extract() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[26u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let s = ticket.to_string();
+
+        let url = embed_in_url("https://example.com/", &ticket, Placement::Query);
+        assert_eq!(url, format!("https://example.com/?ticket={s}"));
+        assert_eq!(extract_from_url::<EndpointTicket>(&url).unwrap(), ticket);
+
+        let url = embed_in_url(
+            "https://example.com/join?ticket=old&room=1#section",
+            &ticket,
+            Placement::Query,
+        );
+        assert_eq!(
+            url,
+            format!("https://example.com/join?room=1&ticket={s}#section")
+        );
+
+        let url = embed_in_url("https://example.com/?a=1#b=2", &ticket, Placement::Fragment);
+        assert_eq!(url, format!("https://example.com/?a=1#b=2&ticket={s}"));
+        assert_eq!(extract_from_url::<EndpointTicket>(&url).unwrap(), ticket);
+
+        // Percent-encoded values, e.g. after a browser normalized the URL.
+        let escaped = url.replace("ticket=endpoint", "ticket=%65ndpoint");
+        assert_eq!(
+            extract_from_url::<EndpointTicket>(&escaped).unwrap(),
+            ticket
+        );
+
+        for url in [
+            "https://example.com/?tickets=abc",
+            "https://example.com/#ticket=%zz",
+        ] {
+            let err = extract_from_url::<EndpointTicket>(url).unwrap_err();
+            assert!(matches!(err, ParseError::Uri { .. }), "{url}");
+        }
+        assert_eq!(percent_encode("a b/é"), "a%20b%2F%C3%A9");
+    }
+}
@@ -0,0 +1,230 @@
+//! Tickets authenticated with a shared secret.
+//!
+//! A [`MacTicket`] wraps another ticket together with a keyed [BLAKE3] MAC over it. Services
+//! that trust each other and share a 32 byte key can detect tickets that were modified in
+//! transit, without managing endpoint keys as a [`SignedTicket`] does:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{Ticket, endpoint::EndpointTicket, mac::MacTicket};
+//!
+//! let key = [7u8; 32];
+//! let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+//! let ticket = MacTicket::new(EndpointTicket::new(addr), &key);
+//!
+//! let parsed = MacTicket::<EndpointTicket>::decode_string(&ticket.encode_string()).unwrap();
+//! assert!(parsed.verify(&key).is_ok());
+//! assert!(parsed.verify(&[8u8; 32]).is_err());
+//! ```
+//!
+//! Anyone holding the key can create valid tickets, so receivers can not tell which of the
+//! services sharing it issued a ticket.
+//!
+//! [BLAKE3]: https://github.com/BLAKE3-team/BLAKE3
+//! [`SignedTicket`]: crate::signed::SignedTicket
+
+use std::{fmt, str::FromStr};
+
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// Domain separation prefix for the MAC.
+const MAC_CONTEXT: &[u8] = b"iroh-tickets mac ticket\0";
+
+/// A ticket together with a MAC over it.
+///
+/// The MAC covers the [`KIND`](Ticket::KIND) and the byte representation of the inner
+/// ticket. It can only be checked with the key, so decoding does not check it, use
+/// [`verify`](Self::verify) before trusting the ticket. The authenticated bytes are kept and
+/// encoded unchanged, so the MAC stays valid even if the inner ticket would encode
+/// differently, e.g. after an upgrade from an older wire format version.
+///
+/// All MAC tickets share the [`KIND`](Ticket::KIND) `"mac"`, regardless of the type of the
+/// inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacTicket<T> {
+    ticket: T,
+    bytes: Vec<u8>,
+    mac: [u8; 32],
+}
+
+/// Wire format for [`MacTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0MacTicket {
+    ticket: Vec<u8>,
+    mac: [u8; 32],
+}
+
+impl Versioned for Variant0MacTicket {
+    const VERSION: u32 = 0;
+}
+
+/// The MAC of a [`MacTicket`] does not match the key.
+#[stack_error(derive, add_meta)]
+#[error("invalid ticket MAC")]
+pub struct InvalidMac {}
+
+fn mac(key: &[u8; 32], kind: &str, ticket: &[u8]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new_keyed(key);
+    hasher.update(MAC_CONTEXT);
+    hasher.update(kind.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(ticket);
+    hasher.finalize()
+}
+
+impl<T: Ticket> MacTicket<T> {
+    /// Authenticates `ticket` with `key`.
+    pub fn new(ticket: T, key: &[u8; 32]) -> Self {
+        let bytes = ticket.encode_bytes();
+        let mac = mac(key, T::KIND, &bytes);
+        Self {
+            ticket,
+            bytes,
+            mac: *mac.as_bytes(),
+        }
+    }
+
+    /// The wrapped ticket.
+    ///
+    /// This does not check the MAC, see [`verify`](Self::verify).
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the wrapped ticket.
+    ///
+    /// This does not check the MAC, see [`verify`](Self::verify).
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+
+    /// The MAC over the ticket.
+    pub fn mac(&self) -> &[u8; 32] {
+        &self.mac
+    }
+
+    /// Checks that the MAC was made with `key` and returns the wrapped ticket.
+    pub fn verify(&self, key: &[u8; 32]) -> Result<&T, InvalidMac> {
+        let expected = mac(key, T::KIND, &self.bytes);
+        // `blake3::Hash` compares in constant time.
+        if expected != blake3::Hash::from_bytes(self.mac) {
+            return Err(e!(InvalidMac));
+        }
+        Ok(&self.ticket)
+    }
+}
+
+impl<T: Ticket> Ticket for MacTicket<T> {
+    const KIND: &'static str = "mac";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0MacTicket {
+            ticket: self.bytes.clone(),
+            mac: self.mac,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0MacTicket { ticket, mac } = versioned::decode(bytes)?;
+        Ok(Self {
+            ticket: T::decode_bytes(&ticket)?,
+            bytes: ticket,
+            mac,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for MacTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for MacTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for MacTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for MacTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_mac_ticket() {
+        let key = [27u8; 32];
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[27u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = MacTicket::new(EndpointTicket::new(addr), &key);
+        let s = ticket.to_string();
+        assert!(s.starts_with("mac"));
+        let parsed: MacTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed, ticket);
+        assert_eq!(parsed.verify(&key).unwrap(), ticket.ticket());
+        assert!(parsed.verify(&[28u8; 32]).is_err());
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+
+        // Replacing the inner ticket invalidates the MAC.
+        let other = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[28u8; 32]).public(),
+        ));
+        let tampered = MacTicket {
+            bytes: other.encode_bytes(),
+            ticket: other,
+            mac: *ticket.mac(),
+        };
+        let parsed = MacTicket::<EndpointTicket>::decode_string(&tampered.to_string()).unwrap();
+        assert!(parsed.verify(&key).is_err());
+    }
+
+    #[test]
+    fn test_mac_keeps_bytes() {
+        let key = [27u8; 32];
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[27u8; 32]).public(),
+        ));
+        // The ticket as wire format version 1 with no ALPNs, which decodes to the same
+        // ticket but is encoded as version 0.
+        let mut inner = ticket.encode_bytes();
+        inner[0] = 1;
+        inner.push(0);
+        assert_ne!(ticket.encode_bytes(), inner);
+
+        let bytes = versioned::encode(&Variant0MacTicket {
+            mac: *mac(&key, EndpointTicket::KIND, &inner).as_bytes(),
+            ticket: inner,
+        });
+        let parsed = MacTicket::<EndpointTicket>::decode_bytes(&bytes).unwrap();
+        assert_eq!(parsed.verify(&key).unwrap(), &ticket);
+        assert_eq!(parsed.encode_bytes(), bytes);
+    }
+}
@@ -0,0 +1,208 @@
+//! Tickets for a set of endpoints.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use iroh_base::{EndpointAddr, EndpointId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket,
+    endpoint::Variant1EndpointAddr,
+    ensure_len,
+    versioned::{self, Versioned},
+};
+
+/// A token containing information to connect to any of several endpoints.
+///
+/// This is useful when any one of several providers can serve a request, e.g. for
+/// replicated content.
+///
+/// The ticket holds at most one [`EndpointAddr`] per [`EndpointId`]: adding an address for
+/// an endpoint that is already contained merges the addresses of both.
+#[derive(Debug, Clone, Default, PartialEq, Eq, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct MultiEndpointTicket {
+    endpoints: BTreeMap<EndpointId, EndpointAddr>,
+}
+
+/// Wire format for [`MultiEndpointTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0MultiEndpointTicket {
+    endpoints: Vec<Variant1EndpointAddr>,
+}
+
+impl Versioned for Variant0MultiEndpointTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for MultiEndpointTicket {
+    const KIND: &'static str = "multiendpoint";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0MultiEndpointTicket {
+            endpoints: self.endpoints.values().map(Into::into).collect(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0MultiEndpointTicket { endpoints } = versioned::decode(bytes)?;
+        Ok(endpoints.into_iter().map(EndpointAddr::from).collect())
+    }
+}
+
+impl FromStr for MultiEndpointTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl MultiEndpointTicket {
+    /// Creates a new ticket from the given endpoints.
+    pub fn new(endpoints: impl IntoIterator<Item = EndpointAddr>) -> Self {
+        endpoints.into_iter().collect()
+    }
+
+    /// Adds an endpoint, merging its addresses if the endpoint is already contained.
+    pub fn insert(&mut self, addr: EndpointAddr) {
+        match self.endpoints.get_mut(&addr.id) {
+            Some(existing) => existing.addrs.extend(addr.addrs),
+            None => {
+                self.endpoints.insert(addr.id, addr);
+            }
+        }
+    }
+
+    /// Adds all endpoints of `other` to this ticket.
+    pub fn merge(&mut self, other: MultiEndpointTicket) {
+        self.extend(other.endpoints.into_values());
+    }
+
+    /// Removes an endpoint and returns its addressing info.
+    pub fn remove(&mut self, id: &EndpointId) -> Option<EndpointAddr> {
+        self.endpoints.remove(id)
+    }
+
+    /// The addressing info for the endpoint with the given id.
+    pub fn get(&self, id: &EndpointId) -> Option<&EndpointAddr> {
+        self.endpoints.get(id)
+    }
+
+    /// Returns an iterator over the endpoints, ordered by [`EndpointId`].
+    pub fn endpoints(&self) -> impl Iterator<Item = &EndpointAddr> {
+        self.endpoints.values()
+    }
+
+    /// The number of endpoints in this ticket.
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Whether this ticket contains no endpoints.
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+}
+
+impl FromIterator<EndpointAddr> for MultiEndpointTicket {
+    fn from_iter<I: IntoIterator<Item = EndpointAddr>>(iter: I) -> Self {
+        let mut ticket = Self::default();
+        ticket.extend(iter);
+        ticket
+    }
+}
+
+impl Extend<EndpointAddr> for MultiEndpointTicket {
+    fn extend<I: IntoIterator<Item = EndpointAddr>>(&mut self, iter: I) {
+        for addr in iter {
+            self.insert(addr);
+        }
+    }
+}
+
+impl IntoIterator for MultiEndpointTicket {
+    type Item = EndpointAddr;
+    type IntoIter = std::collections::btree_map::IntoValues<EndpointId, EndpointAddr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.endpoints.into_values()
+    }
+}
+
+impl Serialize for MultiEndpointTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.encode_string())
+        } else {
+            serializer.collect_seq(self.endpoints())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MultiEndpointTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::decode_string(&s).map_err(serde::de::Error::custom)
+        } else {
+            let endpoints = Vec::<EndpointAddr>::deserialize(deserializer)?;
+            Ok(endpoints.into_iter().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use iroh_base::SecretKey;
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    fn addr(key: u8, port: u16) -> EndpointAddr {
+        EndpointAddr::new(SecretKey::from_bytes(&[key; 32]).public())
+            .with_ip_addr(SocketAddr::from(([127, 0, 0, 1], port)))
+    }
+
+    #[test]
+    fn test_multi_merge_dedup() {
+        let mut ticket = MultiEndpointTicket::new([addr(1, 1000), addr(2, 2000)]);
+        ticket.merge(MultiEndpointTicket::new([addr(1, 1001), addr(3, 3000)]));
+        assert_eq!(ticket.len(), 3);
+
+        let id = SecretKey::from_bytes(&[1; 32]).public();
+        assert_eq!(ticket.get(&id).unwrap().ip_addrs().count(), 2);
+    }
+
+    #[test]
+    fn test_multi_roundtrip() {
+        let ticket = MultiEndpointTicket::new([addr(1, 1000), addr(2, 2000)]);
+        let s = ticket.to_string();
+        assert!(s.starts_with("multiendpoint"));
+        assert_eq!(s.parse::<MultiEndpointTicket>().unwrap(), ticket);
+
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<MultiEndpointTicket>(&bytes).unwrap(),
+            ticket
+        );
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MultiEndpointTicket>(&json).unwrap(),
+            ticket
+        );
+    }
+
+    #[test]
+    fn test_multi_not_an_endpoint_ticket() {
+        let s = MultiEndpointTicket::new([addr(1, 1000)]).to_string();
+        let err = EndpointTicket::decode_string(&s).unwrap_err();
+        assert!(
+            matches!(err, ParseError::Kind { expected: "endpoint", found: Some(ref found), .. } if found == "multiendpoint"),
+            "{err:?}"
+        );
+    }
+}
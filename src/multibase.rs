@@ -0,0 +1,222 @@
+//! Self-describing payload encodings.
+//!
+//! The canonical string form of a ticket is its [`KIND`](Ticket::KIND) followed by
+//! base32. To allow other encodings without breaking existing parsers, the payload can
+//! instead start with `_` and a [multibase] prefix character naming its encoding:
+//!
+//! - `b`: base32, lowercase without padding, see [`Base::Base32`].
+//! - `z`: base58 with the bitcoin alphabet, see [`Base::Base58Btc`]. Requires the `base58`
+//!   feature.
+//! - `u`: base64url without padding, see [`Base::Base64Url`].
+//!
+//! The `_` never appears in base32, so it can not be confused with a canonical payload.
+//! The default [`Ticket::decode_string`] detects the prefix and decodes all of these, so
+//! tickets written by [`encode`] parse anywhere a canonical ticket does.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     Ticket,
+//!     endpoint::EndpointTicket,
+//!     multibase::{self, Base},
+//! };
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let s = multibase::encode(&ticket, Base::Base64Url);
+//! assert!(s.starts_with("endpoint_u"));
+//! assert_eq!(EndpointTicket::decode_string(&s).unwrap(), ticket);
+//! ```
+//!
+//! [multibase]: https://github.com/multiformats/multibase
+
+use alloc::{string::String, vec::Vec};
+
+use data_encoding::Encoding;
+
+use crate::{BASE32_LOWER, ParseError, Ticket, e};
+
+/// The marker between the kind and a multibase prefixed payload.
+const MARKER: char = '_';
+
+/// An encoding for the payload of a ticket string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Base {
+    /// Lowercase base32 without padding, the encoding of the canonical string form.
+    Base32,
+    /// Base58 with the bitcoin alphabet.
+    #[cfg(feature = "base58")]
+    Base58Btc,
+    /// URL safe base64 without padding.
+    Base64Url,
+}
+
+impl Base {
+    /// The multibase prefix character of this encoding.
+    pub fn prefix(self) -> char {
+        match self {
+            Self::Base32 => 'b',
+            #[cfg(feature = "base58")]
+            Self::Base58Btc => 'z',
+            Self::Base64Url => 'u',
+        }
+    }
+
+    /// Returns the encoding with the given multibase prefix character, if it is supported.
+    pub fn from_prefix(prefix: char) -> Option<Self> {
+        match prefix {
+            'b' => Some(Self::Base32),
+            #[cfg(feature = "base58")]
+            'z' => Some(Self::Base58Btc),
+            'u' => Some(Self::Base64Url),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes a ticket as its kind followed by a payload in the given encoding.
+pub fn encode<T: Ticket>(ticket: &T, base: Base) -> String {
+    let mut out = String::new();
+    write_to(ticket, base, &mut out);
+    out
+}
+
+/// Appends the string form of a ticket in the given encoding to `out`, see [`encode`].
+pub fn write_to<T: Ticket>(ticket: &T, base: Base, out: &mut String) {
+    let bytes = ticket.encode_bytes();
+    out.push_str(T::KIND);
+    out.push(MARKER);
+    out.push(base.prefix());
+    match base {
+        Base::Base32 => BASE32_LOWER.encode_append(&bytes, out),
+        #[cfg(feature = "base58")]
+        Base::Base58Btc => out.push_str(&bs58::encode(bytes).into_string()),
+        Base::Base64Url => data_encoding::BASE64URL_NOPAD.encode_append(&bytes, out),
+    }
+}
+
+/// Decodes the payload of a ticket string that starts at `offset`.
+///
+/// Payloads with a multibase prefix are decoded accordingly, all others as canonical
+/// case-insensitive base32.
+pub(crate) fn decode_payload(payload: &str, offset: usize) -> Result<Vec<u8>, ParseError> {
+    let mut out = Vec::new();
+    decode_payload_into(payload, offset, &mut out)?;
+    Ok(out)
+}
+
+/// Appends the decoded payload to `out`, see [`decode_payload`].
+///
+/// Canonical base32 payloads are decoded directly into `out`, without allocating.
+pub(crate) fn decode_payload_into(
+    payload: &str,
+    offset: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), ParseError> {
+    let Some(prefixed) = payload.strip_prefix(MARKER) else {
+        return decode_base32_into(payload, offset, out);
+    };
+    let mut chars = prefixed.chars();
+    let prefix = chars.next().unwrap_or(MARKER);
+    let Some(base) = Base::from_prefix(prefix) else {
+        return Err(e!(ParseError::UnknownEncoding { prefix }));
+    };
+    let data = chars.as_str();
+    let offset = offset + 2;
+    match base {
+        Base::Base32 => decode_base32_into(data, offset, out),
+        #[cfg(feature = "base58")]
+        Base::Base58Btc => {
+            out.extend(crate::base58::decode_payload(data, offset)?);
+            Ok(())
+        }
+        Base::Base64Url => decode_into(&data_encoding::BASE64URL_NOPAD, data, offset, out),
+    }
+}
+
+fn decode_base32_into(payload: &str, offset: usize, out: &mut Vec<u8>) -> Result<(), ParseError> {
+    decode_into(&BASE32_LOWER, payload, offset, out)
+}
+
+fn decode_into(
+    encoding: &Encoding,
+    payload: &str,
+    offset: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), ParseError> {
+    let start = out.len();
+    let len = encoding
+        .decode_len(payload.len())
+        .map_err(|err| ParseError::from_data_encoding(err, offset))?;
+    out.resize(start + len, 0);
+    match encoding.decode_mut(payload.as_bytes(), &mut out[start..]) {
+        Ok(len) => {
+            out.truncate(start + len);
+            Ok(())
+        }
+        Err(partial) => {
+            out.truncate(start);
+            Err(ParseError::from_data_encoding(partial.error, offset))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_multibase_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[16u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let bases = [
+            Base::Base32,
+            #[cfg(feature = "base58")]
+            Base::Base58Btc,
+            Base::Base64Url,
+        ];
+        for base in bases {
+            let s = encode(&ticket, base);
+            assert!(s.starts_with(&format!("endpoint_{}", base.prefix())));
+            assert_eq!(
+                EndpointTicket::decode_string(&s).unwrap(),
+                ticket,
+                "{base:?}"
+            );
+            assert_eq!(Base::from_prefix(base.prefix()), Some(base));
+        }
+        assert_eq!(
+            encode(&ticket, Base::Base32),
+            ticket.to_string().replacen("endpoint", "endpoint_b", 1)
+        );
+    }
+
+    #[test]
+    fn test_multibase_errors() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[16u8; 32]).public(),
+        ));
+        let s = encode(&ticket, Base::Base32).replacen("_b", "_x", 1);
+        let err = EndpointTicket::decode_string(&s).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnknownEncoding { prefix: 'x', .. }
+        ));
+        let err = EndpointTicket::decode_string("endpoint_").unwrap_err();
+        assert!(matches!(err, ParseError::UnknownEncoding { .. }));
+
+        let mut s = encode(&ticket, Base::Base64Url);
+        s.replace_range(12..13, "!");
+        let err = EndpointTicket::decode_string(&s).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidCharacter { offset: 12, .. }
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//! NFC Data Exchange Format (NDEF) messages containing tickets.
+//!
+//! NFC tags and phone-to-phone NFC transfer exchange NDEF messages. [`encode`] creates a
+//! message with a single record containing a ticket, either a URI record with the
+//! [`iroh:` URI](crate::uri) of the ticket, which phones can open in an app registered for
+//! the scheme, or a MIME record of type [`MIME_TYPE`] with the string form. [`decode`] reads
+//! the ticket from the first record of either type in a message.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     ndef::{self, RecordType},
+//! };
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let message = ndef::encode(&ticket, RecordType::Uri);
+//! assert_eq!(ndef::decode::<EndpointTicket>(&message).unwrap(), ticket);
+//! ```
+
+use n0_error::e;
+
+use crate::{MIME_TYPE, ParseError, Ticket, uri};
+
+/// Message begin flag.
+const MB: u8 = 0x80;
+/// Message end flag.
+const ME: u8 = 0x40;
+/// Chunk flag.
+const CF: u8 = 0x20;
+/// Short record flag, the payload length is a single byte.
+const SR: u8 = 0x10;
+/// ID length present flag.
+const IL: u8 = 0x08;
+/// Mask of the type name format.
+const TNF_MASK: u8 = 0x07;
+
+/// Type name format of NFC Forum well-known types.
+const TNF_WELL_KNOWN: u8 = 0x01;
+/// Type name format of MIME media types.
+const TNF_MIME: u8 = 0x02;
+
+/// The well-known type of URI records.
+const URI_TYPE: &[u8] = b"U";
+/// The URI identifier code for URIs without an abbreviated prefix.
+const URI_NO_PREFIX: u8 = 0x00;
+
+/// The type of the record created by [`encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    /// A URI record with the `iroh:` URI of the ticket.
+    Uri,
+    /// A MIME record of type [`MIME_TYPE`] with the string form of the ticket.
+    Mime,
+}
+
+/// Encodes a ticket as an NDEF message with a single record.
+pub fn encode<T: Ticket>(ticket: &T, record_type: RecordType) -> Vec<u8> {
+    let (tnf, type_, payload) = match record_type {
+        RecordType::Uri => {
+            let mut payload = vec![URI_NO_PREFIX];
+            payload.extend_from_slice(uri::encode(ticket).as_bytes());
+            (TNF_WELL_KNOWN, URI_TYPE, payload)
+        }
+        RecordType::Mime => (
+            TNF_MIME,
+            MIME_TYPE.as_bytes(),
+            ticket.encode_string().into_bytes(),
+        ),
+    };
+    let mut out = Vec::with_capacity(6 + type_.len() + payload.len());
+    let mut header = MB | ME | tnf;
+    if payload.len() <= usize::from(u8::MAX) {
+        header |= SR;
+    }
+    out.push(header);
+    out.push(type_.len() as u8);
+    if payload.len() <= usize::from(u8::MAX) {
+        out.push(payload.len() as u8);
+    } else {
+        let len = u32::try_from(payload.len()).expect("ticket length fits in u32");
+        out.extend_from_slice(&len.to_be_bytes());
+    }
+    out.extend_from_slice(type_);
+    out.extend_from_slice(&payload);
+    out
+}
+
+/// A record of an NDEF message.
+struct Record<'a> {
+    tnf: u8,
+    type_: &'a [u8],
+    payload: &'a [u8],
+}
+
+/// Splits off `n` bytes from the start of `bytes`.
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], ParseError> {
+    let (head, rest) = bytes
+        .split_at_checked(n)
+        .ok_or_else(|| e!(ParseError::Truncated))?;
+    *bytes = rest;
+    Ok(head)
+}
+
+/// Parses the records of an NDEF message.
+fn records(mut message: &[u8]) -> Result<Vec<Record<'_>>, ParseError> {
+    let mut records = Vec::new();
+    loop {
+        let header = take(&mut message, 1)?[0];
+        if records.is_empty() != (header & MB != 0) || header & CF != 0 {
+            // Only the first record begins the message. Chunked records are not
+            // supported, they are only used for streaming large payloads.
+            return Err(e!(ParseError::Ndef));
+        }
+        let type_len = usize::from(take(&mut message, 1)?[0]);
+        let payload_len = if header & SR != 0 {
+            usize::from(take(&mut message, 1)?[0])
+        } else {
+            let len: [u8; 4] = take(&mut message, 4)?.try_into().expect("4 bytes");
+            u32::from_be_bytes(len) as usize
+        };
+        let id_len = if header & IL != 0 {
+            usize::from(take(&mut message, 1)?[0])
+        } else {
+            0
+        };
+        let type_ = take(&mut message, type_len)?;
+        take(&mut message, id_len)?;
+        let payload = take(&mut message, payload_len)?;
+        records.push(Record {
+            tnf: header & TNF_MASK,
+            type_,
+            payload,
+        });
+        if header & ME != 0 {
+            break;
+        }
+    }
+    if !message.is_empty() {
+        return Err(e!(ParseError::TrailingBytes { len: message.len() }));
+    }
+    Ok(records)
+}
+
+/// Decodes a ticket from an NDEF message.
+///
+/// The ticket is read from the first URI record with an `iroh:` or `web+iroh:` URI or MIME
+/// record of type [`MIME_TYPE`], other records are ignored. Fails with
+/// [`ParseError::Ndef`] if the message is malformed and with [`ParseError::Kind`] if it
+/// contains no ticket.
+pub fn decode<T: Ticket>(message: &[u8]) -> Result<T, ParseError> {
+    for record in records(message)? {
+        match (record.tnf, record.type_) {
+            (TNF_WELL_KNOWN, URI_TYPE) => {
+                if let [URI_NO_PREFIX, uri @ ..] = record.payload
+                    && let Ok(uri) = std::str::from_utf8(uri)
+                    && uri.split_once(':').is_some_and(|(scheme, _)| {
+                        scheme.eq_ignore_ascii_case(uri::SCHEME)
+                            || scheme.eq_ignore_ascii_case(uri::WEB_SCHEME)
+                    })
+                {
+                    return uri::decode(uri);
+                }
+            }
+            (TNF_MIME, type_) if type_.eq_ignore_ascii_case(MIME_TYPE.as_bytes()) => {
+                let s = std::str::from_utf8(record.payload).map_err(|_| e!(ParseError::Ndef))?;
+                return T::decode_string(s);
+            }
+            _ => {}
+        }
+    }
+    Err(ParseError::wrong_prefix(T::KIND))
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{endpoint::EndpointTicket, multi::MultiEndpointTicket};
+
+    #[test]
+    fn test_ndef_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[28u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        for record_type in [RecordType::Uri, RecordType::Mime] {
+            let message = encode(&ticket, record_type);
+            assert_eq!(message[0] & (MB | ME | SR), MB | ME | SR);
+            assert_eq!(decode::<EndpointTicket>(&message).unwrap(), ticket);
+        }
+        let message = encode(&ticket, RecordType::Uri);
+        assert_eq!(&message[3..10], b"U\0iroh:");
+
+        // Long records have a four byte payload length.
+        let addrs = (0..8u8).map(|i| EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public()));
+        let multi = MultiEndpointTicket::new(addrs);
+        let message = encode(&multi, RecordType::Mime);
+        assert_eq!(message[0] & SR, 0);
+        assert_eq!(decode::<MultiEndpointTicket>(&message).unwrap(), multi);
+    }
+
+    #[test]
+    fn test_ndef_records() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[28u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        // A text record before the ticket, with an ID.
+        let mut message = vec![
+            MB | SR | IL | TNF_WELL_KNOWN,
+            1,
+            3,
+            1,
+            b'T',
+            b'x',
+            2,
+            b'e',
+            b'n',
+        ];
+        let record = encode(&ticket, RecordType::Uri);
+        message.push(record[0] & !MB);
+        message.extend_from_slice(&record[1..]);
+        assert_eq!(decode::<EndpointTicket>(&message).unwrap(), ticket);
+
+        let err = decode::<EndpointTicket>(&message[..message.len() - 1]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+        let err = decode::<EndpointTicket>(&message[9..]).unwrap_err();
+        assert!(matches!(err, ParseError::Ndef { .. }));
+        let err =
+            decode::<EndpointTicket>(&[MB | ME | SR | TNF_WELL_KNOWN, 1, 0, b'T']).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+        let mut trailing = record.clone();
+        trailing.push(0);
+        let err = decode::<EndpointTicket>(&trailing).unwrap_err();
+        assert!(matches!(err, ParseError::TrailingBytes { len: 1, .. }));
+    }
+}
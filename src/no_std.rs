@@ -0,0 +1,207 @@
+//! The error types without the `std` feature.
+//!
+//! `n0-error` requires the standard library, so without it the errors are plain types with
+//! the same variants and messages, but without the error metadata.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// Constructs an error, like `n0_error::e!` does for the types with metadata.
+macro_rules! e {
+    ($($path:ident)::+ { $($fields:tt)* }) => {
+        $($path)::+ { $($fields)* }
+    };
+    ($($path:ident)::+) => {
+        $($path)::+ {}
+    };
+}
+
+pub(crate) use e;
+
+/// An error serializing an iroh ticket, see [`Ticket::try_encode_bytes`].
+///
+/// [`Ticket::try_encode_bytes`]: crate::Ticket::try_encode_bytes
+#[derive(Debug)]
+pub struct EncodeError {
+    source: postcard::Error,
+}
+
+impl From<postcard::Error> for EncodeError {
+    fn from(source: postcard::Error) -> Self {
+        Self { source }
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to encode ticket")
+    }
+}
+
+impl core::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// A ticket can not be encoded with the requested wire format version, see
+/// [`Ticket::to_bytes_versioned`].
+///
+/// [`Ticket::to_bytes_versioned`]: crate::Ticket::to_bytes_versioned
+#[derive(Debug)]
+pub struct VersionError {
+    /// The oldest version that can represent the ticket.
+    pub required: u32,
+    /// The newest version that was allowed.
+    pub max_version: u32,
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the ticket requires wire format version {}, newer than {}",
+            self.required, self.max_version
+        )
+    }
+}
+
+impl core::error::Error for VersionError {}
+
+/// An error deserializing an iroh ticket.
+#[derive(Debug)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Found a ticket with the wrong prefix, indicating the wrong kind.
+    Kind {
+        /// The expected prefix.
+        expected: &'static str,
+        /// The kind of the ticket, if it is known.
+        found: Option<String>,
+    },
+    /// The string contains a character that is not valid in its encoding.
+    InvalidCharacter {
+        /// The byte offset of the character in the string.
+        offset: usize,
+    },
+    /// This looks like a ticket, but base32 decoding failed.
+    Encoding { source: data_encoding::DecodeError },
+    /// The input is longer than allowed for this kind of ticket, see
+    /// [`Ticket::MAX_LEN`](crate::Ticket::MAX_LEN).
+    TooLong {
+        /// The length of the input.
+        len: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The checksum of the string does not match, it was mistyped or corrupted.
+    Checksum {},
+    /// The ticket data ends before the ticket is complete.
+    Truncated {},
+    /// The ticket data continues after the end of the ticket.
+    TrailingBytes {
+        /// The number of unexpected bytes.
+        len: usize,
+    },
+    /// This looks like a ticket, but postcard deserialization failed.
+    Postcard { source: postcard::Error },
+    /// The ticket kind is not one of the kinds that can be parsed here.
+    UnknownKind {
+        /// A known kind that is similar to the start of the string, if any.
+        suggestion: Option<&'static str>,
+    },
+    /// The payload uses a wire format version this implementation does not know.
+    ///
+    /// This is usually a ticket from a newer release. `raw` holds its byte representation,
+    /// so it can be stored or forwarded unchanged, e.g. with [`encode_raw`](crate::encode_raw).
+    UnknownVersion {
+        /// The version found on the wire.
+        version: u32,
+        /// The byte representation of the ticket, including the version.
+        ///
+        /// This is empty if only the payload after the version was available, see
+        /// [`versioned::decode_version`](crate::versioned::decode_version).
+        raw: Vec<u8>,
+    },
+    /// The payload has a multibase prefix for an unsupported encoding.
+    UnknownEncoding {
+        /// The multibase prefix character.
+        prefix: char,
+    },
+    /// A word is not in the word list.
+    UnknownWord {
+        /// The position of the word, starting at 0.
+        index: usize,
+    },
+    /// The string is not a valid part of a ticket.
+    InvalidPart {},
+    /// Not all parts of a ticket were given.
+    MissingParts {
+        /// The number of missing parts.
+        count: usize,
+    },
+    /// The string is not a valid ticket URI, or a URL does not contain a ticket.
+    Uri {},
+    /// The data is not a valid NDEF message.
+    Ndef {},
+    /// The string is not a valid ASCII-armored ticket.
+    Armor {},
+    /// The JSON value is not a ticket object.
+    Json {},
+    /// Verification of the deserialized bytes failed.
+    Verify { message: &'static str },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kind { expected, found } => {
+                write!(f, "wrong kind, expected {expected}")?;
+                match found {
+                    Some(found) => write!(f, ", found {found}"),
+                    None => Ok(()),
+                }
+            }
+            Self::InvalidCharacter { offset } => write!(f, "invalid character at offset {offset}"),
+            Self::Encoding { source } => fmt::Display::fmt(source, f),
+            Self::TooLong { len, max } => {
+                write!(f, "ticket is too long, {len} exceeds the maximum of {max}")
+            }
+            Self::Checksum {} => f.write_str("checksum mismatch"),
+            Self::Truncated {} => f.write_str("ticket data is truncated"),
+            Self::TrailingBytes { len } => {
+                write!(f, "{len} unexpected bytes after the ticket data")
+            }
+            Self::Postcard { source } => fmt::Display::fmt(source, f),
+            Self::UnknownKind { suggestion } => {
+                f.write_str("unknown ticket kind")?;
+                match suggestion {
+                    Some(kind) => write!(f, ", did you mean {kind}?"),
+                    None => Ok(()),
+                }
+            }
+            Self::UnknownVersion { version, .. } => {
+                write!(f, "unknown wire format version {version}")
+            }
+            Self::UnknownEncoding { prefix } => write!(f, "unknown payload encoding {prefix:?}"),
+            Self::UnknownWord { index } => write!(f, "unknown word at position {index}"),
+            Self::InvalidPart {} => f.write_str("invalid ticket part"),
+            Self::MissingParts { count } => write!(f, "{count} ticket parts are missing"),
+            Self::Uri {} => f.write_str("invalid ticket URI"),
+            Self::Ndef {} => f.write_str("invalid NDEF message"),
+            Self::Armor {} => f.write_str("invalid armored ticket"),
+            Self::Json {} => f.write_str("invalid ticket JSON"),
+            Self::Verify { message } => write!(f, "verification failed: {message}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Postcard { source } => source.source(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Tickets that can be redeemed at most once.
+//!
+//! A [`OnceTicket`] wraps another ticket together with a nonce. The acceptor records the
+//! nonces of redeemed tickets in a [`NonceStore`] and rejects tickets whose nonce it has
+//! seen before:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     Ticket,
+//!     endpoint::EndpointTicket,
+//!     once::{MemoryNonceStore, OnceTicket},
+//! };
+//!
+//! let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+//! let ticket = OnceTicket::new(EndpointTicket::new(addr), [7u8; 16]);
+//!
+//! let store = MemoryNonceStore::default();
+//! let received = OnceTicket::<EndpointTicket>::decode_string(&ticket.encode_string()).unwrap();
+//! assert!(received.redeem(&store).is_ok());
+//! assert!(received.redeem(&store).is_err());
+//! ```
+//!
+//! Anyone holding the ticket can replace the nonce, so the acceptor should only accept
+//! tickets it issued itself, e.g. by wrapping them in a [`SignedTicket`].
+//!
+//! [`SignedTicket`]: crate::signed::SignedTicket
+
+use std::{collections::HashSet, fmt, str::FromStr, sync::Mutex};
+
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket together with a nonce identifying it for single use.
+///
+/// All once tickets share the [`KIND`](Ticket::KIND) `"once"`, regardless of the type of
+/// the inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnceTicket<T> {
+    ticket: T,
+    nonce: [u8; 16],
+}
+
+/// Wire format for [`OnceTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0OnceTicket {
+    ticket: Vec<u8>,
+    nonce: [u8; 16],
+}
+
+impl Versioned for Variant0OnceTicket {
+    const VERSION: u32 = 0;
+}
+
+/// The nonce of a [`OnceTicket`] was already redeemed.
+#[stack_error(derive, add_meta)]
+#[error("ticket was already redeemed")]
+pub struct AlreadyRedeemed {}
+
+/// Records the nonces of redeemed [`OnceTicket`]s.
+///
+/// Implement this on top of a database to reject tickets across restarts or across several
+/// acceptors, see [`MemoryNonceStore`] for an implementation in memory.
+pub trait NonceStore {
+    /// Records `nonce`, returns `false` if it was recorded before.
+    ///
+    /// Checking and recording must be atomic, otherwise a ticket redeemed concurrently can
+    /// be accepted twice.
+    fn insert(&self, nonce: &[u8; 16]) -> bool;
+}
+
+/// A [`NonceStore`] keeping the nonces in memory.
+///
+/// Nonces are never removed, so the store grows with every redeemed ticket.
+#[derive(Debug, Default)]
+pub struct MemoryNonceStore {
+    nonces: Mutex<HashSet<[u8; 16]>>,
+}
+
+impl MemoryNonceStore {
+    /// The number of recorded nonces.
+    pub fn len(&self) -> usize {
+        self.nonces.lock().expect("poisoned").len()
+    }
+
+    /// Returns whether no nonces are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl NonceStore for MemoryNonceStore {
+    fn insert(&self, nonce: &[u8; 16]) -> bool {
+        self.nonces.lock().expect("poisoned").insert(*nonce)
+    }
+}
+
+impl<T: Ticket> OnceTicket<T> {
+    /// Wraps `ticket` with the given nonce.
+    ///
+    /// The nonce must be unique among the tickets accepted by a [`NonceStore`], e.g. random
+    /// bytes or a counter.
+    pub fn new(ticket: T, nonce: [u8; 16]) -> Self {
+        Self { ticket, nonce }
+    }
+
+    /// The wrapped ticket.
+    ///
+    /// This does not check whether the ticket was redeemed, see [`redeem`](Self::redeem).
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// The nonce of the ticket.
+    pub fn nonce(&self) -> &[u8; 16] {
+        &self.nonce
+    }
+
+    /// Records the nonce in `store` and returns the wrapped ticket, unless the nonce was
+    /// already redeemed.
+    pub fn redeem(&self, store: &impl NonceStore) -> Result<&T, AlreadyRedeemed> {
+        if !store.insert(&self.nonce) {
+            return Err(e!(AlreadyRedeemed));
+        }
+        Ok(&self.ticket)
+    }
+
+    /// Returns the wrapped ticket and the nonce.
+    pub fn into_parts(self) -> (T, [u8; 16]) {
+        (self.ticket, self.nonce)
+    }
+}
+
+impl<T: Ticket> Ticket for OnceTicket<T> {
+    const KIND: &'static str = "once";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0OnceTicket {
+            ticket: self.ticket.encode_bytes(),
+            nonce: self.nonce,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0OnceTicket { ticket, nonce } = versioned::decode(bytes)?;
+        Ok(Self {
+            ticket: T::decode_bytes(&ticket)?,
+            nonce,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for OnceTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for OnceTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for OnceTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for OnceTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{endpoint::EndpointTicket, signed::SignedTicket};
+
+    fn make_ticket(nonce: u8) -> OnceTicket<EndpointTicket> {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[25u8; 32]).public());
+        OnceTicket::new(EndpointTicket::new(addr), [nonce; 16])
+    }
+
+    #[test]
+    fn test_once_ticket() {
+        let ticket = make_ticket(1);
+        let s = ticket.to_string();
+        assert!(s.starts_with("once"));
+        let parsed: OnceTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed, ticket);
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+
+        let store = MemoryNonceStore::default();
+        assert_eq!(parsed.redeem(&store).unwrap(), ticket.ticket());
+        assert!(ticket.redeem(&store).is_err());
+        assert!(make_ticket(2).redeem(&store).is_ok());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_signed_once_ticket() {
+        let secret_key = SecretKey::from_bytes(&[26u8; 32]);
+        let signed = SignedTicket::sign(make_ticket(3), &secret_key);
+        let parsed =
+            SignedTicket::<OnceTicket<EndpointTicket>>::decode_string(&signed.to_string()).unwrap();
+        let store = MemoryNonceStore::default();
+        let ticket = parsed.verify(&secret_key.public()).unwrap();
+        assert!(ticket.redeem(&store).is_ok());
+        assert!(ticket.redeem(&store).is_err());
+    }
+}
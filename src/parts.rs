@@ -0,0 +1,255 @@
+//! Splitting tickets into multiple parts.
+//!
+//! Some channels limit the length of a message, like SMS, posts on social media or NFC
+//! tags. [`split`] cuts the string form of a ticket into parts that each fit such a limit,
+//! and [`join`] or a [`Reassembler`] put them back together.
+//!
+//! A part is the [`KIND`](Ticket::KIND), `p`, the number of the part, `of`, the number of
+//! parts, `_` and a piece of the [string form](Ticket::encode_string) after the kind, e.g.
+//! `endpointp1of3_aeb...`. Parts are numbered starting at 1 and can be reassembled in any
+//! order. Tickets that fit into a single part are not split, so short tickets stay in
+//! their canonical form.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, parts};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let mut parts = parts::split(&ticket, 30).unwrap();
+//! assert!(parts.iter().all(|part| part.len() <= 30));
+//! assert!(parts[0].starts_with("endpointp1of"));
+//!
+//! parts.reverse();
+//! assert_eq!(parts::join::<EndpointTicket>(&parts).unwrap(), ticket);
+//! ```
+
+use std::marker::PhantomData;
+
+use n0_error::{e, stack_error};
+
+use crate::{ParseError, Ticket, strip_ticket_kind};
+
+/// The maximum part length is too short to split the ticket.
+#[stack_error(derive, add_meta)]
+#[error("part length {max_len} is too short for the ticket header")]
+pub struct SplitError {
+    max_len: usize,
+}
+
+/// Returns the number of decimal digits of `n`.
+fn digits(n: usize) -> usize {
+    n.checked_ilog10().unwrap_or(0) as usize + 1
+}
+
+/// Splits a ticket into parts of at most `max_len` bytes each.
+///
+/// Returns the [string form](Ticket::encode_string) of the ticket as the only part if it
+/// is not longer than `max_len`.
+pub fn split<T: Ticket>(ticket: &T, max_len: usize) -> Result<Vec<String>, SplitError> {
+    let s = ticket.encode_string();
+    if s.len() <= max_len {
+        return Ok(vec![s]);
+    }
+    let payload = &s[T::KIND.len()..];
+    // Find the number of parts, the header grows with the number of digits.
+    let mut width = 1;
+    let (chunk_len, total) = loop {
+        let header_len = T::KIND.len() + "p".len() + width + "of".len() + width + "_".len();
+        let Some(chunk_len) = max_len.checked_sub(header_len).filter(|len| *len > 0) else {
+            return Err(e!(SplitError { max_len }));
+        };
+        let total = payload.len().div_ceil(chunk_len);
+        if digits(total) <= width {
+            break (chunk_len, total);
+        }
+        width += 1;
+    };
+    let parts = payload
+        .as_bytes()
+        .chunks(chunk_len)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let chunk = std::str::from_utf8(chunk).expect("string form is ascii");
+            format!("{}p{}of{}_{}", T::KIND, i + 1, total, chunk)
+        })
+        .collect();
+    Ok(parts)
+}
+
+/// Reassembles a ticket from parts written by [`split`], in any order.
+///
+/// Fails with [`ParseError::MissingParts`] if not all parts are given.
+pub fn join<T: Ticket>(parts: impl IntoIterator<Item = impl AsRef<str>>) -> Result<T, ParseError> {
+    let mut reassembler = Reassembler::new();
+    for part in parts {
+        if let Some(ticket) = reassembler.push(part.as_ref())? {
+            return Ok(ticket);
+        }
+    }
+    Err(e!(ParseError::MissingParts {
+        count: reassembler.missing()
+    }))
+}
+
+/// Collects the parts of a ticket as they arrive, see [`split`].
+#[derive(Debug, Clone)]
+pub struct Reassembler<T> {
+    parts: Vec<Option<String>>,
+    _ticket: PhantomData<fn() -> T>,
+}
+
+impl<T: Ticket> Default for Reassembler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ticket> Reassembler<T> {
+    /// Creates a reassembler that has not received any parts yet.
+    pub fn new() -> Self {
+        Self {
+            parts: Vec::new(),
+            _ticket: PhantomData,
+        }
+    }
+
+    /// Adds a part, returning the ticket once all parts have been added.
+    ///
+    /// A ticket that was not split is returned right away. Adding a part again is
+    /// ignored. Parts of a different ticket, that is parts that disagree about the number
+    /// of parts or a different content for the same part, are rejected with
+    /// [`ParseError::InvalidPart`].
+    pub fn push(&mut self, part: &str) -> Result<Option<T>, ParseError> {
+        let rest = strip_ticket_kind::<T>(part)?;
+        let Some((index, total, chunk)) = parse_header(rest) else {
+            return T::decode_string(part).map(Some);
+        };
+        // Reject more parts than needed for the longest ticket with one character each.
+        let max_total = data_encoding::BASE32_NOPAD.encode_len(T::MAX_LEN) + 2;
+        if index == 0 || index > total || total > max_total {
+            return Err(e!(ParseError::InvalidPart));
+        }
+        if self.parts.is_empty() {
+            self.parts = vec![None; total];
+        } else if self.parts.len() != total {
+            return Err(e!(ParseError::InvalidPart));
+        }
+        match &self.parts[index - 1] {
+            Some(existing) if existing != chunk => return Err(e!(ParseError::InvalidPart)),
+            Some(_) => {}
+            None => self.parts[index - 1] = Some(chunk.to_string()),
+        }
+        if self.missing() > 0 {
+            return Ok(None);
+        }
+        let mut s = String::from(T::KIND);
+        s.extend(self.parts.iter().flatten().map(String::as_str));
+        T::decode_string(&s).map(Some)
+    }
+
+    /// The number of parts, if any part has been added.
+    pub fn total(&self) -> Option<usize> {
+        (!self.parts.is_empty()).then_some(self.parts.len())
+    }
+
+    /// The number of parts that have not been added yet.
+    ///
+    /// This is 0 before the first part is added, as the number of parts is not known yet.
+    pub fn missing(&self) -> usize {
+        self.parts.iter().filter(|part| part.is_none()).count()
+    }
+}
+
+/// Parses `p<index>of<total>_<chunk>`, the part of a part after the kind.
+///
+/// The header is case insensitive, like the rest of the string form.
+fn parse_header(s: &str) -> Option<(usize, usize, &str)> {
+    let (header, chunk) = s.split_once('_')?;
+    let header = header.to_ascii_lowercase();
+    let (index, total) = header.strip_prefix('p')?.split_once("of")?;
+    // Only plain digits, `parse` also accepts a leading `+`.
+    let number = |s: &str| {
+        if s.bytes().all(|b| b.is_ascii_digit()) {
+            s.parse().ok()
+        } else {
+            None
+        }
+    };
+    Some((number(index)?, number(total)?, chunk))
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{blob::BlobTicket, endpoint::EndpointTicket};
+
+    fn ticket() -> EndpointTicket {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[18u8; 32]).public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        EndpointTicket::new(addr)
+    }
+
+    #[test]
+    fn test_split_join() {
+        let ticket = ticket();
+        let s = ticket.to_string();
+        assert_eq!(join::<EndpointTicket>([&s]).unwrap(), ticket);
+        assert_eq!(split(&ticket, s.len()).unwrap(), [s]);
+        let s = ticket.to_string();
+
+        for max_len in [s.len() - 1, 40, 20] {
+            let parts = split(&ticket, max_len).unwrap();
+            assert!(parts.len() > 1);
+            assert!(parts.iter().all(|part| part.len() <= max_len), "{max_len}");
+            let total = parts.len();
+            assert!(parts[total - 1].starts_with(&format!("endpointp{total}of{total}_")));
+            assert_eq!(join::<EndpointTicket>(parts.iter().rev()).unwrap(), ticket);
+        }
+        let upper = split(&ticket, 40)
+            .unwrap()
+            .into_iter()
+            .map(|part| part.to_uppercase());
+        assert_eq!(join::<EndpointTicket>(upper).unwrap(), ticket);
+        // The header takes 16 bytes with two digit part numbers.
+        assert!(split(&ticket, 16).is_err());
+    }
+
+    #[test]
+    fn test_reassembler() {
+        let ticket = ticket();
+        let parts = split(&ticket, 40).unwrap();
+        let mut reassembler = Reassembler::<EndpointTicket>::new();
+        assert_eq!(reassembler.total(), None);
+        assert_eq!(reassembler.push(&parts[1]).unwrap(), None);
+        assert_eq!(reassembler.push(&parts[1]).unwrap(), None);
+        assert_eq!(reassembler.total(), Some(parts.len()));
+        assert_eq!(reassembler.missing(), parts.len() - 1);
+
+        let err = join::<EndpointTicket>(&parts[1..]).unwrap_err();
+        assert!(matches!(err, ParseError::MissingParts { count: 1, .. }));
+
+        let other = split(&ticket, 30).unwrap();
+        let err = reassembler.push(&other[0]).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidPart { .. }));
+        let err = reassembler.push("endpointp0of3_aaaa").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidPart { .. }));
+        let err = reassembler
+            .push(&parts[1].replace("endpoint", "blob"))
+            .unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+        let blob = BlobTicket::new(ticket.endpoint_addr().clone(), [0; 32], Default::default());
+        let err = reassembler.push(&split(&blob, 40).unwrap()[0]).unwrap_err();
+        assert!(matches!(err, ParseError::Kind { .. }));
+
+        let mut result = None;
+        for part in &parts {
+            result = reassembler.push(part).unwrap();
+        }
+        assert_eq!(result, Some(ticket));
+    }
+}
@@ -0,0 +1,277 @@
+//! Trust on first use for endpoint tickets.
+//!
+//! Like the `known_hosts` file of SSH, [`KnownTickets`] remembers the last accepted
+//! [`EndpointTicket`] of every [`EndpointId`] and reports when a ticket for a known endpoint
+//! points somewhere else. This can warn about tickets that were tampered with, but also
+//! fires when an endpoint legitimately moves to another relay or network, so applications
+//! usually ask the user before [accepting](KnownTickets::accept) the new ticket:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     pinning::{KnownTickets, MemoryPinStore, PinStatus},
+//! };
+//!
+//! let id = SecretKey::from_bytes(&[0u8; 32]).public();
+//! let ticket = EndpointTicket::new(EndpointAddr::new(id).with_ip_addr("192.0.2.1:1234".parse()?));
+//! let moved = EndpointTicket::new(EndpointAddr::new(id).with_ip_addr("192.0.2.2:1234".parse()?));
+//!
+//! let mut known = KnownTickets::new(MemoryPinStore::default());
+//! assert_eq!(known.check(&ticket)?, PinStatus::New);
+//! assert_eq!(known.check(&ticket)?, PinStatus::Unchanged);
+//! assert!(matches!(
+//!     known.check(&moved)?,
+//!     PinStatus::Changed {
+//!         addrs_changed: true,
+//!         ..
+//!     }
+//! ));
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use iroh_base::EndpointId;
+
+use crate::{Ticket, endpoint::EndpointTicket};
+
+/// Storage for the tickets pinned by [`KnownTickets`].
+pub trait PinStore {
+    /// Returns the pinned ticket of `id`, if any.
+    fn get(&self, id: &EndpointId) -> io::Result<Option<EndpointTicket>>;
+
+    /// Pins `ticket` for its endpoint id, replacing any previously pinned ticket.
+    fn set(&mut self, ticket: &EndpointTicket) -> io::Result<()>;
+}
+
+/// A [`PinStore`] keeping the tickets in memory.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryPinStore {
+    tickets: HashMap<EndpointId, EndpointTicket>,
+}
+
+impl PinStore for MemoryPinStore {
+    fn get(&self, id: &EndpointId) -> io::Result<Option<EndpointTicket>> {
+        Ok(self.tickets.get(id).cloned())
+    }
+
+    fn set(&mut self, ticket: &EndpointTicket) -> io::Result<()> {
+        self.tickets
+            .insert(ticket.endpoint_addr().id, ticket.clone());
+        Ok(())
+    }
+}
+
+/// A [`PinStore`] keeping the tickets in a text file, one ticket per line.
+///
+/// Empty lines and lines starting with `#` are ignored, so the file can be edited by hand.
+/// A missing file is treated as empty. Updates write a temporary file next to it and
+/// rename it over the original, comments are not preserved.
+#[derive(Debug, Clone)]
+pub struct FilePinStore {
+    path: PathBuf,
+}
+
+impl FilePinStore {
+    /// Creates a store backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn read(&self) -> io::Result<Vec<EndpointTicket>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                EndpointTicket::decode_string(line)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            })
+            .collect()
+    }
+}
+
+impl PinStore for FilePinStore {
+    fn get(&self, id: &EndpointId) -> io::Result<Option<EndpointTicket>> {
+        let tickets = self.read()?;
+        Ok(tickets
+            .into_iter()
+            .find(|ticket| ticket.endpoint_addr().id == *id))
+    }
+
+    fn set(&mut self, ticket: &EndpointTicket) -> io::Result<()> {
+        let id = ticket.endpoint_addr().id;
+        let mut contents = String::new();
+        for pinned in self.read()? {
+            if pinned.endpoint_addr().id != id {
+                pinned.write_to(&mut contents);
+                contents.push('\n');
+            }
+        }
+        ticket.write_to(&mut contents);
+        contents.push('\n');
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// The result of [checking](KnownTickets::check) a ticket against the pinned one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinStatus {
+    /// No ticket was pinned for the endpoint, the ticket is pinned now.
+    New,
+    /// The ticket has the same addresses as the pinned one.
+    Unchanged,
+    /// The ticket has different addresses than the pinned one, which is kept.
+    ///
+    /// Use [`EndpointTicket::diff`] for the addresses that changed.
+    Changed {
+        /// The pinned ticket.
+        previous: EndpointTicket,
+        /// Whether the relay URLs differ.
+        relay_changed: bool,
+        /// Whether the other addresses, e.g. IP addresses, differ.
+        addrs_changed: bool,
+    },
+}
+
+/// Pins the first seen [`EndpointTicket`] of every endpoint, see the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct KnownTickets<S> {
+    store: S,
+}
+
+impl<S: PinStore> KnownTickets<S> {
+    /// Creates a new instance on top of `store`.
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Checks `ticket` against the pinned ticket of its endpoint.
+    ///
+    /// The ticket is pinned if the endpoint is not known yet. A changed ticket is not
+    /// pinned, call [`accept`](Self::accept) to replace the pinned ticket.
+    pub fn check(&mut self, ticket: &EndpointTicket) -> io::Result<PinStatus> {
+        let Some(previous) = self.store.get(&ticket.endpoint_addr().id)? else {
+            self.store.set(ticket)?;
+            return Ok(PinStatus::New);
+        };
+        let diff = previous.diff(ticket);
+        if diff.added.is_empty() && diff.removed.is_empty() {
+            return Ok(PinStatus::Unchanged);
+        }
+        Ok(PinStatus::Changed {
+            relay_changed: diff.relay_changed(),
+            addrs_changed: diff.addrs_changed(),
+            previous,
+        })
+    }
+
+    /// Pins `ticket` for its endpoint, replacing any previously pinned ticket.
+    pub fn accept(&mut self, ticket: &EndpointTicket) -> io::Result<()> {
+        self.store.set(ticket)
+    }
+
+    /// Returns the pinned ticket of `id`, if any.
+    pub fn get(&self, id: &EndpointId) -> io::Result<Option<EndpointTicket>> {
+        self.store.get(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+
+    fn ticket(addr: &str) -> EndpointTicket {
+        let id = SecretKey::from_bytes(&[29u8; 32]).public();
+        EndpointTicket::new(EndpointAddr::new(id).with_ip_addr(addr.parse().unwrap()))
+    }
+
+    fn check_store(store: impl PinStore) {
+        let mut known = KnownTickets::new(store);
+        let first = ticket("198.51.100.1:1234");
+        assert_eq!(known.check(&first).unwrap(), PinStatus::New);
+        assert_eq!(known.check(&first).unwrap(), PinStatus::Unchanged);
+        let relayed = EndpointTicket::new(
+            first
+                .endpoint_addr()
+                .clone()
+                .with_relay_url("https://relay.example.com".parse().unwrap()),
+        );
+        assert_eq!(
+            known.check(&relayed).unwrap(),
+            PinStatus::Changed {
+                previous: first.clone(),
+                relay_changed: true,
+                addrs_changed: false,
+            }
+        );
+        let moved = ticket("198.51.100.2:1234");
+        assert!(matches!(
+            known.check(&moved).unwrap(),
+            PinStatus::Changed {
+                relay_changed: false,
+                addrs_changed: true,
+                ..
+            }
+        ));
+        assert_eq!(known.get(&first.endpoint_addr().id).unwrap(), Some(first));
+        known.accept(&moved).unwrap();
+        assert_eq!(known.check(&moved).unwrap(), PinStatus::Unchanged);
+
+        let other = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[30u8; 32]).public(),
+        ));
+        assert_eq!(known.check(&other).unwrap(), PinStatus::New);
+        assert_eq!(known.check(&moved).unwrap(), PinStatus::Unchanged);
+    }
+
+    #[test]
+    fn test_memory_pin_store() {
+        check_store(MemoryPinStore::default());
+    }
+
+    #[test]
+    fn test_file_pin_store() {
+        let path =
+            std::env::temp_dir().join(format!("iroh-tickets-known-tickets-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        check_store(FilePinStore::new(&path));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        fs::write(&path, format!("# pinned tickets\n\n{contents}")).unwrap();
+        let store = FilePinStore::new(&path);
+        let ticket = ticket("198.51.100.2:1234");
+        assert_eq!(store.get(&ticket.endpoint_addr().id).unwrap(), Some(ticket));
+        fs::write(&path, "endpointinvalid\n").unwrap();
+        let err = store.get(&SecretKey::from_bytes(&[30u8; 32]).public());
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+//! Publishing tickets in [pkarr] signed packets.
+//!
+//! A pkarr signed packet is a small DNS packet signed by the key of an endpoint, published
+//! to a pkarr relay or the mainline DHT under the endpoint id. [`publish`] stores an
+//! [`EndpointTicket`] in such a packet, and [`resolve`] fetches the current ticket of an
+//! endpoint by its id. Republishing the ticket whenever the addresses change gives a
+//! ticket that never goes stale, only the endpoint id has to be shared once.
+//!
+//! The ticket is stored in the TXT records at [`TXT_NAME`], see [`dns`]. The
+//! packet also contains the `_iroh` records of the iroh address lookup, so iroh's pkarr and
+//! DNS address lookup find the endpoint from the same packet. A key has a single current
+//! packet, so publishing a ticket replaces a packet published by a
+//! [`PkarrPublisher`](iroh::address_lookup::PkarrPublisher) of the endpoint and vice versa.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> n0_error::Result<()> {
+//! use iroh::{Endpoint, SecretKey, endpoint::presets};
+//! use iroh_tickets::{endpoint::EndpointTicket, pkarr};
+//!
+//! let secret_key = SecretKey::generate();
+//! let endpoint = Endpoint::builder(presets::N0)
+//!     .secret_key(secret_key.clone())
+//!     .bind()
+//!     .await?;
+//! let client = pkarr::n0_relay_client(&endpoint)?;
+//! let ticket = EndpointTicket::new(endpoint.addr()).with_alpns([b"my-alpn"]);
+//! pkarr::publish(&client, &ticket, &secret_key).await?;
+//!
+//! // Anyone can look up the ticket by the endpoint id.
+//! let ticket = pkarr::resolve(&client, endpoint.id()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [pkarr]: https://pkarr.org
+
+use iroh::{
+    Endpoint, EndpointId, SecretKey,
+    address_lookup::{
+        EndpointInfo, Error as AddressLookupError, N0_DNS_PKARR_RELAY_PROD,
+        PkarrError as RelayError, PkarrRelayClient,
+    },
+    endpoint::EndpointError,
+};
+use iroh_dns::{
+    IROH_TXT_NAME,
+    pkarr::{SignedPacket, SignedPacketVerifyError, Timestamp},
+};
+use n0_error::{e, stack_error};
+use simple_dns::{CLASS, Name, Packet, ResourceRecord, SimpleDnsError, rdata::RData};
+
+use crate::{ParseError, dns, endpoint::EndpointTicket};
+
+/// The name of the TXT records holding the ticket, relative to the key of the packet.
+pub const TXT_NAME: &str = "_iroh-ticket";
+
+/// The time to live of the records, in seconds.
+pub const DEFAULT_TTL: u32 = 30;
+
+/// The maximum size of the DNS packet in a signed packet.
+const MAX_DNS_PACKET_LEN: usize = 1000;
+
+/// An error publishing or resolving a ticket with pkarr.
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum PkarrError {
+    /// The secret key is not the key of the endpoint of the ticket.
+    #[error("secret key does not match the ticket endpoint")]
+    WrongKey {},
+    /// The ticket does not fit into a signed packet.
+    #[error("packet is too large, {len} exceeds the maximum of {MAX_DNS_PACKET_LEN}")]
+    PacketTooLarge { len: usize },
+    /// Building the DNS packet failed.
+    #[error(transparent)]
+    Dns {
+        #[error(source, from, std_err)]
+        source: SimpleDnsError,
+    },
+    /// The signed packet is invalid.
+    #[error(transparent)]
+    Verify {
+        #[error(source, from, std_err)]
+        source: SignedPacketVerifyError,
+    },
+    /// Talking to the pkarr relay failed.
+    #[error(transparent)]
+    Relay {
+        #[error(source, from, std_err)]
+        source: RelayError,
+    },
+    /// Fetching the packet from the pkarr relay failed.
+    #[error(transparent)]
+    Lookup {
+        #[error(source, from, std_err)]
+        source: AddressLookupError,
+    },
+    /// The packet does not contain a valid ticket.
+    #[error(transparent)]
+    Ticket {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+}
+
+/// Creates a client for the pkarr relay operated by [number 0].
+///
+/// The client uses the TLS configuration and DNS resolver of `endpoint`.
+///
+/// [number 0]: https://n0.computer
+pub fn n0_relay_client(endpoint: &Endpoint) -> Result<PkarrRelayClient, EndpointError> {
+    let url = N0_DNS_PKARR_RELAY_PROD.parse().expect("valid url");
+    Ok(PkarrRelayClient::new(
+        url,
+        endpoint.tls_config().clone(),
+        endpoint.dns_resolver()?.clone(),
+    ))
+}
+
+/// Creates a signed packet holding `ticket`, signed with the key of its endpoint.
+pub fn to_signed_packet(
+    ticket: &EndpointTicket,
+    secret_key: &SecretKey,
+    ttl: u32,
+) -> Result<SignedPacket, PkarrError> {
+    let public_key = secret_key.public();
+    if public_key != ticket.endpoint_addr().id {
+        return Err(e!(PkarrError::WrongKey));
+    }
+    let origin = public_key.to_z32();
+    let addr_records = EndpointInfo::from(ticket.endpoint_addr().clone()).to_txt_strings();
+    let ticket_records = dns::to_txt_records(ticket);
+    let addr_name = format!("{IROH_TXT_NAME}.{origin}");
+    let ticket_name = format!("{TXT_NAME}.{origin}");
+    let records = [
+        (&addr_name, addr_records.iter()),
+        (&ticket_name, ticket_records.iter()),
+    ];
+    let mut packet = Packet::new_reply(0);
+    for (name, values) in records {
+        let name = Name::new(name)?;
+        for value in values {
+            let mut txt = simple_dns::rdata::TXT::new();
+            txt.add_string(value)?;
+            packet.answers.push(ResourceRecord::new(
+                name.clone(),
+                CLASS::IN,
+                ttl,
+                RData::TXT(txt),
+            ));
+        }
+    }
+    let encoded = packet.build_bytes_vec_compressed()?;
+    if encoded.len() > MAX_DNS_PACKET_LEN {
+        return Err(e!(PkarrError::PacketTooLarge { len: encoded.len() }));
+    }
+    // The signature covers the timestamp and the packet, as specified in BEP 44.
+    let timestamp = Timestamp::now();
+    let mut signable =
+        format!("3:seqi{}e1:v{}:", timestamp.as_micros(), encoded.len()).into_bytes();
+    signable.extend_from_slice(&encoded);
+    let signature = secret_key.sign(&signable);
+    let packet = SignedPacket::from_parts_unchecked(
+        public_key.as_bytes(),
+        &signature.to_bytes(),
+        timestamp,
+        &encoded,
+    )?;
+    Ok(packet)
+}
+
+/// Reads the ticket from a signed packet created with [`to_signed_packet`].
+///
+/// The signature of the packet is verified when parsing it, see
+/// [`SignedPacket::from_bytes`].
+pub fn from_signed_packet(packet: &SignedPacket) -> Result<EndpointTicket, ParseError> {
+    let ticket: EndpointTicket = dns::from_txt_records(packet.txt_records(TXT_NAME))?;
+    if ticket.endpoint_addr().id != packet.public_key() {
+        return Err(ParseError::verification_failed(
+            "ticket endpoint does not match the packet key",
+        ));
+    }
+    Ok(ticket)
+}
+
+/// Publishes `ticket` to a pkarr relay, signed with the key of its endpoint.
+pub async fn publish(
+    client: &PkarrRelayClient,
+    ticket: &EndpointTicket,
+    secret_key: &SecretKey,
+) -> Result<(), PkarrError> {
+    let packet = to_signed_packet(ticket, secret_key, DEFAULT_TTL)?;
+    client.publish(&packet).await?;
+    Ok(())
+}
+
+/// Fetches the ticket published for `endpoint_id` from a pkarr relay.
+pub async fn resolve(
+    client: &PkarrRelayClient,
+    endpoint_id: EndpointId,
+) -> Result<EndpointTicket, PkarrError> {
+    let packet = client.resolve(endpoint_id).await?;
+    Ok(from_signed_packet(&packet)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::EndpointAddr;
+
+    use super::*;
+
+    #[test]
+    fn test_signed_packet() {
+        let secret_key = SecretKey::from_bytes(&[23u8; 32]);
+        let addr = EndpointAddr::new(secret_key.public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr.clone()).with_alpns([b"echo"]);
+
+        let packet = to_signed_packet(&ticket, &secret_key, DEFAULT_TTL).unwrap();
+        let packet = SignedPacket::from_bytes(packet.as_bytes()).unwrap();
+        assert_eq!(from_signed_packet(&packet).unwrap(), ticket);
+        // The iroh address lookup can read the packet too.
+        let info = EndpointInfo::from_pkarr_signed_packet(&packet).unwrap();
+        assert_eq!(info.into_endpoint_addr(), addr);
+
+        let other = SecretKey::from_bytes(&[24u8; 32]);
+        let err = to_signed_packet(&ticket, &other, DEFAULT_TTL).unwrap_err();
+        assert!(matches!(err, PkarrError::WrongKey { .. }));
+        let info = EndpointInfo::from(EndpointAddr::new(other.public()));
+        let packet = info.to_pkarr_signed_packet(&other, DEFAULT_TTL).unwrap();
+        assert!(from_signed_packet(&packet).is_err());
+    }
+}
@@ -0,0 +1,361 @@
+//! Restricting the addresses accepted in tickets.
+//!
+//! Deployments that must only connect through approved relays, or must not dial into
+//! private networks, can reject tickets that do not fit with a [`ParsePolicy`]. The policy
+//! is checked on the [`EndpointAddr`]s of tickets implementing [`TicketAddrs`], after they
+//! were decoded:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     Ticket,
+//!     endpoint::EndpointTicket,
+//!     policy::{self, ParsePolicy},
+//! };
+//!
+//! let policy = ParsePolicy::new()
+//!     .allow_relay("*.relay.example.com")
+//!     .refuse_private_addrs();
+//! let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public())
+//!     .with_relay_url("https://eu.relay.example.com".parse()?);
+//! let ticket = EndpointTicket::new(addr.clone()).encode_string();
+//! assert!(policy::decode_string_with_policy::<EndpointTicket>(&ticket, &policy).is_ok());
+//!
+//! let other = addr.with_relay_url("https://relay.example.net".parse()?);
+//! let ticket = EndpointTicket::new(other).encode_string();
+//! assert!(policy::decode_string_with_policy::<EndpointTicket>(&ticket, &policy).is_err());
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Tickets embedded in other types can be checked during deserialization with
+//! [`deserialize_with_policy`].
+
+use std::net::SocketAddr;
+
+use iroh_base::{EndpointAddr, RelayUrl, TransportAddr};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    ParseError, Ticket,
+    blob::BlobTicket,
+    endpoint::{EndpointTicket, is_private_ip},
+    gossip::GossipTicket,
+    invite::InviteTicket,
+    multi::MultiEndpointTicket,
+};
+
+/// Tickets containing [`EndpointAddr`]s, which a [`ParsePolicy`] can be checked on.
+pub trait TicketAddrs {
+    /// The endpoint addresses contained in the ticket.
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr>;
+}
+
+impl TicketAddrs for EndpointTicket {
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr> {
+        std::iter::once(self.endpoint_addr())
+    }
+}
+
+impl TicketAddrs for BlobTicket {
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr> {
+        std::iter::once(self.addr())
+    }
+}
+
+impl TicketAddrs for GossipTicket {
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr> {
+        self.bootstrap().iter()
+    }
+}
+
+impl TicketAddrs for MultiEndpointTicket {
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr> {
+        self.endpoints()
+    }
+}
+
+impl TicketAddrs for InviteTicket {
+    fn endpoint_addrs(&self) -> impl Iterator<Item = &EndpointAddr> {
+        std::iter::once(self.addr())
+    }
+}
+
+/// Restrictions on the addresses of a ticket, see the [module documentation](self).
+///
+/// The default policy accepts all tickets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsePolicy {
+    allowed_relays: Option<Vec<String>>,
+    max_direct_addrs: Option<usize>,
+    refuse_private_addrs: bool,
+}
+
+/// A ticket does not satisfy a [`ParsePolicy`].
+#[stack_error(derive, add_meta)]
+#[non_exhaustive]
+pub enum PolicyError {
+    /// The ticket contains a relay URL that is not allowed.
+    #[error("relay {url} is not allowed")]
+    RelayNotAllowed {
+        /// The relay URL.
+        url: RelayUrl,
+    },
+    /// An endpoint address contains more direct addresses than allowed.
+    #[error("{len} direct addresses, at most {max} are allowed")]
+    TooManyDirectAddrs {
+        /// The number of direct addresses.
+        len: usize,
+        /// The maximum number of direct addresses.
+        max: usize,
+    },
+    /// The ticket contains an IP address that is not reachable from other networks.
+    #[error("private address {addr} is not allowed")]
+    PrivateAddr {
+        /// The address.
+        addr: SocketAddr,
+    },
+}
+
+/// Decoding a ticket with a [`ParsePolicy`] failed.
+#[stack_error(derive, add_meta)]
+#[non_exhaustive]
+pub enum PolicyDecodeError {
+    /// The string is not a valid ticket.
+    #[error(transparent)]
+    Parse {
+        #[error(source, from, std_err)]
+        source: ParseError,
+    },
+    /// The ticket does not satisfy the policy.
+    #[error(transparent)]
+    Policy {
+        #[error(source, from, std_err)]
+        source: PolicyError,
+    },
+}
+
+impl ParsePolicy {
+    /// Creates a policy that accepts all tickets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accepts relay URLs whose host matches `pattern`, in addition to the patterns
+    /// allowed before.
+    ///
+    /// The pattern is a host name like `relay.example.com`, or `*.example.com` to match all
+    /// subdomains of `example.com`. Hosts are compared case insensitively, ignoring a
+    /// trailing dot. Once a pattern is allowed, tickets with relay URLs that match none of
+    /// the patterns are refused.
+    pub fn allow_relay(mut self, pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+        self.allowed_relays.get_or_insert_default().push(pattern);
+        self
+    }
+
+    /// Refuses all relay URLs, only direct addresses are accepted.
+    pub fn refuse_relays(mut self) -> Self {
+        self.allowed_relays = Some(Vec::new());
+        self
+    }
+
+    /// Refuses endpoint addresses with more than `max` IP addresses.
+    pub fn max_direct_addrs(mut self, max: usize) -> Self {
+        self.max_direct_addrs = Some(max);
+        self
+    }
+
+    /// Refuses IP addresses that are not reachable from other networks.
+    ///
+    /// These are loopback, link-local, unspecified and private network addresses, see
+    /// [`EndpointTicket::sanitized`].
+    pub fn refuse_private_addrs(mut self) -> Self {
+        self.refuse_private_addrs = true;
+        self
+    }
+
+    /// Returns whether the relay URL is allowed.
+    pub fn is_relay_allowed(&self, url: &RelayUrl) -> bool {
+        let Some(patterns) = &self.allowed_relays else {
+            return true;
+        };
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+                None => host == *pattern,
+            })
+    }
+
+    /// Checks the endpoint addresses of `ticket`.
+    pub fn check<T: TicketAddrs>(&self, ticket: &T) -> Result<(), PolicyError> {
+        ticket
+            .endpoint_addrs()
+            .try_for_each(|addr| self.check_addr(addr))
+    }
+
+    /// Checks a single endpoint address.
+    pub fn check_addr(&self, addr: &EndpointAddr) -> Result<(), PolicyError> {
+        let mut direct_addrs = 0;
+        for addr in &addr.addrs {
+            match addr {
+                TransportAddr::Relay(url) if !self.is_relay_allowed(url) => {
+                    return Err(e!(PolicyError::RelayNotAllowed { url: url.clone() }));
+                }
+                TransportAddr::Ip(addr) => {
+                    if self.refuse_private_addrs && is_private_ip(addr.ip()) {
+                        return Err(e!(PolicyError::PrivateAddr { addr: *addr }));
+                    }
+                    direct_addrs += 1;
+                }
+                _ => {}
+            }
+        }
+        match self.max_direct_addrs {
+            Some(max) if direct_addrs > max => Err(e!(PolicyError::TooManyDirectAddrs {
+                len: direct_addrs,
+                max
+            })),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Decodes a ticket from its string form and checks it against `policy`.
+pub fn decode_string_with_policy<T: Ticket + TicketAddrs>(
+    s: &str,
+    policy: &ParsePolicy,
+) -> Result<T, PolicyDecodeError> {
+    let ticket = T::decode_string(s)?;
+    policy.check(&ticket)?;
+    Ok(ticket)
+}
+
+/// Deserializes a ticket and checks it against `policy`.
+///
+/// Fails with a custom error of the deserializer if the ticket does not satisfy the policy.
+/// Use it in a manual [`Deserialize`] implementation or with `#[serde(deserialize_with)]`
+/// through a wrapper function holding the policy:
+///
+/// ```
+/// use iroh_tickets::{
+///     endpoint::EndpointTicket,
+///     policy::{ParsePolicy, deserialize_with_policy},
+/// };
+/// use serde::{Deserialize, Deserializer};
+///
+/// fn approved<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EndpointTicket, D::Error> {
+///     let policy = ParsePolicy::new().allow_relay("relay.example.com");
+///     deserialize_with_policy(deserializer, &policy)
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     #[serde(deserialize_with = "approved")]
+///     peer: EndpointTicket,
+/// }
+/// ```
+pub fn deserialize_with_policy<'de, T, D>(
+    deserializer: D,
+    policy: &ParsePolicy,
+) -> Result<T, D::Error>
+where
+    T: Deserialize<'de> + TicketAddrs,
+    D: Deserializer<'de>,
+{
+    let ticket = T::deserialize(deserializer)?;
+    policy.check(&ticket).map_err(serde::de::Error::custom)?;
+    Ok(ticket)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::SecretKey;
+
+    use super::*;
+
+    fn addr(relay: &str, ips: &[&str]) -> EndpointAddr {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[34u8; 32]).public())
+            .with_relay_url(relay.parse().unwrap());
+        ips.iter()
+            .fold(addr, |addr, ip| addr.with_ip_addr(ip.parse().unwrap()))
+    }
+
+    #[test]
+    fn test_relay_patterns() {
+        let policy = ParsePolicy::new()
+            .allow_relay("Relay.Example.com.")
+            .allow_relay("*.n0.example");
+        for (url, allowed) in [
+            ("https://relay.example.com", true),
+            ("https://RELAY.example.com./", true),
+            ("https://other.example.com", false),
+            ("https://eu.n0.example", true),
+            ("https://a.b.n0.example", true),
+            ("https://n0.example", false),
+            ("https://evil-n0.example", false),
+        ] {
+            let url: RelayUrl = url.parse().unwrap();
+            assert_eq!(policy.is_relay_allowed(&url), allowed, "{url}");
+        }
+        let url = "https://relay.example.com".parse().unwrap();
+        assert!(ParsePolicy::new().is_relay_allowed(&url));
+        assert!(!ParsePolicy::new().refuse_relays().is_relay_allowed(&url));
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let policy = ParsePolicy::new()
+            .allow_relay("relay.example.com")
+            .max_direct_addrs(2)
+            .refuse_private_addrs();
+        let ok = EndpointTicket::new(addr("https://relay.example.com", &["198.51.100.1:1"]));
+        assert!(policy.check(&ok).is_ok());
+        assert!(ParsePolicy::default().check(&ok).is_ok());
+
+        let relay = EndpointTicket::new(addr("https://relay.example.net", &[]));
+        assert!(matches!(
+            policy.check(&relay),
+            Err(PolicyError::RelayNotAllowed { .. })
+        ));
+        let private = EndpointTicket::new(addr("https://relay.example.com", &["10.0.0.1:1"]));
+        assert!(matches!(
+            policy.check(&private),
+            Err(PolicyError::PrivateAddr { .. })
+        ));
+        let many = addr(
+            "https://relay.example.com",
+            &["198.51.100.1:1", "198.51.100.2:1", "198.51.100.3:1"],
+        );
+        assert!(matches!(
+            policy.check(&EndpointTicket::new(many.clone())),
+            Err(PolicyError::TooManyDirectAddrs { len: 3, max: 2, .. })
+        ));
+        // Every address of a ticket is checked.
+        let multi = MultiEndpointTicket::new([ok.endpoint_addr().clone(), many]);
+        assert!(policy.check(&multi).is_err());
+
+        let s = private.encode_string();
+        let err = decode_string_with_policy::<EndpointTicket>(&s, &policy).unwrap_err();
+        assert!(matches!(err, PolicyDecodeError::Policy { .. }));
+        let err = decode_string_with_policy::<EndpointTicket>("blob", &policy).unwrap_err();
+        assert!(matches!(err, PolicyDecodeError::Parse { .. }));
+        assert_eq!(
+            decode_string_with_policy::<EndpointTicket>(&ok.encode_string(), &policy).unwrap(),
+            ok
+        );
+
+        let json = serde_json::to_string(&private).unwrap();
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let err = deserialize_with_policy::<EndpointTicket, _>(&mut de, &policy).unwrap_err();
+        assert!(err.to_string().contains("private address 10.0.0.1:1"));
+    }
+}
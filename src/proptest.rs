@@ -0,0 +1,207 @@
+//! [proptest] strategies for property testing.
+//!
+//! The strategies generate the built-in ticket types with relay and IP addresses, and
+//! [`EndpointTicket`] and [`AnyTicket`] implement [`Arbitrary`], so `any::<EndpointTicket>()`
+//! works as well. [`endpoint_addr`] is useful for writing strategies for custom ticket
+//! types.
+//!
+//! ```
+//! use iroh_tickets::{Ticket, endpoint::EndpointTicket};
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(ticket in any::<EndpointTicket>())| {
+//!     let s = ticket.encode_string();
+//!     prop_assert_eq!(EndpointTicket::decode_string(&s).unwrap(), ticket);
+//! });
+//! ```
+//!
+//! [proptest]: https://docs.rs/proptest
+
+use std::net::SocketAddr;
+
+use iroh_base::{EndpointAddr, EndpointId, RelayUrl, SecretKey, TransportAddr};
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::vec,
+    prelude::{BoxedStrategy, Just, Strategy, any, prop_oneof},
+};
+
+use crate::{
+    AnyTicket,
+    blob::{BlobFormat, BlobTicket},
+    bundle::BundleTicket,
+    dns::DnsTicket,
+    endpoint::EndpointTicket,
+    gossip::GossipTicket,
+    invite::InviteTicket,
+    multi::MultiEndpointTicket,
+    relay::RelayTicket,
+};
+
+/// Endpoint ids of random secret keys.
+pub fn endpoint_id() -> impl Strategy<Value = EndpointId> {
+    any::<[u8; 32]>().prop_map(|bytes| SecretKey::from_bytes(&bytes).public())
+}
+
+/// Relay URLs.
+pub fn relay_url() -> impl Strategy<Value = RelayUrl> {
+    any::<u16>().prop_map(|n| {
+        let url = format!("https://relay{n}.example.com");
+        url.parse::<RelayUrl>().expect("valid relay URL")
+    })
+}
+
+/// Relay and IP addresses.
+pub fn transport_addr() -> impl Strategy<Value = TransportAddr> {
+    prop_oneof![
+        relay_url().prop_map(TransportAddr::Relay),
+        // The wire format does not contain the IPv6 flow info and scope id.
+        any::<SocketAddr>()
+            .prop_map(|addr| TransportAddr::Ip(SocketAddr::new(addr.ip(), addr.port()))),
+    ]
+}
+
+/// Endpoint addresses with up to four [transport addresses](transport_addr).
+pub fn endpoint_addr() -> impl Strategy<Value = EndpointAddr> {
+    (endpoint_id(), vec(transport_addr(), 0..=4))
+        .prop_map(|(id, addrs)| EndpointAddr::from_parts(id, addrs))
+}
+
+/// Endpoint tickets with up to four ALPNs.
+pub fn endpoint_ticket() -> impl Strategy<Value = EndpointTicket> {
+    (endpoint_addr(), vec(vec(any::<u8>(), 1..=32), 0..=4))
+        .prop_map(|(addr, alpns)| EndpointTicket::new(addr).with_alpns(alpns))
+}
+
+/// Blob tickets of both formats.
+pub fn blob_ticket() -> impl Strategy<Value = BlobTicket> {
+    let format = prop_oneof![Just(BlobFormat::Raw), Just(BlobFormat::HashSeq)];
+    (endpoint_addr(), any::<[u8; 32]>(), format)
+        .prop_map(|(addr, hash, format)| BlobTicket::new(addr, hash, format))
+}
+
+/// Gossip tickets with up to four bootstrap endpoints.
+pub fn gossip_ticket() -> impl Strategy<Value = GossipTicket> {
+    (any::<[u8; 32]>(), vec(endpoint_addr(), 0..=4))
+        .prop_map(|(topic, bootstrap)| GossipTicket::new(topic, bootstrap))
+}
+
+/// Multi endpoint tickets with up to four endpoints.
+pub fn multi_endpoint_ticket() -> impl Strategy<Value = MultiEndpointTicket> {
+    vec(endpoint_addr(), 0..=4).prop_map(MultiEndpointTicket::new)
+}
+
+/// Relay tickets with and without a token.
+pub fn relay_ticket() -> impl Strategy<Value = RelayTicket> {
+    (relay_url(), proptest::option::of("[a-z0-9]{1,32}")).prop_map(|(url, token)| {
+        let ticket = RelayTicket::new(url);
+        match token {
+            Some(token) => ticket.with_token(token),
+            None => ticket,
+        }
+    })
+}
+
+/// DNS tickets for subdomains of `example.com`.
+pub fn dns_ticket() -> impl Strategy<Value = DnsTicket> {
+    "[a-z0-9]{1,16}".prop_map(|label| DnsTicket::new(format!("{label}.example.com")))
+}
+
+/// Invite tickets with and without a label.
+pub fn invite_ticket() -> impl Strategy<Value = InviteTicket> {
+    (
+        endpoint_addr(),
+        any::<[u8; 32]>(),
+        proptest::option::of("[a-z ]{1,16}"),
+    )
+        .prop_map(|(addr, token, label)| {
+            let ticket = InviteTicket::new(addr, token);
+            match label {
+                Some(label) => ticket.with_label(label),
+                None => ticket,
+            }
+        })
+}
+
+/// Tickets of any kind but [`BundleTicket`].
+fn leaf_ticket() -> impl Strategy<Value = AnyTicket> {
+    prop_oneof![
+        endpoint_ticket().prop_map(AnyTicket::Endpoint),
+        blob_ticket().prop_map(AnyTicket::Blob),
+        gossip_ticket().prop_map(AnyTicket::Gossip),
+        multi_endpoint_ticket().prop_map(AnyTicket::MultiEndpoint),
+        relay_ticket().prop_map(AnyTicket::Relay),
+        dns_ticket().prop_map(AnyTicket::Dns),
+        invite_ticket().prop_map(AnyTicket::Invite),
+    ]
+}
+
+/// Bundles of up to four tickets of the other built-in kinds.
+pub fn bundle_ticket() -> impl Strategy<Value = BundleTicket> {
+    vec(leaf_ticket(), 0..=4).prop_map(|tickets| {
+        let mut bundle = BundleTicket::new();
+        for ticket in tickets {
+            match ticket {
+                AnyTicket::Endpoint(ticket) => bundle.push(&ticket),
+                AnyTicket::Blob(ticket) => bundle.push(&ticket),
+                AnyTicket::Gossip(ticket) => bundle.push(&ticket),
+                AnyTicket::MultiEndpoint(ticket) => bundle.push(&ticket),
+                AnyTicket::Relay(ticket) => bundle.push(&ticket),
+                AnyTicket::Dns(ticket) => bundle.push(&ticket),
+                AnyTicket::Invite(ticket) => bundle.push(&ticket),
+                AnyTicket::Bundle(ticket) => bundle.push(&ticket),
+            }
+        }
+        bundle
+    })
+}
+
+/// Tickets of all the built-in kinds.
+pub fn any_ticket() -> impl Strategy<Value = AnyTicket> {
+    prop_oneof![
+        4 => leaf_ticket(),
+        1 => bundle_ticket().prop_map(AnyTicket::Bundle),
+    ]
+}
+
+impl Arbitrary for EndpointTicket {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        endpoint_ticket().boxed()
+    }
+}
+
+impl Arbitrary for AnyTicket {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        any_ticket().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::Ticket;
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(ticket in any::<AnyTicket>()) {
+            let s = ticket.encode_string();
+            prop_assert_eq!(AnyTicket::decode_string(&s).unwrap(), ticket.clone());
+            let bytes = ticket.encode_bytes();
+            prop_assert_eq!(AnyTicket::decode_bytes(ticket.kind(), &bytes).unwrap(), ticket);
+        }
+
+        #[test]
+        fn test_endpoint_roundtrip(ticket in endpoint_ticket()) {
+            let bytes = ticket.encode_bytes();
+            prop_assert_eq!(EndpointTicket::decode_bytes(&bytes).unwrap(), ticket);
+        }
+    }
+}
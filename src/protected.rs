@@ -0,0 +1,225 @@
+//! Password protected tickets.
+//!
+//! A [`ProtectedTicket`] contains another ticket encrypted with a key derived from a
+//! password, so it can be shared over semi-public channels while the password is passed on
+//! out of band.
+//!
+//! The key is derived from the password and a random salt with Argon2id, version 0x13,
+//! using 19 MiB of memory, 2 iterations and a parallelism of 1. The ticket is encrypted with
+//! ChaCha20-Poly1305.
+
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit,
+    aead::{Aead, Payload},
+};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket encrypted with a password.
+///
+/// All protected tickets share the [`KIND`](Ticket::KIND) `"protected"`, regardless of the
+/// type of the inner ticket. The kind of the inner ticket is authenticated, so unlocking as
+/// the wrong type fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectedTicket<T> {
+    salt: [u8; 16],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+    _ticket: PhantomData<fn() -> T>,
+}
+
+/// Wire format for [`ProtectedTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0ProtectedTicket {
+    salt: [u8; 16],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+impl Versioned for Variant0ProtectedTicket {
+    const VERSION: u32 = 0;
+}
+
+/// An error unlocking a [`ProtectedTicket`].
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum UnlockError {
+    /// The password is wrong, or the ticket was modified.
+    #[error("wrong password")]
+    Decrypt {},
+    /// The decrypted ticket could not be decoded.
+    #[error("failed to decode protected ticket")]
+    Ticket { source: ParseError },
+}
+
+/// The Argon2 variant used to derive keys.
+const KDF_ALGORITHM: Algorithm = Algorithm::Argon2id;
+/// The Argon2 version used to derive keys.
+const KDF_VERSION: Version = Version::V0x13;
+/// The Argon2 parameters used to derive keys.
+///
+/// These are part of the wire format: changing them makes existing tickets undecryptable.
+const KDF_PARAMS: Params = match Params::new(19 * 1024, 2, 1, Some(32)) {
+    Ok(params) => params,
+    Err(_) => panic!("invalid argon2 parameters"),
+};
+
+fn cipher(password: &str, salt: &[u8; 16]) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    Argon2::new(KDF_ALGORITHM, KDF_VERSION, KDF_PARAMS)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .expect("valid argon2 output length");
+    let cipher = ChaCha20Poly1305::new(&key.into());
+    key.zeroize();
+    cipher
+}
+
+impl<T: Ticket> ProtectedTicket<T> {
+    /// Encrypts `ticket` with `password`.
+    pub fn protect(ticket: &T, password: &str) -> Self {
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        getrandom::fill(&mut salt).expect("failed to get random bytes");
+        getrandom::fill(&mut nonce).expect("failed to get random bytes");
+        let payload = Payload {
+            msg: &ticket.encode_bytes(),
+            aad: T::KIND.as_bytes(),
+        };
+        let ciphertext = cipher(password, &salt)
+            .encrypt(&nonce.into(), payload)
+            .expect("encrypting into a vec is infallible");
+        Self {
+            salt,
+            nonce,
+            ciphertext,
+            _ticket: PhantomData,
+        }
+    }
+
+    /// Decrypts the ticket with `password`.
+    pub fn unlock(&self, password: &str) -> Result<T, UnlockError> {
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: T::KIND.as_bytes(),
+        };
+        let bytes = cipher(password, &self.salt)
+            .decrypt(&self.nonce.into(), payload)
+            .map_err(|_| e!(UnlockError::Decrypt))?;
+        T::decode_bytes(&bytes).map_err(|source| e!(UnlockError::Ticket { source }))
+    }
+}
+
+impl<T: Ticket> Ticket for ProtectedTicket<T> {
+    const KIND: &'static str = "protected";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0ProtectedTicket {
+            salt: self.salt,
+            nonce: self.nonce,
+            ciphertext: self.ciphertext.clone(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0ProtectedTicket {
+            salt,
+            nonce,
+            ciphertext,
+        } = versioned::decode(bytes)?;
+        Ok(Self {
+            salt,
+            nonce,
+            ciphertext,
+            _ticket: PhantomData,
+        })
+    }
+}
+
+impl<T: Ticket> fmt::Display for ProtectedTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for ProtectedTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for ProtectedTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for ProtectedTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_protect_unlock() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[8u8; 32]).public(),
+        ));
+        let protected = ProtectedTicket::protect(&ticket, "hunter2");
+        let s = protected.to_string();
+        assert!(s.starts_with("protected"));
+
+        let parsed: ProtectedTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed.unlock("hunter2").unwrap(), ticket);
+        assert!(matches!(
+            parsed.unlock("hunter3"),
+            Err(UnlockError::Decrypt { .. })
+        ));
+    }
+
+    #[test]
+    fn test_kdf_params_pinned() {
+        // Tickets protected before the parameters were pinned used the defaults of argon2 0.5.
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[8u8; 32]).public(),
+        ));
+        let (salt, nonce) = ([1u8; 16], [2u8; 12]);
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(b"hunter2", &salt, &mut key)
+            .unwrap();
+        let payload = Payload {
+            msg: &ticket.encode_bytes(),
+            aad: EndpointTicket::KIND.as_bytes(),
+        };
+        let ciphertext = ChaCha20Poly1305::new(&key.into())
+            .encrypt(&nonce.into(), payload)
+            .unwrap();
+        let protected = ProtectedTicket::<EndpointTicket> {
+            salt,
+            nonce,
+            ciphertext,
+            _ticket: PhantomData,
+        };
+        assert_eq!(protected.unlock("hunter2").unwrap(), ticket);
+    }
+}
@@ -0,0 +1,293 @@
+//! Python bindings via [PyO3].
+//!
+//! This defines the `iroh_tickets` Python module, with an `EndpointTicket` class, a generic
+//! `Ticket` class for all built-in kinds and the `parse`, `kind_of` and `inspect` functions,
+//! so scripts and test harnesses can mint and check tickets. Build the extension module
+//! with [maturin], e.g. `maturin develop --features python,pyo3/extension-module`.
+//!
+//! ```python
+//! import iroh_tickets
+//!
+//! ticket = iroh_tickets.EndpointTicket(endpoint_id, ip_addrs=["198.51.100.1:1234"])
+//! assert iroh_tickets.parse(str(ticket)).kind == "endpoint"
+//! print(iroh_tickets.inspect(str(ticket))["fields"])
+//! ```
+//!
+//! Parse errors are raised as `iroh_tickets.TicketError`, a subclass of `ValueError`.
+//!
+//! [PyO3]: https://pyo3.rs
+//! [maturin]: https://www.maturin.rs
+
+use pyo3::{
+    create_exception,
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyDict, PyList},
+};
+
+use crate::{AnyTicket, FieldValue, ParseError, Ticket, endpoint::EndpointTicket};
+
+create_exception!(
+    iroh_tickets,
+    TicketError,
+    PyValueError,
+    "A ticket could not be parsed."
+);
+
+fn to_py_err(err: ParseError) -> PyErr {
+    TicketError::new_err(err.to_string())
+}
+
+fn invalid(field: &str) -> PyErr {
+    PyValueError::new_err(format!("invalid {field}"))
+}
+
+/// An [`EndpointTicket`] in Python.
+#[pyclass(name = "EndpointTicket", module = "iroh_tickets", frozen, eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyEndpointTicket(EndpointTicket);
+
+#[pymethods]
+impl PyEndpointTicket {
+    /// Creates a ticket for an endpoint.
+    #[new]
+    #[pyo3(signature = (
+        endpoint_id, relay_urls = Vec::new(), ip_addrs = Vec::new(), alpns = Vec::new()
+    ))]
+    fn new(
+        endpoint_id: &str,
+        relay_urls: Vec<String>,
+        ip_addrs: Vec<String>,
+        alpns: Vec<Vec<u8>>,
+    ) -> PyResult<Self> {
+        let id = endpoint_id.parse().map_err(|_| invalid("endpoint id"))?;
+        let mut addr = iroh_base::EndpointAddr::new(id);
+        for url in relay_urls {
+            addr = addr.with_relay_url(url.parse().map_err(|_| invalid("relay URL"))?);
+        }
+        for ip_addr in ip_addrs {
+            addr = addr.with_ip_addr(ip_addr.parse().map_err(|_| invalid("IP address"))?);
+        }
+        Ok(Self(EndpointTicket::new(addr).with_alpns(alpns)))
+    }
+
+    /// Parses a ticket from its string form.
+    #[staticmethod]
+    fn parse(s: &str) -> PyResult<Self> {
+        EndpointTicket::decode_string(s)
+            .map(Self)
+            .map_err(to_py_err)
+    }
+
+    /// Decodes a ticket from its byte representation.
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        EndpointTicket::decode_bytes(bytes)
+            .map(Self)
+            .map_err(to_py_err)
+    }
+
+    /// The byte representation of the ticket.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.encode_bytes())
+    }
+
+    /// The endpoint id.
+    #[getter]
+    fn endpoint_id(&self) -> String {
+        self.0.endpoint_addr().id.to_string()
+    }
+
+    /// The relay URLs of the endpoint.
+    #[getter]
+    fn relay_urls(&self) -> Vec<String> {
+        let addr = self.0.endpoint_addr();
+        addr.relay_urls().map(ToString::to_string).collect()
+    }
+
+    /// The direct addresses of the endpoint, as `ip:port` strings.
+    #[getter]
+    fn ip_addrs(&self) -> Vec<String> {
+        let addr = self.0.endpoint_addr();
+        addr.ip_addrs().map(ToString::to_string).collect()
+    }
+
+    /// The ALPNs of the ticket.
+    #[getter]
+    fn alpns<'py>(&self, py: Python<'py>) -> Vec<Bound<'py, PyBytes>> {
+        let alpns = self.0.alpns().iter();
+        alpns.map(|alpn| PyBytes::new(py, alpn)).collect()
+    }
+
+    /// The fingerprint of the ticket.
+    #[getter]
+    fn fingerprint(&self) -> String {
+        self.0.fingerprint().to_string()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.encode_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("EndpointTicket({:?})", self.0.encode_string())
+    }
+}
+
+/// An [`AnyTicket`] in Python.
+#[pyclass(name = "Ticket", module = "iroh_tickets", frozen, eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyTicket(AnyTicket);
+
+#[pymethods]
+impl PyTicket {
+    /// The kind of the ticket.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        self.0.kind()
+    }
+
+    /// The byte representation of the ticket.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.encode_bytes())
+    }
+
+    /// The fingerprint of the ticket.
+    #[getter]
+    fn fingerprint(&self) -> String {
+        self.0.fingerprint().to_string()
+    }
+
+    /// The ticket as an `EndpointTicket`, or `None` for other kinds.
+    fn as_endpoint(&self) -> Option<PyEndpointTicket> {
+        match &self.0 {
+            AnyTicket::Endpoint(ticket) => Some(PyEndpointTicket(ticket.clone())),
+            _ => None,
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.0.encode_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Ticket({:?})", self.0.encode_string())
+    }
+}
+
+/// Parses a ticket string of any of the built-in kinds.
+#[pyfunction]
+fn parse(s: &str) -> PyResult<PyTicket> {
+    AnyTicket::decode_string(s).map(PyTicket).map_err(to_py_err)
+}
+
+/// Returns the kind of a ticket string without decoding its payload.
+#[pyfunction]
+fn kind_of(s: &str) -> PyResult<&'static str> {
+    crate::kind_of(s).map_err(to_py_err)
+}
+
+fn field_to_py<'py>(py: Python<'py>, value: &FieldValue) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        FieldValue::Text(text) => text.into_pyobject(py)?.into_any(),
+        FieldValue::Bytes(bytes) => PyBytes::new(py, bytes).into_any(),
+        FieldValue::List(values) => {
+            let values = values
+                .iter()
+                .map(|value| field_to_py(py, value))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, values)?.into_any()
+        }
+        FieldValue::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (name, value) in entries {
+                dict.set_item(name, field_to_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Reports what can be found out about a ticket string, see [`crate::inspect`].
+///
+/// Returns a dict with the keys `kind`, `payload_len`, `version`, `fields` and `error`,
+/// with `None` for anything that could not be found out.
+#[pyfunction]
+fn inspect<'py>(py: Python<'py>, s: &str) -> PyResult<Bound<'py, PyDict>> {
+    let inspection = crate::inspect(s);
+    let dict = PyDict::new(py);
+    dict.set_item("kind", inspection.kind())?;
+    dict.set_item("payload_len", inspection.payload_len())?;
+    dict.set_item("version", inspection.version())?;
+    let fields = inspection
+        .fields()
+        .map(|fields| field_to_py(py, fields))
+        .transpose()?;
+    dict.set_item("fields", fields)?;
+    dict.set_item("error", inspection.error().map(ToString::to_string))?;
+    Ok(dict)
+}
+
+/// The `iroh_tickets` Python module.
+#[pymodule]
+fn iroh_tickets(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEndpointTicket>()?;
+    m.add_class::<PyTicket>()?;
+    m.add("TicketError", m.py().get_type::<TicketError>())?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(kind_of, m)?)?;
+    m.add_function(wrap_pyfunction!(inspect, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::SecretKey;
+    use pyo3::ffi::c_str;
+
+    use super::*;
+
+    #[test]
+    fn test_python_module() {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "iroh_tickets").unwrap();
+            iroh_tickets(&module).unwrap();
+            let id = SecretKey::from_bytes(&[33u8; 32]).public();
+            let locals = PyDict::new(py);
+            locals.set_item("iroh_tickets", module).unwrap();
+            locals.set_item("endpoint_id", id.to_string()).unwrap();
+            py.run(
+                c_str!(
+                    r#"
+ticket = iroh_tickets.EndpointTicket(endpoint_id, ip_addrs=["198.51.100.1:1234"], alpns=[b"echo"])
+assert ticket.endpoint_id == endpoint_id
+assert ticket.ip_addrs == ["198.51.100.1:1234"]
+assert ticket.alpns == [b"echo"]
+assert iroh_tickets.EndpointTicket.parse(str(ticket)) == ticket
+assert iroh_tickets.EndpointTicket.from_bytes(ticket.to_bytes()) == ticket
+
+parsed = iroh_tickets.parse(str(ticket))
+assert parsed.kind == "endpoint"
+assert parsed.as_endpoint() == ticket
+assert iroh_tickets.kind_of(str(ticket)) == "endpoint"
+
+inspection = iroh_tickets.inspect(str(ticket))
+assert inspection["kind"] == "endpoint"
+assert inspection["error"] is None
+assert isinstance(inspection["fields"], dict)
+
+try:
+    iroh_tickets.parse("endpoint0")
+    assert False
+except iroh_tickets.TicketError as err:
+    assert isinstance(err, ValueError)
+"#
+                ),
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
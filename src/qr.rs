@@ -0,0 +1,128 @@
+//! QR codes for tickets.
+//!
+//! The QR code contains the string form of the ticket in uppercase, which allows using the
+//! compact alphanumeric mode of QR codes. Use [`decode`] to parse the scanned text.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let qr = ticket.to_qr().unwrap();
+//! let svg = qr.to_svg();
+//! assert!(svg.contains("<svg"));
+//!
+//! let parsed: EndpointTicket = iroh_tickets::qr::decode(qr.text()).unwrap();
+//! assert_eq!(parsed, ticket);
+//! ```
+
+use n0_error::{e, stack_error};
+use qrcode::{
+    QrCode,
+    render::{svg, unicode},
+};
+
+use crate::{ParseError, Ticket};
+
+/// A QR code for a ticket, see [`TicketExt::to_qr`](crate::TicketExt::to_qr).
+#[derive(Clone)]
+pub struct Qr {
+    text: String,
+    code: QrCode,
+}
+
+impl std::fmt::Debug for Qr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Qr")
+            .field("text", &self.text)
+            .field("width", &self.width())
+            .finish()
+    }
+}
+
+/// The ticket does not fit into a QR code.
+#[stack_error(derive, add_meta)]
+#[error("failed to create QR code")]
+pub struct QrError {
+    #[error(source, std_err)]
+    source: qrcode::types::QrError,
+}
+
+impl Qr {
+    /// Creates a QR code for a ticket.
+    pub fn new<T: Ticket>(ticket: &T) -> Result<Self, QrError> {
+        let text = ticket.encode_string().to_ascii_uppercase();
+        let code = QrCode::new(&text).map_err(|source| e!(QrError { source }))?;
+        Ok(Self { text, code })
+    }
+
+    /// The text contained in the QR code.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The number of modules per side, without the quiet zone.
+    pub fn width(&self) -> usize {
+        self.code.width()
+    }
+
+    /// Renders the QR code as an SVG image.
+    pub fn to_svg(&self) -> String {
+        self.code.render::<svg::Color<'_>>().build()
+    }
+
+    /// Renders the QR code with unicode block characters, e.g. for printing to a terminal.
+    ///
+    /// Each character covers two modules stacked on top of each other, using the upper and
+    /// lower half blocks. Dark modules are drawn as spaces and light modules as blocks, so
+    /// the code can be scanned from terminals with light text on a dark background. The
+    /// code is surrounded by the quiet zone required by scanners, see also
+    /// [`TicketExt::print_qr`](crate::TicketExt::print_qr).
+    pub fn to_unicode(&self) -> String {
+        self.code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build()
+    }
+}
+
+/// Decodes a ticket from the text of a scanned QR code created by [`Qr`].
+pub fn decode<T: Ticket>(text: &str) -> Result<T, ParseError> {
+    T::decode_string(&text.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_qr() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[8u8; 32]).public())
+            .with_relay_url("https://relay.example./".parse().unwrap())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let qr = ticket.to_qr().unwrap();
+
+        assert_eq!(qr.text(), ticket.to_string().to_ascii_uppercase());
+        assert_eq!(decode::<EndpointTicket>(qr.text()).unwrap(), ticket);
+
+        // Alphanumeric mode fits the ticket into a smaller code than byte mode.
+        let bytes = QrCode::new(ticket.to_string()).unwrap();
+        assert!(qr.width() < bytes.width());
+
+        let unicode = qr.to_unicode();
+        assert_eq!(unicode.lines().count(), (qr.width() + 8).div_ceil(2));
+        assert!(
+            unicode
+                .lines()
+                .all(|line| line.chars().count() == qr.width() + 8)
+        );
+        ticket.print_qr().unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+//! Displaying tickets without their content.
+
+use core::fmt;
+
+use crate::Ticket;
+
+/// The number of payload characters shown by [`Redacted`].
+const PAYLOAD_CHARS: usize = 6;
+
+/// Displays a ticket without the information needed to use it, see
+/// [`TicketExt::redacted`](crate::TicketExt::redacted).
+///
+/// Both the [`Display`](fmt::Display) and the [`Debug`](fmt::Debug) form consist of the
+/// [`KIND`](Ticket::KIND), the first few characters of the payload and the
+/// [fingerprint](Ticket::fingerprint), e.g. `endpointaeaqcb…(5fa4e8b1c0d2a3f9)`. This is
+/// enough to tell tickets apart in logs without leaking the addresses, hashes or secrets
+/// they contain.
+pub struct Redacted<'a, T>(&'a T);
+
+impl<'a, T: Ticket> Redacted<'a, T> {
+    /// Wraps `ticket` for display.
+    pub fn new(ticket: &'a T) -> Self {
+        Self(ticket)
+    }
+}
+
+impl<T: Ticket> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.0.encode_string();
+        let payload = s.strip_prefix(T::KIND).unwrap_or(&s);
+        let end = payload
+            .char_indices()
+            .nth(PAYLOAD_CHARS)
+            .map_or(payload.len(), |(i, _)| i);
+        write!(
+            f,
+            "{}{}…({})",
+            T::KIND,
+            &payload[..end],
+            self.0.fingerprint()
+        )
+    }
+}
+
+impl<T: Ticket> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use crate::{Ticket, TicketExt, endpoint::EndpointTicket};
+
+    #[test]
+    fn test_redacted() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[22u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let ticket = EndpointTicket::new(addr);
+        let s = ticket.encode_string();
+        let redacted = ticket.redacted().to_string();
+        assert_eq!(redacted, format!("{}…({})", &s[..14], ticket.fingerprint()));
+        assert_eq!(format!("{:?}", ticket.redacted()), redacted);
+        assert!(!redacted.contains(&s[14..30]));
+    }
+}
@@ -0,0 +1,405 @@
+//! Runtime dispatch on ticket kinds.
+//!
+//! A [`Registry`] maps ticket kinds to their concrete types, so that tools accepting "any
+//! ticket" can parse a string without knowing its type up front:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, registry::Registry};
+//!
+//! let mut registry = Registry::new();
+//! registry.register::<EndpointTicket>();
+//!
+//! let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+//! let encoded = EndpointTicket::new(addr).to_string();
+//!
+//! let ticket = registry.parse(&encoded).unwrap();
+//! assert_eq!(ticket.kind(), "endpoint");
+//! assert!(ticket.downcast_ref::<EndpointTicket>().is_some());
+//! ```
+//!
+//! Kinds are global across the ecosystem, [`check_collisions`] catches custom kinds that
+//! are already used by n0 crates, see [`RESERVED_KINDS`].
+
+use std::{any::Any, collections::BTreeMap, fmt::Debug};
+
+use n0_error::{e, stack_error};
+use serde::{Serialize, Serializer};
+
+use crate::{ParseError, Ticket, longest_kind_prefix, suggest_kind};
+
+/// An object-safe view of a [`Ticket`].
+///
+/// This is implemented for every [`Ticket`] that is [`Debug`], [`Send`] and [`Sync`], and
+/// allows holding tickets of different types behind a `Box<dyn ErasedTicket>`, e.g. to list
+/// mixed tickets in a UI:
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::{endpoint::EndpointTicket, gossip::GossipTicket, registry::ErasedTicket};
+///
+/// let addr = EndpointAddr::new(SecretKey::from_bytes(&[0u8; 32]).public());
+/// let tickets: Vec<Box<dyn ErasedTicket>> = vec![
+///     Box::new(EndpointTicket::new(addr.clone())),
+///     Box::new(GossipTicket::new([1u8; 32], [addr])),
+/// ];
+/// let kinds: Vec<_> = tickets.iter().map(|t| t.kind()).collect();
+/// assert_eq!(kinds, ["endpoint", "topic"]);
+///
+/// // Serializes as a list of ticket strings.
+/// let json = serde_json::to_value(&tickets).unwrap();
+/// assert_eq!(json[1], tickets[1].to_string());
+/// ```
+pub trait ErasedTicket: Debug + Send + Sync + 'static {
+    /// The kind of the underlying ticket, see [`Ticket::KIND`].
+    fn kind(&self) -> &'static str;
+
+    /// Encodes the underlying ticket into its canonical string form.
+    ///
+    /// This is [`Ticket::encode_string`], named differently to not clash with it.
+    fn to_ticket_string(&self) -> String;
+
+    /// Encodes the underlying ticket into its byte representation.
+    ///
+    /// This is [`Ticket::encode_bytes`], named differently to not clash with it.
+    fn to_ticket_bytes(&self) -> Vec<u8>;
+
+    /// Returns the underlying ticket as [`Any`], to downcast it to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Ticket + Debug + Send + Sync + 'static> ErasedTicket for T {
+    fn kind(&self) -> &'static str {
+        T::KIND
+    }
+
+    fn to_ticket_string(&self) -> String {
+        self.encode_string()
+    }
+
+    fn to_ticket_bytes(&self) -> Vec<u8> {
+        self.encode_bytes()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn ErasedTicket {
+    /// Returns the underlying ticket if it is of type `T`.
+    pub fn downcast_ref<T: ErasedTicket>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+impl std::fmt::Display for dyn ErasedTicket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_ticket_string())
+    }
+}
+
+/// Serializes as the canonical string form of the underlying ticket.
+impl Serialize for dyn ErasedTicket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_ticket_string())
+    }
+}
+
+/// Ticket kinds used by n0 crates, in lexicographic order.
+///
+/// This contains the kinds of this crate as well as those of e.g. `iroh-docs` and the
+/// `node` kind of tickets from older iroh releases. Custom tickets should not reuse them.
+pub const RESERVED_KINDS: &[&str] = &[
+    "blob",
+    "bundle",
+    "compressed",
+    "dns",
+    "doc",
+    "endpoint",
+    "expiring",
+    "invite",
+    "mac",
+    "multiendpoint",
+    "node",
+    "once",
+    "protected",
+    "relay",
+    "revocations",
+    "scoped",
+    "sealed",
+    "signed",
+    "topic",
+];
+
+/// A ticket kind is used more than once, see [`check_collisions`].
+#[stack_error(derive, add_meta)]
+#[non_exhaustive]
+pub enum CollisionError {
+    /// The kind is one of the [`RESERVED_KINDS`].
+    #[error("ticket kind {kind:?} is reserved")]
+    Reserved {
+        /// The reserved kind.
+        kind: String,
+    },
+    /// The kind was given more than once.
+    #[error("ticket kind {kind:?} is used more than once")]
+    Duplicate {
+        /// The duplicated kind.
+        kind: String,
+    },
+}
+
+/// Returns whether `kind` is one of the [`RESERVED_KINDS`].
+pub fn is_reserved(kind: &str) -> bool {
+    RESERVED_KINDS.binary_search(&kind).is_ok()
+}
+
+/// Checks that the kinds of custom ticket types neither collide with each other nor with
+/// the [`RESERVED_KINDS`].
+///
+/// This is meant for tests of crates defining their own tickets:
+///
+/// ```
+/// use iroh_tickets::registry::{self, CollisionError};
+///
+/// registry::check_collisions(&["room", "lobby"]).unwrap();
+/// assert!(matches!(
+///     registry::check_collisions(&["room", "doc"]),
+///     Err(CollisionError::Reserved { .. })
+/// ));
+/// ```
+pub fn check_collisions(kinds: &[&str]) -> Result<(), CollisionError> {
+    for (i, kind) in kinds.iter().enumerate() {
+        if is_reserved(kind) {
+            return Err(e!(CollisionError::Reserved {
+                kind: kind.to_string()
+            }));
+        }
+        if kinds[..i].contains(kind) {
+            return Err(e!(CollisionError::Duplicate {
+                kind: kind.to_string()
+            }));
+        }
+    }
+    Ok(())
+}
+
+type DecodeFn = fn(&str) -> Result<Box<dyn ErasedTicket>, ParseError>;
+
+/// A set of ticket kinds that can be parsed at runtime.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    kinds: BTreeMap<&'static str, DecodeFn>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the ticket type `T` under its [`KIND`](Ticket::KIND) and its
+    /// [`KIND_ALIASES`](Ticket::KIND_ALIASES).
+    ///
+    /// Registering a second type with the same kind replaces the first one.
+    pub fn register<T: Ticket + Debug + Send + Sync + 'static>(&mut self) -> &mut Self {
+        let decode: DecodeFn =
+            |s| T::decode_string(s).map(|t| Box::new(t) as Box<dyn ErasedTicket>);
+        for kind in std::iter::once(T::KIND).chain(T::KIND_ALIASES.iter().copied()) {
+            self.kinds.insert(kind, decode);
+        }
+        self
+    }
+
+    /// Returns whether a ticket type is registered for `kind`.
+    pub fn contains(&self, kind: &str) -> bool {
+        self.kinds.contains_key(kind)
+    }
+
+    /// Returns the registered kinds in lexicographic order.
+    pub fn kinds(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.kinds.keys().copied()
+    }
+
+    /// Returns the registered kind of a ticket string without decoding its payload.
+    ///
+    /// If several registered kinds are a prefix of `s`, the longest one is returned. If
+    /// none is, the error suggests a registered kind that is similar to the start of `s`,
+    /// to help with typos.
+    pub fn kind_of(&self, s: &str) -> Result<&'static str, ParseError> {
+        longest_kind_prefix(s, self.kinds()).ok_or_else(|| {
+            let suggestion = suggest_kind(s, self.kinds());
+            e!(ParseError::UnknownKind { suggestion })
+        })
+    }
+
+    /// Parses a ticket string of any registered kind.
+    ///
+    /// The concrete type is picked by [`kind_of`](Self::kind_of).
+    pub fn parse(&self, s: &str) -> Result<Box<dyn ErasedTicket>, ParseError> {
+        let kind = self.kind_of(s)?;
+        (self.kinds[kind])(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{encode_raw, endpoint::EndpointTicket};
+
+    #[derive(Debug, PartialEq)]
+    struct OtherTicket(u8);
+
+    impl Ticket for OtherTicket {
+        const KIND: &'static str = "endpointother";
+        const KIND_ALIASES: &'static [&'static str] = &["other"];
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            vec![self.0]
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            match bytes {
+                [b] => Ok(Self(*b)),
+                _ => Err(ParseError::verification_failed("expected a single byte")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_parse() {
+        let mut registry = Registry::new();
+        registry
+            .register::<EndpointTicket>()
+            .register::<OtherTicket>();
+        assert_eq!(
+            registry.kinds().collect::<Vec<_>>(),
+            ["endpoint", "endpointother", "other"]
+        );
+
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[1u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let parsed = registry.parse(&ticket.encode_string()).unwrap();
+        assert_eq!(parsed.kind(), "endpoint");
+        assert_eq!(parsed.downcast_ref::<EndpointTicket>(), Some(&ticket));
+        assert_eq!(parsed.to_ticket_bytes(), ticket.encode_bytes());
+        assert_eq!(parsed.to_string(), ticket.encode_string());
+
+        let parsed = registry.parse(&OtherTicket(5).encode_string()).unwrap();
+        assert_eq!(parsed.downcast_ref::<OtherTicket>(), Some(&OtherTicket(5)));
+
+        // Aliases are accepted, but the kind is always emitted.
+        let renamed = encode_raw("other", &[5]);
+        assert_eq!(
+            OtherTicket::decode_string(&renamed).unwrap(),
+            OtherTicket(5)
+        );
+        assert_eq!(
+            OtherTicket::canonicalize(&renamed).unwrap(),
+            OtherTicket(5).encode_string()
+        );
+        let parsed = registry.parse(&renamed.to_ascii_uppercase()).unwrap();
+        assert_eq!(parsed.kind(), "endpointother");
+        assert!(matches!(
+            EndpointTicket::decode_string(&renamed),
+            Err(ParseError::Kind { .. })
+        ));
+
+        let err = registry.parse("blobaaaa").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnknownKind {
+                suggestion: None,
+                ..
+            }
+        ));
+
+        let typo = ticket.encode_string().replacen("endpoint", "endpiont", 1);
+        let err = registry.parse(&typo).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnknownKind {
+                suggestion: Some("endpoint"),
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "unknown ticket kind, did you mean endpoint?"
+        );
+        let missing = ticket.encode_string().replacen("endpoint", "ndpoint", 1);
+        let err = registry.parse(&missing).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnknownKind {
+                suggestion: Some("endpoint"),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_check_collisions() {
+        assert!(RESERVED_KINDS.is_sorted());
+        for kind in crate::AnyTicket::KINDS {
+            assert!(is_reserved(kind), "{kind} is not reserved");
+        }
+        assert!(is_reserved(
+            crate::signed::SignedTicket::<EndpointTicket>::KIND
+        ));
+
+        check_collisions(&[]).unwrap();
+        check_collisions(&["room", OtherTicket::KIND]).unwrap();
+        let err = check_collisions(&["room", "topic"]).unwrap_err();
+        assert!(matches!(err, CollisionError::Reserved { kind, .. } if kind == "topic"));
+        let err = check_collisions(&["room", "lobby", "room"]).unwrap_err();
+        assert!(matches!(err, CollisionError::Duplicate { kind, .. } if kind == "room"));
+    }
+
+    #[test]
+    fn test_reserved_kinds_prefix_free() {
+        // Otherwise the payload of a ticket could continue its kind into a longer one.
+        for kind in RESERVED_KINDS {
+            for other in RESERVED_KINDS {
+                assert!(
+                    kind == other || !other.starts_with(kind),
+                    "{kind} is a prefix of {other}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_custom_kind() {
+        // A custom kind that is a prefix of the reserved kind `doc`.
+        #[derive(Debug, PartialEq)]
+        struct DoTicket(Vec<u8>);
+
+        impl Ticket for DoTicket {
+            const KIND: &'static str = "do";
+
+            fn encode_bytes(&self) -> Vec<u8> {
+                self.0.clone()
+            }
+
+            fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+                Ok(Self(bytes.to_vec()))
+            }
+        }
+
+        let ticket = DoTicket(vec![0x10, 0x01]);
+        let s = ticket.encode_string();
+        assert_eq!(s, "docaaq");
+        assert_eq!(DoTicket::decode_string(&s).unwrap(), ticket);
+        for byte in 0..=u8::MAX {
+            let ticket = DoTicket(vec![byte, byte]);
+            assert_eq!(
+                DoTicket::decode_string(&ticket.encode_string()).unwrap(),
+                ticket
+            );
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Tickets for relay servers.
+//!
+//! A [`RelayTicket`] contains the URL of a relay server and optionally a token to
+//! authenticate with it, so relay operators can hand out access to their relays as a
+//! ticket, like any other connection info:
+//!
+//! ```
+//! use iroh_tickets::{Ticket, relay::RelayTicket};
+//!
+//! let ticket =
+//!     RelayTicket::new("https://relay.example.com".parse().unwrap()).with_token("secret");
+//! let s = ticket.to_string();
+//! assert!(s.starts_with("relay"));
+//!
+//! let parsed: RelayTicket = s.parse().unwrap();
+//! assert_eq!(parsed.token(), Some("secret"));
+//! ```
+
+use std::str::FromStr;
+
+use iroh_base::RelayUrl;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket for a relay server.
+///
+/// Contains
+/// - The [`RelayUrl`] of the relay server.
+/// - Optionally a token to authenticate with the relay, its meaning is up to the relay.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, derive_more::Display)]
+#[display("{}", Ticket::encode_string(self))]
+pub struct RelayTicket {
+    url: RelayUrl,
+    token: Option<String>,
+}
+
+/// Wire format for [`RelayTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0RelayTicket {
+    url: RelayUrl,
+    token: Option<String>,
+}
+
+impl Versioned for Variant0RelayTicket {
+    const VERSION: u32 = 0;
+}
+
+impl Ticket for RelayTicket {
+    const KIND: &'static str = "relay";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0RelayTicket {
+            url: self.url.clone(),
+            token: self.token.clone(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0RelayTicket { url, token } = versioned::decode(bytes)?;
+        Ok(Self { url, token })
+    }
+}
+
+impl RelayTicket {
+    /// Creates a ticket for the relay at `url`, without a token.
+    pub fn new(url: RelayUrl) -> Self {
+        Self { url, token: None }
+    }
+
+    /// Sets the token to authenticate with the relay.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// The URL of the relay.
+    pub fn url(&self) -> &RelayUrl {
+        &self.url
+    }
+
+    /// The token to authenticate with the relay, if any.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Returns the contents of the ticket.
+    pub fn into_parts(self) -> (RelayUrl, Option<String>) {
+        (self.url, self.token)
+    }
+}
+
+impl From<RelayUrl> for RelayTicket {
+    fn from(url: RelayUrl) -> Self {
+        Self::new(url)
+    }
+}
+
+impl FromStr for RelayTicket {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl Serialize for RelayTicket {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RelayTicket {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_ticket_roundtrip() {
+        let url: RelayUrl = "https://relay.example./".parse().unwrap();
+        let ticket = RelayTicket::from(url.clone());
+        assert_eq!(ticket.token(), None);
+        let parsed: RelayTicket = ticket.to_string().parse().unwrap();
+        assert_eq!(parsed, ticket);
+
+        let ticket = ticket.with_token("secret");
+        let s = ticket.to_string();
+        assert!(s.starts_with("relay"));
+        let parsed: RelayTicket = s.parse().unwrap();
+        assert_eq!(parsed.url(), &url);
+        assert_eq!(parsed.token(), Some("secret"));
+
+        let bytes = postcard::to_stdvec(&ticket).unwrap();
+        assert_eq!(postcard::from_bytes::<RelayTicket>(&bytes).unwrap(), ticket);
+        let json = serde_json::to_string(&ticket).unwrap();
+        assert_eq!(json, format!("\"{s}\""));
+        assert_eq!(parsed.into_parts(), (url, Some("secret".to_string())));
+    }
+}
@@ -0,0 +1,538 @@
+//! Sending tickets through a rendezvous server with a short code.
+//!
+//! This is the [magic wormhole] workflow: the sending side [publishes](publish) a ticket
+//! to a rendezvous endpoint and gets a short code like `7-guitarist-revenge`, which is read
+//! out or typed on the receiving side to [retrieve] the ticket.
+//!
+//! The number at the start of the code is the nameplate, it identifies the waiting sender
+//! on the rendezvous server. The words are a password that never leaves the two sides. Both
+//! sides run [SPAKE2] with the words to agree on a key, and the ticket is encrypted with
+//! ChaCha20-Poly1305 under that key, so the rendezvous server never sees the ticket. The
+//! server frees a nameplate at the first attempt to retrieve from it, so an attacker with a
+//! wrong code gets a single guess and makes the exchange fail instead of getting the ticket.
+//!
+//! The [`Rendezvous`] protocol handler is the server side, it relays the messages between
+//! the two sides. Any endpoint can run it under [`ALPN`].
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> n0_error::Result<()> {
+//! use iroh::{Endpoint, endpoint::presets};
+//! use iroh_tickets::{endpoint::EndpointTicket, rendezvous};
+//!
+//! # let server: iroh::EndpointAddr = todo!();
+//! let sender = Endpoint::bind(presets::N0).await?;
+//! let ticket = EndpointTicket::new(sender.addr());
+//! let offer = rendezvous::publish(&sender, server.clone(), &ticket).await?;
+//! println!("code: {}", offer.code());
+//! offer.finish().await?;
+//!
+//! // On the receiving side.
+//! let receiver = Endpoint::bind(presets::N0).await?;
+//! let ticket: EndpointTicket =
+//!     rendezvous::retrieve(&receiver, server, "7-guitarist-revenge").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [magic wormhole]: https://magic-wormhole.readthedocs.io
+//! [SPAKE2]: https://datatracker.ietf.org/doc/html/rfc9382
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit,
+    aead::{Aead, Payload},
+};
+use iroh::{
+    Endpoint, EndpointAddr,
+    endpoint::{
+        ClosedStream, ConnectError, Connection, ConnectionError, ReadExactError, RecvStream,
+        SendStream, WriteError,
+    },
+    protocol::{AcceptError, ProtocolHandler},
+};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use spake2::{Ed25519Group, Identity, Password, Spake2};
+use tokio::sync::oneshot;
+
+use crate::{DEFAULT_MAX_LEN, ParseError, Ticket, words::WORDS};
+
+/// The ALPN of the rendezvous protocol.
+pub const ALPN: &[u8] = b"/iroh-tickets/rendezvous/1";
+
+/// The maximum size of a message, leaving room for the encryption overhead.
+const MAX_MESSAGE_LEN: usize = DEFAULT_MAX_LEN + 1024;
+
+/// The SPAKE2 identity of the sending side.
+const SENDER_ID: &[u8] = b"iroh-tickets rendezvous sender";
+
+/// The SPAKE2 identity of the receiving side.
+const RECEIVER_ID: &[u8] = b"iroh-tickets rendezvous receiver";
+
+/// The key derivation context for the encryption key.
+const KEY_CONTEXT: &str = "iroh-tickets 2026 rendezvous ticket key";
+
+/// The number of words in a code.
+const CODE_WORDS: usize = 2;
+
+/// Messages from the sending and receiving sides to the server.
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    /// Allocates a nameplate, sent by the sending side.
+    Allocate { message: Vec<u8> },
+    /// Claims a nameplate, sent by the receiving side.
+    Claim { nameplate: u32, message: Vec<u8> },
+    /// The encrypted ticket, sent by the sending side.
+    Sealed { ciphertext: Vec<u8> },
+}
+
+/// Messages from the server to the sending and receiving sides.
+#[derive(Debug, Serialize, Deserialize)]
+enum Response {
+    /// The nameplate allocated for the sending side.
+    Allocated { nameplate: u32 },
+    /// The SPAKE2 message of the other side.
+    Peer { message: Vec<u8> },
+    /// The encrypted ticket, relayed to the receiving side.
+    Sealed { ciphertext: Vec<u8> },
+    /// There is no sender waiting at the nameplate.
+    NotFound,
+}
+
+/// A sender waiting for a receiver at a nameplate.
+struct Mailbox {
+    message: Vec<u8>,
+    peer: oneshot::Sender<(Vec<u8>, oneshot::Sender<Vec<u8>>)>,
+}
+
+/// Protocol handler for the rendezvous server, see the [module docs](self).
+#[derive(Clone, Default)]
+pub struct Rendezvous {
+    mailboxes: Arc<Mutex<BTreeMap<u32, Mailbox>>>,
+}
+
+impl fmt::Debug for Rendezvous {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mailboxes = self.mailboxes.lock().expect("poisoned");
+        f.debug_struct("Rendezvous")
+            .field("nameplates", &mailboxes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Rendezvous {
+    /// Creates a rendezvous server without any waiting senders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates the lowest free nameplate, so codes stay short.
+    fn allocate(&self, mailbox: Mailbox) -> u32 {
+        let mut mailboxes = self.mailboxes.lock().expect("poisoned");
+        let nameplate = (1..)
+            .find(|n| !mailboxes.contains_key(n))
+            .expect("free nameplate");
+        mailboxes.insert(nameplate, mailbox);
+        nameplate
+    }
+
+    async fn sender(
+        &self,
+        connection: &Connection,
+        mut send: SendStream,
+        mut recv: RecvStream,
+        message: Vec<u8>,
+    ) -> Result<(), AcceptError> {
+        let (peer, claimed) = oneshot::channel();
+        let nameplate = self.allocate(Mailbox { message, peer });
+        write_message(&mut send, &Response::Allocated { nameplate })
+            .await
+            .map_err(AcceptError::from_err)?;
+        let claim = tokio::select! {
+            claim = claimed => claim.ok(),
+            _ = connection.closed() => None,
+        };
+        let Some((message, sealed)) = claim else {
+            // The sender went away before a receiver claimed the nameplate.
+            self.mailboxes.lock().expect("poisoned").remove(&nameplate);
+            return Ok(());
+        };
+        write_message(&mut send, &Response::Peer { message })
+            .await
+            .map_err(AcceptError::from_err)?;
+        match read_message(&mut recv)
+            .await
+            .map_err(AcceptError::from_err)?
+        {
+            Request::Sealed { ciphertext } => {
+                sealed.send(ciphertext).ok();
+            }
+            _ => return Err(AcceptError::from_err(e!(RendezvousError::Protocol))),
+        }
+        send.finish().map_err(AcceptError::from_err)?;
+        Ok(())
+    }
+
+    async fn receiver(
+        &self,
+        mut send: SendStream,
+        nameplate: u32,
+        message: Vec<u8>,
+    ) -> Result<(), AcceptError> {
+        // A nameplate can only be claimed once, wrong codes get a single guess.
+        let mailbox = self.mailboxes.lock().expect("poisoned").remove(&nameplate);
+        let (sealed, receive_sealed) = oneshot::channel();
+        let response = match mailbox {
+            Some(mailbox) => match mailbox.peer.send((message, sealed)) {
+                Ok(()) => Response::Peer {
+                    message: mailbox.message,
+                },
+                // The sender went away in the meantime.
+                Err(_) => Response::NotFound,
+            },
+            None => Response::NotFound,
+        };
+        let found = matches!(response, Response::Peer { .. });
+        write_message(&mut send, &response)
+            .await
+            .map_err(AcceptError::from_err)?;
+        if found && let Ok(ciphertext) = receive_sealed.await {
+            write_message(&mut send, &Response::Sealed { ciphertext })
+                .await
+                .map_err(AcceptError::from_err)?;
+        }
+        send.finish().map_err(AcceptError::from_err)?;
+        Ok(())
+    }
+}
+
+impl ProtocolHandler for Rendezvous {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        let (send, mut recv) = connection.accept_bi().await?;
+        match read_message(&mut recv)
+            .await
+            .map_err(AcceptError::from_err)?
+        {
+            Request::Allocate { message } => {
+                self.sender(&connection, send, recv, message).await?;
+            }
+            Request::Claim { nameplate, message } => {
+                self.receiver(send, nameplate, message).await?;
+            }
+            Request::Sealed { .. } => {
+                return Err(AcceptError::from_err(e!(RendezvousError::Protocol)));
+            }
+        }
+        // Wait for the other side to read everything before closing the connection.
+        connection.closed().await;
+        Ok(())
+    }
+}
+
+/// An error publishing or retrieving a ticket through a rendezvous server.
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum RendezvousError {
+    /// Connecting to the rendezvous server failed.
+    #[error(transparent)]
+    Connect {
+        #[error(source, from, std_err)]
+        source: ConnectError,
+    },
+    /// The connection to the rendezvous server failed.
+    #[error(transparent)]
+    Connection {
+        #[error(source, from, std_err)]
+        source: ConnectionError,
+    },
+    /// Sending a message failed.
+    #[error(transparent)]
+    Write {
+        #[error(source, from, std_err)]
+        source: WriteError,
+    },
+    /// The stream was closed before the message was sent.
+    #[error(transparent)]
+    ClosedStream {
+        #[error(source, from, std_err)]
+        source: ClosedStream,
+    },
+    /// Receiving a message failed.
+    #[error(transparent)]
+    Read {
+        #[error(source, from, std_err)]
+        source: ReadExactError,
+    },
+    /// The code is not a number followed by words, like `7-guitarist-revenge`.
+    #[error("invalid rendezvous code")]
+    InvalidCode {},
+    /// No sender is waiting for the code, or the code was already used.
+    #[error("no ticket is waiting for this code")]
+    NotFound {},
+    /// The two sides used different codes.
+    #[error("wrong rendezvous code")]
+    WrongCode {},
+    /// The other side or the server did not follow the protocol.
+    #[error("rendezvous protocol violation")]
+    Protocol {},
+    /// The received ticket could not be decoded.
+    #[error("failed to decode received ticket")]
+    Ticket { source: ParseError },
+}
+
+async fn write_message(
+    send: &mut SendStream,
+    message: &impl Serialize,
+) -> Result<(), RendezvousError> {
+    let message = postcard::to_stdvec(message).expect("postcard serialization failed");
+    let len = u32::try_from(message.len()).expect("message length fits in u32");
+    send.write_all(&len.to_be_bytes()).await?;
+    send.write_all(&message).await?;
+    Ok(())
+}
+
+async fn read_message<M: DeserializeOwned>(recv: &mut RecvStream) -> Result<M, RendezvousError> {
+    let mut len = [0u8; 4];
+    recv.read_exact(&mut len).await?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(e!(RendezvousError::Protocol));
+    }
+    let mut message = vec![0u8; len];
+    recv.read_exact(&mut message).await?;
+    postcard::from_bytes(&message).map_err(|_| e!(RendezvousError::Protocol))
+}
+
+/// Normalizes a code to the nameplate and the lowercase code, the SPAKE2 password.
+fn parse_code(code: &str) -> Result<(u32, String), RendezvousError> {
+    let code = code.trim().to_ascii_lowercase();
+    let mut parts = code.split('-');
+    let nameplate = parts
+        .next()
+        .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| e!(RendezvousError::InvalidCode))?;
+    let words = parts.collect::<Vec<_>>();
+    if words.len() != CODE_WORDS || words.iter().any(|word| !WORDS.contains(word)) {
+        return Err(e!(RendezvousError::InvalidCode));
+    }
+    Ok((nameplate, code))
+}
+
+/// Returns the words of a new code, separated by `-`.
+fn random_words() -> String {
+    let words: Vec<_> = (0..CODE_WORDS)
+        .map(|_| {
+            let mut index = [0u8; 2];
+            getrandom::fill(&mut index).expect("failed to get random bytes");
+            WORDS[usize::from(u16::from_be_bytes(index)) % WORDS.len()]
+        })
+        .collect();
+    words.join("-")
+}
+
+fn spake(code: &str, sender: bool) -> (Spake2<Ed25519Group>, Vec<u8>) {
+    let password = Password::new(code.as_bytes());
+    let (sender_id, receiver_id) = (Identity::new(SENDER_ID), Identity::new(RECEIVER_ID));
+    if sender {
+        Spake2::start_a(&password, &sender_id, &receiver_id)
+    } else {
+        Spake2::start_b(&password, &sender_id, &receiver_id)
+    }
+}
+
+fn cipher(
+    state: Spake2<Ed25519Group>,
+    message: &[u8],
+) -> Result<ChaCha20Poly1305, RendezvousError> {
+    let secret = state
+        .finish(message)
+        .map_err(|_| e!(RendezvousError::Protocol))?;
+    let key = blake3::derive_key(KEY_CONTEXT, &secret);
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+/// A ticket published to a rendezvous server, waiting for a receiver.
+///
+/// Show the [`code`](Self::code) to the receiving side and call [`finish`](Self::finish)
+/// to hand over the ticket once it arrives.
+#[derive(Debug)]
+pub struct Offer {
+    code: String,
+    kind: &'static str,
+    bytes: Vec<u8>,
+    state: Spake2<Ed25519Group>,
+    connection: Connection,
+    send: SendStream,
+    recv: RecvStream,
+}
+
+impl Offer {
+    /// The code the receiving side needs to [`retrieve`] the ticket.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Waits for a receiver and sends it the encrypted ticket.
+    ///
+    /// This returns once the encrypted ticket was handed to the rendezvous server. If the
+    /// receiver used a wrong code, this still succeeds, but the receiver fails to decrypt
+    /// the ticket.
+    pub async fn finish(mut self) -> Result<(), RendezvousError> {
+        let Response::Peer { message } = read_message(&mut self.recv).await? else {
+            return Err(e!(RendezvousError::Protocol));
+        };
+        let payload = Payload {
+            msg: &self.bytes,
+            aad: self.kind.as_bytes(),
+        };
+        // The key is only used once, so a fixed nonce is fine.
+        let ciphertext = cipher(self.state, &message)?
+            .encrypt(&Default::default(), payload)
+            .expect("encrypting into a vec is infallible");
+        write_message(&mut self.send, &Request::Sealed { ciphertext }).await?;
+        self.send.finish()?;
+        self.recv.read_to_end(0).await.ok();
+        self.connection.close(0u32.into(), b"done");
+        Ok(())
+    }
+}
+
+/// Publishes a ticket to the rendezvous server at `server`.
+///
+/// The returned [`Offer`] holds the code for the receiving side.
+pub async fn publish<T: Ticket>(
+    endpoint: &Endpoint,
+    server: impl Into<EndpointAddr>,
+    ticket: &T,
+) -> Result<Offer, RendezvousError> {
+    let connection = endpoint.connect(server, ALPN).await?;
+    let (mut send, mut recv) = connection.open_bi().await?;
+    let words = random_words();
+    // The nameplate is not known yet, so it is not part of the password.
+    let (state, message) = spake(&words, true);
+    write_message(&mut send, &Request::Allocate { message }).await?;
+    let Response::Allocated { nameplate } = read_message(&mut recv).await? else {
+        return Err(e!(RendezvousError::Protocol));
+    };
+    Ok(Offer {
+        code: format!("{nameplate}-{words}"),
+        kind: T::KIND,
+        bytes: ticket.encode_bytes(),
+        state,
+        connection,
+        send,
+        recv,
+    })
+}
+
+/// Retrieves a ticket published with [`publish`] from the rendezvous server at `server`.
+///
+/// The code is case insensitive.
+pub async fn retrieve<T: Ticket>(
+    endpoint: &Endpoint,
+    server: impl Into<EndpointAddr>,
+    code: &str,
+) -> Result<T, RendezvousError> {
+    let (nameplate, code) = parse_code(code)?;
+    let words = code.split_once('-').expect("parsed code").1;
+    let connection = endpoint.connect(server, ALPN).await?;
+    let (mut send, mut recv) = connection.open_bi().await?;
+    let (state, message) = spake(words, false);
+    write_message(&mut send, &Request::Claim { nameplate, message }).await?;
+    send.finish()?;
+    let message = match read_message(&mut recv).await? {
+        Response::Peer { message } => message,
+        Response::NotFound => return Err(e!(RendezvousError::NotFound)),
+        _ => return Err(e!(RendezvousError::Protocol)),
+    };
+    let cipher = cipher(state, &message)?;
+    let Response::Sealed { ciphertext } = read_message(&mut recv).await? else {
+        return Err(e!(RendezvousError::Protocol));
+    };
+    connection.close(0u32.into(), b"done");
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: T::KIND.as_bytes(),
+    };
+    let bytes = cipher
+        .decrypt(&Default::default(), payload)
+        .map_err(|_| e!(RendezvousError::WrongCode))?;
+    T::decode_bytes(&bytes).map_err(|source| e!(RendezvousError::Ticket { source }))
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::{endpoint::presets, protocol::Router};
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_parse_code() {
+        let (nameplate, code) = parse_code(" 7-Guitar-Ability ").unwrap();
+        assert_eq!(nameplate, 7);
+        assert_eq!(code, "7-guitar-ability");
+        for code in [
+            "guitar-ability",
+            "7-guitar",
+            "7-guitar-tickets",
+            "+7-zoo-zoo",
+            "",
+        ] {
+            let err = parse_code(code).unwrap_err();
+            assert!(matches!(err, RendezvousError::InvalidCode { .. }), "{code}");
+        }
+        let words = random_words();
+        assert!(parse_code(&format!("1-{words}")).is_ok(), "{words}");
+    }
+
+    #[tokio::test]
+    async fn test_rendezvous() {
+        let server = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let router = Router::builder(server.clone())
+            .accept(ALPN, Rendezvous::new())
+            .spawn();
+        let sender = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let receiver = Endpoint::builder(presets::Minimal).bind().await.unwrap();
+        let ticket = EndpointTicket::new(sender.addr());
+
+        let offer = publish(&sender, server.addr(), &ticket).await.unwrap();
+        let code = offer.code().to_uppercase();
+        assert!(code.starts_with("1-"), "{code}");
+        let finish = tokio::spawn(offer.finish());
+        let received: EndpointTicket = retrieve(&receiver, server.addr(), &code).await.unwrap();
+        assert_eq!(received, ticket);
+        finish.await.unwrap().unwrap();
+
+        // The nameplate is freed after use.
+        let err = retrieve::<EndpointTicket>(&receiver, server.addr(), &code)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RendezvousError::NotFound { .. }));
+
+        let offer = publish(&sender, server.addr(), &ticket).await.unwrap();
+        let code = offer.code().to_string();
+        let finish = tokio::spawn(offer.finish());
+        let (nameplate, _) = parse_code(&code).unwrap();
+        let wrong = if code.ends_with("-zoo") {
+            format!("{nameplate}-zoo-zone")
+        } else {
+            format!("{nameplate}-zoo-zoo")
+        };
+        let err = retrieve::<EndpointTicket>(&receiver, server.addr(), &wrong)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RendezvousError::WrongCode { .. }));
+        finish.await.unwrap().unwrap();
+
+        router.shutdown().await.unwrap();
+    }
+}
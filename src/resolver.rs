@@ -0,0 +1,134 @@
+//! Refreshing the addresses in tickets.
+//!
+//! The addresses in an [`EndpointTicket`] are a snapshot from when the ticket was created.
+//! Direct addresses change when an endpoint moves between networks, and the relay can
+//! change too, so the addresses of an old ticket may no longer work. A [`Resolver`] looks
+//! up the current addresses of the endpoint, e.g. with the address lookup services (DNS,
+//! pkarr) of an iroh [`Endpoint`] in the [`DiscoveryResolver`].
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> n0_error::Result<()> {
+//! use iroh::{Endpoint, endpoint::presets};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     resolver::{DiscoveryResolver, Resolver},
+//! };
+//!
+//! # let ticket: EndpointTicket = todo!();
+//! let endpoint = Endpoint::bind(presets::N0).await?;
+//! let resolver = DiscoveryResolver::new(endpoint.clone());
+//! let addr = resolver.resolve(&ticket).await?;
+//! let connection = endpoint.connect(addr, b"my-alpn").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use iroh::{Endpoint, EndpointAddr, address_lookup::AddressLookupFailed, endpoint::EndpointError};
+use n0_error::stack_error;
+use n0_future::StreamExt;
+
+use crate::endpoint::EndpointTicket;
+
+/// Looks up the current address of the endpoint of a ticket.
+pub trait Resolver: fmt::Debug + Send + Sync {
+    /// Returns a refreshed address for the endpoint of `ticket`.
+    ///
+    /// The returned address has the same id as the ticket.
+    fn resolve(
+        &self,
+        ticket: &EndpointTicket,
+    ) -> impl Future<Output = Result<EndpointAddr, ResolveError>> + Send;
+}
+
+/// An error resolving the address of a ticket.
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ResolveError {
+    /// The endpoint used for the lookup is closed.
+    #[error(transparent)]
+    Endpoint {
+        #[error(source, from, std_err)]
+        source: EndpointError,
+    },
+    /// No address lookup service found the endpoint.
+    #[error(transparent)]
+    Lookup {
+        #[error(source, from, std_err)]
+        source: AddressLookupFailed,
+    },
+}
+
+/// A [`Resolver`] using the address lookup services of an iroh [`Endpoint`].
+///
+/// The services are configured on the endpoint, e.g. DNS and pkarr with the
+/// [`N0`](iroh::endpoint::presets::N0) preset. The resolved address contains the addresses
+/// found by the first service that answers together with the addresses of the ticket, so
+/// dialing it works as long as either of them is current.
+#[derive(Debug, Clone)]
+pub struct DiscoveryResolver {
+    endpoint: Endpoint,
+}
+
+impl DiscoveryResolver {
+    /// Creates a resolver using the address lookup services of `endpoint`.
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl Resolver for DiscoveryResolver {
+    async fn resolve(&self, ticket: &EndpointTicket) -> Result<EndpointAddr, ResolveError> {
+        let addr = ticket.endpoint_addr();
+        let mut items = self.endpoint.address_lookup()?.resolve(addr.id);
+        // Errors of single services are reported inline and again at the end of the stream
+        // if no service finds the endpoint, so skip them.
+        while let Some(item) = items.next().await {
+            if let Ok(item) = item? {
+                let mut resolved = item.into_endpoint_addr();
+                resolved.addrs.extend(addr.addrs.iter().cloned());
+                return Ok(resolved);
+            }
+        }
+        Ok(addr.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh::{address_lookup::MemoryLookup, endpoint::presets};
+    use iroh_base::SecretKey;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_discovery_resolver() {
+        let id = SecretKey::from_bytes(&[22u8; 32]).public();
+        let stale = EndpointAddr::new(id).with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        let current = EndpointAddr::new(id).with_ip_addr("203.0.113.5:1234".parse().unwrap());
+        let lookup = MemoryLookup::new();
+        let endpoint = Endpoint::builder(presets::Minimal)
+            .address_lookup(lookup.clone())
+            .bind()
+            .await
+            .unwrap();
+        let resolver = DiscoveryResolver::new(endpoint.clone());
+        let ticket = EndpointTicket::new(stale.clone());
+
+        let err = resolver.resolve(&ticket).await.unwrap_err();
+        assert!(matches!(err, ResolveError::Lookup { .. }), "{err:?}");
+
+        lookup.add_endpoint_info(current.clone());
+        let resolved = resolver.resolve(&ticket).await.unwrap();
+        assert_eq!(resolved.id, id);
+        assert!(resolved.addrs.is_superset(&current.addrs));
+        assert!(resolved.addrs.is_superset(&stale.addrs));
+
+        endpoint.close().await;
+        let err = resolver.resolve(&ticket).await.unwrap_err();
+        assert!(matches!(err, ResolveError::Endpoint { .. }));
+    }
+}
@@ -0,0 +1,269 @@
+//! Lists of revoked tickets.
+//!
+//! Issuers of long-lived tickets, e.g. [invites](crate::invite), publish a [`RevocationList`]
+//! with the [revocation ids](Ticket::revocation_id) of the tickets they no longer accept. The
+//! list is itself a ticket, wrapping it in a [`SignedTicket`] lets receivers check that it
+//! comes from the issuer:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     Ticket, endpoint::EndpointTicket, revocation::RevocationList, signed::SignedTicket,
+//! };
+//!
+//! let secret_key = SecretKey::from_bytes(&[0u8; 32]);
+//! let ticket = EndpointTicket::new(EndpointAddr::new(secret_key.public()));
+//!
+//! let mut list = RevocationList::new(1);
+//! list.revoke(&ticket);
+//! let published = SignedTicket::sign(list, &secret_key).encode_string();
+//!
+//! let received = SignedTicket::<RevocationList>::decode_string(&published).unwrap();
+//! let list = received.verify(&secret_key.public()).unwrap();
+//! assert!(list.contains(&ticket));
+//! ```
+//!
+//! [`SignedTicket`]: crate::signed::SignedTicket
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A set of revoked tickets, identified by their [revocation ids](Ticket::revocation_id).
+///
+/// The `sequence` number orders lists from the same issuer, receivers should only replace
+/// a list with one that has a higher sequence number. Each entry takes 8 bytes on the wire.
+///
+/// Revocation ids are short, so someone can craft a ticket with the id of a revoked one.
+/// Such a ticket is rejected as well, which only affects that crafted ticket.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevocationList {
+    sequence: u64,
+    revoked: BTreeSet<Fingerprint>,
+}
+
+/// Wire format for [`RevocationList`].
+#[derive(Serialize, Deserialize)]
+struct Variant0RevocationList {
+    sequence: u64,
+    revoked: Vec<[u8; 8]>,
+}
+
+impl Versioned for Variant0RevocationList {
+    const VERSION: u32 = 0;
+}
+
+impl RevocationList {
+    /// Creates an empty list with the given sequence number.
+    pub fn new(sequence: u64) -> Self {
+        Self {
+            sequence,
+            revoked: BTreeSet::new(),
+        }
+    }
+
+    /// The sequence number of the list.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Adds `ticket` to the list.
+    pub fn revoke<T: Ticket>(&mut self, ticket: &T) {
+        self.revoked.insert(ticket.revocation_id());
+    }
+
+    /// Adds the ticket with the given [revocation id](Ticket::revocation_id) to the list.
+    pub fn revoke_fingerprint(&mut self, fingerprint: Fingerprint) {
+        self.revoked.insert(fingerprint);
+    }
+
+    /// Returns whether `ticket` is revoked.
+    pub fn contains<T: Ticket>(&self, ticket: &T) -> bool {
+        self.contains_fingerprint(&ticket.revocation_id())
+    }
+
+    /// Returns whether the ticket with the given [revocation id](Ticket::revocation_id) is
+    /// revoked.
+    pub fn contains_fingerprint(&self, fingerprint: &Fingerprint) -> bool {
+        self.revoked.contains(fingerprint)
+    }
+
+    /// The revocation ids of the revoked tickets, in ascending order.
+    pub fn fingerprints(&self) -> impl Iterator<Item = &Fingerprint> {
+        self.revoked.iter()
+    }
+
+    /// The number of revoked tickets.
+    pub fn len(&self) -> usize {
+        self.revoked.len()
+    }
+
+    /// Returns whether no tickets are revoked.
+    pub fn is_empty(&self) -> bool {
+        self.revoked.is_empty()
+    }
+}
+
+impl Ticket for RevocationList {
+    const KIND: &'static str = "revocations";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0RevocationList {
+            sequence: self.sequence,
+            revoked: self.revoked.iter().map(|f| *f.as_bytes()).collect(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0RevocationList { sequence, revoked } = versioned::decode(bytes)?;
+        Ok(Self {
+            sequence,
+            revoked: revoked.into_iter().map(Fingerprint::from_bytes).collect(),
+        })
+    }
+}
+
+impl fmt::Display for RevocationList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl FromStr for RevocationList {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl Serialize for RevocationList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RevocationList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        compressed::{CompressedTicket, Compression},
+        endpoint::EndpointTicket,
+        expiring::ExpiringTicket,
+        invite::{InviteIssuer, InviteTicket},
+        mac::MacTicket,
+        once::OnceTicket,
+        scoped::ScopedTicket,
+        signed::SignedTicket,
+    };
+
+    fn ticket(i: u8) -> EndpointTicket {
+        EndpointTicket::new(EndpointAddr::new(SecretKey::from_bytes(&[i; 32]).public()))
+    }
+
+    #[test]
+    fn test_revocation_list() {
+        let mut list = RevocationList::new(3);
+        assert!(list.is_empty());
+        list.revoke(&ticket(1));
+        list.revoke(&ticket(2));
+        list.revoke(&ticket(1));
+        assert_eq!(list.len(), 2);
+        assert!(list.contains(&ticket(1)));
+        assert!(!list.contains(&ticket(3)));
+
+        let parsed: RevocationList = list.to_string().parse().unwrap();
+        assert_eq!(parsed, list);
+        assert_eq!(parsed.sequence(), 3);
+        assert_eq!(list.encode_bytes().len(), 1 + 1 + 1 + 2 * 8);
+        let json = serde_json::to_string(&list).unwrap();
+        assert_eq!(serde_json::from_str::<RevocationList>(&json).unwrap(), list);
+    }
+
+    #[test]
+    fn test_revocation_bypass() {
+        // Reordering the ALPNs does not change the ticket.
+        let ticket = ticket(1).with_alpns([&b"a"[..], b"b"]);
+        let mut list = RevocationList::new(1);
+        list.revoke(&ticket);
+        let reordered = ticket.clone().with_alpns([&b"b"[..], b"a"]);
+        assert_ne!(reordered.encode_bytes(), ticket.encode_bytes());
+        assert!(list.contains(&reordered));
+
+        // Invites are redeemed with their token, changing the label does not help.
+        let secret_key = SecretKey::from_bytes(&[1u8; 32]);
+        let issuer = InviteIssuer::from_secret_key(&secret_key);
+        let invite = issuer.mint(
+            EndpointAddr::new(secret_key.public()),
+            1,
+            Some("guest".into()),
+        );
+        list.revoke(&invite);
+        let (addr, token, _) = invite.into_parts();
+        let unlabeled = InviteTicket::new(addr.clone(), token);
+        assert!(list.contains(&unlabeled));
+        assert!(!list.contains(&InviteTicket::new(addr, [0u8; 32])));
+    }
+
+    #[test]
+    fn test_wrapped_revocation() {
+        // Wrapping a revoked invite does not escape the revocation.
+        let secret_key = SecretKey::from_bytes(&[1u8; 32]);
+        let invite = InviteIssuer::from_secret_key(&secret_key).mint(
+            EndpointAddr::new(secret_key.public()),
+            1,
+            Some("guest".into()),
+        );
+        let mut list = RevocationList::new(1);
+        list.revoke(&invite);
+        let id = invite.revocation_id();
+        let not_before = std::time::SystemTime::now();
+        let expires_at = not_before + std::time::Duration::from_secs(60);
+        let wrapped = [
+            ExpiringTicket::new(invite.clone(), not_before, expires_at).revocation_id(),
+            ScopedTicket::new(invite.clone(), "read".to_string()).revocation_id(),
+            OnceTicket::new(invite.clone(), [7u8; 16]).revocation_id(),
+            SignedTicket::sign(invite.clone(), &secret_key).revocation_id(),
+            MacTicket::new(invite.clone(), &[3u8; 32]).revocation_id(),
+            CompressedTicket::new(invite.clone(), Compression::None).revocation_id(),
+        ];
+        for wrapped in wrapped {
+            assert_eq!(wrapped, id);
+        }
+        let signed = SignedTicket::sign(invite, &secret_key);
+        assert!(list.contains(&signed));
+        let (addr, token, _) = signed
+            .verify(&secret_key.public())
+            .unwrap()
+            .clone()
+            .into_parts();
+        let relabeled = InviteTicket::new(addr, token).with_label("other");
+        assert!(list.contains(&ExpiringTicket::new(relabeled, not_before, expires_at)));
+    }
+
+    #[test]
+    fn test_signed_revocation_list() {
+        let secret_key = SecretKey::from_bytes(&[9u8; 32]);
+        let mut list = RevocationList::new(1);
+        list.revoke_fingerprint(ticket(1).revocation_id());
+        let signed = SignedTicket::sign(list.clone(), &secret_key);
+        let parsed = SignedTicket::<RevocationList>::decode_string(&signed.to_string()).unwrap();
+        assert_eq!(parsed.verify(&secret_key.public()).unwrap(), &list);
+        let other = SecretKey::from_bytes(&[10u8; 32]).public();
+        assert!(parsed.verify(&other).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Storing tickets in SQLite databases with [rusqlite].
+//!
+//! The built-in ticket types and [`AnyTicket`] implement [`ToSql`] and [`FromSql`], so
+//! tickets can be stored in `TEXT` columns and used as query parameters and column values
+//! directly. Tickets are stored in their canonical string form, reading a value that is not
+//! a valid ticket fails with [`FromSqlError::Other`] containing the [`ParseError`].
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::endpoint::EndpointTicket;
+//! use rusqlite::Connection;
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let conn = Connection::open_in_memory().unwrap();
+//! conn.execute("CREATE TABLE peers (ticket TEXT NOT NULL)", ())
+//!     .unwrap();
+//! conn.execute("INSERT INTO peers (ticket) VALUES (?1)", [&ticket])
+//!     .unwrap();
+//! let stored: EndpointTicket = conn
+//!     .query_row("SELECT ticket FROM peers", (), |row| row.get(0))
+//!     .unwrap();
+//! assert_eq!(stored, ticket);
+//! ```
+//!
+//! [rusqlite]: https://docs.rs/rusqlite
+//! [`ParseError`]: crate::ParseError
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+use crate::{
+    AnyTicket, Ticket, blob::BlobTicket, bundle::BundleTicket, endpoint::EndpointTicket,
+    gossip::GossipTicket, multi::MultiEndpointTicket,
+};
+
+macro_rules! impl_rusqlite {
+    ($($ty:ty),*) => {
+        $(
+            impl ToSql for $ty {
+                fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+                    Ok(self.encode_string().into())
+                }
+            }
+
+            impl FromSql for $ty {
+                fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+                    <$ty>::decode_string(value.as_str()?)
+                        .map_err(|err| FromSqlError::Other(Box::new(err)))
+                }
+            }
+        )*
+    };
+}
+
+impl_rusqlite!(
+    EndpointTicket,
+    BlobTicket,
+    GossipTicket,
+    MultiEndpointTicket,
+    BundleTicket,
+    AnyTicket
+);
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+    use rusqlite::Connection;
+
+    use super::*;
+    use crate::ParseError;
+
+    #[test]
+    fn test_rusqlite() {
+        let ticket = BlobTicket::new(
+            EndpointAddr::new(SecretKey::from_bytes(&[38u8; 32]).public()),
+            [3u8; 32],
+            Default::default(),
+        );
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE blobs (ticket TEXT NOT NULL)", ())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO blobs (ticket) VALUES (?1), ('blob0')",
+            [&ticket],
+        )
+        .unwrap();
+
+        let query = |offset: u32| {
+            conn.query_row(
+                "SELECT ticket FROM blobs LIMIT 1 OFFSET ?1",
+                [offset],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, BlobTicket>(0))),
+            )
+            .unwrap()
+        };
+        let (s, stored) = query(0);
+        assert_eq!(s, ticket.to_string());
+        assert_eq!(stored.unwrap(), ticket);
+        let any: AnyTicket = conn
+            .query_row("SELECT ticket FROM blobs LIMIT 1", (), |row| row.get(0))
+            .unwrap();
+        assert_eq!(any, AnyTicket::Blob(ticket));
+
+        let (_, err) = query(1);
+        let Err(rusqlite::Error::FromSqlConversionFailure(_, _, source)) = err else {
+            panic!("unexpected result {err:?}");
+        };
+        assert!(source.downcast_ref::<ParseError>().is_some());
+    }
+}
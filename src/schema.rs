@@ -0,0 +1,149 @@
+//! [JSON Schema] for tickets, via [schemars].
+//!
+//! The built-in ticket types implement [`JsonSchema`] as a string with a `pattern` matching
+//! the canonical string form, the kind prefix followed by lowercase base32. This is what
+//! they serialize as in human readable formats, so OpenAPI specs for services that accept
+//! tickets in JSON are generated automatically:
+//!
+//! ```
+//! use iroh_tickets::endpoint::EndpointTicket;
+//!
+//! #[derive(serde::Deserialize, schemars::JsonSchema)]
+//! struct JoinRequest {
+//!     ticket: EndpointTicket,
+//! }
+//!
+//! let schema = schemars::schema_for!(JoinRequest);
+//! let ticket = &schema.as_value()["$defs"]["EndpointTicket"];
+//! assert_eq!(ticket["type"], "string");
+//! assert_eq!(ticket["pattern"], "^endpoint[a-z2-7]+$");
+//! ```
+//!
+//! Custom ticket types can use [`ticket_schema`] in their own implementation.
+//!
+//! [JSON Schema]: https://json-schema.org
+//! [schemars]: https://docs.rs/schemars
+
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+
+#[cfg(feature = "password")]
+use crate::protected::ProtectedTicket;
+#[cfg(feature = "sealed")]
+use crate::sealed::SealedTicket;
+use crate::{
+    AnyTicket, Ticket, blob::BlobTicket, bundle::BundleTicket, compressed::CompressedTicket,
+    endpoint::EndpointTicket, expiring::ExpiringTicket, gossip::GossipTicket,
+    multi::MultiEndpointTicket, signed::SignedTicket,
+};
+
+/// The schema of the canonical string form of tickets of type `T`.
+///
+/// The `pattern` contains [`Ticket::KIND`] verbatim, so kinds should not contain regex
+/// metacharacters. Uppercase strings and [`multibase`](crate::multibase) payloads are
+/// accepted when parsing but do not match the pattern.
+pub fn ticket_schema<T: Ticket>() -> Schema {
+    kinds_schema(&[T::KIND])
+}
+
+fn kinds_schema(kinds: &[&str]) -> Schema {
+    let pattern = match kinds {
+        [kind] => format!("^{kind}[a-z2-7]+$"),
+        kinds => format!("^({})[a-z2-7]+$", kinds.join("|")),
+    };
+    json_schema!({
+        "type": "string",
+        "pattern": pattern,
+    })
+}
+
+macro_rules! impl_json_schema {
+    ($($(#[$attr:meta])* $ty:ident$(<$param:ident>)? => $path:literal,)*) => {
+        $(
+            $(#[$attr])*
+            impl$(<$param: Ticket>)? JsonSchema for $ty$(<$param>)? {
+                fn schema_name() -> Cow<'static, str> {
+                    stringify!($ty).into()
+                }
+
+                fn schema_id() -> Cow<'static, str> {
+                    $path.into()
+                }
+
+                fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+                    ticket_schema::<Self>()
+                }
+            }
+        )*
+    };
+}
+
+impl_json_schema! {
+    EndpointTicket => "iroh_tickets::endpoint::EndpointTicket",
+    BlobTicket => "iroh_tickets::blob::BlobTicket",
+    GossipTicket => "iroh_tickets::gossip::GossipTicket",
+    MultiEndpointTicket => "iroh_tickets::multi::MultiEndpointTicket",
+    BundleTicket => "iroh_tickets::bundle::BundleTicket",
+    // The string form of the wrappers does not depend on the wrapped ticket type.
+    CompressedTicket<T> => "iroh_tickets::compressed::CompressedTicket",
+    ExpiringTicket<T> => "iroh_tickets::expiring::ExpiringTicket",
+    SignedTicket<T> => "iroh_tickets::signed::SignedTicket",
+    #[cfg(feature = "password")]
+    ProtectedTicket<T> => "iroh_tickets::protected::ProtectedTicket",
+    #[cfg(feature = "sealed")]
+    SealedTicket<T> => "iroh_tickets::sealed::SealedTicket",
+}
+
+impl JsonSchema for AnyTicket {
+    fn schema_name() -> Cow<'static, str> {
+        "AnyTicket".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        "iroh_tickets::AnyTicket".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        kinds_schema(AnyTicket::KINDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        let schema = schemars::schema_for!(EndpointTicket);
+        assert_eq!(schema.get("type").unwrap(), "string");
+        assert_eq!(schema.get("pattern").unwrap(), "^endpoint[a-z2-7]+$");
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[35u8; 32]).public(),
+        ));
+        let payload = ticket.encode_string()["endpoint".len()..].to_string();
+        assert!(
+            payload
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || ('2'..='7').contains(&c))
+        );
+
+        let any = schemars::schema_for!(AnyTicket);
+        assert_eq!(
+            any.get("pattern").unwrap(),
+            "^(endpoint|blob|topic|multiendpoint|bundle|relay|dns|invite)[a-z2-7]+$"
+        );
+        assert_eq!(
+            ticket_schema::<SignedTicket<EndpointTicket>>()
+                .get("pattern")
+                .unwrap(),
+            "^signed[a-z2-7]+$"
+        );
+        assert_eq!(
+            SignedTicket::<EndpointTicket>::schema_id(),
+            SignedTicket::<BlobTicket>::schema_id()
+        );
+    }
+}
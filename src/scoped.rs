@@ -0,0 +1,279 @@
+//! Tickets limited to a scope.
+//!
+//! A [`ScopedTicket`] wraps another ticket together with a scope defined by the
+//! application, e.g. whether the ticket grants read or write access. This replaces separate
+//! ticket kinds per permission level. The scope can optionally be signed by the issuing
+//! endpoint, so a holder can not widen it:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{Ticket, endpoint::EndpointTicket, scoped::ScopedTicket};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+//! enum Access {
+//!     ReadOnly,
+//!     ReadWrite,
+//! }
+//!
+//! let secret_key = SecretKey::from_bytes(&[0u8; 32]);
+//! let ticket = EndpointTicket::new(EndpointAddr::new(secret_key.public()));
+//! let scoped = ScopedTicket::new(ticket, Access::ReadOnly).sign(&secret_key);
+//!
+//! let parsed =
+//!     ScopedTicket::<EndpointTicket, Access>::decode_string(&scoped.encode_string()).unwrap();
+//! assert_eq!(
+//!     parsed.verify(&secret_key.public()).unwrap(),
+//!     &Access::ReadOnly
+//! );
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use iroh_base::{EndpointId, SecretKey, Signature};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// Domain separation prefix for the signed message.
+const SIGNATURE_CONTEXT: &[u8] = b"iroh-tickets scoped ticket\0";
+
+/// A ticket together with a scope and optionally a signature over both.
+///
+/// The scope `S` is serialized with postcard next to the byte representation of the inner
+/// ticket. A signature covers the [`KIND`](Ticket::KIND) of the inner ticket, its bytes and
+/// the scope, and is checked when decoding. Use [`verify`](Self::verify) to check that the
+/// scope was signed by the expected endpoint.
+///
+/// All scoped tickets share the [`KIND`](Ticket::KIND) `"scoped"`, regardless of the type
+/// of the inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopedTicket<T, S> {
+    ticket: T,
+    scope: S,
+    signature: Option<(EndpointId, Signature)>,
+}
+
+/// Wire format for [`ScopedTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0ScopedTicket<S> {
+    ticket: Vec<u8>,
+    scope: S,
+    signature: Option<(EndpointId, Signature)>,
+}
+
+impl<S: Serialize + DeserializeOwned> Versioned for Variant0ScopedTicket<S> {
+    const VERSION: u32 = 0;
+}
+
+/// The scope of a [`ScopedTicket`] was not signed by the expected endpoint.
+#[stack_error(derive, add_meta)]
+#[non_exhaustive]
+pub enum ScopeVerifyError {
+    /// The ticket is not signed.
+    #[error("scoped ticket is not signed, expected a signature by {expected}")]
+    Unsigned {
+        /// The expected signer.
+        expected: EndpointId,
+    },
+    /// The ticket is signed by another endpoint.
+    #[error("scoped ticket was signed by {actual}, expected {expected}")]
+    WrongSigner {
+        /// The expected signer.
+        expected: EndpointId,
+        /// The actual signer.
+        actual: EndpointId,
+    },
+}
+
+fn signing_message<S: Serialize>(kind: &str, ticket: &[u8], scope: &S) -> Vec<u8> {
+    let mut message = [SIGNATURE_CONTEXT, kind.as_bytes(), b"\0"].concat();
+    message =
+        postcard::to_extend(&(ticket, scope), message).expect("postcard serialization failed");
+    message
+}
+
+impl<T: Ticket, S: Serialize> ScopedTicket<T, S> {
+    /// Creates an unsigned ticket limited to `scope`.
+    pub fn new(ticket: T, scope: S) -> Self {
+        Self {
+            ticket,
+            scope,
+            signature: None,
+        }
+    }
+
+    /// Signs the ticket and its scope with `secret_key`, replacing any previous signature.
+    pub fn sign(mut self, secret_key: &SecretKey) -> Self {
+        let message = signing_message(T::KIND, &self.ticket.encode_bytes(), &self.scope);
+        self.signature = Some((secret_key.public(), secret_key.sign(&message)));
+        self
+    }
+
+    /// The wrapped ticket.
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// The scope of the ticket.
+    ///
+    /// This does not check who signed it, see [`verify`](Self::verify).
+    pub fn scope(&self) -> &S {
+        &self.scope
+    }
+
+    /// The endpoint that signed the ticket and its scope, if it is signed.
+    pub fn signer(&self) -> Option<EndpointId> {
+        self.signature.map(|(signer, _)| signer)
+    }
+
+    /// Checks that the scope was signed by `endpoint_id` and returns it.
+    pub fn verify(&self, endpoint_id: &EndpointId) -> Result<&S, ScopeVerifyError> {
+        match self.signer() {
+            None => Err(e!(ScopeVerifyError::Unsigned {
+                expected: *endpoint_id
+            })),
+            Some(actual) if actual != *endpoint_id => Err(e!(ScopeVerifyError::WrongSigner {
+                expected: *endpoint_id,
+                actual,
+            })),
+            Some(_) => Ok(&self.scope),
+        }
+    }
+
+    /// Returns the wrapped ticket and its scope.
+    pub fn into_parts(self) -> (T, S) {
+        (self.ticket, self.scope)
+    }
+}
+
+impl<T: Ticket, S: Serialize + DeserializeOwned + Clone> Ticket for ScopedTicket<T, S> {
+    const KIND: &'static str = "scoped";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0ScopedTicket {
+            ticket: self.ticket.encode_bytes(),
+            scope: self.scope.clone(),
+            signature: self.signature,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0ScopedTicket {
+            ticket,
+            scope,
+            signature,
+        } = versioned::decode(bytes)?;
+        if let Some((signer, signature)) = &signature {
+            signer
+                .verify(&signing_message(T::KIND, &ticket, &scope), signature)
+                .map_err(|_| ParseError::verification_failed("invalid scope signature"))?;
+        }
+        Ok(Self {
+            ticket: T::decode_bytes(&ticket)?,
+            scope,
+            signature,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket, S: Serialize + DeserializeOwned + Clone> fmt::Display for ScopedTicket<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket, S: Serialize + DeserializeOwned + Clone> FromStr for ScopedTicket<T, S> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket, S: Serialize + DeserializeOwned + Clone> Serialize for ScopedTicket<T, S> {
+    fn serialize<Se: serde::Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket, S: Serialize + DeserializeOwned + Clone> Deserialize<'de>
+    for ScopedTicket<T, S>
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::EndpointAddr;
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    enum Access {
+        ReadOnly,
+        ReadWrite,
+    }
+
+    type Scoped = ScopedTicket<EndpointTicket, Access>;
+
+    fn make_ticket() -> (SecretKey, EndpointTicket) {
+        let secret_key = SecretKey::from_bytes(&[23u8; 32]);
+        let ticket = EndpointTicket::new(EndpointAddr::new(secret_key.public()));
+        (secret_key, ticket)
+    }
+
+    #[test]
+    fn test_scoped_roundtrip() {
+        let (secret_key, ticket) = make_ticket();
+        let unsigned = ScopedTicket::new(ticket.clone(), Access::ReadWrite);
+        let s = unsigned.to_string();
+        assert!(s.starts_with("scoped"));
+        let parsed: Scoped = s.parse().unwrap();
+        assert_eq!(parsed, unsigned);
+        assert_eq!(parsed.signer(), None);
+        assert!(matches!(
+            parsed.verify(&secret_key.public()),
+            Err(ScopeVerifyError::Unsigned { .. })
+        ));
+
+        let signed = unsigned.sign(&secret_key);
+        let parsed: Scoped = signed.to_string().parse().unwrap();
+        assert_eq!(parsed, signed);
+        assert_eq!(
+            parsed.verify(&secret_key.public()).unwrap(),
+            &Access::ReadWrite
+        );
+        let other = SecretKey::from_bytes(&[24u8; 32]).public();
+        assert!(matches!(
+            parsed.verify(&other),
+            Err(ScopeVerifyError::WrongSigner { .. })
+        ));
+        assert_eq!(parsed.into_parts(), (ticket, Access::ReadWrite));
+
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(serde_json::from_str::<Scoped>(&json).unwrap(), signed);
+    }
+
+    #[test]
+    fn test_scoped_widened() {
+        let (secret_key, ticket) = make_ticket();
+        let signed = ScopedTicket::new(ticket, Access::ReadOnly).sign(&secret_key);
+        let mut widened = signed.clone();
+        widened.scope = Access::ReadWrite;
+        let err = Scoped::decode_bytes(&widened.encode_bytes()).unwrap_err();
+        assert!(matches!(err, ParseError::Verify { .. }));
+    }
+}
@@ -0,0 +1,159 @@
+//! Tickets encrypted to a recipient endpoint.
+//!
+//! A [`SealedTicket`] contains another ticket encrypted to the [`EndpointId`] of its
+//! recipient, so it can be shared over public channels but only be opened by the holder of
+//! the matching [`SecretKey`].
+//!
+//! The encryption is a libsodium compatible sealed box, using the X25519 keys derived from
+//! the ed25519 keys of the endpoints.
+
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use crypto_box::aead::OsRng;
+use iroh_base::{EndpointId, SecretKey};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ParseError, Ticket, deserialize_ticket, ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// A ticket encrypted to a recipient.
+///
+/// The recipient is not contained in the sealed ticket. Opening it with the wrong key fails
+/// the same way as opening a corrupted ticket.
+///
+/// All sealed tickets share the [`KIND`](Ticket::KIND) `"sealed"`, regardless of the type
+/// of the inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedTicket<T> {
+    ciphertext: Vec<u8>,
+    _ticket: PhantomData<fn() -> T>,
+}
+
+/// Wire format for [`SealedTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0SealedTicket {
+    ciphertext: Vec<u8>,
+}
+
+impl Versioned for Variant0SealedTicket {
+    const VERSION: u32 = 0;
+}
+
+/// An error opening a [`SealedTicket`].
+#[stack_error(derive, add_meta)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum OpenError {
+    /// The ticket was not sealed to this key, or was modified.
+    #[error("failed to decrypt ticket")]
+    Decrypt {},
+    /// The decrypted ticket could not be decoded.
+    #[error("failed to decode sealed ticket")]
+    Ticket { source: ParseError },
+}
+
+fn public_x25519(endpoint_id: &EndpointId) -> crypto_box::PublicKey {
+    crypto_box::PublicKey::from(endpoint_id.as_verifying_key().to_montgomery().to_bytes())
+}
+
+fn secret_x25519(secret_key: &SecretKey) -> crypto_box::SecretKey {
+    crypto_box::SecretKey::from(secret_key.as_signing_key().to_scalar_bytes())
+}
+
+impl<T: Ticket> SealedTicket<T> {
+    /// Encrypts `ticket` so that only `recipient` can open it.
+    pub fn seal(ticket: &T, recipient: &EndpointId) -> Self {
+        let ciphertext = public_x25519(recipient)
+            .seal(&mut OsRng, &ticket.encode_bytes())
+            .expect("sealing into a vec is infallible");
+        Self {
+            ciphertext,
+            _ticket: PhantomData,
+        }
+    }
+
+    /// Decrypts the ticket with the recipient's `secret_key`.
+    pub fn open(&self, secret_key: &SecretKey) -> Result<T, OpenError> {
+        let bytes = secret_x25519(secret_key)
+            .unseal(&self.ciphertext)
+            .map_err(|_| e!(OpenError::Decrypt))?;
+        T::decode_bytes(&bytes).map_err(|source| e!(OpenError::Ticket { source }))
+    }
+}
+
+impl<T: Ticket> Ticket for SealedTicket<T> {
+    const KIND: &'static str = "sealed";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0SealedTicket {
+            ciphertext: self.ciphertext.clone(),
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0SealedTicket { ciphertext } = versioned::decode(bytes)?;
+        Ok(Self {
+            ciphertext,
+            _ticket: PhantomData,
+        })
+    }
+}
+
+impl<T: Ticket> fmt::Display for SealedTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for SealedTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for SealedTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for SealedTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::EndpointAddr;
+
+    use super::*;
+    use crate::endpoint::EndpointTicket;
+
+    #[test]
+    fn test_seal_open() {
+        let recipient = SecretKey::from_bytes(&[5u8; 32]);
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[6u8; 32]).public(),
+        ));
+
+        let sealed = SealedTicket::seal(&ticket, &recipient.public());
+        let s = sealed.to_string();
+        assert!(s.starts_with("sealed"));
+
+        let parsed: SealedTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed.open(&recipient).unwrap(), ticket);
+
+        let other = SecretKey::from_bytes(&[7u8; 32]);
+        assert!(matches!(
+            parsed.open(&other),
+            Err(OpenError::Decrypt { .. })
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+//! Tickets carrying secrets.
+//!
+//! Tickets like invites or write capabilities grant access to whoever holds them. A
+//! [`SecretTicket`] wraps such a ticket, zeroizes it when it is dropped and keeps it out of
+//! logs: its [`Debug`](fmt::Debug) form is redacted and it does not implement
+//! [`Display`](fmt::Display) or serde. The ticket is only reachable through
+//! [`expose_secret`](SecretTicket::expose_secret).
+//!
+//! ```
+//! use iroh_tickets::{ParseError, Ticket, secret::SecretTicket};
+//! use zeroize::Zeroize;
+//!
+//! struct InviteTicket {
+//!     token: [u8; 32],
+//! }
+//!
+//! impl Zeroize for InviteTicket {
+//!     fn zeroize(&mut self) {
+//!         self.token.zeroize();
+//!     }
+//! }
+//!
+//! impl Ticket for InviteTicket {
+//!     const KIND: &'static str = "invite";
+//!
+//!     fn encode_bytes(&self) -> Vec<u8> {
+//!         self.token.to_vec()
+//!     }
+//!
+//!     fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+//!         let token = bytes
+//!             .try_into()
+//!             .map_err(|_| ParseError::verification_failed("expected 32 bytes"))?;
+//!         Ok(Self { token })
+//!     }
+//! }
+//!
+//! let ticket = SecretTicket::new(InviteTicket { token: [7; 32] });
+//! assert_eq!(format!("{ticket:?}"), "SecretTicket<invite>(..)");
+//! let s = ticket.to_secret_string();
+//! let parsed = SecretTicket::<InviteTicket>::decode_string(&s).unwrap();
+//! assert_eq!(parsed.expose_secret().token, [7; 32]);
+//! ```
+//!
+//! Strings and bytes returned by the [`Ticket`] methods are not zeroized,
+//! [`to_secret_string`](SecretTicket::to_secret_string) and
+//! [`to_secret_bytes`](SecretTicket::to_secret_bytes) return them in [`Zeroizing`] instead.
+
+use std::fmt;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{Fingerprint, ParseError, Ticket, decode_string_payload, ensure_len, write_raw};
+
+/// A ticket that is zeroized on drop and redacted in its [`Debug`](fmt::Debug) form.
+///
+/// This has the same [`KIND`](Ticket::KIND), byte representation and string form as the
+/// wrapped ticket.
+pub struct SecretTicket<T: Zeroize>(T);
+
+impl<T: Zeroize> SecretTicket<T> {
+    /// Wraps `ticket`.
+    pub fn new(ticket: T) -> Self {
+        Self(ticket)
+    }
+
+    /// The wrapped ticket.
+    ///
+    /// Take care not to log or copy the returned ticket, copies are not zeroized.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Ticket + Zeroize> SecretTicket<T> {
+    /// Encodes the string form into a buffer that is zeroized on drop.
+    ///
+    /// The string is encoded from [`to_secret_bytes`](Self::to_secret_bytes), so no copy of
+    /// the byte representation is left behind.
+    pub fn to_secret_string(&self) -> Zeroizing<String> {
+        let mut out = Zeroizing::new(String::new());
+        write_raw(Self::KIND, &self.to_secret_bytes(), &mut out);
+        out
+    }
+
+    /// Encodes the byte representation into a buffer that is zeroized on drop.
+    pub fn to_secret_bytes(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.0.encode_bytes())
+    }
+}
+
+impl<T: Zeroize> Drop for SecretTicket<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for SecretTicket<T> {
+    fn from(ticket: T) -> Self {
+        Self::new(ticket)
+    }
+}
+
+impl<T: Ticket + Zeroize> fmt::Debug for SecretTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretTicket<{}>(..)", T::KIND)
+    }
+}
+
+impl<T: Ticket + Zeroize> Ticket for SecretTicket<T> {
+    const KIND: &'static str = T::KIND;
+    const KIND_ALIASES: &'static [&'static str] = T::KIND_ALIASES;
+    const MAX_LEN: usize = T::MAX_LEN;
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.0.encode_bytes()
+    }
+
+    fn canonical_bytes(&self) -> Vec<u8> {
+        self.0.canonical_bytes()
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        T::decode_bytes(bytes).map(Self)
+    }
+
+    /// Decodes the wrapped ticket, zeroizing the decoded bytes afterwards.
+    fn decode_string(s: &str) -> Result<Self, ParseError> {
+        let bytes = Zeroizing::new(decode_string_payload::<Self>(s)?);
+        ensure_len::<Self>(&bytes)?;
+        Self::decode_bytes(&bytes)
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.0.revocation_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_MAX_LEN;
+
+    #[derive(Debug, PartialEq)]
+    struct TokenTicket([u8; 4]);
+
+    impl Zeroize for TokenTicket {
+        fn zeroize(&mut self) {
+            self.0.zeroize();
+        }
+    }
+
+    impl Ticket for TokenTicket {
+        const KIND: &'static str = "token";
+        const KIND_ALIASES: &'static [&'static str] = &["secret"];
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            self.0.to_vec()
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            let token = bytes
+                .try_into()
+                .map_err(|_| ParseError::verification_failed("expected 4 bytes"))?;
+            Ok(Self(token))
+        }
+    }
+
+    #[test]
+    fn test_secret_ticket() {
+        let ticket = SecretTicket::new(TokenTicket([1, 2, 3, 4]));
+        assert_eq!(format!("{ticket:?}"), "SecretTicket<token>(..)");
+        assert_eq!(*ticket.to_secret_bytes(), [1, 2, 3, 4]);
+        let s = ticket.to_secret_string();
+        assert_eq!(*s, TokenTicket([1, 2, 3, 4]).encode_string());
+        let parsed = SecretTicket::<TokenTicket>::decode_string(&s).unwrap();
+        assert_eq!(parsed.expose_secret(), &TokenTicket([1, 2, 3, 4]));
+
+        let err = SecretTicket::<TokenTicket>::decode_string(&s.replacen("token", "blob", 1));
+        assert!(matches!(err, Err(ParseError::Kind { .. })));
+        let renamed = SecretTicket::<TokenTicket>::decode_string(&s.replacen("token", "secret", 1));
+        assert_eq!(renamed.unwrap().expose_secret(), &TokenTicket([1, 2, 3, 4]));
+        let long = format!("token{}", "a".repeat(DEFAULT_MAX_LEN * 2));
+        let err = SecretTicket::<TokenTicket>::decode_string(&long);
+        assert!(matches!(err, Err(ParseError::TooLong { .. })));
+
+        let mut ticket = SecretTicket::new(TokenTicket([1, 2, 3, 4]));
+        ticket.0.zeroize();
+        assert_eq!(ticket.expose_secret(), &TokenTicket([0; 4]));
+    }
+}
@@ -0,0 +1,93 @@
+//! Helpers for serializing tickets in other types with serde.
+
+/// Serializes a [`Ticket`](crate::Ticket) field as the ticket string in human readable
+/// formats like JSON and as the byte representation in binary formats like postcard.
+///
+/// This is how the built-in tickets serialize, use it with `#[serde(with = ...)]` for
+/// ticket types that do not implement serde or serialize differently:
+///
+/// ```
+/// use iroh_base::{EndpointAddr, SecretKey};
+/// use iroh_tickets::{Ticket, endpoint::EndpointTicket, serde_helpers::as_ticket_string};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Invite {
+///     name: String,
+///     #[serde(with = "as_ticket_string")]
+///     ticket: EndpointTicket,
+/// }
+///
+/// let invite = Invite {
+///     name: "lobby".into(),
+///     ticket: EndpointTicket::new(EndpointAddr::new(
+///         SecretKey::from_bytes(&[0u8; 32]).public(),
+///     )),
+/// };
+/// let json = serde_json::to_value(&invite).unwrap();
+/// assert_eq!(json["ticket"], invite.ticket.encode_string());
+/// let bytes = postcard::to_allocvec(&invite).unwrap();
+/// assert_eq!(postcard::from_bytes::<Invite>(&bytes).unwrap(), invite);
+/// ```
+pub mod as_ticket_string {
+    use serde::{Deserializer, Serializer};
+
+    use crate::Ticket;
+
+    /// Serializes `ticket`, see the [module docs](self).
+    pub fn serialize<T: Ticket, S: Serializer>(
+        ticket: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        crate::serialize_ticket(ticket, serializer)
+    }
+
+    /// Deserializes a ticket written by [`serialize`].
+    pub fn deserialize<'de, T: Ticket, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        crate::deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::{ParseError, Ticket};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Room(Vec<u8>);
+
+    impl Ticket for Room {
+        const KIND: &'static str = "room";
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            Ok(Self(bytes.to_vec()))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wrapper {
+        #[serde(with = "super::as_ticket_string")]
+        room: Room,
+    }
+
+    #[test]
+    fn test_as_ticket_string() {
+        let wrapper = Wrapper {
+            room: Room(vec![1, 2, 3]),
+        };
+        let json = serde_json::to_string(&wrapper).unwrap();
+        assert_eq!(json, r#"{"room":"roomaebag"}"#);
+        assert_eq!(serde_json::from_str::<Wrapper>(&json).unwrap(), wrapper);
+
+        let bytes = postcard::to_allocvec(&wrapper).unwrap();
+        assert_eq!(bytes, [3, 1, 2, 3]);
+        assert_eq!(postcard::from_bytes::<Wrapper>(&bytes).unwrap(), wrapper);
+    }
+}
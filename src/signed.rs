@@ -0,0 +1,264 @@
+//! Tickets signed by an endpoint.
+//!
+//! A [`SignedTicket`] wraps any other ticket together with an ed25519 signature made with an
+//! iroh [`SecretKey`]. Since an [`EndpointId`] is the public key of an endpoint, this lets a
+//! receiver check that a ticket was issued by the endpoint it claims to be from.
+
+use std::{fmt, str::FromStr};
+
+use iroh_base::{EndpointId, SecretKey, Signature};
+use n0_error::{e, stack_error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Fingerprint, ParseError, Ticket, deserialize_ticket,
+    endpoint::EndpointTicket,
+    ensure_len, serialize_ticket,
+    versioned::{self, Versioned},
+};
+
+/// Domain separation prefix for the signed message.
+const SIGNATURE_CONTEXT: &[u8] = b"iroh-tickets signed ticket\0";
+
+/// A ticket together with a signature over it.
+///
+/// The signature covers the [`KIND`](Ticket::KIND) and the byte representation of the
+/// inner ticket. It is checked when decoding, so a [`SignedTicket`] always carries a valid
+/// signature by its [`signer`](Self::signer). The signed bytes are kept and encoded
+/// unchanged, so the signature stays valid even if the inner ticket would encode
+/// differently, e.g. after an upgrade from an older wire format version. Use [`verify`](Self::verify) to check that
+/// the signer is the expected one.
+///
+/// All signed tickets share the [`KIND`](Ticket::KIND) `"signed"`, regardless of the type
+/// of the inner ticket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTicket<T> {
+    ticket: T,
+    bytes: Vec<u8>,
+    signer: EndpointId,
+    signature: Signature,
+}
+
+/// Wire format for [`SignedTicket`].
+#[derive(Serialize, Deserialize)]
+struct Variant0SignedTicket {
+    ticket: Vec<u8>,
+    signer: EndpointId,
+    signature: Signature,
+}
+
+impl Versioned for Variant0SignedTicket {
+    const VERSION: u32 = 0;
+}
+
+/// The signer of a [`SignedTicket`] is not the expected one.
+#[stack_error(derive, add_meta)]
+#[error("ticket was signed by {actual}, expected {expected}")]
+pub struct VerifyError {
+    /// The expected signer.
+    pub expected: EndpointId,
+    /// The actual signer.
+    pub actual: EndpointId,
+}
+
+fn signing_message(kind: &str, ticket: &[u8]) -> Vec<u8> {
+    [SIGNATURE_CONTEXT, kind.as_bytes(), b"\0", ticket].concat()
+}
+
+impl<T: Ticket> SignedTicket<T> {
+    /// Signs `ticket` with `secret_key`.
+    pub fn sign(ticket: T, secret_key: &SecretKey) -> Self {
+        let bytes = ticket.encode_bytes();
+        Self {
+            signature: secret_key.sign(&signing_message(T::KIND, &bytes)),
+            signer: secret_key.public(),
+            ticket,
+            bytes,
+        }
+    }
+
+    /// The signed ticket.
+    ///
+    /// This does not check who signed it, see [`verify`](Self::verify).
+    pub fn ticket(&self) -> &T {
+        &self.ticket
+    }
+
+    /// Returns the signed ticket.
+    ///
+    /// This does not check who signed it, see [`verify`](Self::verify).
+    pub fn into_ticket(self) -> T {
+        self.ticket
+    }
+
+    /// The endpoint that signed the ticket.
+    pub fn signer(&self) -> EndpointId {
+        self.signer
+    }
+
+    /// The signature over the ticket.
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Checks that the ticket was signed by `endpoint_id` and returns it.
+    pub fn verify(&self, endpoint_id: &EndpointId) -> Result<&T, VerifyError> {
+        if self.signer != *endpoint_id {
+            return Err(e!(VerifyError {
+                expected: *endpoint_id,
+                actual: self.signer,
+            }));
+        }
+        Ok(&self.ticket)
+    }
+}
+
+impl SignedTicket<EndpointTicket> {
+    /// Checks that the ticket was signed by the endpoint it points to and returns it.
+    pub fn verify_endpoint(&self) -> Result<&EndpointTicket, VerifyError> {
+        self.verify(&self.ticket.endpoint_addr().id)
+    }
+}
+
+impl<T: Ticket> Ticket for SignedTicket<T> {
+    const KIND: &'static str = "signed";
+
+    fn encode_bytes(&self) -> Vec<u8> {
+        versioned::encode(&Variant0SignedTicket {
+            ticket: self.bytes.clone(),
+            signer: self.signer,
+            signature: self.signature,
+        })
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        ensure_len::<Self>(bytes)?;
+        let Variant0SignedTicket {
+            ticket,
+            signer,
+            signature,
+        } = versioned::decode(bytes)?;
+        signer
+            .verify(&signing_message(T::KIND, &ticket), &signature)
+            .map_err(|_| ParseError::verification_failed("invalid ticket signature"))?;
+        Ok(Self {
+            ticket: T::decode_bytes(&ticket)?,
+            bytes: ticket,
+            signer,
+            signature,
+        })
+    }
+
+    fn revocation_id(&self) -> Fingerprint {
+        self.ticket.revocation_id()
+    }
+}
+
+impl<T: Ticket> fmt::Display for SignedTicket<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode_string())
+    }
+}
+
+impl<T: Ticket> FromStr for SignedTicket<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ticket::decode_string(s)
+    }
+}
+
+impl<T: Ticket> Serialize for SignedTicket<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_ticket(self, serializer)
+    }
+}
+
+impl<'de, T: Ticket> Deserialize<'de> for SignedTicket<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_ticket(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::EndpointAddr;
+
+    use super::*;
+
+    fn make_signed() -> (SecretKey, SignedTicket<EndpointTicket>) {
+        let secret_key = SecretKey::from_bytes(&[3u8; 32]);
+        let ticket = EndpointTicket::new(EndpointAddr::new(secret_key.public()));
+        (secret_key.clone(), SignedTicket::sign(ticket, &secret_key))
+    }
+
+    #[test]
+    fn test_signed_roundtrip() {
+        let (secret_key, signed) = make_signed();
+        let s = signed.to_string();
+        assert!(s.starts_with("signed"));
+
+        let parsed: SignedTicket<EndpointTicket> = s.parse().unwrap();
+        assert_eq!(parsed, signed);
+        assert_eq!(parsed.signer(), secret_key.public());
+        assert!(parsed.verify_endpoint().is_ok());
+
+        let bytes = postcard::to_stdvec(&signed).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<SignedTicket<EndpointTicket>>(&bytes).unwrap(),
+            signed
+        );
+        let json = serde_json::to_string(&signed).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SignedTicket<EndpointTicket>>(&json).unwrap(),
+            signed
+        );
+    }
+
+    #[test]
+    fn test_signed_wrong_signer() {
+        let (_, signed) = make_signed();
+        let other = SecretKey::from_bytes(&[4u8; 32]).public();
+        assert!(signed.verify(&other).is_err());
+
+        let forged = SignedTicket::sign(
+            EndpointTicket::new(EndpointAddr::new(other)),
+            &SecretKey::from_bytes(&[3u8; 32]),
+        );
+        assert!(forged.verify_endpoint().is_err());
+    }
+
+    #[test]
+    fn test_signed_keeps_bytes() {
+        let secret_key = SecretKey::from_bytes(&[3u8; 32]);
+        let ticket = EndpointTicket::new(EndpointAddr::new(secret_key.public()));
+        // The ticket as wire format version 1 with no ALPNs, which decodes to the same
+        // ticket but is encoded as version 0.
+        let mut inner = ticket.encode_bytes();
+        inner[0] = 1;
+        inner.push(0);
+        assert_eq!(EndpointTicket::decode_bytes(&inner).unwrap(), ticket);
+        assert_ne!(ticket.encode_bytes(), inner);
+
+        let bytes = versioned::encode(&Variant0SignedTicket {
+            signature: secret_key.sign(&signing_message(EndpointTicket::KIND, &inner)),
+            ticket: inner,
+            signer: secret_key.public(),
+        });
+        let signed = SignedTicket::<EndpointTicket>::decode_bytes(&bytes).unwrap();
+        assert_eq!(signed.ticket(), &ticket);
+        assert_eq!(signed.encode_bytes(), bytes);
+        let parsed: SignedTicket<EndpointTicket> = signed.to_string().parse().unwrap();
+        assert_eq!(parsed, signed);
+    }
+
+    #[test]
+    fn test_signed_tampered() {
+        let (_, signed) = make_signed();
+        let mut bytes = signed.encode_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let err = SignedTicket::<EndpointTicket>::decode_bytes(&bytes).unwrap_err();
+        assert!(matches!(err, ParseError::Verify { .. }));
+    }
+}
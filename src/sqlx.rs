@@ -0,0 +1,139 @@
+//! Storing tickets in databases with [sqlx].
+//!
+//! The built-in ticket types and [`AnyTicket`] implement [`Type`], [`Encode`] and
+//! [`Decode`] for all databases that support strings, so tickets can be stored in `TEXT`
+//! columns and bound and fetched like strings. Tickets are stored in their canonical string
+//! form, reading a value that is not a valid ticket fails with the [`ParseError`].
+//!
+//! ```no_run
+//! # async fn run(pool: sqlx::SqlitePool) -> sqlx::Result<()> {
+//! use iroh_tickets::endpoint::EndpointTicket;
+//!
+//! let ticket: EndpointTicket = sqlx::query_scalar("SELECT ticket FROM peers WHERE name = ?")
+//!     .bind("alice")
+//!     .fetch_one(&pool)
+//!     .await?;
+//! sqlx::query("INSERT INTO peers (name, ticket) VALUES (?, ?)")
+//!     .bind("bob")
+//!     .bind(&ticket)
+//!     .execute(&pool)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [sqlx]: https://docs.rs/sqlx
+//! [`ParseError`]: crate::ParseError
+
+use sqlx::{
+    Database, Type,
+    decode::Decode,
+    encode::{Encode, IsNull},
+    error::BoxDynError,
+};
+
+use crate::{
+    AnyTicket, Ticket, blob::BlobTicket, bundle::BundleTicket, endpoint::EndpointTicket,
+    gossip::GossipTicket, multi::MultiEndpointTicket,
+};
+
+macro_rules! impl_sqlx {
+    ($($ty:ty),*) => {
+        $(
+            impl<DB: Database> Type<DB> for $ty
+            where
+                str: Type<DB>,
+            {
+                fn type_info() -> DB::TypeInfo {
+                    <str as Type<DB>>::type_info()
+                }
+
+                fn compatible(ty: &DB::TypeInfo) -> bool {
+                    <str as Type<DB>>::compatible(ty)
+                }
+            }
+
+            impl<'q, DB: Database> Encode<'q, DB> for $ty
+            where
+                String: Encode<'q, DB>,
+            {
+                fn encode_by_ref(
+                    &self,
+                    buf: &mut DB::ArgumentBuffer<'q>,
+                ) -> Result<IsNull, BoxDynError> {
+                    self.encode_string().encode(buf)
+                }
+            }
+
+            impl<'r, DB: Database> Decode<'r, DB> for $ty
+            where
+                &'r str: Decode<'r, DB>,
+            {
+                fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+                    let s = <&str as Decode<DB>>::decode(value)?;
+                    Ok(<$ty>::decode_string(s)?)
+                }
+            }
+        )*
+    };
+}
+
+impl_sqlx!(
+    EndpointTicket,
+    BlobTicket,
+    GossipTicket,
+    MultiEndpointTicket,
+    BundleTicket,
+    AnyTicket
+);
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+    use sqlx::{Connection, SqliteConnection};
+
+    use super::*;
+    use crate::ParseError;
+
+    #[tokio::test]
+    async fn test_sqlite() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[37u8; 32]).public(),
+        ));
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE peers (ticket TEXT NOT NULL)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO peers (ticket) VALUES (?), ('endpoint0')")
+            .bind(&ticket)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        let s: String = sqlx::query_scalar("SELECT ticket FROM peers LIMIT 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(s, ticket.to_string());
+        let stored: EndpointTicket = sqlx::query_scalar("SELECT ticket FROM peers LIMIT 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(stored, ticket);
+        let any: AnyTicket = sqlx::query_scalar("SELECT ticket FROM peers LIMIT 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(any, AnyTicket::Endpoint(ticket));
+
+        let err = sqlx::query_scalar::<_, EndpointTicket>("SELECT ticket FROM peers LIMIT 1, 1")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap_err();
+        let sqlx::Error::ColumnDecode { source, .. } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert!(source.downcast_ref::<ParseError>().is_some());
+    }
+}
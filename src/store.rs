@@ -0,0 +1,341 @@
+//! Storing tickets under labels.
+//!
+//! A [`TicketStore`] keeps tickets of any kind under labels chosen by the application,
+//! optionally with a time to live after which they are no longer returned. This covers the
+//! bookkeeping CLIs and daemons need to remember tickets they were given or handed out:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{
+//!     endpoint::EndpointTicket,
+//!     store::{MemoryTicketStore, TicketStore},
+//! };
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let mut store = MemoryTicketStore::default();
+//! store.put("laptop", &ticket, Some(Duration::from_secs(3600)))?;
+//!
+//! let stored = store.get("laptop")?.unwrap();
+//! assert_eq!(stored.kind(), "endpoint");
+//! assert_eq!(stored.decode::<EndpointTicket>()?, ticket);
+//! # Ok::<_, Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! With the `serde_json` feature, [`JsonFileStore`] keeps the tickets in a JSON file. With
+//! the `keyring` feature, [`KeyringTicketStore`] keeps them in the platform keychain.
+//!
+//! [`KeyringTicketStore`]: crate::keyring::KeyringTicketStore
+
+use std::{
+    collections::BTreeMap,
+    io,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ParseError, Ticket, kind_of};
+
+/// A ticket stored under a label, see [`TicketStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredTicket {
+    label: String,
+    ticket: String,
+    /// Seconds since the unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+impl StoredTicket {
+    /// Creates an entry for `ticket` that expires after `ttl`, if given.
+    pub fn new<T: Ticket>(label: impl Into<String>, ticket: &T, ttl: Option<Duration>) -> Self {
+        let expires_at = ttl.map(|ttl| {
+            // Round up, so short but non-zero durations do not expire immediately.
+            let secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+            unix_secs(SystemTime::now()).saturating_add(secs)
+        });
+        Self {
+            label: label.into(),
+            ticket: ticket.encode_string(),
+            expires_at,
+        }
+    }
+
+    /// The label of the ticket.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The string form of the ticket.
+    pub fn ticket(&self) -> &str {
+        &self.ticket
+    }
+
+    /// The kind of the ticket.
+    pub fn kind(&self) -> &str {
+        kind_of(&self.ticket).unwrap_or_default()
+    }
+
+    /// Decodes the ticket.
+    pub fn decode<T: Ticket>(&self) -> Result<T, ParseError> {
+        T::decode_string(&self.ticket)
+    }
+
+    /// The time after which the ticket expires, if any.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Returns whether the ticket is expired at `now`.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| unix_secs(now) >= expires_at)
+    }
+
+    /// Returns whether the ticket is expired.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Storage for tickets under labels.
+///
+/// Expired tickets are not returned by [`get`](Self::get) and [`list`](Self::list), but
+/// stay in the store until [`gc`](Self::gc) removes them.
+pub trait TicketStore {
+    /// Stores `ticket` under `label`, replacing any ticket stored under it before.
+    ///
+    /// If `ttl` is given, the ticket expires after that duration.
+    fn put<T: Ticket>(&mut self, label: &str, ticket: &T, ttl: Option<Duration>) -> io::Result<()> {
+        self.insert(StoredTicket::new(label, ticket, ttl))
+    }
+
+    /// Stores `entry` under its label, replacing any ticket stored under it before.
+    fn insert(&mut self, entry: StoredTicket) -> io::Result<()>;
+
+    /// Returns the ticket stored under `label`, unless it is expired.
+    fn get(&self, label: &str) -> io::Result<Option<StoredTicket>>;
+
+    /// Returns all tickets that are not expired, ordered by label.
+    fn list(&self) -> io::Result<Vec<StoredTicket>>;
+
+    /// Removes the ticket stored under `label`, returns whether there was one.
+    fn remove(&mut self, label: &str) -> io::Result<bool>;
+
+    /// Removes all expired tickets, returns how many were removed.
+    fn gc(&mut self) -> io::Result<usize>;
+}
+
+/// A [`TicketStore`] keeping the tickets in memory.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryTicketStore {
+    tickets: BTreeMap<String, StoredTicket>,
+}
+
+impl TicketStore for MemoryTicketStore {
+    fn insert(&mut self, entry: StoredTicket) -> io::Result<()> {
+        self.tickets.insert(entry.label.clone(), entry);
+        Ok(())
+    }
+
+    fn get(&self, label: &str) -> io::Result<Option<StoredTicket>> {
+        Ok(self
+            .tickets
+            .get(label)
+            .filter(|entry| !entry.is_expired())
+            .cloned())
+    }
+
+    fn list(&self) -> io::Result<Vec<StoredTicket>> {
+        Ok(self
+            .tickets
+            .values()
+            .filter(|entry| !entry.is_expired())
+            .cloned()
+            .collect())
+    }
+
+    fn remove(&mut self, label: &str) -> io::Result<bool> {
+        Ok(self.tickets.remove(label).is_some())
+    }
+
+    fn gc(&mut self) -> io::Result<usize> {
+        let now = SystemTime::now();
+        let before = self.tickets.len();
+        self.tickets.retain(|_, entry| !entry.is_expired_at(now));
+        Ok(before - self.tickets.len())
+    }
+}
+
+#[cfg(feature = "serde_json")]
+pub use self::json::JsonFileStore;
+
+#[cfg(feature = "serde_json")]
+mod json {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::*;
+
+    /// A [`TicketStore`] keeping the tickets in a JSON file.
+    ///
+    /// The file contains an array of objects with the `label`, the `ticket` string and, for
+    /// tickets with a time to live, `expires_at` in seconds since the unix epoch. A missing
+    /// file is treated as empty. Every change reads the file, and writes a temporary file
+    /// next to it that is renamed over the original.
+    #[derive(Debug, Clone)]
+    pub struct JsonFileStore {
+        path: PathBuf,
+    }
+
+    impl JsonFileStore {
+        /// Creates a store backed by the file at `path`.
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self { path: path.into() }
+        }
+
+        /// The path of the file.
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        fn read(&self) -> io::Result<MemoryTicketStore> {
+            let contents = match fs::read(&self.path) {
+                Ok(contents) => contents,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    return Ok(MemoryTicketStore::default());
+                }
+                Err(err) => return Err(err),
+            };
+            let entries: Vec<StoredTicket> = serde_json::from_slice(&contents)?;
+            let tickets = entries
+                .into_iter()
+                .map(|entry| (entry.label.clone(), entry))
+                .collect();
+            Ok(MemoryTicketStore { tickets })
+        }
+
+        fn write(&self, store: &MemoryTicketStore) -> io::Result<()> {
+            let entries: Vec<_> = store.tickets.values().collect();
+            let contents = serde_json::to_vec_pretty(&entries)?;
+            let mut tmp = self.path.clone().into_os_string();
+            tmp.push(".tmp");
+            fs::write(&tmp, contents)?;
+            fs::rename(&tmp, &self.path)
+        }
+
+        fn update<R>(
+            &self,
+            f: impl FnOnce(&mut MemoryTicketStore) -> io::Result<R>,
+        ) -> io::Result<R> {
+            let mut store = self.read()?;
+            let res = f(&mut store)?;
+            self.write(&store)?;
+            Ok(res)
+        }
+    }
+
+    impl TicketStore for JsonFileStore {
+        fn insert(&mut self, entry: StoredTicket) -> io::Result<()> {
+            self.update(|store| store.insert(entry))
+        }
+
+        fn get(&self, label: &str) -> io::Result<Option<StoredTicket>> {
+            self.read()?.get(label)
+        }
+
+        fn list(&self) -> io::Result<Vec<StoredTicket>> {
+            self.read()?.list()
+        }
+
+        fn remove(&mut self, label: &str) -> io::Result<bool> {
+            self.update(|store| store.remove(label))
+        }
+
+        fn gc(&mut self) -> io::Result<usize> {
+            self.update(|store| store.gc())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{blob::BlobTicket, endpoint::EndpointTicket};
+
+    fn check_store(mut store: impl TicketStore) {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[31u8; 32]).public());
+        let endpoint = EndpointTicket::new(addr.clone());
+        let blob = BlobTicket::new(addr, [1u8; 32], Default::default());
+
+        store.put("laptop", &endpoint, None).unwrap();
+        store
+            .put("photo", &blob, Some(Duration::from_secs(3600)))
+            .unwrap();
+        store.put("old", &endpoint, Some(Duration::ZERO)).unwrap();
+
+        let stored = store.get("laptop").unwrap().unwrap();
+        assert_eq!(stored.kind(), "endpoint");
+        assert_eq!(stored.decode::<EndpointTicket>().unwrap(), endpoint);
+        assert_eq!(stored.expires_at(), None);
+        let stored = store.get("photo").unwrap().unwrap();
+        assert_eq!(stored.decode::<BlobTicket>().unwrap(), blob);
+        assert!(stored.expires_at().unwrap() > SystemTime::now());
+        assert_eq!(store.get("old").unwrap(), None);
+        assert_eq!(store.get("missing").unwrap(), None);
+
+        let labels: Vec<_> = store
+            .list()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.label().to_string())
+            .collect();
+        assert_eq!(labels, ["laptop", "photo"]);
+        assert_eq!(store.gc().unwrap(), 1);
+        assert_eq!(store.gc().unwrap(), 0);
+
+        store.put("laptop", &blob, None).unwrap();
+        assert_eq!(store.get("laptop").unwrap().unwrap().kind(), "blob");
+        assert!(store.remove("laptop").unwrap());
+        assert!(!store.remove("laptop").unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_memory_ticket_store() {
+        check_store(MemoryTicketStore::default());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_json_file_store() {
+        let path =
+            std::env::temp_dir().join(format!("iroh-tickets-store-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        check_store(JsonFileStore::new(&path));
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json[0]["label"], "photo");
+        assert!(json[0]["expires_at"].is_u64());
+        std::fs::write(&path, "not json").unwrap();
+        let err = JsonFileStore::new(&path).list().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,296 @@
+//! Utilities for testing ticket types.
+//!
+//! [`assert_roundtrip`] checks that a ticket survives its string and byte forms, the
+//! [`ticket_conformance_tests!`](crate::ticket_conformance_tests) macro generates tests for
+//! the conventions of this crate, and [`Vector`]s check that a wire format stays the same
+//! across releases. [`small_ticket`] and [`large_ticket`] generate deterministic tickets,
+//! e.g. for benchmarks:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, testing};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! testing::assert_roundtrip(&ticket);
+//! testing::assert_serde_roundtrip(&ticket);
+//!
+//! let vectors = testing::parse_vectors(&format!(
+//!     r#"[{{ "name": "no addresses", "ticket": "{ticket}" }}]"#
+//! ));
+//! testing::assert_vectors::<EndpointTicket>(&vectors);
+//!
+//! testing::assert_roundtrip(&testing::large_ticket(16));
+//! ```
+
+use std::{fmt::Debug, net::SocketAddr, path::Path, str::FromStr};
+
+use iroh_base::{EndpointAddr, RelayUrl, SecretKey};
+use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+
+use crate::{Ticket, endpoint::EndpointTicket, multi::MultiEndpointTicket};
+
+/// Asserts that `ticket` round-trips through its string and byte forms.
+///
+/// This checks that the string form is the lowercase [`KIND`](Ticket::KIND) prefix followed
+/// by base32, that uppercase and [lenient](Ticket::decode_string_lenient) parsing decode the
+/// same ticket and that the byte form is within [`MAX_LEN`](Ticket::MAX_LEN).
+#[track_caller]
+pub fn assert_roundtrip<T: Ticket + PartialEq + Debug>(ticket: &T) {
+    let s = ticket.encode_string();
+    let payload = s
+        .strip_prefix(T::KIND)
+        .unwrap_or_else(|| panic!("{s:?} does not start with the kind {:?}", T::KIND));
+    assert!(
+        payload
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || (b'2'..=b'7').contains(&b)),
+        "{s:?} is not lowercase base32"
+    );
+    assert_eq!(&T::decode_string(&s).expect("decoding the string"), ticket);
+    assert_eq!(
+        &T::decode_string(&s.to_ascii_uppercase()).expect("decoding the uppercase string"),
+        ticket
+    );
+    let wrapped = format!("\"{}\n{}\"", &s[..s.len() / 2], &s[s.len() / 2..]);
+    assert_eq!(
+        &T::decode_string_lenient(&wrapped).expect("decoding the wrapped string"),
+        ticket
+    );
+
+    let bytes = ticket.encode_bytes();
+    assert!(
+        bytes.len() <= T::MAX_LEN,
+        "{} bytes exceed the maximum length of {}",
+        bytes.len(),
+        T::MAX_LEN
+    );
+    assert_eq!(
+        bytes,
+        ticket.encode_bytes(),
+        "encoding is not deterministic"
+    );
+    assert_eq!(
+        &T::decode_bytes(&bytes).expect("decoding the bytes"),
+        ticket
+    );
+    T::decode_bytes(&ticket.canonical_bytes()).expect("decoding the canonical bytes");
+}
+
+/// Asserts that `ticket` serializes as its string form in JSON and round-trips through JSON
+/// and postcard.
+#[track_caller]
+pub fn assert_serde_roundtrip<T>(ticket: &T)
+where
+    T: Ticket + Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let json = serde_json::to_value(ticket).expect("serializing to JSON");
+    assert_eq!(json, serde_json::Value::String(ticket.encode_string()));
+    assert_eq!(
+        &serde_json::from_value::<T>(json).expect("deserializing from JSON"),
+        ticket
+    );
+    let bytes = postcard::to_stdvec(ticket).expect("serializing to postcard");
+    assert_eq!(
+        &postcard::from_bytes::<T>(&bytes).expect("deserializing from postcard"),
+        ticket
+    );
+}
+
+/// Asserts that [`Display`](std::fmt::Display) and [`FromStr`] use the string form.
+#[track_caller]
+pub fn assert_display_from_str<T>(ticket: &T)
+where
+    T: Ticket + std::fmt::Display + FromStr + PartialEq + Debug,
+    T::Err: Debug,
+{
+    let s = ticket.to_string();
+    assert_eq!(s, ticket.encode_string());
+    assert_eq!(&s.parse::<T>().expect("parsing with FromStr"), ticket);
+}
+
+/// A golden test vector, a ticket string that must keep decoding to the same bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[non_exhaustive]
+pub struct Vector {
+    /// A description of the vector.
+    pub name: String,
+    /// The ticket string.
+    pub ticket: String,
+    /// The expected byte representation, hex encoded in the fixture.
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub bytes: Option<Vec<u8>>,
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<u8>>, D::Error> {
+    let Some(hex) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    data_encoding::HEXLOWER_PERMISSIVE
+        .decode(hex.as_bytes())
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Parses golden vectors from a JSON array of objects with a `name`, a `ticket` string and
+/// optionally the hex encoded `bytes`.
+///
+/// # Panics
+///
+/// If the JSON is not valid.
+#[track_caller]
+pub fn parse_vectors(json: &str) -> Vec<Vector> {
+    serde_json::from_str(json).unwrap_or_else(|err| panic!("invalid vectors: {err}"))
+}
+
+/// Loads golden vectors from a JSON file, see [`parse_vectors`].
+///
+/// # Panics
+///
+/// If the file can not be read or is not valid.
+#[track_caller]
+pub fn load_vectors(path: impl AsRef<Path>) -> Vec<Vector> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("reading {}: {err}", path.display()));
+    parse_vectors(&json)
+}
+
+/// Asserts that all `vectors` decode as `T`, to the expected bytes if given, and that
+/// encoding the decoded tickets produces the vector strings again.
+#[track_caller]
+pub fn assert_vectors<T: Ticket>(vectors: &[Vector]) {
+    for vector in vectors {
+        let name = &vector.name;
+        let ticket = T::decode_string(&vector.ticket)
+            .unwrap_or_else(|err| panic!("vector {name:?} does not decode: {err}"));
+        if let Some(bytes) = &vector.bytes {
+            assert_eq!(&ticket.encode_bytes(), bytes, "bytes of vector {name:?}");
+        }
+        assert_eq!(
+            ticket.encode_string(),
+            vector.ticket,
+            "string of vector {name:?}"
+        );
+    }
+}
+
+/// A deterministic endpoint address with an IP address and a relay URL.
+///
+/// Different values of `i` produce different endpoint ids and addresses.
+pub fn endpoint_addr(i: u32) -> EndpointAddr {
+    let mut key = [0u8; 32];
+    key[..4].copy_from_slice(&i.to_le_bytes());
+    let [_, _, hi, lo] = i.to_be_bytes();
+    let ip = SocketAddr::from(([198, 51, hi, lo], 1234));
+    let relay: RelayUrl = format!("https://relay{i}.example.com")
+        .parse()
+        .expect("valid relay URL");
+    EndpointAddr::new(SecretKey::from_bytes(&key).public())
+        .with_ip_addr(ip)
+        .with_relay_url(relay)
+}
+
+/// A typical endpoint ticket, for a single [endpoint address](endpoint_addr) and an ALPN.
+pub fn small_ticket() -> EndpointTicket {
+    EndpointTicket::new(endpoint_addr(0)).with_alpns([b"iroh/bench/0"])
+}
+
+/// A multi endpoint ticket with `endpoints` [endpoint addresses](endpoint_addr).
+pub fn large_ticket(endpoints: u32) -> MultiEndpointTicket {
+    MultiEndpointTicket::new((0..endpoints).map(endpoint_addr))
+}
+
+/// Generates tests for the conventions of ticket types.
+///
+/// This expands to a module named `$name` with tests calling [`assert_roundtrip`],
+/// [`assert_serde_roundtrip`] and [`assert_display_from_str`] on the ticket created by
+/// `$ticket`, an expression that is evaluated in the module calling the macro. The ticket type
+/// must implement serde, `Display` and `FromStr`, as `#[derive(Ticket)]` does.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # mod tests {
+/// use iroh_tickets::Ticket;
+///
+/// #[derive(Debug, PartialEq, Ticket)]
+/// #[ticket(kind = "counter")]
+/// struct CounterTicket(u64);
+///
+/// iroh_tickets::ticket_conformance_tests!(counter, CounterTicket, CounterTicket(42));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ticket_conformance_tests {
+    ($name:ident, $ty:ty, $ticket:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[test]
+            fn roundtrip() {
+                let ticket: $ty = $ticket;
+                $crate::testing::assert_roundtrip(&ticket);
+            }
+
+            #[test]
+            fn serde_roundtrip() {
+                let ticket: $ty = $ticket;
+                $crate::testing::assert_serde_roundtrip(&ticket);
+            }
+
+            #[test]
+            fn display_from_str() {
+                let ticket: $ty = $ticket;
+                $crate::testing::assert_display_from_str(&ticket);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{blob::BlobTicket, endpoint::EndpointTicket};
+
+    fn endpoint_ticket() -> EndpointTicket {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[39u8; 32]).public())
+            .with_ip_addr("198.51.100.1:1234".parse().unwrap());
+        EndpointTicket::new(addr).with_alpns([b"echo"])
+    }
+
+    crate::ticket_conformance_tests!(endpoint, EndpointTicket, endpoint_ticket());
+
+    #[test]
+    fn test_vectors() {
+        let ticket = endpoint_ticket();
+        let hex = data_encoding::HEXUPPER.encode(&ticket.encode_bytes());
+        let vectors = parse_vectors(&format!(
+            r#"[
+                {{ "name": "with bytes", "ticket": "{ticket}", "bytes": "{hex}" }},
+                {{ "name": "without bytes", "ticket": "{ticket}" }}
+            ]"#
+        ));
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(vectors[0].bytes, Some(ticket.encode_bytes()));
+        assert_eq!(vectors[1].bytes, None);
+        assert_vectors::<EndpointTicket>(&vectors);
+
+        let result = std::panic::catch_unwind(|| assert_vectors::<BlobTicket>(&vectors));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_generators() {
+        assert_eq!(small_ticket(), small_ticket());
+        assert_roundtrip(&small_ticket());
+        let large = large_ticket(300);
+        assert_eq!(large.len(), 300);
+        assert_roundtrip(&large);
+    }
+}
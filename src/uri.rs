@@ -0,0 +1,215 @@
+//! Tickets as `iroh:` URIs.
+//!
+//! A ticket URI has the form `iroh:<kind>/<payload>`, where `<payload>` is the string form
+//! of the ticket without its kind prefix. This allows registering tickets as deep links,
+//! e.g. for opening an app from a web page.
+//!
+//! Browsers only let web apps register schemes starting with `web+` as protocol handlers,
+//! so [`encode_web`] produces links of the form `web+iroh://<kind>/<payload>` instead. The
+//! [`decode`] functions accept both forms, also when percent-encoded as a whole, as
+//! browsers pass the link to the handler URL.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{endpoint::EndpointTicket, uri};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let s = uri::encode(&ticket);
+//! assert!(s.starts_with("iroh:endpoint/"));
+//! assert_eq!(uri::decode::<EndpointTicket>(&s).unwrap(), ticket);
+//! ```
+
+use n0_error::e;
+
+use crate::{AnyTicket, ParseError, Ticket, matches_kind};
+
+/// The URI scheme for tickets.
+pub const SCHEME: &str = "iroh";
+
+/// The URI scheme for web links to tickets, see [`encode_web`].
+pub const WEB_SCHEME: &str = "web+iroh";
+
+/// Formats a ticket as an `iroh:` URI.
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let s = ticket.encode_string();
+    let payload = &s[T::KIND.len()..];
+    format!("{SCHEME}:{}/{payload}", T::KIND)
+}
+
+/// Formats a ticket as a `web+iroh://` link.
+///
+/// The kind is the host of the link and the payload its path, so the link survives URL
+/// normalization, e.g. by a browser passing it to a registered protocol handler.
+pub fn encode_web<T: Ticket>(ticket: &T) -> String {
+    let s = ticket.encode_string();
+    let payload = &s[T::KIND.len()..];
+    format!("{WEB_SCHEME}://{}/{payload}", T::KIND)
+}
+
+/// Parses a ticket from an `iroh:` URI or a `web+iroh://` link.
+pub fn decode<T: Ticket>(uri: &str) -> Result<T, ParseError> {
+    let (kind, payload) = split(uri)?;
+    if !matches_kind::<T>(&kind) {
+        return Err(ParseError::wrong_kind(T::KIND, kind));
+    }
+    T::decode_string(&format!("{kind}{payload}"))
+}
+
+/// Parses a ticket of any of the built-in kinds from an `iroh:` URI or a `web+iroh://`
+/// link.
+pub fn decode_any(uri: &str) -> Result<AnyTicket, ParseError> {
+    let (kind, payload) = split(uri)?;
+    let bytes = data_encoding::BASE32_NOPAD.decode(payload.to_ascii_uppercase().as_bytes())?;
+    AnyTicket::decode_bytes(&kind, &bytes)
+}
+
+/// Splits a URI into its kind and payload, percent-decoding both.
+fn split(uri: &str) -> Result<(String, String), ParseError> {
+    let mut uri = uri.trim().to_string();
+    if !uri.contains(':') {
+        // The whole link is percent-encoded, e.g. `web%2Biroh%3A%2F%2F...`.
+        uri = percent_decode(&uri)?;
+    }
+    let Some((scheme, rest)) = uri.split_once(':') else {
+        return Err(e!(ParseError::Uri));
+    };
+    if !scheme.eq_ignore_ascii_case(SCHEME) && !scheme.eq_ignore_ascii_case(WEB_SCHEME) {
+        return Err(e!(ParseError::Uri));
+    }
+    let rest = percent_decode(rest.strip_prefix("//").unwrap_or(rest))?;
+    let Some((kind, payload)) = rest.split_once('/') else {
+        return Err(e!(ParseError::Uri));
+    };
+    // URL normalization may add a trailing slash.
+    let payload = payload.strip_suffix('/').unwrap_or(payload);
+    Ok((kind.to_ascii_lowercase(), payload.to_ascii_lowercase()))
+}
+
+pub(crate) fn percent_decode(s: &str) -> Result<String, ParseError> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [
+                bytes.next().ok_or_else(|| e!(ParseError::Uri))?,
+                bytes.next().ok_or_else(|| e!(ParseError::Uri))?,
+            ];
+            let byte = data_encoding::HEXLOWER_PERMISSIVE
+                .decode(&hex)
+                .map_err(|_| e!(ParseError::Uri))?;
+            out.extend(byte);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8(out).map_err(|_| e!(ParseError::Uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{
+        blob::{BlobFormat, BlobTicket},
+        endpoint::EndpointTicket,
+    };
+
+    #[test]
+    fn test_uri_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[11u8; 32]).public());
+        let ticket = EndpointTicket::new(addr.clone());
+        let uri = encode(&ticket);
+        let payload = uri.strip_prefix("iroh:endpoint/").unwrap();
+        assert_eq!(format!("endpoint{payload}"), ticket.to_string());
+
+        assert_eq!(decode::<EndpointTicket>(&uri).unwrap(), ticket);
+        assert_eq!(decode_any(&uri).unwrap(), AnyTicket::from(ticket.clone()));
+        let upper = uri.to_ascii_uppercase();
+        assert_eq!(decode::<EndpointTicket>(&upper).unwrap(), ticket);
+        let slashes = uri.replace("iroh:", "iroh://");
+        assert_eq!(decode::<EndpointTicket>(&slashes).unwrap(), ticket);
+        let escaped = uri.replace("endpoint/", "endpoint%2F");
+        assert_eq!(decode::<EndpointTicket>(&escaped).unwrap(), ticket);
+
+        let blob = BlobTicket::new(addr, [0u8; 32], BlobFormat::Raw);
+        assert!(matches!(
+            decode::<EndpointTicket>(&encode(&blob)),
+            Err(ParseError::Kind { .. })
+        ));
+    }
+
+    #[test]
+    fn test_web_link_roundtrip() {
+        let addr = EndpointAddr::new(SecretKey::from_bytes(&[27u8; 32]).public());
+        let ticket = EndpointTicket::new(addr);
+        let link = encode_web(&ticket);
+        assert!(link.starts_with("web+iroh://endpoint/"));
+        assert_eq!(decode::<EndpointTicket>(&link).unwrap(), ticket);
+
+        let url = url::Url::parse(&link).unwrap();
+        assert_eq!(url.scheme(), WEB_SCHEME);
+        assert_eq!(url.host_str(), Some("endpoint"));
+        assert_eq!(url.as_str(), link);
+        let url = url::Url::parse(&link.to_ascii_uppercase()).unwrap();
+        assert_eq!(decode::<EndpointTicket>(url.as_str()).unwrap(), ticket);
+        let url = url::Url::parse(&format!("{link}/")).unwrap();
+        assert_eq!(
+            decode_any(url.as_str()).unwrap(),
+            AnyTicket::from(ticket.clone())
+        );
+
+        // A protocol handler receives the link percent-encoded in its own URL.
+        let handler =
+            url::Url::parse_with_params("https://app.example/open", [("uri", &link)]).unwrap();
+        let (_, escaped) = handler.query().unwrap().split_once('=').unwrap();
+        assert!(escaped.starts_with("web%2Biroh%3A%2F%2F"), "{escaped}");
+        assert_eq!(decode::<EndpointTicket>(escaped).unwrap(), ticket);
+        let (_, value) = handler.query_pairs().next().unwrap();
+        assert_eq!(decode::<EndpointTicket>(&value).unwrap(), ticket);
+    }
+
+    #[test]
+    fn test_uri_invalid() {
+        for uri in [
+            "endpoint/abc",
+            "http:endpoint/abc",
+            "iroh:endpoint",
+            "iroh:endpoint%2",
+            "iroh:endpoint%zz/abc",
+            "iroh:endpoint%+2/abc",
+        ] {
+            assert!(
+                matches!(decode::<EndpointTicket>(uri), Err(ParseError::Uri { .. })),
+                "{uri}"
+            );
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct RoomTicket(Vec<u8>);
+
+    impl Ticket for RoomTicket {
+        const KIND: &'static str = "room";
+        const KIND_ALIASES: &'static [&'static str] = &["chat"];
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            Ok(Self(bytes.to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_uri_kind_alias() {
+        let ticket = RoomTicket(vec![1, 2, 3]);
+        let uri = encode(&ticket).replace("iroh:room/", "iroh:chat/");
+        assert_eq!(decode::<RoomTicket>(&uri).unwrap(), ticket);
+        let link = encode_web(&ticket).replace("//room/", "//CHAT/");
+        assert_eq!(decode::<RoomTicket>(&link).unwrap(), ticket);
+    }
+}
@@ -0,0 +1,171 @@
+//! Frozen test vectors of the built-in ticket types.
+
+use std::str::FromStr;
+
+use iroh_base::{EndpointAddr, PublicKey, RelayUrl};
+
+use crate::{
+    AnyTicket,
+    blob::{BlobFormat, BlobTicket},
+    bundle::BundleTicket,
+    dns::DnsTicket,
+    endpoint::EndpointTicket,
+    gossip::GossipTicket,
+    invite::InviteTicket,
+    multi::MultiEndpointTicket,
+    relay::RelayTicket,
+};
+
+/// A test vector, a ticket string with its byte representation and decoded ticket.
+///
+/// See [`vectors`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TestVector {
+    /// A description of the vector.
+    pub name: &'static str,
+    /// The canonical string form of the ticket.
+    pub ticket: &'static str,
+    /// The byte representation of the ticket.
+    pub bytes: Vec<u8>,
+    /// The decoded ticket.
+    pub decoded: AnyTicket,
+}
+
+/// Returns test vectors for the wire formats of the built-in ticket types.
+///
+/// The strings and bytes are frozen, a release that changes them breaks compatibility.
+/// Implementations of the ticket formats in other languages can check against them, e.g.
+/// by comparing the decoded fields of [`TestVector::decoded`] with their own decoding of
+/// [`TestVector::ticket`].
+///
+/// ```
+/// for vector in iroh_tickets::vectors() {
+///     assert_eq!(vector.decoded.encode_string(), vector.ticket);
+///     assert_eq!(vector.decoded.encode_bytes(), vector.bytes);
+/// }
+/// ```
+pub fn vectors() -> Vec<TestVector> {
+    let id1 = endpoint_id("8a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c");
+    let id2 = endpoint_id("8139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394");
+    let relay = RelayUrl::from_str("https://relay.example.com").expect("valid relay URL");
+    let addr1 = EndpointAddr::new(id1);
+    let addr2 = EndpointAddr::new(id2)
+        .with_relay_url(relay.clone())
+        .with_ip_addr("192.0.2.1:4433".parse().expect("valid address"))
+        .with_ip_addr("[2001:db8::1]:4433".parse().expect("valid address"));
+
+    let vector = |name, ticket, bytes: &str, decoded: AnyTicket| TestVector {
+        name,
+        ticket,
+        bytes: data_encoding::HEXLOWER
+            .decode(bytes.as_bytes())
+            .expect("valid hex"),
+        decoded,
+    };
+    vec![
+        vector(
+            "endpoint without addresses",
+            "endpointacfiry65oqe7dfp5klns2pf2lvzmuzyjx4ozieq36n2iqanub5xvyaa",
+            "008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c00",
+            EndpointTicket::new(addr1.clone()).into(),
+        ),
+        vector(
+            "endpoint with relay and IP addresses",
+            "endpointacats5yovb6rox2wunkgnq2mp3gmxdmksg2o4n5clx3a6w4pzgzziayadjuhi5dqom5c6l3smvwgc6jomv4gc3lqnrss4y3pnuxqcagaaabadujcaeasaainxaaaaaaaaaaaaaaaaaaadujc",
+            "008139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b39403001a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0100c0000201d122010120010db8000000000000000000000001d122",
+            EndpointTicket::new(addr2.clone()).into(),
+        ),
+        vector(
+            "endpoint with ALPNs",
+            "endpointagats5yovb6rox2wunkgnq2mp3gmxdmksg2o4n5clx3a6w4pzgzziayadjuhi5dqom5c6l3smvwgc6jomv4gc3lqnrss4y3pnuxqcagaaabadujcaeasaainxaaaaaaaaaaaaaaaaaaadujcaifws4tpnawwky3in4xtaatigm",
+            "018139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b39403001a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0100c0000201d122010120010db8000000000000000000000001d122020b69726f682d6563686f2f30026833",
+            EndpointTicket::new(addr2.clone())
+                .with_alpns([&b"iroh-echo/0"[..], b"h3"])
+                .into(),
+        ),
+        vector(
+            "raw blob",
+            "blobacfiry65oqe7dfp5klns2pf2lvzmuzyjx4ozieq36n2iqanub5xvyaaaacv2xk5lvov2xk5lvov2xk5lvov2xk5lvov2xk5lvov2xk5lvov2w",
+            "008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c000000abababababababababababababababababababababababababababababababab",
+            BlobTicket::new(addr1.clone(), [0xab; 32], BlobFormat::Raw).into(),
+        ),
+        vector(
+            "hash sequence blob with relay and IP addresses",
+            "blobacats5yovb6rox2wunkgnq2mp3gmxdmksg2o4n5clx3a6w4pzgzziai2nb2hi4dthixs64tfnrqxsltfpbqw24dmmuxgg33nf4babqaaaia5ciqbeaaq3oaaaaaaaaaaaaaaaaaaahiseaonzxg43tonzxg43tonzxg43tonzxg43tonzxg43tonzxg43tonzu",
+            "008139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b394011a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0200c0000201d1220120010db8000000000000000000000001d12201cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd",
+            BlobTicket::new(addr2.clone(), [0xcd; 32], BlobFormat::HashSeq).into(),
+        ),
+        vector(
+            "gossip topic with two bootstrap endpoints",
+            "topicaairceirceirceirceirceirceirceirceirceirceirceirceircaukrdr525aj6gk72uw3fu6luxlszjtqtpy5sqjbx43uraa3id3plqaicolxb2uh2f27k2rvizwdjr7mzs4nrki3j3rxujo7md23r7e3hfadaangq5duobztulzpojswyylzfzsxqylnobwgkltdn5ws6aiayaaaeaoreiaqciabbw4aaaaaaaaaaaaaaaaaaaorei",
+            "001111111111111111111111111111111111111111111111111111111111111111028a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c008139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b39403001a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0100c0000201d122010120010db8000000000000000000000001d122",
+            GossipTicket::new([0x11; 32], [addr1.clone(), addr2.clone()]).into(),
+        ),
+        vector(
+            "two endpoints",
+            "multiendpointaabicolxb2uh2f27k2rvizwdjr7mzs4nrki3j3rxujo7md23r7e3hfadaangq5duobztulzpojswyylzfzsxqylnobwgkltdn5ws6aiayaaaeaoreiaqciabbw4aaaaaaaaaaaaaaaaaaaorekfiry65oqe7dfp5klns2pf2lvzmuzyjx4ozieq36n2iqanub5xvyaa",
+            "00028139770ea87d175f56a35466c34c7ecccb8d8a91b4ee37a25df60f5b8fc9b39403001a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0100c0000201d122010120010db8000000000000000000000001d1228a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c00",
+            MultiEndpointTicket::new([addr1.clone(), addr2]).into(),
+        ),
+        vector(
+            "relay with a token",
+            "relayaangq5duobztulzpojswyylzfzsxqylnobwgkltdn5ws6aigonswg4tfoq",
+            "001a68747470733a2f2f72656c61792e6578616d706c652e636f6d2f0106736563726574",
+            RelayTicket::new(relay).with_token("secret").into(),
+        ),
+        vector(
+            "dns name",
+            "dnsaafwk6dbnvygyzjomnxw2",
+            "000b6578616d706c652e636f6d",
+            DnsTicket::new("example.com").into(),
+        ),
+        vector(
+            "invite with a label",
+            "inviteacfiry65oqe7dfp5klns2pf2lvzmuzyjx4ozieq36n2iqanub5xvyaccijbeeqscijbeeqscijbeeqscijbeeqscijbeeqscijbeeqsciiaqkylmnfrwk",
+            "008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c0042424242424242424242424242424242424242424242424242424242424242420105616c696365",
+            InviteTicket::new(addr1.clone(), [0x42; 32])
+                .with_label("alice")
+                .into(),
+        ),
+        vector(
+            "bundle of an endpoint and a blob ticket",
+            "bundleaabaqzlomryg62looqrabcui4poxicprsx6vfwznhs5f24wkm4e36hmucin7g5eiag2a6324aacge3dpmjcabcui4poxicprsx6vfwznhs5f24wkm4e36hmucin7g5eiag2a6324aaaabk5lvov2xk5lvov2xk5lvov2xk5lvov2xk5lvov2xk5lvov2xk5l",
+            "000208656e64706f696e7422008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c0004626c6f6244008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c000000abababababababababababababababababababababababababababababababab",
+            BundleTicket::new()
+                .with(&EndpointTicket::new(addr1.clone()))
+                .with(&BlobTicket::new(addr1, [0xab; 32], BlobFormat::Raw))
+                .into(),
+        ),
+    ]
+}
+
+fn endpoint_id(hex: &str) -> PublicKey {
+    PublicKey::from_str(hex).expect("valid endpoint id")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        let vectors = vectors();
+        for kind in AnyTicket::KINDS {
+            assert!(vectors.iter().any(|vector| vector.decoded.kind() == *kind));
+        }
+        for vector in vectors {
+            let name = vector.name;
+            let decoded = AnyTicket::decode_string(vector.ticket).unwrap();
+            assert_eq!(decoded, vector.decoded, "{name}");
+            assert_eq!(decoded.encode_string(), vector.ticket, "{name}");
+            assert_eq!(decoded.encode_bytes(), vector.bytes, "{name}");
+            let kind = decoded.kind();
+            assert_eq!(
+                AnyTicket::decode_bytes(kind, &vector.bytes).unwrap(),
+                decoded,
+                "{name}"
+            );
+        }
+    }
+}
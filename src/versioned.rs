@@ -0,0 +1,286 @@
+//! Helpers for versioned wire formats.
+//!
+//! Ticket wire formats are commonly written as a postcard enum with one variant per
+//! version, so that older tickets keep parsing after the format changes. On the wire such
+//! an enum is the variant index as a varint, followed by the postcard encoding of the
+//! variant.
+//!
+//! This module produces the same layout without the hand-written enum: each version is its
+//! own type implementing [`Versioned`], and older versions are upgraded to the newest one
+//! while decoding. Each version implements [`UpgradeFrom`] for its predecessor and
+//! delegates to [`upgrade`] in [`Versioned::decode_older`], so a V0 ticket is upgraded to V1
+//! and then to V2 when decoding V2.
+//!
+//! ```
+//! use iroh_tickets::{
+//!     ParseError,
+//!     versioned::{self, UpgradeFrom, Versioned},
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct V0 {
+//!     name: String,
+//! }
+//!
+//! impl Versioned for V0 {
+//!     const VERSION: u32 = 0;
+//! }
+//!
+//! #[derive(Debug, Serialize, Deserialize)]
+//! struct V1 {
+//!     name: String,
+//!     port: u16,
+//! }
+//!
+//! impl UpgradeFrom<V0> for V1 {
+//!     fn upgrade_from(v0: V0) -> Self {
+//!         Self {
+//!             name: v0.name,
+//!             port: 443,
+//!         }
+//!     }
+//! }
+//!
+//! impl Versioned for V1 {
+//!     const VERSION: u32 = 1;
+//!
+//!     fn decode_older(version: u32, payload: &[u8]) -> Result<Self, ParseError> {
+//!         versioned::upgrade::<V0, Self>(version, payload)
+//!     }
+//! }
+//!
+//! let old = versioned::encode(&V0 { name: "a".into() });
+//! let new: V1 = versioned::decode(&old).unwrap();
+//! assert_eq!(new.port, 443);
+//! ```
+
+use alloc::vec::Vec;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    EncodeError, ParseError,
+    codec::{Postcard, WireCodec},
+    e,
+};
+
+/// A single version of a wire format.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The version written on the wire.
+    ///
+    /// This is the variant index of the equivalent postcard enum.
+    const VERSION: u32;
+
+    /// Decodes a version other than [`VERSION`](Self::VERSION) and upgrades it to `Self`.
+    ///
+    /// The default implementation rejects all other versions, which is correct for the
+    /// first version of a format. Later versions usually delegate to [`upgrade`] for their
+    /// predecessor, which chains upgrades across any number of versions. [`upgrade`] only
+    /// works for postcard payloads, formats with another [`WireCodec`] decode their
+    /// predecessor with [`decode_version_with`] instead.
+    fn decode_older(version: u32, payload: &[u8]) -> Result<Self, ParseError> {
+        let _ = payload;
+        Err(e!(ParseError::UnknownVersion {
+            version,
+            raw: Vec::new()
+        }))
+    }
+}
+
+/// Conversion from the previous version of a wire format.
+///
+/// Implementations only convert from the direct predecessor, older versions are first
+/// upgraded to the predecessor by its own [`Versioned::decode_older`].
+pub trait UpgradeFrom<Old: Versioned>: Versioned {
+    /// Upgrades `old` to this version.
+    fn upgrade_from(old: Old) -> Self;
+}
+
+/// Decodes a payload of `version` as `Old` and upgrades it to `New`.
+///
+/// This is meant to be called from [`Versioned::decode_older`] of `New`. Versions older
+/// than `Old` are handled by the [`Versioned::decode_older`] of `Old`.
+///
+/// The payload is decoded with postcard, so this only works for formats written with
+/// [`encode`]. For other codecs, decode `Old` with [`decode_version_with`] and convert it
+/// with [`UpgradeFrom::upgrade_from`].
+pub fn upgrade<Old: Versioned, New: UpgradeFrom<Old>>(
+    version: u32,
+    payload: &[u8],
+) -> Result<New, ParseError> {
+    decode_version::<Old>(version, payload).map(New::upgrade_from)
+}
+
+/// Encodes `value` prefixed by its version.
+pub fn encode<T: Versioned>(value: &T) -> Vec<u8> {
+    encode_with::<Postcard, T>(value)
+}
+
+/// Encodes `value` prefixed by its version with the given [`WireCodec`].
+///
+/// The version and the value are encoded one after the other, for [`Postcard`] this is
+/// the same as [`encode`].
+pub fn encode_with<C: WireCodec, T: Versioned>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    C::encode_into(&T::VERSION, &mut out);
+    C::encode_into(value, &mut out);
+    out
+}
+
+/// Encodes `value` prefixed by its version, returning an error if serialization fails.
+///
+/// [`encode`] panics instead, which is fine for wire formats that can not fail to
+/// serialize, like the ones in this crate.
+pub fn try_encode<T: Versioned>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    Ok(postcard::to_allocvec(&(T::VERSION, value))?)
+}
+
+/// Appends `value` prefixed by its version to `out`, see [`encode`].
+pub fn encode_into<T: Versioned>(value: &T, out: &mut Vec<u8>) {
+    *out = postcard::to_extend(&(T::VERSION, value), core::mem::take(out))
+        .expect("postcard serialization failed");
+}
+
+/// Decodes bytes written by [`encode`] for `T` or any version it can be upgraded from.
+pub fn decode<T: Versioned>(bytes: &[u8]) -> Result<T, ParseError> {
+    decode_with::<Postcard, T>(bytes)
+}
+
+/// Decodes bytes written by [`encode_with`] with the same codec.
+///
+/// A [`ParseError::UnknownVersion`] contains `bytes`, so newer tickets can be passed on
+/// unchanged.
+pub fn decode_with<C: WireCodec, T: Versioned>(bytes: &[u8]) -> Result<T, ParseError> {
+    let (version, payload) = C::take_from_bytes::<u32>(bytes)?;
+    decode_version_with::<C, T>(version, payload).map_err(|mut err| {
+        if let ParseError::UnknownVersion { raw, .. } = &mut err
+            && raw.is_empty()
+        {
+            *raw = bytes.to_vec();
+        }
+        err
+    })
+}
+
+/// Decodes a payload that was written with the given `version`.
+///
+/// If `version` is not [`T::VERSION`](Versioned::VERSION) this defers to
+/// [`Versioned::decode_older`]. Data after the end of the value is rejected with
+/// [`ParseError::TrailingBytes`].
+pub fn decode_version<T: Versioned>(version: u32, payload: &[u8]) -> Result<T, ParseError> {
+    decode_version_with::<Postcard, T>(version, payload)
+}
+
+/// Decodes a payload that was written with the given `version` and codec, see
+/// [`decode_version`].
+///
+/// [`Versioned::decode_older`] is not told about the codec, implementations for formats
+/// with another codec call this for their predecessor.
+pub fn decode_version_with<C: WireCodec, T: Versioned>(
+    version: u32,
+    payload: &[u8],
+) -> Result<T, ParseError> {
+    if version == T::VERSION {
+        C::decode(payload)
+    } else {
+        T::decode_older(version, payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V0(u8);
+
+    impl Versioned for V0 {
+        const VERSION: u32 = 0;
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V1(u16);
+
+    impl UpgradeFrom<V0> for V1 {
+        fn upgrade_from(v0: V0) -> Self {
+            Self(v0.0.into())
+        }
+    }
+
+    impl Versioned for V1 {
+        const VERSION: u32 = 1;
+
+        fn decode_older(version: u32, payload: &[u8]) -> Result<Self, ParseError> {
+            upgrade::<V0, Self>(version, payload)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct V2(u32);
+
+    impl UpgradeFrom<V1> for V2 {
+        fn upgrade_from(v1: V1) -> Self {
+            Self(v1.0.into())
+        }
+    }
+
+    impl Versioned for V2 {
+        const VERSION: u32 = 2;
+
+        fn decode_older(version: u32, payload: &[u8]) -> Result<Self, ParseError> {
+            upgrade::<V1, Self>(version, payload)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum WireFormat {
+        Variant0(V0),
+        Variant1(V1),
+    }
+
+    #[test]
+    fn test_matches_postcard_enum() {
+        let enum_bytes = postcard::to_allocvec(&WireFormat::Variant1(V1(300))).unwrap();
+        assert_eq!(encode(&V1(300)), enum_bytes);
+        let enum_bytes = postcard::to_allocvec(&WireFormat::Variant0(V0(7))).unwrap();
+        assert_eq!(encode(&V0(7)), enum_bytes);
+    }
+
+    #[test]
+    fn test_upgrade_and_unknown_version() {
+        assert_eq!(decode::<V1>(&encode(&V0(7))).unwrap(), V1(7));
+        assert_eq!(decode::<V1>(&encode(&V1(300))).unwrap(), V1(300));
+        assert_eq!(decode::<V2>(&encode(&V0(7))).unwrap(), V2(7));
+        assert_eq!(decode::<V2>(&encode(&V1(300))).unwrap(), V2(300));
+
+        let future = postcard::to_allocvec(&(3u32, 1u8)).unwrap();
+        let err = decode::<V2>(&future).unwrap_err();
+        let ParseError::UnknownVersion { version, raw, .. } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(version, 3);
+        assert_eq!(raw, future);
+        let err = decode_version::<V1>(2, &[1]).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownVersion { raw, .. } if raw.is_empty()));
+    }
+
+    #[test]
+    fn test_encode_into() {
+        let mut out = vec![9];
+        encode_into(&V1(300), &mut out);
+        assert_eq!(out, [&[9][..], &encode(&V1(300))].concat());
+    }
+
+    #[test]
+    fn test_truncated_and_trailing() {
+        let bytes = encode(&V1(300));
+        let err = decode::<V1>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+
+        let err = decode::<V1>(&[&bytes[..], &[0, 0]].concat()).unwrap_err();
+        assert!(matches!(err, ParseError::TrailingBytes { len: 2, .. }));
+    }
+}
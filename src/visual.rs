@@ -0,0 +1,118 @@
+//! Visual hashes of tickets.
+//!
+//! Reading a ticket string aloud to check that both sides of a call hold the same ticket is
+//! tedious. Instead, both sides can compare an [`identicon`] or a short [`emoji`] sequence
+//! derived from the [fingerprint](crate::Ticket::fingerprint) of the ticket:
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{Ticket, endpoint::EndpointTicket, visual};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let svg = visual::identicon(&ticket.fingerprint());
+//! assert!(svg.starts_with("<svg"));
+//! let emoji = visual::emoji(&ticket.fingerprint());
+//! assert_eq!(emoji.chars().count(), visual::EMOJI_LEN);
+//! ```
+//!
+//! Both are deterministic and only depend on the fingerprint, so they are the same on all
+//! platforms and versions of this crate. Like the fingerprint, they help humans spot
+//! mistakes, they are not meant to protect against an attacker crafting a similar ticket.
+
+use std::fmt::Write;
+
+use crate::Fingerprint;
+
+/// The number of cells per side of an [`identicon`].
+const CELLS: u64 = 5;
+
+/// The number of emoji in an [`emoji`] sequence.
+pub const EMOJI_LEN: usize = 6;
+
+/// The emoji used by [`emoji`], 6 bits each.
+///
+/// These are single code points that render in color on common platforms and are easy to
+/// name.
+const EMOJI: [char; 64] = [
+    '🐶', '🐱', '🐭', '🐹', '🐰', '🦊', '🐻', '🐼', '🐨', '🐯', '🦁', '🐮', '🐷', '🐸', '🐵', '🐔',
+    '🐧', '🐦', '🦆', '🦉', '🐴', '🦄', '🐝', '🐛', '🦋', '🐌', '🐞', '🐢', '🐍', '🐙', '🦀', '🐠',
+    '🐬', '🐳', '🌵', '🌲', '🌴', '🍀', '🍁', '🍄', '🌻', '🌹', '🌈', '🌙', '🔥', '💧', '🍎', '🍋',
+    '🍉', '🍇', '🍓', '🍒', '🍑', '🍍', '🥕', '🌽', '🍕', '🍔', '🎈', '🎁', '🔔', '🔑', '🎸', '🚀',
+];
+
+/// Renders a symmetric 5×5 identicon for `fingerprint` as an SVG image.
+///
+/// The first 15 bits select the filled cells of the left half and the middle column, the
+/// left half is mirrored to the right. The next bits select the hue, saturation and
+/// lightness of the filled cells.
+pub fn identicon(fingerprint: &Fingerprint) -> String {
+    let bits = u64::from_be_bytes(*fingerprint.as_bytes());
+    let hue = (bits >> 33) % 360;
+    let saturation = 45 + (bits >> 25) % 31;
+    let lightness = 40 + (bits >> 17) % 21;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -1 {size} {size}\" \
+         shape-rendering=\"crispEdges\"><rect x=\"-1\" y=\"-1\" width=\"{size}\" \
+         height=\"{size}\" fill=\"#f0f0f0\"/><g fill=\"hsl({hue},{saturation}%,{lightness}%)\">",
+        size = CELLS + 2,
+    );
+    for row in 0..CELLS {
+        for col in 0..CELLS {
+            let half_col = col.min(CELLS - 1 - col);
+            let bit = 63 - (row * CELLS.div_ceil(2) + half_col);
+            if bits >> bit & 1 == 1 {
+                write!(
+                    svg,
+                    "<rect x=\"{col}\" y=\"{row}\" width=\"1\" height=\"1\"/>"
+                )
+                .expect("writing to a string");
+            }
+        }
+    }
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// Returns a sequence of [`EMOJI_LEN`] emoji for `fingerprint`, taking 6 bits each from the
+/// start.
+pub fn emoji(fingerprint: &Fingerprint) -> String {
+    let bits = u64::from_be_bytes(*fingerprint.as_bytes());
+    (0..EMOJI_LEN)
+        .map(|i| EMOJI[(bits >> (58 - 6 * i) & 0x3f) as usize])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_emoji() {
+        assert_eq!(EMOJI.iter().collect::<HashSet<_>>().len(), EMOJI.len());
+        let zero = Fingerprint::from_bytes([0; 8]);
+        assert_eq!(emoji(&zero), "🐶".repeat(EMOJI_LEN));
+        let ones = Fingerprint::from_bytes([0xff; 8]);
+        assert_eq!(emoji(&ones), "🚀".repeat(EMOJI_LEN));
+        let first = Fingerprint::from_bytes([0b0000_0100, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(emoji(&first), "🐱🐶🐶🐶🐶🐶");
+    }
+
+    #[test]
+    fn test_identicon() {
+        let zero = identicon(&Fingerprint::from_bytes([0; 8]));
+        assert!(!zero.contains("<rect x=\"0\""));
+        let fingerprint = Fingerprint::from_bytes([0x80, 0, 0, 0, 0, 0, 0, 0]);
+        let svg = identicon(&fingerprint);
+        // The first bit fills the top left cell and its mirror image.
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"1\" height=\"1\"/>"));
+        assert!(svg.contains("<rect x=\"4\" y=\"0\" width=\"1\" height=\"1\"/>"));
+        assert_eq!(svg.matches("width=\"1\"").count(), 2);
+        assert!(svg.ends_with("</svg>"));
+        let other = identicon(&Fingerprint::from_bytes([0x80, 0, 0, 0, 1, 0, 0, 0]));
+        assert_ne!(svg, other);
+    }
+}
@@ -0,0 +1,125 @@
+//! JavaScript bindings for `wasm32-unknown-unknown`.
+//!
+//! This exposes an `EndpointTicket` class via [`wasm-bindgen`](mod@wasm_bindgen), so
+//! browser apps can parse and validate tickets client-side:
+//!
+//! ```js
+//! import { EndpointTicket } from "iroh-tickets";
+//!
+//! const ticket = EndpointTicket.parse(input);
+//! console.log(ticket.endpointId, ticket.relayUrls, ticket.ipAddrs);
+//! ```
+//!
+//! Errors are thrown as JavaScript `Error`s with the message of the [`ParseError`].
+
+use wasm_bindgen::prelude::*;
+
+use crate::{ParseError, Ticket, endpoint::EndpointTicket};
+
+fn to_js_error(err: ParseError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// An [`EndpointTicket`] exported to JavaScript.
+#[wasm_bindgen(js_name = EndpointTicket)]
+#[derive(Debug, Clone)]
+pub struct JsEndpointTicket(EndpointTicket);
+
+#[wasm_bindgen(js_class = EndpointTicket)]
+impl JsEndpointTicket {
+    /// Parses a ticket from its string form.
+    pub fn parse(s: &str) -> Result<JsEndpointTicket, JsError> {
+        EndpointTicket::decode_string(s)
+            .map(Self)
+            .map_err(to_js_error)
+    }
+
+    /// Parses a ticket from its string form, tolerating mangling from copy and paste.
+    #[wasm_bindgen(js_name = parseLenient)]
+    pub fn parse_lenient(s: &str) -> Result<JsEndpointTicket, JsError> {
+        EndpointTicket::decode_string_lenient(s)
+            .map(Self)
+            .map_err(to_js_error)
+    }
+
+    /// Decodes a ticket from its byte representation.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<JsEndpointTicket, JsError> {
+        EndpointTicket::decode_bytes(bytes)
+            .map(Self)
+            .map_err(to_js_error)
+    }
+
+    /// The string form of the ticket.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        self.0.encode_string()
+    }
+
+    /// The byte representation of the ticket.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.encode_bytes()
+    }
+
+    /// The endpoint id.
+    #[wasm_bindgen(getter, js_name = endpointId)]
+    pub fn endpoint_id(&self) -> String {
+        self.0.endpoint_addr().id.to_string()
+    }
+
+    /// The relay URLs of the endpoint.
+    #[wasm_bindgen(getter, js_name = relayUrls)]
+    pub fn relay_urls(&self) -> Vec<String> {
+        self.0
+            .endpoint_addr()
+            .relay_urls()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// The direct addresses of the endpoint, as `ip:port` strings.
+    #[wasm_bindgen(getter, js_name = ipAddrs)]
+    pub fn ip_addrs(&self) -> Vec<String> {
+        self.0
+            .endpoint_addr()
+            .ip_addrs()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// The ALPNs of the ticket, decoded as UTF-8 with invalid bytes replaced.
+    #[wasm_bindgen(getter)]
+    pub fn alpns(&self) -> Vec<String> {
+        self.0
+            .alpns()
+            .iter()
+            .map(|alpn| String::from_utf8_lossy(alpn).into_owned())
+            .collect()
+    }
+
+    /// The fingerprint of the ticket, see [`Ticket::fingerprint`].
+    #[wasm_bindgen(getter)]
+    pub fn fingerprint(&self) -> String {
+        self.0.fingerprint().to_string()
+    }
+}
+
+impl JsEndpointTicket {
+    /// Returns the wrapped ticket.
+    pub fn into_inner(self) -> EndpointTicket {
+        self.0
+    }
+}
+
+impl From<EndpointTicket> for JsEndpointTicket {
+    fn from(ticket: EndpointTicket) -> Self {
+        Self(ticket)
+    }
+}
+
+impl From<JsEndpointTicket> for EndpointTicket {
+    fn from(ticket: JsEndpointTicket) -> Self {
+        ticket.0
+    }
+}
@@ -0,0 +1,250 @@
+//! Word list forms of tickets, for reading tickets aloud.
+//!
+//! The [byte representation](Ticket::encode_bytes) of a ticket is written as a sequence
+//! of words from the [BIP39] english word list, each word encoding 11 bits. A checksum of
+//! at least 11 bits over the kind and the bytes fills up the last word, so a misheard or
+//! mistyped word is detected when decoding. The kind itself is not part of the words, the
+//! receiving side has to know which kind of ticket to expect.
+//!
+//! Every word is determined by its first four letters, so decoding also accepts words
+//! abbreviated to four letters. Words are case insensitive and separated by whitespace.
+//!
+//! This is only practical for small tickets, an [`EndpointTicket`] with just an endpoint
+//! id takes 26 words.
+//!
+//! ```
+//! use iroh_base::{EndpointAddr, SecretKey};
+//! use iroh_tickets::{TicketExt, endpoint::EndpointTicket};
+//!
+//! let ticket = EndpointTicket::new(EndpointAddr::new(
+//!     SecretKey::from_bytes(&[0u8; 32]).public(),
+//! ));
+//! let words = ticket.to_words();
+//! assert_eq!(EndpointTicket::from_words(&words).unwrap(), ticket);
+//! ```
+//!
+//! [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+//! [`EndpointTicket`]: crate::endpoint::EndpointTicket
+
+use n0_error::e;
+
+use crate::{ParseError, Ticket};
+
+mod english;
+
+pub(crate) use self::english::WORDS;
+
+/// The number of bits encoded by each word.
+const WORD_BITS: usize = 11;
+
+/// The minimum number of checksum bits.
+const CHECKSUM_BITS: usize = 11;
+
+/// The key derivation context for the checksum.
+const CHECKSUM_CONTEXT: &str = "iroh-tickets 2026 ticket words checksum";
+
+/// The length of the longest word in [`WORDS`].
+const MAX_WORD_LEN: usize = 8;
+
+/// Returns the number of words for a ticket of `len` bytes.
+///
+/// The words hold a bit for the parity of `len`, the bytes and the checksum. The parity
+/// bit distinguishes the two lengths that can result in the same number of words.
+fn word_count(len: usize) -> usize {
+    (1 + 8 * len + CHECKSUM_BITS).div_ceil(WORD_BITS)
+}
+
+/// Returns the first `bits` bits of the checksum, as the low bits of the result.
+fn checksum(kind: &str, bytes: &[u8], bits: usize) -> u32 {
+    let mut hasher = blake3::Hasher::new_derive_key(CHECKSUM_CONTEXT);
+    hasher.update(kind.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(bytes);
+    let hash = hasher.finalize();
+    let [a, b, c, ..] = *hash.as_bytes();
+    u32::from_be_bytes([0, a, b, c]) >> (24 - bits)
+}
+
+/// Returns the index of `word` in the word list, also accepting four letter prefixes.
+fn find_word(word: &str) -> Option<usize> {
+    let word = word.to_ascii_lowercase();
+    if let Ok(index) = WORDS.binary_search(&word.as_str()) {
+        return Some(index);
+    }
+    if word.len() != 4 {
+        return None;
+    }
+    let index = WORDS.partition_point(|w| *w < word.as_str());
+    WORDS
+        .get(index)
+        .filter(|w| w.starts_with(&word))
+        .map(|_| index)
+}
+
+/// Writes bits to a sequence of words.
+struct Writer {
+    words: Vec<&'static str>,
+    acc: u32,
+    bits: usize,
+}
+
+impl Writer {
+    fn push(&mut self, value: u32, bits: usize) {
+        self.acc = (self.acc << bits) | value;
+        self.bits += bits;
+        while self.bits >= WORD_BITS {
+            self.bits -= WORD_BITS;
+            self.words
+                .push(WORDS[(self.acc >> self.bits) as usize & 0x7ff]);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+}
+
+/// Reads bits from a sequence of word indices.
+struct Reader<'a> {
+    indices: &'a [u16],
+    acc: u32,
+    bits: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, bits: usize) -> u32 {
+        while self.bits < bits {
+            let (first, rest) = self.indices.split_first().expect("enough words");
+            self.acc = (self.acc << WORD_BITS) | u32::from(*first);
+            self.bits += WORD_BITS;
+            self.indices = rest;
+        }
+        self.bits -= bits;
+        let value = self.acc >> self.bits;
+        self.acc &= (1 << self.bits) - 1;
+        value
+    }
+}
+
+/// Encodes a ticket as a sequence of words separated by spaces.
+pub fn encode<T: Ticket>(ticket: &T) -> String {
+    let bytes = ticket.encode_bytes();
+    let count = word_count(bytes.len());
+    let checksum_bits = count * WORD_BITS - 1 - 8 * bytes.len();
+    let mut writer = Writer {
+        words: Vec::with_capacity(count),
+        acc: 0,
+        bits: 0,
+    };
+    writer.push(bytes.len() as u32 & 1, 1);
+    for byte in &bytes {
+        writer.push(u32::from(*byte), 8);
+    }
+    writer.push(checksum(T::KIND, &bytes, checksum_bits), checksum_bits);
+    writer.words.join(" ")
+}
+
+/// Decodes a ticket from words written by [`encode`].
+pub fn decode<T: Ticket>(s: &str) -> Result<T, ParseError> {
+    let max = word_count(T::MAX_LEN) * (MAX_WORD_LEN + 1);
+    if s.len() > max {
+        return Err(e!(ParseError::TooLong { len: s.len(), max }));
+    }
+    let indices = s
+        .split_whitespace()
+        .enumerate()
+        .map(|(index, word)| {
+            find_word(word)
+                .map(|i| i as u16)
+                .ok_or_else(|| e!(ParseError::UnknownWord { index }))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let count = indices.len();
+    if count < word_count(0) {
+        return Err(e!(ParseError::Truncated));
+    }
+    let mut reader = Reader {
+        indices: &indices,
+        acc: 0,
+        bits: 0,
+    };
+    let parity = reader.take(1) as usize;
+    // At most two lengths result in `count` words, they differ in parity.
+    let mut len = (count * WORD_BITS - 1 - CHECKSUM_BITS) / 8;
+    if len & 1 != parity {
+        len -= 1;
+    }
+    if word_count(len) != count {
+        return Err(e!(ParseError::Checksum));
+    }
+    let bytes: Vec<u8> = (0..len).map(|_| reader.take(8) as u8).collect();
+    let checksum_bits = count * WORD_BITS - 1 - 8 * len;
+    if reader.take(checksum_bits) != checksum(T::KIND, &bytes, checksum_bits) {
+        return Err(e!(ParseError::Checksum));
+    }
+    T::decode_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use iroh_base::{EndpointAddr, SecretKey};
+
+    use super::*;
+    use crate::{TicketExt, blob::BlobTicket, endpoint::EndpointTicket};
+
+    #[derive(Debug, PartialEq)]
+    struct Raw(Vec<u8>);
+
+    impl Ticket for Raw {
+        const KIND: &'static str = "raw";
+
+        fn encode_bytes(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+
+        fn decode_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+            Ok(Self(bytes.to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_words_all_lengths() {
+        for len in 0..40u8 {
+            let raw = Raw((0..len).map(|i| i.wrapping_mul(97)).collect());
+            let words = encode(&raw);
+            assert_eq!(words.split(' ').count(), word_count(len.into()));
+            assert_eq!(decode::<Raw>(&words).unwrap(), raw, "{len}");
+        }
+    }
+
+    #[test]
+    fn test_words_roundtrip() {
+        let ticket = EndpointTicket::new(EndpointAddr::new(
+            SecretKey::from_bytes(&[17u8; 32]).public(),
+        ));
+        let words = ticket.to_words();
+        assert_eq!(EndpointTicket::from_words(&words).unwrap(), ticket);
+
+        let abbreviated: Vec<_> = words
+            .split(' ')
+            .map(|word| word[..word.len().min(4)].to_uppercase())
+            .collect();
+        let abbreviated = abbreviated.join("\n");
+        assert_eq!(EndpointTicket::from_words(&abbreviated).unwrap(), ticket);
+
+        // Replacing any single word is detected.
+        let list: Vec<_> = words.split(' ').collect();
+        for i in 0..list.len() {
+            let mut typo = list.clone();
+            typo[i] = if typo[i] == "zoo" { "zone" } else { "zoo" };
+            let err = EndpointTicket::from_words(&typo.join(" ")).unwrap_err();
+            assert!(matches!(err, ParseError::Checksum { .. }), "{i}");
+        }
+
+        // The checksum covers the kind.
+        let err = BlobTicket::from_words(&words).unwrap_err();
+        assert!(matches!(err, ParseError::Checksum { .. }));
+
+        let err = EndpointTicket::from_words("abandon ability tickets").unwrap_err();
+        assert!(matches!(err, ParseError::UnknownWord { index: 2, .. }));
+        let err = EndpointTicket::from_words("abandon").unwrap_err();
+        assert!(matches!(err, ParseError::Truncated { .. }));
+    }
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "derive")]
+
+use std::str::FromStr;
+
+use iroh_base::{EndpointAddr, SecretKey};
+use iroh_tickets::Ticket;
+
+#[derive(Debug, Clone, PartialEq, Eq, Ticket)]
+#[ticket(kind = "myproto")]
+struct MyTicket {
+    addr: EndpointAddr,
+    topic: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Ticket)]
+#[ticket(kind = "tuple")]
+struct TupleTicket(u64, String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Ticket)]
+#[ticket(kind = "extensible", allow_trailing_bytes)]
+struct ExtensibleTicket(u64);
+
+fn make_ticket() -> MyTicket {
+    let id = SecretKey::from_bytes(&[7u8; 32]).public();
+    MyTicket {
+        addr: EndpointAddr::new(id).with_ip_addr("127.0.0.1:1234".parse().unwrap()),
+        topic: [3u8; 32],
+    }
+}
+
+#[test]
+fn test_derive_string_roundtrip() {
+    let ticket = make_ticket();
+    let s = ticket.to_string();
+    assert!(s.starts_with("myproto"));
+    assert_eq!(s, ticket.encode_string());
+    assert_eq!(MyTicket::from_str(&s).unwrap(), ticket);
+    assert!(MyTicket::from_str("othera").is_err());
+}
+
+#[test]
+fn test_derive_bytes_match_fields() {
+    let ticket = make_ticket();
+    let expected = postcard::to_stdvec(&(&ticket.addr, &ticket.topic)).unwrap();
+    assert_eq!(ticket.encode_bytes(), expected);
+    assert_eq!(MyTicket::decode_bytes(&expected).unwrap(), ticket);
+}
+
+#[test]
+fn test_derive_serde() {
+    let ticket = make_ticket();
+    let json = serde_json::to_string(&ticket).unwrap();
+    assert_eq!(json, format!("\"{ticket}\""));
+    assert_eq!(serde_json::from_str::<MyTicket>(&json).unwrap(), ticket);
+
+    let bytes = postcard::to_stdvec(&ticket).unwrap();
+    assert_eq!(postcard::from_bytes::<MyTicket>(&bytes).unwrap(), ticket);
+
+    let tuple = TupleTicket(42, "hello".into());
+    let s = tuple.to_string();
+    assert!(s.starts_with("tuple"));
+    assert_eq!(s.parse::<TupleTicket>().unwrap(), tuple);
+}
+
+#[test]
+fn test_derive_trailing_bytes() {
+    let tuple = TupleTicket(42, "hello".into());
+    let mut bytes = tuple.encode_bytes();
+    bytes.push(7);
+    let err = TupleTicket::decode_bytes(&bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        iroh_tickets::ParseError::TrailingBytes { len: 1, .. }
+    ));
+
+    // A newer version of the ticket with an appended field.
+    let bytes = postcard::to_stdvec(&(42u64, "new field")).unwrap();
+    assert_eq!(
+        ExtensibleTicket::decode_bytes(&bytes).unwrap(),
+        ExtensibleTicket(42)
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+struct Unserializable;
+
+impl serde::Serialize for Unserializable {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("not serializable"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Ticket)]
+#[ticket(kind = "failing")]
+struct FailingTicket(Unserializable);
+
+#[test]
+fn test_derive_try_encode() {
+    let tuple = TupleTicket(42, "hello".into());
+    assert_eq!(tuple.try_encode_bytes().unwrap(), tuple.encode_bytes());
+    assert!(FailingTicket(Unserializable).try_encode_bytes().is_err());
+}
+
+#[cfg(feature = "testing")]
+iroh_tickets::ticket_conformance_tests!(my_ticket, MyTicket, make_ticket());